@@ -25,6 +25,9 @@ mod macros;
 mod timer;
 
 pub use counter::Counter;
+// The macros are `#[macro_export]`ed at the crate root already, the glob is kept for
+// compatibility.
+#[allow(unused_imports)]
 pub use macros::*;
 pub use timer::Timer;
 
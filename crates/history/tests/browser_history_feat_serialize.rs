@@ -2,10 +2,10 @@ use wasm_bindgen_test::wasm_bindgen_test_configure;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
-#[cfg(all(feature = "query"))]
+#[cfg(feature = "query")]
 mod utils;
 
-#[cfg(all(feature = "query"))]
+#[cfg(feature = "query")]
 mod feat_serialize {
     use super::*;
 
@@ -0,0 +1,448 @@
+//! Procedural macros for `gloo-net`, used through its `macros` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    parse_macro_input, Attribute, Error, FnArg, GenericArgument, Ident, ItemTrait, LitStr, Pat,
+    PathArguments, ReturnType, TraitItem, TraitItemFn, Type,
+};
+
+/// Implements a trait describing an HTTP API with requests sent by a `gloo_net::http::Client`.
+///
+/// Every method of the trait is an `async fn` taking `&self`, annotated with the method and
+/// path of its request: `#[get("/users/{id}")]`, `#[post(..)]`, `#[put(..)]`, `#[patch(..)]` or
+/// `#[delete(..)]`. Its parameters are sent as
+///
+/// - a path segment, for parameters named in the path like `{id}`, percent-encoded,
+/// - a query parameter, with `#[query]`, or `#[query("name")]` for another name,
+/// - a header, with `#[header("X-Name")]`,
+/// - the JSON body, with `#[body]`.
+///
+/// Query and header values are converted with [`ToString`], and skipped for `Option`s which are
+/// `None`.
+///
+/// Methods return a `Result` whose error type implements `From<gloo_net::Error>`. Responses
+/// with an unsuccessful status fail with `Error::Status`, unless the error type is an
+/// `ApiError<E>`, in which case the body of the response is parsed as `E`, see
+/// `Response::json_or_error`. Successful responses are parsed as JSON, except for `()`, for
+/// which the body is ignored, `String` and `Vec<u8>`, for which the body is returned as text or
+/// bytes, and `Response`, which is returned as it is, whatever its status.
+///
+/// The macro adds a struct named after the trait with a `Client` suffix implementing it, created
+/// with `new` from the `Client` the requests are sent with, e.g. with a base URL.
+///
+/// # Example
+///
+/// ```
+/// use gloo_net::http::{gloo_api, ApiError, Client};
+/// use gloo_net::Error;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     name: String,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct NewUser<'a> {
+///     name: &'a str,
+/// }
+///
+/// #[derive(Debug, Deserialize)]
+/// struct Problem {
+///     message: String,
+/// }
+///
+/// #[gloo_api]
+/// trait UsersApi {
+///     #[get("/users/{id}")]
+///     async fn user(&self, id: u32) -> Result<User, Error>;
+///
+///     #[get("/users")]
+///     async fn search(
+///         &self,
+///         #[query("q")] name: &str,
+///         #[query] page: Option<u32>,
+///         #[header("X-Request-Id")] request_id: &str,
+///     ) -> Result<Vec<User>, Error>;
+///
+///     #[post("/users")]
+///     async fn create(&self, #[body] user: &NewUser<'_>) -> Result<User, ApiError<Problem>>;
+///
+///     #[delete("/users/{id}")]
+///     async fn delete(&self, id: u32) -> Result<(), Error>;
+/// }
+///
+/// # async fn no_run() -> Result<(), Error> {
+/// let api = UsersApiClient::new(Client::builder().base_url("/api").build());
+/// let user = api.user(1).await?;
+/// let users = api.search("fer", Some(2), "42").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[proc_macro_attribute]
+pub fn gloo_api(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let attr = TokenStream2::from(attr);
+        return Error::new(attr.span(), "`gloo_api` takes no arguments")
+            .to_compile_error()
+            .into();
+    }
+    let mut item = parse_macro_input!(item as ItemTrait);
+    match expand(&mut item) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand(item: &mut ItemTrait) -> syn::Result<TokenStream2> {
+    let mut methods = Vec::new();
+    for trait_item in &mut item.items {
+        if let TraitItem::Fn(method) = trait_item {
+            methods.push(method_impl(method)?);
+        }
+    }
+
+    let vis = &item.vis;
+    let name = &item.ident;
+    let client = format_ident!("{}Client", name);
+    let doc = format!("Implements [`{name}`] with requests sent by a `gloo_net::http::Client`.");
+    Ok(quote! {
+        // The futures of `gloo-net` are not `Send` anyway.
+        #[allow(async_fn_in_trait)]
+        #item
+
+        #[doc = #doc]
+        #[derive(Clone, Debug)]
+        #vis struct #client {
+            client: ::gloo_net::http::Client,
+        }
+
+        impl #client {
+            /// Creates the API client, sending requests with `client`.
+            #vis fn new(client: ::gloo_net::http::Client) -> Self {
+                Self { client }
+            }
+        }
+
+        impl #name for #client {
+            #(#methods)*
+        }
+    })
+}
+
+/// How a parameter is sent.
+enum Param {
+    Path,
+    Query(String),
+    Header(String),
+    Body,
+}
+
+/// The implementation of `method`, whose attributes for the macro are removed.
+fn method_impl(method: &mut TraitItemFn) -> syn::Result<TokenStream2> {
+    let sig = &method.sig;
+    if sig.asyncness.is_none() {
+        return Err(Error::new(
+            sig.fn_token.span(),
+            "API methods must be `async`",
+        ));
+    }
+    if let Some(default) = &method.default {
+        return Err(Error::new(default.span(), "API methods can't have a body"));
+    }
+    match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver))
+            if receiver.reference.is_some() && receiver.mutability.is_none() => {}
+        _ => {
+            return Err(Error::new(
+                sig.ident.span(),
+                "API methods must take `&self`",
+            ))
+        }
+    }
+    let (http_method, path) = take_route(&mut method.attrs, &sig.ident)?;
+
+    let mut params = Vec::new();
+    for input in method.sig.inputs.iter_mut().skip(1) {
+        let input = match input {
+            FnArg::Typed(input) => input,
+            FnArg::Receiver(receiver) => {
+                return Err(Error::new(receiver.span(), "unexpected receiver"))
+            }
+        };
+        let ident = match &*input.pat {
+            Pat::Ident(pat) => pat.ident.clone(),
+            pat => return Err(Error::new(pat.span(), "expected a parameter name")),
+        };
+        let param = take_param(&mut input.attrs, &ident)?;
+        params.push((ident, param, is_option(&input.ty)));
+    }
+
+    let (format, path_args) = path_format(&path, &params)?;
+    let mut request = Vec::new();
+    let mut body = None;
+    for (ident, param, optional) in &params {
+        let add = match param {
+            Param::Path => continue,
+            Param::Body => {
+                if body.replace(ident).is_some() {
+                    return Err(Error::new(
+                        ident.span(),
+                        "only one parameter can be the body",
+                    ));
+                }
+                continue;
+            }
+            Param::Query(name) => {
+                quote!(__request.query([(#name, ::std::string::ToString::to_string(__value))]))
+            }
+            Param::Header(name) => {
+                quote!(__request.header(#name, &::std::string::ToString::to_string(__value)))
+            }
+        };
+        request.push(if *optional {
+            quote! {
+                if let ::std::option::Option::Some(__value) = &#ident {
+                    __request = #add;
+                }
+            }
+        } else {
+            quote! {
+                let __value = &#ident;
+                __request = #add;
+            }
+        });
+    }
+    let send = match body {
+        Some(body) => quote!(__request.json(&#body)?.send().await?),
+        None => quote!(__request.send().await?),
+    };
+    let output = output(&method.sig.output)?;
+
+    let sig = &method.sig;
+    Ok(quote! {
+        #sig {
+            let __url = ::std::format!(#format #(, #path_args)*);
+            #[allow(unused_mut)]
+            let mut __request = self
+                .client
+                .request(::gloo_net::http::Method::#http_method, &__url);
+            #(#request)*
+            let __response = #send;
+            #output
+        }
+    })
+}
+
+/// Removes the route attribute, like `#[get("/users")]`, returning the method and the path.
+fn take_route(attrs: &mut Vec<Attribute>, method: &Ident) -> syn::Result<(Ident, LitStr)> {
+    let mut route = None;
+    let mut result = Ok(());
+    attrs.retain(|attr| {
+        let http_method = match attr.path().get_ident().map(Ident::to_string).as_deref() {
+            Some("get") => "GET",
+            Some("post") => "POST",
+            Some("put") => "PUT",
+            Some("patch") => "PATCH",
+            Some("delete") => "DELETE",
+            _ => return true,
+        };
+        let parsed = attr.parse_args::<LitStr>().and_then(|path| {
+            let previous = route.replace((Ident::new(http_method, attr.span()), path));
+            match previous {
+                Some(_) => Err(Error::new(attr.span(), "API methods have a single route")),
+                None => Ok(()),
+            }
+        });
+        if let Err(e) = parsed {
+            result = Err(e);
+        }
+        false
+    });
+    result?;
+    route.ok_or_else(|| {
+        Error::new(
+            method.span(),
+            "API methods need a route, like `#[get(\"/path\")]`",
+        )
+    })
+}
+
+/// Removes the attribute saying how the parameter `ident` is sent.
+fn take_param(attrs: &mut Vec<Attribute>, ident: &Ident) -> syn::Result<Param> {
+    let mut param = None;
+    let mut result = Ok(());
+    attrs.retain(|attr| {
+        let parsed = match attr.path().get_ident().map(Ident::to_string).as_deref() {
+            Some("query") => match &attr.meta {
+                syn::Meta::Path(_) => Ok(Param::Query(ident.to_string())),
+                _ => attr
+                    .parse_args::<LitStr>()
+                    .map(|name| Param::Query(name.value())),
+            },
+            Some("header") => attr
+                .parse_args::<LitStr>()
+                .map(|name| Param::Header(name.value())),
+            Some("body") => match &attr.meta {
+                syn::Meta::Path(_) => Ok(Param::Body),
+                meta => Err(Error::new(meta.span(), "`#[body]` takes no arguments")),
+            },
+            _ => return true,
+        };
+        match parsed {
+            Ok(parsed) if param.is_none() => param = Some(parsed),
+            Ok(_) => {
+                result = Err(Error::new(
+                    attr.span(),
+                    "parameters are sent in a single way",
+                ))
+            }
+            Err(e) => result = Err(e),
+        }
+        false
+    });
+    result?;
+    Ok(param.unwrap_or(Param::Path))
+}
+
+/// The `format!` string for `path` and the percent-encoded parameters it contains.
+fn path_format(
+    path: &LitStr,
+    params: &[(Ident, Param, bool)],
+) -> syn::Result<(String, Vec<TokenStream2>)> {
+    let value = path.value();
+    let mut format = String::new();
+    let mut args = Vec::new();
+    let mut rest = value.as_str();
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| Error::new(path.span(), "unclosed `{` in the path"))?;
+        format.push_str(&rest[..start]);
+        format.push_str("{}");
+        let name = &rest[start + 1..end];
+        let ident = params
+            .iter()
+            .find(|(ident, param, _)| matches!(param, Param::Path) && ident == name)
+            .map(|(ident, _, _)| ident)
+            .ok_or_else(|| {
+                Error::new(
+                    path.span(),
+                    format!("`{{{name}}}` isn't a parameter of the method"),
+                )
+            })?;
+        args.push(quote!(::gloo_net::http::__private::encode_path(&#ident)));
+        rest = &rest[end + 1..];
+    }
+    if rest.contains('}') {
+        return Err(Error::new(path.span(), "unopened `}` in the path"));
+    }
+    format.push_str(rest);
+    for (ident, param, _) in params {
+        let in_path = value.contains(&format!("{{{ident}}}"));
+        if matches!(param, Param::Path) && !in_path {
+            return Err(Error::new(
+                ident.span(),
+                "parameters not in the path need `#[query]`, `#[header(..)]` or `#[body]`",
+            ));
+        }
+    }
+    Ok((format, args))
+}
+
+/// The code turning `__response` into the return value of the method.
+fn output(output: &ReturnType) -> syn::Result<TokenStream2> {
+    let (ok, error) = match output {
+        ReturnType::Type(_, ty) => result_types(ty)
+            .ok_or_else(|| Error::new(ty.span(), "API methods must return a `Result`"))?,
+        ReturnType::Default => {
+            return Err(Error::new(
+                output.span(),
+                "API methods must return a `Result`",
+            ))
+        }
+    };
+    if is_named(ok, "Response") {
+        return Ok(quote!(::std::result::Result::Ok(__response)));
+    }
+    if is_named(error, "ApiError") {
+        return Ok(if is_unit(ok) {
+            quote!(::gloo_net::http::__private::unit_or_error(__response).await)
+        } else {
+            quote!(__response.json_or_error().await)
+        });
+    }
+    let read = if is_unit(ok) {
+        quote!(())
+    } else if is_named(ok, "String") {
+        quote!(__response.text().await?)
+    } else if is_bytes(ok) {
+        quote!(__response.binary().await?)
+    } else {
+        quote!(__response.json().await?)
+    };
+    Ok(quote! {
+        let __response = __response.error_for_status()?;
+        let __output = #read;
+        ::std::result::Result::Ok(__output)
+    })
+}
+
+/// The success and error types of `Result<T, E>`.
+fn result_types(ty: &Type) -> Option<(&Type, &Type)> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) if segment.ident == "Result" => {
+            let mut types = args.args.iter().filter_map(|arg| match arg {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            });
+            Some((types.next()?, types.next()?))
+        }
+        _ => None,
+    }
+}
+
+/// Whether the last segment of the path of `ty` is `name`, ignoring generic arguments.
+fn is_named(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == name),
+        _ => false,
+    }
+}
+
+/// Whether `ty` is `Vec<u8>`.
+fn is_bytes(ty: &Type) -> bool {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last(),
+        _ => None,
+    };
+    match segment {
+        Some(segment) if segment.ident == "Vec" => match &segment.arguments {
+            PathArguments::AngleBracketed(args) => {
+                matches!(args.args.first(), Some(GenericArgument::Type(item)) if is_named(item, "u8"))
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn is_unit(ty: &Type) -> bool {
+    matches!(ty, Type::Tuple(tuple) if tuple.elems.is_empty())
+}
+
+fn is_option(ty: &Type) -> bool {
+    is_named(ty, "Option")
+}
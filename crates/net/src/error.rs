@@ -16,14 +16,67 @@ pub enum Error {
         #[from]
         serde_json::Error,
     ),
+    /// Error returned by `serde` during deserialization, with where in the JSON it happened.
+    #[cfg(feature = "json-path")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json-path")))]
+    #[error("{source} at `{path}`, near `{snippet}`")]
+    JsonPathError {
+        /// The path of the value which failed to parse, like `users[2].email`.
+        path: String,
+        /// The part of the JSON around the error.
+        snippet: String,
+        /// The error of `serde_json`.
+        #[source]
+        source: serde_json::Error,
+    },
     /// Error returned by this crate
     #[error("{0}")]
     GlooError(String),
+    /// The request was aborted, e.g. by dropping its
+    /// [`PendingRequest`](crate::http::PendingRequest).
+    #[error("request aborted")]
+    Aborted,
+    /// The request took longer than its
+    /// [`timeout`](crate::http::RequestBuilder::timeout).
+    #[error("request timed out")]
+    Timeout,
+    /// The request was rejected by a [`RateLimit`](crate::http::RateLimit) without being sent.
+    #[error("rate limit exceeded, retry in {retry_after:?}")]
+    RateLimited {
+        /// How long until the request would be allowed.
+        retry_after: std::time::Duration,
+    },
+    /// The digest of a body doesn't match its subresource integrity, returned by
+    /// [`verify_integrity`](crate::http::verify_integrity).
+    #[error("the body doesn't match the integrity `{integrity}`")]
+    IntegrityMismatch {
+        /// The expected integrity.
+        integrity: String,
+    },
+    /// The server responded with an unsuccessful status, returned by
+    /// [`Response::error_for_status`](crate::http::Response::error_for_status).
+    #[error("HTTP status {status} for {url}")]
+    Status {
+        /// The status of the response.
+        status: u16,
+        /// The URL of the response.
+        url: String,
+    },
 }
 
-#[cfg(any(feature = "http", feature = "websocket", feature = "eventsource"))]
+#[cfg(any(
+    feature = "http",
+    feature = "websocket",
+    feature = "eventsource",
+    feature = "webrtc"
+))]
 pub(crate) use conversion::*;
-#[cfg(any(feature = "http", feature = "websocket", feature = "eventsource"))]
+#[cfg(any(
+    feature = "http",
+    feature = "websocket",
+    feature = "eventsource",
+    feature = "webrtc"
+))]
 mod conversion {
     use gloo_utils::errors::JsError;
     use std::convert::TryFrom;
@@ -31,7 +84,13 @@ mod conversion {
 
     #[cfg(feature = "http")]
     pub(crate) fn js_to_error(js_value: JsValue) -> super::Error {
-        super::Error::JsError(js_to_js_error(js_value))
+        let name = js_sys::Reflect::get(&js_value, &JsValue::from_str("name"))
+            .ok()
+            .and_then(|name| name.as_string());
+        match name.as_deref() {
+            Some("AbortError") => super::Error::Aborted,
+            _ => super::Error::JsError(js_to_js_error(js_value)),
+        }
     }
 
     pub(crate) fn js_to_js_error(js_value: JsValue) -> JsError {
@@ -36,13 +36,16 @@
 //! ```
 use crate::eventsource::{EventSourceError, State};
 use crate::js_to_js_error;
-use futures_channel::mpsc;
+use futures_channel::{mpsc, oneshot};
 use futures_core::{ready, Stream};
 use gloo_utils::errors::JsError;
 use pin_project::{pin_project, pinned_drop};
+use std::cell::RefCell;
 use std::fmt;
 use std::fmt::Formatter;
+use std::future::Future;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::task::{Context, Poll};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
@@ -50,9 +53,14 @@ use web_sys::MessageEvent;
 
 /// Wrapper around browser's EventSource API. Dropping
 /// this will close the underlying event source.
+///
+/// Closing it ends the streams of all subscriptions, after the events received before. When the
+/// browser gives up on the connection, e.g. because of a response which isn't an event stream,
+/// the streams yield [`EventSourceError::ConnectionError`] before ending.
 #[derive(Clone)]
 pub struct EventSource {
     es: web_sys::EventSource,
+    subscribers: Rc<RefCell<Vec<mpsc::UnboundedSender<StreamMessage>>>>,
 }
 
 impl fmt::Debug for EventSource {
@@ -98,7 +106,10 @@ impl EventSource {
     pub fn new(url: &str) -> Result<Self, JsError> {
         let es = web_sys::EventSource::new(url).map_err(js_to_js_error)?;
 
-        Ok(Self { es })
+        Ok(Self {
+            es,
+            subscribers: Rc::default(),
+        })
     }
 
     /// Subscribes to listening for a specific type of event.
@@ -110,12 +121,17 @@ impl EventSource {
     /// events without an event field as well as events that have the
     /// specific type `event: message`. It will not trigger on any
     /// other event type.
+    ///
+    /// Subscribing to a closed EventSource returns a subscription whose stream ends right away.
     pub fn subscribe(
         &mut self,
         event_type: impl Into<String>,
     ) -> Result<EventSourceSubscription, JsError> {
         let event_type = event_type.into();
         let (message_sender, message_receiver) = mpsc::unbounded();
+        if self.state() == State::Closed {
+            message_sender.close_channel();
+        }
 
         let message_callback: Closure<dyn FnMut(MessageEvent)> = {
             let event_type = event_type.clone();
@@ -134,15 +150,20 @@ impl EventSource {
             .map_err(js_to_js_error)?;
 
         let error_callback: Closure<dyn FnMut(web_sys::Event)> = {
+            let message_sender = message_sender.clone();
             Closure::wrap(Box::new(move |e: web_sys::Event| {
-                let is_connecting = e
+                let ready_state = e
                     .current_target()
                     .map(|target| target.unchecked_into::<web_sys::EventSource>())
-                    .map(|es| es.ready_state() == web_sys::EventSource::CONNECTING)
-                    .unwrap_or(false);
-                if !is_connecting {
+                    .map(|es| es.ready_state());
+                // The browser reconnects by itself while connecting.
+                if ready_state != Some(web_sys::EventSource::CONNECTING) {
                     let _ = message_sender.unbounded_send(StreamMessage::ErrorEvent);
-                };
+                }
+                // Nothing is received anymore once the browser gave up.
+                if ready_state == Some(web_sys::EventSource::CLOSED) {
+                    message_sender.close_channel();
+                }
             }) as Box<dyn FnMut(web_sys::Event)>)
         };
 
@@ -150,6 +171,11 @@ impl EventSource {
             .add_event_listener_with_callback("error", error_callback.as_ref().unchecked_ref())
             .map_err(js_to_js_error)?;
 
+        let mut subscribers = self.subscribers.borrow_mut();
+        subscribers.retain(|sender| !sender.is_closed());
+        subscribers.push(message_sender);
+        drop(subscribers);
+
         Ok(EventSourceSubscription {
             error_callback,
             es: self.es.clone(),
@@ -169,10 +195,46 @@ impl EventSource {
 
     fn close_and_notify(&mut self) {
         self.es.close();
-        // Fire an error event to cause all subscriber
-        // streams to close down.
-        if let Ok(event) = web_sys::Event::new("error") {
-            let _ = self.es.dispatch_event(&event);
+        // End all subscriber streams.
+        for sender in self.subscribers.borrow_mut().drain(..) {
+            sender.close_channel();
+        }
+    }
+
+    /// Waits until the connection is open.
+    ///
+    /// Resolves right away if it is open already, and with
+    /// [`EventSourceError::ConnectionError`] on the first error, even if the browser keeps
+    /// reconnecting, or if the EventSource is closed.
+    pub fn connected(&self) -> impl Future<Output = Result<(), EventSourceError>> {
+        let es = self.es.clone();
+        async move {
+            match es.ready_state() {
+                web_sys::EventSource::OPEN => return Ok(()),
+                web_sys::EventSource::CLOSED => return Err(EventSourceError::ConnectionError),
+                _ => {}
+            }
+            let (sender, receiver) = oneshot::channel();
+            let mut sender = Some(sender);
+            let callback: Closure<dyn FnMut(web_sys::Event)> =
+                Closure::wrap(Box::new(move |e: web_sys::Event| {
+                    if let Some(sender) = sender.take() {
+                        let _ = sender.send(e.type_() == "open");
+                    }
+                }) as Box<dyn FnMut(web_sys::Event)>);
+            for ty in ["open", "error"] {
+                let _ = es.add_event_listener_with_callback(ty, callback.as_ref().unchecked_ref());
+            }
+            let opened = receiver.await.unwrap_or(false);
+            for ty in ["open", "error"] {
+                let _ =
+                    es.remove_event_listener_with_callback(ty, callback.as_ref().unchecked_ref());
+            }
+            if opened {
+                Ok(())
+            } else {
+                Err(EventSourceError::ConnectionError)
+            }
         }
     }
 
@@ -242,6 +304,7 @@ mod tests {
     wasm_bindgen_test_configure!(run_in_browser);
 
     #[wasm_bindgen_test]
+    #[allow(clippy::option_env_unwrap)]
     fn eventsource_works() {
         let sse_echo_server_url =
             option_env!("SSE_ECHO_SERVER_URL").expect("Did you set SSE_ECHO_SERVER_URL?");
@@ -270,4 +333,15 @@ mod tests {
             );
         })
     }
+
+    #[wasm_bindgen_test]
+    fn eventsource_close_ends_streams() {
+        let mut es = EventSource::new("rubbish").unwrap();
+        let mut servers = es.subscribe("server").unwrap();
+        es.close();
+
+        spawn_local(async move {
+            assert_eq!(servers.next().await, None);
+        })
+    }
 }
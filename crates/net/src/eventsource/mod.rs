@@ -2,8 +2,12 @@
 //!
 //! This API is provided in the following flavors:
 //! - [Futures API][futures]
+//!
+//! A [reconnecting][reconnect::ReconnectingEventSource] wrapper resumes streams the browser
+//! gave up on.
 
 pub mod futures;
+pub mod reconnect;
 
 use std::fmt;
 
@@ -11,7 +15,7 @@ use std::fmt;
 ///
 /// See [`EventSource.readyState` on MDN](https://developer.mozilla.org/en-US/docs/Web/API/EventSource/readyState)
 /// to learn more.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum State {
     /// The connection has not yet been established.
     Connecting,
@@ -0,0 +1,362 @@
+//! An [`EventSource`] wrapper which resumes streams after the browser gave up on them.
+//!
+//! Browsers reconnect an `EventSource` by themselves after network errors, sending the ID of the
+//! last received event in the `Last-Event-ID` header. They stop for good after a response which
+//! isn't an event stream, e.g. a `502` of a restarting server, and the ID is lost when the page
+//! is reloaded. A [`ReconnectingEventSource`] opens a new connection in both cases and passes
+//! the last event ID as a query parameter, since it can't be sent as a header.
+//!
+//! With the `storage` feature, the ID can be kept in session storage so that streams resume
+//! after a reload of the tab.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::eventsource::reconnect::ReconnectingEventSource;
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::StreamExt;
+//! use std::time::Duration;
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! let mut es = ReconnectingEventSource::builder("https://api.example.com/feed")
+//!     .event_types(["price", "trade"])
+//!     .retry_interval(Duration::from_secs(5))
+//!     .max_retries(Some(10))
+//!     .open()
+//!     .unwrap();
+//!
+//! spawn_local(async move {
+//!     while let Some(Ok((event_type, msg))) = es.next().await {
+//!         console_log!(format!("{}: {:?}", event_type, msg.data()))
+//!     }
+//!     console_log!("Gave up reconnecting")
+//! })
+//! # }
+//! ```
+use crate::eventsource::futures::{EventSource, EventSourceSubscription};
+use crate::eventsource::{EventSourceError, State};
+use futures_core::{ready, Stream};
+use gloo_timers::future::TimeoutFuture;
+use gloo_utils::errors::JsError;
+use std::convert::TryFrom;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use web_sys::MessageEvent;
+
+/// Builder for a [`ReconnectingEventSource`], created by [`ReconnectingEventSource::builder`].
+#[derive(Clone, Debug)]
+pub struct ReconnectingEventSourceBuilder {
+    url: String,
+    event_types: Vec<String>,
+    retry_interval: Duration,
+    max_retries: Option<u32>,
+    last_event_id_param: String,
+    last_event_id: Option<String>,
+    #[cfg(feature = "storage")]
+    storage_key: Option<String>,
+}
+
+impl ReconnectingEventSourceBuilder {
+    /// Sets the event types to subscribe to.
+    ///
+    /// Defaults to `["message"]`, the events without an `event` field.
+    pub fn event_types<I>(mut self, event_types: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.event_types = event_types.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets how long to wait before opening a new connection after the browser gave up.
+    ///
+    /// Defaults to 3 seconds. While the browser reconnects by itself, it waits for the interval
+    /// sent by the server in a `retry` field instead.
+    pub fn retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Sets the maximum number of consecutive connections opened after the browser gave up.
+    ///
+    /// `None`, the default, retries forever. The counter is reset every time an event is
+    /// received.
+    pub fn max_retries(mut self, max_retries: Option<u32>) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the query parameter the last event ID is passed in, `lastEventId` by default.
+    pub fn last_event_id_param(mut self, name: &str) -> Self {
+        self.last_event_id_param = name.to_string();
+        self
+    }
+
+    /// Resumes the stream after the event with the given ID, e.g. one persisted by the
+    /// application.
+    pub fn last_event_id(mut self, id: &str) -> Self {
+        self.last_event_id = Some(id.to_string());
+        self
+    }
+
+    /// Keeps the last event ID in session storage under the given key, so that a stream opened
+    /// with the same key after a reload of the tab resumes where it stopped.
+    ///
+    /// An ID found in storage takes precedence over one set with
+    /// [`last_event_id`](Self::last_event_id).
+    #[cfg(feature = "storage")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "storage")))]
+    pub fn persist_last_event_id(mut self, key: &str) -> Self {
+        self.storage_key = Some(key.to_string());
+        self
+    }
+
+    /// Opens the first connection.
+    ///
+    /// This fails under the same conditions as [`EventSource::new`] and
+    /// [`EventSource::subscribe`]. Failures of later connections are retried instead.
+    pub fn open(self) -> Result<ReconnectingEventSource, JsError> {
+        #[cfg_attr(not(feature = "storage"), allow(unused_mut))]
+        let mut last_event_id = self.last_event_id;
+        #[cfg(feature = "storage")]
+        if let Some(key) = &self.storage_key {
+            use gloo_storage::Storage;
+            if let Ok(id) = gloo_storage::SessionStorage::get::<String>(key) {
+                last_event_id = Some(id);
+            }
+        }
+
+        let mut es = ReconnectingEventSource {
+            url: self.url,
+            event_types: self.event_types,
+            retry_interval: self.retry_interval,
+            max_retries: self.max_retries,
+            last_event_id_param: self.last_event_id_param,
+            last_event_id,
+            #[cfg(feature = "storage")]
+            storage_key: self.storage_key,
+            attempt: 0,
+            connection: Connection::Closed,
+        };
+        es.connection = es.connect()?;
+        Ok(es)
+    }
+}
+
+enum Connection {
+    Open {
+        es: EventSource,
+        subscriptions: Vec<EventSourceSubscription>,
+    },
+    Waiting(TimeoutFuture),
+    Closed,
+}
+
+/// An EventSource which opens a new connection when the browser gave up on the current one,
+/// resuming after the last received event.
+///
+/// It streams the events of all [subscribed types](ReconnectingEventSourceBuilder::event_types)
+/// like an [`EventSourceSubscription`]. Errors the browser recovers from are not yielded. Once
+/// [`max_retries`](ReconnectingEventSourceBuilder::max_retries) is exhausted, the stream yields
+/// [`EventSourceError::ConnectionError`] and then ends.
+pub struct ReconnectingEventSource {
+    url: String,
+    event_types: Vec<String>,
+    retry_interval: Duration,
+    max_retries: Option<u32>,
+    last_event_id_param: String,
+    last_event_id: Option<String>,
+    #[cfg(feature = "storage")]
+    storage_key: Option<String>,
+    attempt: u32,
+    connection: Connection,
+}
+
+impl ReconnectingEventSource {
+    /// Creates a builder for a stream from the given URL.
+    pub fn builder(url: &str) -> ReconnectingEventSourceBuilder {
+        ReconnectingEventSourceBuilder {
+            url: url.to_string(),
+            event_types: vec!["message".to_string()],
+            retry_interval: Duration::from_secs(3),
+            max_retries: None,
+            last_event_id_param: "lastEventId".to_string(),
+            last_event_id: None,
+            #[cfg(feature = "storage")]
+            storage_key: None,
+        }
+    }
+
+    /// The ID of the last received event with one.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// The state of the current underlying connection.
+    ///
+    /// While waiting to reconnect, this is [`State::Connecting`].
+    pub fn state(&self) -> State {
+        match &self.connection {
+            Connection::Open { es, .. } => es.state(),
+            Connection::Waiting(_) => State::Connecting,
+            Connection::Closed => State::Closed,
+        }
+    }
+
+    /// Closes the current connection and stops reconnecting.
+    ///
+    /// The stream ends after the events received before.
+    pub fn close(mut self) {
+        self.connection = Connection::Closed;
+    }
+
+    fn connect(&self) -> Result<Connection, JsError> {
+        let url = match &self.last_event_id {
+            Some(id) => resume_url(&self.url, &self.last_event_id_param, id),
+            None => self.url.clone(),
+        };
+        let mut es = EventSource::new(&url)?;
+        let subscriptions = self
+            .event_types
+            .iter()
+            .map(|event_type| es.subscribe(event_type.as_str()))
+            .collect::<Result<_, _>>()?;
+        Ok(Connection::Open { es, subscriptions })
+    }
+
+    fn set_last_event_id(&mut self, id: String) {
+        #[cfg(feature = "storage")]
+        if let Some(key) = &self.storage_key {
+            use gloo_storage::Storage;
+            let _ = gloo_storage::SessionStorage::set(key, &id);
+        }
+        self.last_event_id = Some(id);
+    }
+
+    /// Schedules a new connection, returning `false` if the retries are exhausted.
+    fn schedule_reconnect(&mut self) -> bool {
+        if matches!(self.max_retries, Some(max) if self.attempt >= max) {
+            self.connection = Connection::Closed;
+            return false;
+        }
+        self.attempt += 1;
+        let millis = u32::try_from(self.retry_interval.as_millis()).unwrap_or(u32::MAX);
+        self.connection = Connection::Waiting(TimeoutFuture::new(millis));
+        true
+    }
+}
+
+impl Stream for ReconnectingEventSource {
+    type Item = Result<(String, MessageEvent), EventSourceError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match &mut this.connection {
+                Connection::Closed => return Poll::Ready(None),
+                Connection::Waiting(timeout) => {
+                    ready!(Pin::new(timeout).poll(cx));
+                    // Failing to create an EventSource for a URL which worked before is retried.
+                    this.connection = match this.connect() {
+                        Ok(connection) => connection,
+                        Err(_) if this.schedule_reconnect() => continue,
+                        Err(_) => return Poll::Ready(Some(Err(EventSourceError::ConnectionError))),
+                    };
+                }
+                Connection::Open { subscriptions, .. } => {
+                    let mut event = None;
+                    let mut ended = false;
+                    for subscription in subscriptions.iter_mut() {
+                        match Pin::new(subscription).poll_next(cx) {
+                            Poll::Ready(Some(Ok(e))) => {
+                                event = Some(e);
+                                break;
+                            }
+                            // The browser gave up, the subscriptions end right after.
+                            Poll::Ready(Some(Err(_))) => {}
+                            Poll::Ready(None) => ended = true,
+                            Poll::Pending => {}
+                        }
+                    }
+                    if let Some((event_type, msg)) = event {
+                        this.attempt = 0;
+                        let id = msg.last_event_id();
+                        if !id.is_empty() {
+                            this.set_last_event_id(id);
+                        }
+                        return Poll::Ready(Some(Ok((event_type, msg))));
+                    }
+                    if !ended {
+                        return Poll::Pending;
+                    }
+                    if !this.schedule_reconnect() {
+                        return Poll::Ready(Some(Err(EventSourceError::ConnectionError)));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ReconnectingEventSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectingEventSource")
+            .field("url", &self.url)
+            .field("event_types", &self.event_types)
+            .field("last_event_id", &self.last_event_id)
+            .field("state", &self.state())
+            .finish_non_exhaustive()
+    }
+}
+
+/// `url` with the `param` query parameter set to `id`.
+fn resume_url(url: &str, param: &str, id: &str) -> String {
+    let (url, fragment) = match url.find('#') {
+        Some(index) => url.split_at(index),
+        None => (url, ""),
+    };
+    let separator = if url.contains('?') { '&' } else { '?' };
+    format!(
+        "{url}{separator}{}={}{fragment}",
+        encode_component(param),
+        encode_component(id)
+    )
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986.
+fn encode_component(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(char::from(byte))
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resume_urls() {
+        assert_eq!(
+            resume_url("https://example.com/feed", "lastEventId", "42"),
+            "https://example.com/feed?lastEventId=42"
+        );
+        assert_eq!(
+            resume_url("/feed?topic=a#top", "last", "a b/é"),
+            "/feed?topic=a&last=a%20b%2F%C3%A9#top"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! GraphQL operations over HTTP, following the
+//! [GraphQL over HTTP](https://graphql.github.io/graphql-over-http/draft/) conventions.
+//!
+//! The types describing operations and errors are shared with the WebSocket client of
+//! `websocket::graphql`, used for subscriptions.
+//!
+//! # Example
+//!
+//! ```
+//! # use gloo_net::http::Client;
+//! # use gloo_net::graphql::{self, QueryError};
+//! # use serde::Deserialize;
+//! #[derive(Deserialize)]
+//! struct Data {
+//!     user: User,
+//! }
+//!
+//! #[derive(Deserialize)]
+//! struct User {
+//!     name: String,
+//! }
+//!
+//! # async fn no_run() -> Result<(), QueryError> {
+//! let client = Client::builder().base_url("https://example.com/graphql").build();
+//! let data: Data = graphql::query(
+//!     &client,
+//!     "query User($id: ID!) { user(id: $id) { name } }",
+//!     &serde_json::json!({ "id": "1" }),
+//! )
+//! .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error as ThisError;
+
+#[cfg(feature = "http")]
+use crate::http::{Client, Response};
+use crate::Error;
+#[cfg(feature = "http")]
+use serde::{de::DeserializeOwned, Serialize};
+
+/// A GraphQL operation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphQlRequest {
+    /// The query document
+    pub query: String,
+    /// The operation to execute if the document contains several
+    pub operation_name: Option<String>,
+    /// The variables of the operation
+    pub variables: Option<Value>,
+    /// Protocol extensions
+    pub extensions: Option<Value>,
+}
+
+impl GraphQlRequest {
+    /// An operation without variables.
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            operation_name: None,
+            variables: None,
+            extensions: None,
+        }
+    }
+
+    /// Sets the variables of the operation.
+    pub fn variables(mut self, variables: Value) -> Self {
+        self.variables = Some(variables);
+        self
+    }
+
+    /// Sets the name of the operation to execute.
+    pub fn operation_name(mut self, operation_name: &str) -> Self {
+        self.operation_name = Some(operation_name.to_string());
+        self
+    }
+
+    /// Sets the protocol extensions.
+    pub fn extensions(mut self, extensions: Value) -> Self {
+        self.extensions = Some(extensions);
+        self
+    }
+
+    /// The body of the request, sent over HTTP and WebSockets alike.
+    #[cfg(any(feature = "http", feature = "websocket"))]
+    pub(crate) fn to_json(&self) -> Value {
+        let mut payload = serde_json::json!({ "query": self.query });
+        if let Some(operation_name) = &self.operation_name {
+            payload["operationName"] = Value::from(operation_name.as_str());
+        }
+        if let Some(variables) = &self.variables {
+            payload["variables"] = variables.clone();
+        }
+        if let Some(extensions) = &self.extensions {
+            payload["extensions"] = extensions.clone();
+        }
+        payload
+    }
+}
+
+/// An error reported by a GraphQL server.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ServerError {
+    /// The description of the error.
+    pub message: String,
+    /// Where the error is in the query document.
+    #[serde(default)]
+    pub locations: Vec<Location>,
+    /// The path of the field which failed in the result, made of field names and list indices.
+    #[serde(default)]
+    pub path: Vec<Value>,
+    /// Additional details, like an error code.
+    #[serde(default)]
+    pub extensions: Option<Value>,
+}
+
+/// A position in a query document.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct Location {
+    /// The line, starting at 1.
+    pub line: u32,
+    /// The column, starting at 1.
+    pub column: u32,
+}
+
+/// Errors of [`query`] and [`execute`].
+#[derive(Debug, ThisError)]
+#[non_exhaustive]
+pub enum QueryError {
+    /// The request failed, the response wasn't a GraphQL response or its data couldn't be
+    /// deserialized.
+    #[error("{0}")]
+    Http(Error),
+    /// The server reported errors, with the data it could resolve despite them.
+    #[error("{}", display_errors(.errors))]
+    GraphQl {
+        /// The errors, at least one.
+        errors: Vec<ServerError>,
+        /// The partial result, if any.
+        data: Option<Value>,
+    },
+}
+
+impl From<Error> for QueryError {
+    fn from(error: Error) -> Self {
+        QueryError::Http(error)
+    }
+}
+
+fn display_errors(errors: &[ServerError]) -> String {
+    match errors {
+        [] => "GraphQL operation failed".to_string(),
+        [error] => error.message.clone(),
+        [error, rest @ ..] => format!("{} (and {} more errors)", error.message, rest.len()),
+    }
+}
+
+/// The body of a response to a GraphQL operation.
+#[cfg(feature = "http")]
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    data: Option<Value>,
+    #[serde(default)]
+    errors: Vec<ServerError>,
+}
+
+#[cfg(feature = "http")]
+impl Envelope {
+    fn into_result<T: serde::de::DeserializeOwned>(self) -> Result<T, QueryError> {
+        if !self.errors.is_empty() {
+            return Err(QueryError::GraphQl {
+                errors: self.errors,
+                data: self.data.filter(|data| !data.is_null()),
+            });
+        }
+        match self.data {
+            Some(data) => Ok(serde_json::from_value(data).map_err(Error::from)?),
+            None => Err(QueryError::Http(Error::GlooError(
+                "GraphQL response without data nor errors".to_string(),
+            ))),
+        }
+    }
+}
+
+/// Runs `query` with `variables`, POSTing it to the base URL of `client` and deserializing the
+/// `data` of the result as `T`.
+///
+/// Fails with [`QueryError::GraphQl`] if the server reports errors, also when it returns
+/// partial data along them.
+#[cfg(feature = "http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+pub async fn query<T, V>(client: &Client, query: &str, variables: &V) -> Result<T, QueryError>
+where
+    T: DeserializeOwned,
+    V: Serialize + ?Sized,
+{
+    let variables = serde_json::to_value(variables).map_err(Error::from)?;
+    execute(client, &GraphQlRequest::new(query).variables(variables)).await
+}
+
+/// Runs `request`, POSTing it to the base URL of `client` and deserializing the `data` of the
+/// result as `T`.
+///
+/// Fails like [`query`].
+#[cfg(feature = "http")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+pub async fn execute<T>(client: &Client, request: &GraphQlRequest) -> Result<T, QueryError>
+where
+    T: DeserializeOwned,
+{
+    let response = client
+        .post("")
+        .header(
+            "Accept",
+            "application/graphql-response+json, application/json",
+        )
+        .json(&request.to_json())?
+        .send()
+        .await?;
+    let response = envelope(response).await?;
+    response.into_result()
+}
+
+/// Reads the GraphQL response, which servers may send with an unsuccessful status as well.
+#[cfg(feature = "http")]
+async fn envelope(response: Response) -> Result<Envelope, Error> {
+    let is_json = response.headers().content_type().is_some_and(|media_type| {
+        media_type == "application/json" || media_type == "application/graphql-response+json"
+    });
+    let response = if is_json {
+        response
+    } else {
+        response.error_for_status()?
+    };
+    response.json().await
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse<T: serde::de::DeserializeOwned>(body: Value) -> Result<T, QueryError> {
+        serde_json::from_value::<Envelope>(body)
+            .unwrap()
+            .into_result()
+    }
+
+    #[test]
+    fn results() {
+        let data: Value = parse(json!({ "data": { "a": 1 } })).unwrap();
+        assert_eq!(data, json!({ "a": 1 }));
+        assert!(matches!(
+            parse::<Value>(json!({})),
+            Err(QueryError::Http(Error::GlooError(_)))
+        ));
+        assert!(matches!(
+            parse::<String>(json!({ "data": { "a": 1 } })),
+            Err(QueryError::Http(Error::SerdeError(_)))
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let result = parse::<Value>(json!({
+            "data": { "a": 1, "b": null },
+            "errors": [
+                { "message": "no b", "locations": [{ "line": 1, "column": 7 }], "path": ["b"] },
+                { "message": "other", "extensions": { "code": "E" } },
+            ],
+        }));
+        let e = result.unwrap_err();
+        assert_eq!(e.to_string(), "no b (and 1 more errors)");
+        match e {
+            QueryError::GraphQl { errors, data } => {
+                assert_eq!(errors[0].locations, vec![Location { line: 1, column: 7 }]);
+                assert_eq!(errors[0].path, vec![json!("b")]);
+                assert_eq!(errors[1].extensions, Some(json!({ "code": "E" })));
+                assert_eq!(data, Some(json!({ "a": 1, "b": null })));
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+        match parse::<Value>(json!({ "data": null, "errors": [{ "message": "no" }] })) {
+            Err(QueryError::GraphQl { data: None, .. }) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use crate::http::browser::Response;
+use crate::{js_to_error, Error};
+use futures_core::ready;
+use gloo_timers::future::TimeoutFuture;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{AbortController, AbortSignal};
+
+/// The `AbortController` of a request built by a [`RequestBuilder`](crate::http::RequestBuilder).
+pub(crate) struct Abort {
+    controller: AbortController,
+    /// Aborts the controller when the signal set with
+    /// [`abort_signal`](crate::http::RequestBuilder::abort_signal) is aborted.
+    forward: Option<(AbortSignal, Closure<dyn FnMut()>)>,
+    timeout: Option<Duration>,
+}
+
+impl Abort {
+    pub(crate) fn new(
+        signal: Option<AbortSignal>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, Error> {
+        let controller = AbortController::new().map_err(js_to_error)?;
+        let forward = match signal {
+            Some(signal) if signal.aborted() => {
+                controller.abort();
+                None
+            }
+            Some(signal) => {
+                let target = controller.clone();
+                // The reason is not forwarded, so that the request always fails with an
+                // `AbortError` which is turned into `Error::Aborted`.
+                let callback = Closure::wrap(Box::new(move || target.abort()) as Box<dyn FnMut()>);
+                signal
+                    .add_event_listener_with_callback("abort", callback.as_ref().unchecked_ref())
+                    .map_err(js_to_error)?;
+                Some((signal, callback))
+            }
+            None => None,
+        };
+        Ok(Self {
+            controller,
+            forward,
+            timeout,
+        })
+    }
+
+    pub(crate) fn signal(&self) -> AbortSignal {
+        self.controller.signal()
+    }
+
+    /// Fails `fetch` with [`Error::Timeout`] and aborts it if the response takes longer than
+    /// the timeout set with [`timeout`](crate::http::RequestBuilder::timeout).
+    pub(crate) fn with_timeout(
+        &self,
+        fetch: impl Future<Output = Result<Response, Error>> + 'static,
+    ) -> Pin<Box<dyn Future<Output = Result<Response, Error>>>> {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return Box::pin(fetch),
+        };
+        let controller = self.controller.clone();
+        let millis = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+        let mut timer = TimeoutFuture::new(millis);
+        let mut fetch = Box::pin(fetch);
+        Box::pin(std::future::poll_fn(move |cx| {
+            if let Poll::Ready(result) = fetch.as_mut().poll(cx) {
+                return Poll::Ready(result);
+            }
+            ready!(Pin::new(&mut timer).poll(cx));
+            controller.abort();
+            Poll::Ready(Err(Error::Timeout))
+        }))
+    }
+}
+
+impl Drop for Abort {
+    fn drop(&mut self) {
+        if let Some((signal, callback)) = &self.forward {
+            let _ = signal
+                .remove_event_listener_with_callback("abort", callback.as_ref().unchecked_ref());
+        }
+    }
+}
+
+/// Aborts the request of a [`PendingRequest`], created by [`PendingRequest::abort_handle`].
+///
+/// Aborting after the response was received fails the reads of its body which are still in
+/// progress.
+#[derive(Clone)]
+pub struct AbortHandle {
+    controller: Option<AbortController>,
+}
+
+impl AbortHandle {
+    /// Aborts the request, making it fail with [`Error::Aborted`].
+    ///
+    /// This does nothing for requests converted from a `web_sys::Request`, which can only be
+    /// aborted through their own signal.
+    pub fn abort(&self) {
+        if let Some(controller) = &self.controller {
+            controller.abort();
+        }
+    }
+
+    /// Whether the request was aborted, through this handle or otherwise.
+    pub fn is_aborted(&self) -> bool {
+        self.controller
+            .as_ref()
+            .is_some_and(|controller| controller.signal().aborted())
+    }
+}
+
+impl fmt::Debug for AbortHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AbortHandle")
+            .field("aborted", &self.is_aborted())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A request in flight, resolving to its [`Response`].
+///
+/// Dropping it before the response is received aborts the request.
+#[must_use = "requests are aborted when dropped"]
+pub struct PendingRequest {
+    future: Pin<Box<dyn Future<Output = Result<Response, Error>>>>,
+    abort: Option<Abort>,
+    done: bool,
+}
+
+impl PendingRequest {
+    pub(crate) fn new(
+        future: Pin<Box<dyn Future<Output = Result<Response, Error>>>>,
+        abort: Option<Abort>,
+    ) -> Self {
+        Self {
+            future,
+            abort,
+            done: false,
+        }
+    }
+
+    /// A request which failed before being sent.
+    pub(crate) fn failed(error: Error) -> Self {
+        Self::new(Box::pin(std::future::ready(Err(error))), None)
+    }
+
+    /// A handle aborting this request, e.g. from a cancel button.
+    pub fn abort_handle(&self) -> AbortHandle {
+        AbortHandle {
+            controller: self.abort.as_ref().map(|abort| abort.controller.clone()),
+        }
+    }
+
+    /// Aborts the request.
+    ///
+    /// Awaiting it afterwards yields [`Error::Aborted`].
+    pub fn abort(&self) {
+        self.abort_handle().abort();
+    }
+}
+
+impl Future for PendingRequest {
+    type Output = Result<Response, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let result = ready!(this.future.as_mut().poll(cx));
+        this.done = true;
+        Poll::Ready(result)
+    }
+}
+
+impl Drop for PendingRequest {
+    fn drop(&mut self) {
+        if !self.done {
+            self.abort();
+        }
+    }
+}
+
+impl fmt::Debug for PendingRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingRequest")
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
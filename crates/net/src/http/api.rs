@@ -0,0 +1,40 @@
+//! Support for the code generated by [`gloo_api`](super::gloo_api).
+
+use std::fmt;
+
+#[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+use crate::http::{ApiError, Response};
+
+/// Percent-encodes `value` as a path segment, leaving only unreserved characters as they are.
+pub fn encode_path<T: fmt::Display + ?Sized>(value: &T) -> String {
+    crate::url::encode(&value.to_string(), b"")
+}
+
+/// Like [`Response::json_or_error`], for methods without a response body.
+#[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+pub async fn unit_or_error<E>(response: Response) -> Result<(), ApiError<E>>
+where
+    E: serde::de::DeserializeOwned,
+{
+    if response.ok() {
+        Ok(())
+    } else {
+        Err(ApiError::Status {
+            status: response.status(),
+            body: response.json().await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_paths() {
+        assert_eq!(encode_path(&42), "42");
+        assert_eq!(encode_path("a-b_c.d~e"), "a-b_c.d~e");
+        assert_eq!(encode_path("a b/c?d"), "a%20b%2Fc%3Fd");
+        assert_eq!(encode_path("é"), "%C3%A9");
+    }
+}
@@ -0,0 +1,261 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Poll, Waker};
+
+use crate::http::browser::{Request, Response};
+use crate::http::middleware::{Middleware, MiddlewareFuture, Next};
+use crate::Error;
+
+type RefreshFuture = Pin<Box<dyn Future<Output = Result<String, Error>>>>;
+
+/// Sends requests with a bearer token, refreshing it when the server responds with
+/// `401 Unauthorized`.
+///
+/// Requests get an `Authorization: Bearer <token>` header with the current token, if there is
+/// one. When a request is rejected with a `401`, the refresh closure is called for a new token
+/// and the request is sent again with it, once. Requests rejected while a refresh is running
+/// wait for it instead of starting their own, so the closure runs once for all of them. If the
+/// refresh fails, the requests waiting for it fail as well.
+///
+/// Clones share the token, so an `Auth` can be shared between clients and requests.
+///
+/// # Example
+///
+/// ```
+/// # use gloo_net::http::Auth;
+/// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+/// # use gloo_net::http::browser::{Client, Request};
+/// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+/// # use gloo_net::http::{Client, Request};
+/// # async fn no_run() {
+/// let auth = Auth::new(Some("initial-token"), || async {
+///     let resp = Request::post("/auth/refresh").send().await?.error_for_status()?;
+///     resp.text().await
+/// });
+/// let client = Client::builder().auth(auth.clone()).build();
+/// let resp = client.get("/api/me").send().await.unwrap();
+///
+/// // Logging out
+/// auth.set_token(None);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Auth {
+    state: Rc<RefCell<State>>,
+    refresh: Rc<dyn Fn() -> RefreshFuture>,
+}
+
+#[derive(Default)]
+struct State {
+    token: Option<String>,
+    /// Incremented by every completed refresh.
+    generation: u64,
+    refreshing: bool,
+    waiters: Vec<Waker>,
+    /// The error of the last refresh, `None` if it succeeded.
+    failed: Option<String>,
+}
+
+impl Auth {
+    /// Creates the middleware with an initial `token`, getting new ones from `refresh`.
+    pub fn new<F, Fut>(token: Option<&str>, refresh: F) -> Self
+    where
+        F: Fn() -> Fut + 'static,
+        Fut: Future<Output = Result<String, Error>> + 'static,
+    {
+        Self {
+            state: Rc::new(RefCell::new(State {
+                token: token.map(str::to_string),
+                ..State::default()
+            })),
+            refresh: Rc::new(move || Box::pin(refresh())),
+        }
+    }
+
+    /// The current token.
+    pub fn token(&self) -> Option<String> {
+        self.state.borrow().token.clone()
+    }
+
+    /// Replaces the token, e.g. after logging in, or removes it.
+    pub fn set_token(&self, token: Option<&str>) {
+        self.state.borrow_mut().token = token.map(str::to_string);
+    }
+
+    /// Sets the `Authorization` header of `request`, returning the generation of the token.
+    fn authorize(&self, request: &Request) -> u64 {
+        let state = self.state.borrow();
+        if let Some(token) = &state.token {
+            request
+                .headers()
+                .set("Authorization", &format!("Bearer {token}"));
+        }
+        state.generation
+    }
+
+    /// Refreshes the token which had `generation`, unless another request already did.
+    async fn refresh(&self, generation: u64) -> Result<(), Error> {
+        poll_fn(|cx| {
+            let mut state = self.state.borrow_mut();
+            if state.refreshing {
+                state.waiters.push(cx.waker().clone());
+                Poll::Pending
+            } else {
+                Poll::Ready(())
+            }
+        })
+        .await;
+        {
+            let state = self.state.borrow();
+            if state.generation != generation {
+                return match &state.failed {
+                    None => Ok(()),
+                    Some(e) => Err(Error::GlooError(format!(
+                        "refreshing the token failed: {e}"
+                    ))),
+                };
+            }
+        }
+
+        self.state.borrow_mut().refreshing = true;
+        let _refreshing = Refreshing(&self.state);
+        trace_event!(debug, "refreshing token");
+        let result = (self.refresh)().await;
+        let mut state = self.state.borrow_mut();
+        state.generation += 1;
+        match result {
+            Ok(token) => {
+                state.token = Some(token);
+                state.failed = None;
+                Ok(())
+            }
+            Err(e) => {
+                state.failed = Some(e.to_string());
+                Err(e)
+            }
+        }
+    }
+
+    async fn send(self, request: Request, next: Next) -> Result<Response, Error> {
+        let retry = request.try_clone()?;
+        let generation = self.authorize(&request);
+        let response = next.clone().run(request).await?;
+        if response.status() != 401 {
+            return Ok(response);
+        }
+        self.refresh(generation).await?;
+        self.authorize(&retry);
+        next.run(retry).await
+    }
+}
+
+impl Middleware for Auth {
+    fn handle(&self, request: Request, next: Next) -> MiddlewareFuture {
+        Box::pin(self.clone().send(request, next))
+    }
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.state.borrow();
+        f.debug_struct("Auth")
+            .field("has_token", &state.token.is_some())
+            .field("refreshing", &state.refreshing)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Ends a refresh when dropped, also when the request refreshing the token is dropped, letting
+/// a waiting request refresh it instead.
+struct Refreshing<'a>(&'a RefCell<State>);
+
+impl Drop for Refreshing<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.borrow_mut();
+        state.refreshing = false;
+        for waker in state.waiters.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// Returns `Pending` once, so that other futures get polled meanwhile.
+    async fn yield_now() {
+        let mut yielded = false;
+        poll_fn(|cx| {
+            if yielded {
+                Poll::Ready(())
+            } else {
+                yielded = true;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    fn counting(result: Result<&'static str, &'static str>) -> (Auth, Rc<Cell<u32>>) {
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let auth = Auth::new(Some("old"), move || {
+            counter.set(counter.get() + 1);
+            async move {
+                yield_now().await;
+                result
+                    .map(str::to_string)
+                    .map_err(|e| Error::GlooError(e.to_string()))
+            }
+        });
+        (auth, calls)
+    }
+
+    #[test]
+    fn coalesces_refreshes() {
+        let (auth, calls) = counting(Ok("new"));
+        let (a, b) =
+            futures::executor::block_on(futures::future::join(auth.refresh(0), auth.refresh(0)));
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(calls.get(), 1);
+        assert_eq!(auth.token().as_deref(), Some("new"));
+
+        // Requests sent with the old token use the refreshed one.
+        futures::executor::block_on(auth.refresh(0)).unwrap();
+        assert_eq!(calls.get(), 1);
+        futures::executor::block_on(auth.refresh(1)).unwrap();
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn failed_refresh() {
+        let (auth, calls) = counting(Err("expired"));
+        let (a, b) =
+            futures::executor::block_on(futures::future::join(auth.refresh(0), auth.refresh(0)));
+        assert_eq!(a.unwrap_err().to_string(), "expired");
+        assert_eq!(
+            b.unwrap_err().to_string(),
+            "refreshing the token failed: expired"
+        );
+        assert_eq!(calls.get(), 1);
+        assert_eq!(auth.token().as_deref(), Some("old"));
+    }
+
+    #[test]
+    fn dropped_refresh() {
+        let (auth, calls) = counting(Ok("new"));
+        let mut refresh = Box::pin(auth.refresh(0));
+        let waker = futures::task::noop_waker();
+        let mut cx = std::task::Context::from_waker(&waker);
+        assert!(refresh.as_mut().poll(&mut cx).is_pending());
+        drop(refresh);
+        futures::executor::block_on(auth.refresh(0)).unwrap();
+        assert_eq!(calls.get(), 2);
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use crate::http::browser::{RequestBuilder, Response};
+use crate::http::PendingRequest;
+use crate::Error;
+use futures_core::Stream;
+
+/// A [`Stream`] of the responses to a batch of requests, in the order of the requests,
+/// created by [`Client::send_all`](crate::http::Client::send_all).
+///
+/// Requests are sent as the stream is polled, at most `max_concurrency` at a time. A response
+/// received before the ones to earlier requests is held back until they are yielded, without
+/// starting a new request in its place. Dropping the stream aborts the requests in flight.
+#[must_use = "streams do nothing unless polled"]
+pub struct SendAll {
+    requests: VecDeque<RequestBuilder>,
+    in_flight: VecDeque<Slot>,
+    max_concurrency: usize,
+}
+
+enum Slot {
+    Pending(PendingRequest),
+    Done(Result<Response, Error>),
+}
+
+impl SendAll {
+    pub(crate) fn new(requests: VecDeque<RequestBuilder>, max_concurrency: usize) -> Self {
+        Self {
+            requests,
+            in_flight: VecDeque::new(),
+            max_concurrency: max_concurrency.max(1),
+        }
+    }
+}
+
+impl Stream for SendAll {
+    type Item = Result<Response, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while this.in_flight.len() < this.max_concurrency {
+            match this.requests.pop_front() {
+                Some(request) => this.in_flight.push_back(Slot::Pending(request.send())),
+                None => break,
+            }
+        }
+        for slot in this.in_flight.iter_mut() {
+            if let Slot::Pending(request) = slot {
+                if let Poll::Ready(result) = Pin::new(request).poll(cx) {
+                    *slot = Slot::Done(result);
+                }
+            }
+        }
+        match this.in_flight.pop_front() {
+            None => Poll::Ready(None),
+            Some(Slot::Done(result)) => Poll::Ready(Some(result)),
+            Some(pending) => {
+                this.in_flight.push_front(pending);
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SendAll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SendAll")
+            .field("queued", &self.requests.len())
+            .field("in_flight", &self.in_flight.len())
+            .field("max_concurrency", &self.max_concurrency)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,341 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::future::{poll_fn, Future};
+#[cfg(feature = "json")]
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+#[cfg(feature = "json")]
+use crate::http::json::from_json;
+use crate::{js_to_error, Error};
+use futures_core::{ready, Stream};
+use js_sys::{Array, Function, Reflect, Uint8Array};
+#[cfg(feature = "json")]
+use serde::de::DeserializeOwned;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::{future_to_promise, JsFuture};
+use web_sys::{
+    ReadableStream, ReadableStreamDefaultController, ReadableStreamDefaultReader,
+    TextDecodeOptions, TextDecoder, UnderlyingSource,
+};
+
+/// A [`Stream`] of the chunks of a response body, created by
+/// [`Response::body_stream`](crate::http::Response::body_stream).
+///
+/// The chunks are yielded as the browser receives them, their size is up to the browser. The
+/// body is cancelled when the stream is dropped before it ends.
+pub struct BodyStream {
+    state: State,
+}
+
+enum State {
+    Reader {
+        reader: ReadableStreamDefaultReader,
+        read: Option<JsFuture>,
+    },
+    Failed(Error),
+    Done,
+}
+
+impl BodyStream {
+    pub(crate) fn new(body: Option<ReadableStream>) -> Self {
+        let state = match body {
+            // Fails if the body is already being read.
+            Some(body) => match ReadableStreamDefaultReader::new(&body) {
+                Ok(reader) => State::Reader { reader, read: None },
+                Err(e) => State::Failed(js_to_error(e)),
+            },
+            None => State::Done,
+        };
+        Self { state }
+    }
+}
+
+impl Stream for BodyStream {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let (reader, read) = match &mut this.state {
+            State::Reader { reader, read } => (reader, read),
+            State::Failed(_) => match std::mem::replace(&mut this.state, State::Done) {
+                State::Failed(e) => return Poll::Ready(Some(Err(e))),
+                _ => unreachable!(),
+            },
+            State::Done => return Poll::Ready(None),
+        };
+        let future = read.get_or_insert_with(|| JsFuture::from(reader.read()));
+        let result = ready!(Pin::new(future).poll(cx));
+        *read = None;
+
+        match result.and_then(|result| parse_chunk(&result)) {
+            Ok(Some(chunk)) => Poll::Ready(Some(Ok(chunk))),
+            Ok(None) => {
+                this.state = State::Done;
+                Poll::Ready(None)
+            }
+            Err(e) => {
+                this.state = State::Done;
+                Poll::Ready(Some(Err(js_to_error(e))))
+            }
+        }
+    }
+}
+
+/// The bytes of a `ReadableStreamReadResult`, `None` once the stream is done.
+fn parse_chunk(result: &JsValue) -> Result<Option<Vec<u8>>, JsValue> {
+    if Reflect::get(result, &JsValue::from_str("done"))?.is_truthy() {
+        return Ok(None);
+    }
+    let value = Reflect::get(result, &JsValue::from_str("value"))?;
+    Ok(Some(value.unchecked_into::<Uint8Array>().to_vec()))
+}
+
+impl Drop for BodyStream {
+    fn drop(&mut self) {
+        if let State::Reader { reader, .. } = &self.state {
+            let _ = reader.cancel();
+        }
+    }
+}
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self.state {
+            State::Reader { .. } => "reading",
+            State::Failed(_) => "failed",
+            State::Done => "done",
+        };
+        f.debug_struct("BodyStream")
+            .field("state", &state)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A compression format of [`Response::body_stream_decompressed`](crate::http::Response::body_stream_decompressed),
+/// as supported by the browser's `DecompressionStream`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// The gzip format.
+    Gzip,
+    /// The zlib format.
+    Deflate,
+    /// Raw DEFLATE data, without header and checksum.
+    DeflateRaw,
+}
+
+impl Compression {
+    fn as_str(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Deflate => "deflate",
+            Compression::DeflateRaw => "deflate-raw",
+        }
+    }
+}
+
+/// `body` piped through a `DecompressionStream` for `format`.
+pub(crate) fn decompress(
+    body: &ReadableStream,
+    format: Compression,
+) -> Result<ReadableStream, Error> {
+    let constructor = Reflect::get(&js_sys::global(), &JsValue::from_str("DecompressionStream"))
+        .map_err(js_to_error)?
+        .dyn_into::<Function>()
+        .map_err(|_| Error::GlooError("DecompressionStream is not available".to_string()))?;
+    let stream = Reflect::construct(
+        &constructor,
+        &Array::of1(&JsValue::from_str(format.as_str())),
+    )
+    .map_err(js_to_error)?;
+    // A `DecompressionStream` is a pair of a `readable` and a `writable` stream.
+    Ok(body.pipe_through(stream.unchecked_ref()))
+}
+
+/// A [`Stream`] of the lines of a response body, created by
+/// [`Response::text_lines`](crate::http::Response::text_lines).
+///
+/// The body is decoded as UTF-8 as it is received, so lines and characters split across chunks
+/// are put back together. Lines end with `\n` or `\r\n`, which are not part of the yielded
+/// lines. A last line without a line ending is yielded when the body ends.
+pub struct TextLines {
+    body: BodyStream,
+    decoder: TextDecoder,
+    buffer: String,
+    done: bool,
+}
+
+impl TextLines {
+    pub(crate) fn new(body: BodyStream) -> Result<Self, Error> {
+        Ok(Self {
+            body,
+            decoder: TextDecoder::new().map_err(js_to_error)?,
+            buffer: String::new(),
+            done: false,
+        })
+    }
+
+    fn decode(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        let options = TextDecodeOptions::new();
+        // Keeps the bytes of a character split across chunks until the next one.
+        options.set_stream(true);
+        let text = self
+            .decoder
+            .decode_with_u8_array_and_options(chunk, &options)
+            .map_err(js_to_error)?;
+        self.buffer.push_str(&text);
+        Ok(())
+    }
+}
+
+impl Stream for TextLines {
+    type Item = Result<String, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(line) = next_line(&mut this.buffer) {
+                return Poll::Ready(Some(Ok(line)));
+            }
+            if this.done {
+                return match std::mem::take(&mut this.buffer) {
+                    rest if rest.is_empty() => Poll::Ready(None),
+                    rest => Poll::Ready(Some(Ok(rest))),
+                };
+            }
+            let decoded = match ready!(Pin::new(&mut this.body).poll_next(cx)) {
+                Some(Ok(chunk)) => this.decode(&chunk),
+                Some(Err(e)) => Err(e),
+                None => {
+                    this.done = true;
+                    // Flushes an incomplete character at the end of the body.
+                    this.decoder
+                        .decode()
+                        .map(|rest| this.buffer.push_str(&rest))
+                        .map_err(js_to_error)
+                }
+            };
+            if let Err(e) = decoded {
+                this.done = true;
+                this.buffer.clear();
+                return Poll::Ready(Some(Err(e)));
+            }
+        }
+    }
+}
+
+/// Removes the first complete line from `buffer`, without its line ending.
+fn next_line(buffer: &mut String) -> Option<String> {
+    let end = buffer.find('\n')?;
+    let mut line: String = buffer.drain(..=end).collect();
+    line.pop();
+    if line.ends_with('\r') {
+        line.pop();
+    }
+    Some(line)
+}
+
+impl fmt::Debug for TextLines {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TextLines")
+            .field("body", &self.body)
+            .field("buffered", &self.buffer.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`Stream`] of the values of a newline-delimited JSON body, created by
+/// [`Response::ndjson`](crate::http::Response::ndjson).
+///
+/// Every line is parsed as a `T` as soon as it is received, blank lines are skipped. A line
+/// which fails to parse yields an error, and the stream goes on with the next one.
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub struct NdJson<T> {
+    lines: TextLines,
+    item: PhantomData<fn() -> T>,
+}
+
+#[cfg(feature = "json")]
+impl<T> NdJson<T> {
+    pub(crate) fn new(lines: TextLines) -> Self {
+        Self {
+            lines,
+            item: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: DeserializeOwned> Stream for NdJson<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match ready!(Pin::new(&mut this.lines).poll_next(cx)) {
+                Some(Ok(line)) if line.trim().is_empty() => {}
+                Some(Ok(line)) => return Poll::Ready(Some(from_json(&line))),
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T> fmt::Debug for NdJson<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NdJson")
+            .field("lines", &self.lines)
+            .finish()
+    }
+}
+
+/// A `ReadableStream` pulling its chunks from `stream`.
+pub(crate) fn readable_stream<S>(stream: S) -> Result<ReadableStream, Error>
+where
+    S: Stream<Item = Vec<u8>> + 'static,
+{
+    let stream = Rc::new(RefCell::new(Box::pin(stream)));
+    // The browser waits for the returned promise before pulling again, so the stream is never
+    // borrowed twice.
+    let pull = Closure::<dyn FnMut(ReadableStreamDefaultController) -> js_sys::Promise>::new(
+        move |controller: ReadableStreamDefaultController| {
+            let stream = Rc::clone(&stream);
+            future_to_promise(async move {
+                match poll_fn(|cx| stream.borrow_mut().as_mut().poll_next(cx)).await {
+                    Some(chunk) => {
+                        controller.enqueue_with_chunk(&Uint8Array::from(chunk.as_slice()))?
+                    }
+                    None => controller.close()?,
+                }
+                Ok(JsValue::UNDEFINED)
+            })
+        },
+    );
+    let source = UnderlyingSource::new();
+    // Owned by the `ReadableStream` from now on.
+    source.set_pull(&pull.into_js_value().unchecked_into());
+    ReadableStream::new_with_underlying_source(&source).map_err(js_to_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_lines() {
+        let mut buffer = "first\r\nsecond\n\nthi".to_string();
+        assert_eq!(next_line(&mut buffer).as_deref(), Some("first"));
+        assert_eq!(next_line(&mut buffer).as_deref(), Some("second"));
+        assert_eq!(next_line(&mut buffer).as_deref(), Some(""));
+        assert_eq!(next_line(&mut buffer), None);
+        buffer.push_str("rd\n");
+        assert_eq!(next_line(&mut buffer).as_deref(), Some("third"));
+        assert!(buffer.is_empty());
+    }
+}
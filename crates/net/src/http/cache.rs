@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use crate::http::browser::{Request, Response};
+use crate::http::middleware::{Middleware, MiddlewareFuture, Next};
+use crate::http::{CacheControl, Method};
+use crate::{js_to_error, Error};
+use js_sys::{Date, Reflect};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Cache, CacheStorage, ResponseInit};
+
+/// The header a cached response's expiry time is stored in, in milliseconds since the epoch.
+const EXPIRES_HEADER: &str = "x-gloo-cache-expires";
+
+/// A [`Middleware`] keeping `GET` responses in the browser's `CacheStorage`.
+///
+/// Successful (`200`) responses are stored for their `Cache-Control: max-age`, or the
+/// [`default_max_age`](Self::default_max_age) if they have none. Responses marked `no-store`
+/// or `no-cache` are not stored. While a stored response is fresh, it is returned without
+/// sending the request. Once it expired, the request is sent again, and the stale response is
+/// returned if that fails because of a network error, e.g. while the device is offline.
+///
+/// Entries are keyed by URL, request headers and `Vary` are ignored. They are kept in the cache
+/// with the given name, shared with other code such as service workers using it.
+///
+/// # Example
+///
+/// ```
+/// # use gloo_net::http::HttpCache;
+/// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+/// # use gloo_net::http::browser::Client;
+/// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+/// # use gloo_net::http::Client;
+/// # use std::time::Duration;
+/// # async fn no_run() {
+/// let cache = HttpCache::new("api").default_max_age(Duration::from_secs(60));
+/// let client = Client::builder().middleware(cache.clone()).build();
+///
+/// let users = client.get("/api/users").send().await.unwrap();
+/// // After creating a user, the list is outdated.
+/// cache.invalidate("/api/users").await.unwrap();
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HttpCache {
+    name: String,
+    default_max_age: Option<Duration>,
+}
+
+impl HttpCache {
+    /// Creates a cache layer storing responses in the cache with the given name.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            default_max_age: None,
+        }
+    }
+
+    /// Sets how long responses without a `max-age` are stored. By default, they are not.
+    pub fn default_max_age(mut self, max_age: Duration) -> Self {
+        self.default_max_age = Some(max_age);
+        self
+    }
+
+    /// Removes the response stored for `url`, returning whether there was one.
+    pub async fn invalidate(&self, url: &str) -> Result<bool, Error> {
+        let cache = self.open().await?;
+        let deleted = JsFuture::from(cache.delete_with_str(url))
+            .await
+            .map_err(js_to_error)?;
+        Ok(deleted.is_truthy())
+    }
+
+    /// Removes all stored responses.
+    pub async fn clear(&self) -> Result<(), Error> {
+        JsFuture::from(caches()?.delete(&self.name))
+            .await
+            .map_err(js_to_error)?;
+        Ok(())
+    }
+
+    async fn open(&self) -> Result<Cache, Error> {
+        let cache = JsFuture::from(caches()?.open(&self.name))
+            .await
+            .map_err(js_to_error)?;
+        Ok(cache.unchecked_into())
+    }
+
+    async fn send(self, request: Request, next: Next) -> Result<Response, Error> {
+        if request.method() != Method::GET {
+            return next.run(request).await;
+        }
+        let url = request.url();
+        let cache = self.open().await?;
+        let cached = JsFuture::from(cache.match_with_str(&url))
+            .await
+            .map_err(js_to_error)?
+            .dyn_into::<web_sys::Response>()
+            .ok();
+        match cached {
+            Some(cached) if is_fresh(&cached) => {
+                trace_event!(debug, url = %url, "cache hit");
+                return Ok(Response::from(cached));
+            }
+            _ => {}
+        }
+
+        match next.run(request).await {
+            Ok(response) => {
+                let cache_control = response.headers().get("Cache-Control");
+                match lifetime(cache_control.as_deref(), self.default_max_age) {
+                    Some(lifetime) if response.status() == 200 => {
+                        store(cache, url, response.as_raw(), lifetime)?
+                    }
+                    _ => {}
+                }
+                Ok(response)
+            }
+            Err(Error::JsError(e)) => match cached {
+                Some(cached) => {
+                    trace_event!(debug, url = %url, "network error, using stale response");
+                    Ok(Response::from(cached))
+                }
+                None => Err(Error::JsError(e)),
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Middleware for HttpCache {
+    fn handle(&self, request: Request, next: Next) -> MiddlewareFuture {
+        Box::pin(self.clone().send(request, next))
+    }
+}
+
+fn is_fresh(response: &web_sys::Response) -> bool {
+    response
+        .headers()
+        .get(EXPIRES_HEADER)
+        .ok()
+        .flatten()
+        .and_then(|expires| expires.parse::<f64>().ok())
+        .is_some_and(|expires| expires > Date::now())
+}
+
+fn caches() -> Result<CacheStorage, Error> {
+    Reflect::get(&js_sys::global(), &JsValue::from_str("caches"))
+        .map_err(js_to_error)?
+        .dyn_into::<CacheStorage>()
+        .map_err(|_| Error::GlooError("CacheStorage is not available".to_string()))
+}
+
+/// Stores a copy of `response` in the background, so that the response isn't held back until
+/// its body is read.
+fn store(
+    cache: Cache,
+    url: String,
+    response: &web_sys::Response,
+    lifetime: Duration,
+) -> Result<(), Error> {
+    let copy = web_sys::Response::clone(response).map_err(js_to_error)?;
+    let headers = web_sys::Headers::new_with_headers(&copy.headers()).map_err(js_to_error)?;
+    let expires = Date::now() + lifetime.as_millis() as f64;
+    headers
+        .set(EXPIRES_HEADER, &expires.to_string())
+        .map_err(js_to_error)?;
+    let init = ResponseInit::new();
+    init.set_status(copy.status());
+    init.set_status_text(&copy.status_text());
+    init.set_headers(&headers);
+    let stored =
+        web_sys::Response::new_with_opt_readable_stream_and_init(copy.body().as_ref(), &init)
+            .map_err(js_to_error)?;
+    wasm_bindgen_futures::spawn_local(async move {
+        // A response which can't be stored is just sent again next time.
+        let _ = JsFuture::from(cache.put_with_str(&url, &stored)).await;
+    });
+    Ok(())
+}
+
+/// How long a response with the given `Cache-Control` header may be stored.
+fn lifetime(cache_control: Option<&str>, default: Option<Duration>) -> Option<Duration> {
+    let cache_control = CacheControl::parse(cache_control.unwrap_or_default());
+    if cache_control.no_store || cache_control.no_cache {
+        return None;
+    }
+    match cache_control.max_age {
+        Some(max_age) if max_age.is_zero() => None,
+        Some(max_age) => Some(max_age),
+        None => default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lifetimes() {
+        let default = Some(Duration::from_secs(5));
+        assert_eq!(
+            lifetime(Some("public, max-age=600"), default),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(lifetime(Some("Max-Age=0"), default), None);
+        assert_eq!(lifetime(Some("no-store"), default), None);
+        assert_eq!(lifetime(Some("max-age=60, no-cache"), default), None);
+        assert_eq!(lifetime(Some("private"), default), default);
+        assert_eq!(lifetime(None, default), default);
+        assert_eq!(lifetime(None, None), None);
+    }
+}
@@ -0,0 +1,475 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::http::browser::{RequestBuilder, Response};
+use crate::http::conditional::{CachedResponse, MemoryStore, ValidatorStore};
+use crate::http::middleware::Middleware;
+use crate::http::url::join_url;
+use crate::http::{Auth, Method, RateLimit, Retry, SendAll, Signer};
+#[cfg(feature = "json")]
+use crate::http::{Paginated, StaleWhileRevalidate};
+use crate::Error;
+#[cfg(feature = "json")]
+use serde::{de::DeserializeOwned, Serialize};
+use web_sys::{RequestCache, RequestCredentials};
+
+/// A reusable configuration for requests: a base URL, default headers and modes, and
+/// [`Middleware`]s.
+///
+/// Requests created by a client resolve their URL against the base URL, and start with the
+/// client's defaults, which they can override. Cloning a client is cheap, clones share the
+/// configuration.
+///
+/// # Example
+///
+/// ```
+/// # use gloo_net::http::Retry;
+/// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+/// # use gloo_net::http::browser::Client;
+/// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+/// # use gloo_net::http::Client;
+/// # async fn no_run() {
+/// let client = Client::builder()
+///     .base_url("https://api.example.com/v1")
+///     .default_header("Accept", "application/json")
+///     .retry(Retry::new())
+///     .build();
+///
+/// // Sent to https://api.example.com/v1/users
+/// let resp = client.get("/users").send().await.unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Client {
+    inner: Rc<ClientBuilder>,
+    validators: Rc<dyn ValidatorStore>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl Client {
+    /// Creates a client without base URL or defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a builder for a client.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// The base URL relative URLs are resolved against.
+    pub fn base_url(&self) -> Option<&str> {
+        self.inner.base_url.as_deref()
+    }
+
+    /// Creates a request with the given method to `url`, resolved against the base URL.
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let url = self.resolve(url);
+        let mut builder = RequestBuilder::new(&url).method(method);
+        for (name, value) in &self.inner.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(credentials) = self.inner.credentials {
+            builder = builder.credentials(credentials);
+        }
+        if let Some(cache) = self.inner.cache {
+            builder = builder.cache(cache);
+        }
+        builder.middlewares(&self.inner.middlewares)
+    }
+
+    /// Creates a [`GET`][Method::GET] request to `url`.
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    /// Creates a [`POST`][Method::POST] request to `url`.
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    /// Creates a [`PUT`][Method::PUT] request to `url`.
+    pub fn put(&self, url: &str) -> RequestBuilder {
+        self.request(Method::PUT, url)
+    }
+
+    /// Creates a [`DELETE`][Method::DELETE] request to `url`.
+    pub fn delete(&self, url: &str) -> RequestBuilder {
+        self.request(Method::DELETE, url)
+    }
+
+    /// Creates a [`PATCH`][Method::PATCH] request to `url`.
+    pub fn patch(&self, url: &str) -> RequestBuilder {
+        self.request(Method::PATCH, url)
+    }
+
+    /// Sends `requests` with at most `max_concurrency` of them in flight, returning a stream of
+    /// the results in the order of the requests.
+    ///
+    /// This is meant for fanning out many API calls without filling the browser's connection
+    /// pool, which delays other requests of the page. The requests are sent as the stream is
+    /// polled, see [`SendAll`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use futures::StreamExt;
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Client;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Client;
+    /// # async fn no_run() {
+    /// let client = Client::builder().base_url("/api").build();
+    /// let requests = (1..=50).map(|id| client.get(&format!("/users/{id}")));
+    /// let mut responses = client.send_all(requests, 6);
+    /// while let Some(resp) = responses.next().await {
+    ///     let resp = resp.unwrap();
+    /// }
+    /// # }
+    /// ```
+    pub fn send_all(
+        &self,
+        requests: impl IntoIterator<Item = RequestBuilder>,
+        max_concurrency: usize,
+    ) -> SendAll {
+        SendAll::new(requests.into_iter().collect(), max_concurrency)
+    }
+
+    /// Sends a [`GET`][Method::GET] request to `url`, parsing the JSON response as `T`.
+    ///
+    /// Responses with an unsuccessful status fail with [`Error::Status`], see
+    /// [`Response::error_for_status`]. Use [`Response::json_or_error`] to parse error bodies.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Client;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Client;
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Deserialize, Serialize)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// # async fn no_run() -> Result<(), gloo_net::Error> {
+    /// let client = Client::builder().base_url("/api").build();
+    /// let users: Vec<User> = client.get_json("/users").await?;
+    /// let created: User = client.post_json("/users", &User { name: "Ferris".into() }).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, Error> {
+        let request = self.get(url).header("Accept", "application/json");
+        let response = request.send().await?.error_for_status()?;
+        response.json().await
+    }
+
+    /// Requests the pages of a paginated API starting at `url`, returning a stream of the pages
+    /// parsed as `T`.
+    ///
+    /// The next pages are the targets of the `Link` headers with `rel="next"`, see
+    /// [`Paginated`]. Responses with an unsuccessful status fail like [`get_json`](Self::get_json).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use futures::StreamExt;
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Client;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Client;
+    /// # use serde::Deserialize;
+    /// #[derive(Deserialize)]
+    /// struct Issue {
+    ///     title: String,
+    /// }
+    ///
+    /// # async fn no_run() {
+    /// let client = Client::builder().base_url("https://api.github.com").build();
+    /// let mut pages = client.get_paginated::<Vec<Issue>>("/repos/rustwasm/gloo/issues?per_page=100");
+    /// while let Some(issues) = pages.next().await {
+    ///     for issue in issues.unwrap() {}
+    /// }
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn get_paginated<T: DeserializeOwned + 'static>(&self, url: &str) -> Paginated<T> {
+        Paginated::new(self.clone(), url)
+    }
+
+    /// Sends `body` as JSON in a [`POST`][Method::POST] request to `url`, parsing the JSON
+    /// response as `T`.
+    ///
+    /// Fails like [`get_json`](Self::get_json).
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn post_json<B, T>(&self, url: &str, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send_json(Method::POST, url, body).await
+    }
+
+    /// Sends `body` as JSON in a [`PUT`][Method::PUT] request to `url`, parsing the JSON
+    /// response as `T`.
+    ///
+    /// Fails like [`get_json`](Self::get_json).
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn put_json<B, T>(&self, url: &str, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send_json(Method::PUT, url, body).await
+    }
+
+    /// Sends `body` as JSON in a [`PATCH`][Method::PATCH] request to `url`, parsing the JSON
+    /// response as `T`.
+    ///
+    /// Fails like [`get_json`](Self::get_json).
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn patch_json<B, T>(&self, url: &str, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send_json(Method::PATCH, url, body).await
+    }
+
+    #[cfg(feature = "json")]
+    async fn send_json<B, T>(&self, method: Method, url: &str, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let request = self
+            .request(method, url)
+            .header("Accept", "application/json")
+            .json(body)?;
+        let response = request.send().await?.error_for_status()?;
+        response.json().await
+    }
+
+    /// Returns the value of a JSON API cached by a previous call right away, and refreshes it in
+    /// the background, _stale-while-revalidate_.
+    ///
+    /// The fresh value is yielded by the returned [`StaleWhileRevalidate`] stream, unless the
+    /// cached one is still current. Responses are kept in the client's [`ValidatorStore`], a
+    /// [`StorageStore`](crate::http::StorageStore) keeps them across reloads, and revalidated
+    /// with their `ETag` or `Last-Modified` header like with [`get_cached`](Self::get_cached).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use futures::StreamExt;
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Client;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Client;
+    /// # use serde::Deserialize;
+    /// #[derive(Deserialize)]
+    /// struct Profile {
+    ///     name: String,
+    /// }
+    ///
+    /// # fn render(_: &Profile) {}
+    /// # async fn no_run() {
+    /// let client = Client::builder().base_url("/api").build();
+    /// let mut profile = client.get_json_swr::<Profile>("/profile");
+    /// if let Some(cached) = profile.cached() {
+    ///     render(cached);
+    /// }
+    /// if let Some(fresh) = profile.next().await {
+    ///     render(&fresh.unwrap());
+    /// }
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn get_json_swr<T: DeserializeOwned + 'static>(
+        &self,
+        url: &str,
+    ) -> StaleWhileRevalidate<T> {
+        StaleWhileRevalidate::new(self.clone(), url)
+    }
+
+    /// Sends a [`GET`][Method::GET] request to `url`, revalidating the response received
+    /// before, if any.
+    ///
+    /// Successful responses with an `ETag` or `Last-Modified` header are kept in the client's
+    /// [`ValidatorStore`]. The next request to the same URL sends them back in `If-None-Match`
+    /// and `If-Modified-Since` headers, and if the server responds with `304 Not Modified`, the
+    /// stored response is returned instead. Responses from the store, and ones which were just
+    /// stored, only have their body and `Content-Type` header.
+    pub async fn get_cached(&self, url: &str) -> Result<Response, Error> {
+        let key = self.resolve(url);
+        let cached = self.validators.get(&key);
+        let mut builder = self.get(url);
+        if let Some(cached) = &cached {
+            for (name, value) in cached.conditional_headers() {
+                builder = builder.header(name, value);
+            }
+        }
+        let response = builder.send().await?;
+        match (response.status(), cached) {
+            (304, Some(cached)) => {
+                trace_event!(debug, url = %key, "not modified");
+                cached.to_response()
+            }
+            (200, _) => match CachedResponse::validators(&response) {
+                Some((etag, last_modified)) => {
+                    let cached = CachedResponse {
+                        etag,
+                        last_modified,
+                        content_type: response.headers().get("Content-Type"),
+                        body: response.binary().await?,
+                    };
+                    let response = cached.to_response()?;
+                    self.validators.set(&key, cached);
+                    Ok(response)
+                }
+                None => {
+                    self.validators.remove(&key);
+                    Ok(response)
+                }
+            },
+            _ => Ok(response),
+        }
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn validator_store(&self) -> &dyn ValidatorStore {
+        &*self.validators
+    }
+
+    pub(crate) fn resolve(&self, url: &str) -> String {
+        match &self.inner.base_url {
+            Some(base_url) => join_url(base_url, url),
+            None => url.to_string(),
+        }
+    }
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.inner, f)
+    }
+}
+
+/// Builder for a [`Client`], created by [`Client::builder`].
+#[derive(Default)]
+pub struct ClientBuilder {
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+    credentials: Option<RequestCredentials>,
+    cache: Option<RequestCache>,
+    middlewares: Vec<Rc<dyn Middleware>>,
+    validators: Option<Rc<dyn ValidatorStore>>,
+}
+
+impl ClientBuilder {
+    /// Sets the URL relative request URLs are appended to.
+    ///
+    /// URLs with a scheme, like `https://example.com/`, are used as they are.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Adds a header sent with every request, unless the request sets it itself.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the default credentials mode of requests.
+    pub fn credentials(mut self, credentials: RequestCredentials) -> Self {
+        self.credentials = Some(credentials);
+        self
+    }
+
+    /// Sets the default cache mode of requests.
+    pub fn cache(mut self, cache: RequestCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Adds a [`Middleware`] every request is sent through.
+    ///
+    /// The client's middlewares run before the ones added to a single request.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middlewares.push(Rc::new(middleware));
+        self
+    }
+
+    /// Retries all requests according to `retry`.
+    ///
+    /// This adds `retry` as a [`middleware`](Self::middleware).
+    pub fn retry(self, retry: Retry) -> Self {
+        self.middleware(retry)
+    }
+
+    /// Limits how often requests are sent according to `rate_limit`.
+    ///
+    /// This adds `rate_limit` as a [`middleware`](Self::middleware).
+    pub fn rate_limit(self, rate_limit: RateLimit) -> Self {
+        self.middleware(rate_limit)
+    }
+
+    /// Sends all requests with the bearer token of `auth`, refreshing it when it expires.
+    ///
+    /// This adds `auth` as a [`middleware`](Self::middleware).
+    pub fn auth(self, auth: Auth) -> Self {
+        self.middleware(auth)
+    }
+
+    /// Signs all requests with `signer`, right before they are sent.
+    ///
+    /// This adds `signer` as a [`middleware`](Self::middleware).
+    pub fn sign(self, signer: Signer) -> Self {
+        self.middleware(signer)
+    }
+
+    /// Sets where [`Client::get_cached`] keeps responses, a [`MemoryStore`] by default.
+    pub fn validator_store(mut self, store: impl ValidatorStore + 'static) -> Self {
+        self.validators = Some(Rc::new(store));
+        self
+    }
+
+    /// Creates the client.
+    pub fn build(mut self) -> Client {
+        let validators = self
+            .validators
+            .take()
+            .unwrap_or_else(|| Rc::new(MemoryStore::new()));
+        Client {
+            inner: Rc::new(self),
+            validators,
+        }
+    }
+}
+
+impl fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Client")
+            .field("base_url", &self.base_url)
+            .field("headers", &self.headers)
+            .field("credentials", &self.credentials)
+            .field("cache", &self.cache)
+            .field("middlewares", &self.middlewares.len())
+            .finish()
+    }
+}
@@ -0,0 +1,194 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::http::browser::Response;
+use crate::Error;
+
+/// A response stored for [`Client::get_cached`](crate::http::Client::get_cached), with the
+/// validators it is revalidated with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedResponse {
+    /// The `ETag` header of the response.
+    pub etag: Option<String>,
+    /// The `Last-Modified` header of the response.
+    pub last_modified: Option<String>,
+    /// The `Content-Type` header of the response.
+    pub content_type: Option<String>,
+    /// The body of the response.
+    pub body: Vec<u8>,
+}
+
+impl CachedResponse {
+    /// The validators of `response`, `None` if it has none.
+    pub(crate) fn validators(response: &Response) -> Option<(Option<String>, Option<String>)> {
+        let headers = response.headers();
+        match (headers.get("ETag"), headers.get("Last-Modified")) {
+            (None, None) => None,
+            validators => Some(validators),
+        }
+    }
+
+    /// The headers making the server respond with `304 Not Modified` if this is still current.
+    pub(crate) fn conditional_headers(&self) -> Vec<(&'static str, &str)> {
+        let mut headers = Vec::new();
+        if let Some(etag) = &self.etag {
+            headers.push(("If-None-Match", etag.as_str()));
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.push(("If-Modified-Since", last_modified.as_str()));
+        }
+        headers
+    }
+
+    /// A `200 OK` response with the stored body and content type.
+    pub(crate) fn to_response(&self) -> Result<Response, Error> {
+        let mut builder = Response::builder();
+        if let Some(content_type) = &self.content_type {
+            builder = builder.header("Content-Type", content_type);
+        }
+        let mut body = self.body.clone();
+        builder.body(Some(body.as_mut_slice()))
+    }
+}
+
+/// Where [`Client::get_cached`](crate::http::Client::get_cached) keeps responses, keyed by
+/// URL.
+///
+/// Clients use a [`MemoryStore`] unless configured with
+/// [`ClientBuilder::validator_store`](crate::http::ClientBuilder::validator_store).
+pub trait ValidatorStore {
+    /// The response stored for `url`.
+    fn get(&self, url: &str) -> Option<CachedResponse>;
+
+    /// Stores `response` for `url`, replacing the previous one.
+    fn set(&self, url: &str, response: CachedResponse);
+
+    /// Removes the response stored for `url`.
+    fn remove(&self, url: &str);
+}
+
+/// A [`ValidatorStore`] keeping responses in memory, for the lifetime of the page.
+#[derive(Default)]
+pub struct MemoryStore {
+    responses: RefCell<HashMap<String, CachedResponse>>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ValidatorStore for MemoryStore {
+    fn get(&self, url: &str) -> Option<CachedResponse> {
+        self.responses.borrow().get(url).cloned()
+    }
+
+    fn set(&self, url: &str, response: CachedResponse) {
+        self.responses
+            .borrow_mut()
+            .insert(url.to_string(), response);
+    }
+
+    fn remove(&self, url: &str) {
+        self.responses.borrow_mut().remove(url);
+    }
+}
+
+impl fmt::Debug for MemoryStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryStore")
+            .field("len", &self.responses.borrow().len())
+            .finish()
+    }
+}
+
+/// A [`ValidatorStore`] keeping responses in local storage, so that they survive reloads.
+///
+/// Responses are stored as JSON under their URL prefixed with the given prefix. Local storage
+/// is small, this is meant for small responses like API results.
+#[cfg(feature = "storage")]
+#[cfg_attr(docsrs, doc(cfg(feature = "storage")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageStore {
+    prefix: String,
+}
+
+#[cfg(feature = "storage")]
+impl StorageStore {
+    /// Creates a store using keys starting with `prefix`.
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+        }
+    }
+
+    fn key(&self, url: &str) -> String {
+        format!("{}{}", self.prefix, url)
+    }
+}
+
+#[cfg(feature = "storage")]
+type StoredResponse = (Option<String>, Option<String>, Option<String>, Vec<u8>);
+
+#[cfg(feature = "storage")]
+impl ValidatorStore for StorageStore {
+    fn get(&self, url: &str) -> Option<CachedResponse> {
+        use gloo_storage::Storage;
+        let (etag, last_modified, content_type, body) =
+            gloo_storage::LocalStorage::get::<StoredResponse>(self.key(url)).ok()?;
+        Some(CachedResponse {
+            etag,
+            last_modified,
+            content_type,
+            body,
+        })
+    }
+
+    fn set(&self, url: &str, response: CachedResponse) {
+        use gloo_storage::Storage;
+        let stored: StoredResponse = (
+            response.etag,
+            response.last_modified,
+            response.content_type,
+            response.body,
+        );
+        // A response which doesn't fit is fetched in full next time.
+        let _ = gloo_storage::LocalStorage::set(self.key(url), stored);
+    }
+
+    fn remove(&self, url: &str) {
+        use gloo_storage::Storage;
+        gloo_storage::LocalStorage::delete(self.key(url));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_store() {
+        let store = MemoryStore::new();
+        let response = CachedResponse {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
+            content_type: None,
+            body: b"[]".to_vec(),
+        };
+        assert_eq!(store.get("/a"), None);
+        store.set("/a", response.clone());
+        assert_eq!(store.get("/a").as_ref(), Some(&response));
+        assert_eq!(
+            response.conditional_headers(),
+            [
+                ("If-None-Match", "\"v1\""),
+                ("If-Modified-Since", "Wed, 21 Oct 2015 07:28:00 GMT")
+            ]
+        );
+        store.remove("/a");
+        assert_eq!(store.get("/a"), None);
+    }
+}
@@ -0,0 +1,193 @@
+//! Reading and writing cookies through `document.cookie`.
+//!
+//! Names and values are percent-encoded when set and decoded when read, so they can contain
+//! any character. Cookies set by the server with `HttpOnly` are not visible here, but are still
+//! sent with requests, see
+//! [`RequestBuilder::with_credentials`](crate::http::RequestBuilder::with_credentials).
+//!
+//! # Example
+//!
+//! ```
+//! # use gloo_net::http::cookies::{self, CookieOptions, SameSite};
+//! # use std::time::Duration;
+//! # fn no_run() {
+//! let options = CookieOptions::new()
+//!     .max_age(Duration::from_secs(30 * 24 * 60 * 60))
+//!     .same_site(SameSite::Strict);
+//! cookies::set("theme", "dark", &options).unwrap();
+//! assert_eq!(cookies::get("theme").as_deref(), Some("dark"));
+//! cookies::delete("theme", &options).unwrap();
+//! # }
+//! ```
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::{js_to_error, Error};
+use wasm_bindgen::JsCast;
+use web_sys::HtmlDocument;
+
+/// The `SameSite` attribute of a cookie, controlling whether it is sent with cross-site
+/// requests.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SameSite {
+    /// Only sent with same-site requests.
+    Strict,
+    /// Also sent when navigating to the site from another one. Browsers default to this.
+    Lax,
+    /// Sent with all requests. Requires [`secure`](CookieOptions::secure).
+    None,
+}
+
+/// The attributes a cookie is set with.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CookieOptions {
+    path: Option<String>,
+    domain: Option<String>,
+    max_age: Option<Duration>,
+    expires: Option<String>,
+    same_site: Option<SameSite>,
+    secure: bool,
+}
+
+impl CookieOptions {
+    /// Creates options for a session cookie on the current path.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the path the cookie is sent for, e.g. `/` for the whole site.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_string());
+        self
+    }
+
+    /// Sets the domain the cookie is sent to, including its subdomains.
+    pub fn domain(mut self, domain: &str) -> Self {
+        self.domain = Some(domain.to_string());
+        self
+    }
+
+    /// Keeps the cookie for `max_age`, instead of until the browser is closed.
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Keeps the cookie until `expires`. [`max_age`](Self::max_age) takes precedence.
+    pub fn expires(mut self, expires: &js_sys::Date) -> Self {
+        self.expires = Some(String::from(expires.to_utc_string()));
+        self
+    }
+
+    /// Sets the `SameSite` attribute.
+    pub fn same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    /// Only sends the cookie over HTTPS.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+}
+
+/// The value of the cookie `name`, `None` if there is none.
+pub fn get(name: &str) -> Option<String> {
+    parse(&cookie_string().ok()?)
+        .into_iter()
+        .find(|(cookie, _)| cookie == name)
+        .map(|(_, value)| value)
+}
+
+/// All cookies visible to the page, as name and value pairs.
+pub fn all() -> Result<Vec<(String, String)>, Error> {
+    Ok(parse(&cookie_string()?))
+}
+
+/// Sets the cookie `name` to `value`.
+///
+/// Browsers silently ignore invalid cookies, such as a `SameSite=None` cookie which isn't
+/// `Secure`.
+pub fn set(name: &str, value: &str, options: &CookieOptions) -> Result<(), Error> {
+    document()?
+        .set_cookie(&format(name, value, options))
+        .map_err(js_to_error)
+}
+
+/// Deletes the cookie `name`.
+///
+/// The path and domain of `options` must be the ones the cookie was set with.
+pub fn delete(name: &str, options: &CookieOptions) -> Result<(), Error> {
+    let options = CookieOptions {
+        max_age: Some(Duration::ZERO),
+        expires: None,
+        ..options.clone()
+    };
+    set(name, "", &options)
+}
+
+fn document() -> Result<HtmlDocument, Error> {
+    web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.dyn_into::<HtmlDocument>().ok())
+        .ok_or_else(|| Error::GlooError("cookies are only available in documents".to_string()))
+}
+
+fn cookie_string() -> Result<String, Error> {
+    document()?.cookie().map_err(js_to_error)
+}
+
+fn encode(component: &str) -> String {
+    String::from(js_sys::encode_uri_component(component))
+}
+
+fn decode(component: &str) -> String {
+    // Cookies set by other code may not be percent-encoded.
+    js_sys::decode_uri_component(component)
+        .map(String::from)
+        .unwrap_or_else(|_| component.to_string())
+}
+
+/// Splits `document.cookie` into decoded name and value pairs.
+fn parse(cookies: &str) -> Vec<(String, String)> {
+    cookies
+        .split(';')
+        .filter_map(|cookie| {
+            let (name, value) = cookie.split_once('=').unwrap_or(("", cookie));
+            let (name, value) = (name.trim(), value.trim());
+            if name.is_empty() && value.is_empty() {
+                return None;
+            }
+            Some((decode(name), decode(value)))
+        })
+        .collect()
+}
+
+/// The string setting the cookie through `document.cookie`.
+fn format(name: &str, value: &str, options: &CookieOptions) -> String {
+    let mut cookie = format!("{}={}", encode(name), encode(value));
+    if let Some(path) = &options.path {
+        let _ = write!(cookie, "; Path={path}");
+    }
+    if let Some(domain) = &options.domain {
+        let _ = write!(cookie, "; Domain={domain}");
+    }
+    if let Some(max_age) = options.max_age {
+        let _ = write!(cookie, "; Max-Age={}", max_age.as_secs());
+    }
+    if let Some(expires) = &options.expires {
+        let _ = write!(cookie, "; Expires={expires}");
+    }
+    match options.same_site {
+        Some(SameSite::Strict) => cookie.push_str("; SameSite=Strict"),
+        Some(SameSite::Lax) => cookie.push_str("; SameSite=Lax"),
+        Some(SameSite::None) => cookie.push_str("; SameSite=None"),
+        None => {}
+    }
+    if options.secure {
+        cookie.push_str("; Secure");
+    }
+    cookie
+}
@@ -0,0 +1,273 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use crate::http::browser::{Client, Request, RequestBuilder, Response};
+use crate::http::Retry;
+use crate::Error;
+use futures_core::Stream;
+use gloo_timers::future::TimeoutFuture;
+
+/// How far a [`Download`] got, passed to its [`on_progress`](Download::on_progress) callback.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// The bytes received so far.
+    pub received: u64,
+    /// The size of the download, if the server sent it.
+    pub total: Option<u64>,
+}
+
+/// Downloads a large file, resuming with `Range` requests when the connection fails.
+///
+/// After a network error, a server error or a body ending before its `Content-Length`, the
+/// download is resumed from the last received byte, with a `Range` header and an `If-Range`
+/// header with the `ETag` or `Last-Modified` date of the first response. The `Content-Range`
+/// of a `206 Partial Content` response has to match what was received so far. If the server
+/// responds with the whole file instead, e.g. because it changed or doesn't support ranges, the
+/// download starts over. Servers responding with `Accept-Ranges: none` are not sent ranges.
+///
+/// Failures are retried according to a [`Retry`] policy, `Retry::new()` by default. Its
+/// attempts count the failures in a row, receiving data resets them.
+///
+/// # Example
+///
+/// ```
+/// # use gloo_net::http::{Download, Retry};
+/// # async fn no_run() {
+/// let model = Download::new("/models/large.bin")
+///     .retry(Retry::new().max_attempts(10))
+///     .on_progress(|progress| {
+///         if let Some(total) = progress.total {
+///             log(progress.received * 100 / total);
+///         }
+///     })
+///     .send()
+///     .await
+///     .unwrap();
+/// # }
+/// # fn log(_: u64) {}
+/// ```
+#[derive(Clone)]
+pub struct Download {
+    url: String,
+    client: Option<Client>,
+    retry: Retry,
+    on_progress: Option<Rc<dyn Fn(DownloadProgress)>>,
+}
+
+impl Download {
+    /// Creates a download of `url`.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            client: None,
+            retry: Retry::new(),
+            on_progress: None,
+        }
+    }
+
+    /// Sends the requests with `client`, e.g. for its base URL and headers.
+    pub fn client(mut self, client: &Client) -> Self {
+        self.client = Some(client.clone());
+        self
+    }
+
+    /// Sets how failures are retried.
+    pub fn retry(mut self, retry: Retry) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Calls `on_progress` whenever data is received.
+    pub fn on_progress(mut self, on_progress: impl Fn(DownloadProgress) + 'static) -> Self {
+        self.on_progress = Some(Rc::new(on_progress));
+        self
+    }
+
+    /// Downloads the file, returning its content.
+    pub async fn send(self) -> Result<Vec<u8>, Error> {
+        let mut state = State::default();
+        let mut failures = 0;
+        loop {
+            let received = state.body.len();
+            let result = self.attempt(&mut state).await;
+            if state.body.len() > received {
+                failures = 0;
+            }
+            let error = match result {
+                Ok(Attempt::Done) => return Ok(state.body),
+                Ok(Attempt::Failed(e)) => e,
+                Err(e @ Error::JsError(_)) => e,
+                Err(e) => return Err(e),
+            };
+            failures += 1;
+            let delay = self.retry.backoff(failures).ok_or(error)?;
+            trace_event!(
+                debug,
+                url = %self.url,
+                received = state.body.len(),
+                "resuming download"
+            );
+            TimeoutFuture::new(u32::try_from(delay.as_millis()).unwrap_or(u32::MAX)).await;
+        }
+    }
+
+    fn request(&self) -> RequestBuilder {
+        match &self.client {
+            Some(client) => client.get(&self.url),
+            None => Request::get(&self.url),
+        }
+    }
+
+    /// Sends one request, appending what it receives to `state.body`.
+    async fn attempt(&self, state: &mut State) -> Result<Attempt, Error> {
+        let offset = state.body.len() as u64;
+        let mut request = self.request();
+        if offset > 0 && state.resumable {
+            request = request.header("Range", &format!("bytes={offset}-"));
+            if let Some(validator) = &state.validator {
+                request = request.header("If-Range", validator);
+            }
+        }
+        let response = request.send().await?;
+        match response.status() {
+            200 => {
+                state.start(&response);
+                self.progress(state);
+            }
+            206 => {
+                let content_range = response.headers().get("Content-Range");
+                match content_range.as_deref().and_then(content_range_of) {
+                    Some((start, total)) if start == offset && state.total_is(total) => {
+                        state.total = total.or(state.total);
+                    }
+                    _ => {
+                        return Err(Error::GlooError(format!(
+                            "unexpected Content-Range {:?} when resuming the download of {}",
+                            content_range, self.url
+                        )))
+                    }
+                }
+            }
+            416 if state.total == Some(offset) => return Ok(Attempt::Done),
+            status => {
+                let error = Error::Status {
+                    status,
+                    url: response.url(),
+                };
+                return if self.retry.retries_status(status) {
+                    Ok(Attempt::Failed(error))
+                } else {
+                    Err(error)
+                };
+            }
+        }
+
+        let mut body = response.body_stream();
+        while let Some(chunk) = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await {
+            state.body.extend_from_slice(&chunk?);
+            self.progress(state);
+        }
+        match state.total {
+            Some(total) if (state.body.len() as u64) < total => Ok(Attempt::Failed(
+                Error::GlooError(format!("download of {} ended early", self.url)),
+            )),
+            _ => Ok(Attempt::Done),
+        }
+    }
+
+    fn progress(&self, state: &State) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(DownloadProgress {
+                received: state.body.len() as u64,
+                total: state.total,
+            });
+        }
+    }
+}
+
+impl fmt::Debug for Download {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Download")
+            .field("url", &self.url)
+            .field("client", &self.client)
+            .field("retry", &self.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+enum Attempt {
+    Done,
+    /// A failure worth trying again.
+    Failed(Error),
+}
+
+#[derive(Default)]
+struct State {
+    body: Vec<u8>,
+    total: Option<u64>,
+    validator: Option<String>,
+    resumable: bool,
+}
+
+impl State {
+    /// Starts over with the full `response`.
+    fn start(&mut self, response: &Response) {
+        let headers = response.headers();
+        self.body.clear();
+        // The length of an encoded body isn't the length of the decoded data.
+        self.total = match headers.get("Content-Encoding") {
+            None => headers.content_length(),
+            Some(_) => None,
+        };
+        let etag = headers.get("ETag").filter(|etag| !etag.starts_with("W/"));
+        self.validator = etag.or_else(|| headers.get("Last-Modified"));
+        self.resumable = !headers
+            .get("Accept-Ranges")
+            .is_some_and(|ranges| ranges.eq_ignore_ascii_case("none"));
+    }
+
+    /// Whether `total` is consistent with the size known so far.
+    fn total_is(&self, total: Option<u64>) -> bool {
+        match (self.total, total) {
+            (Some(known), Some(total)) => known == total,
+            _ => true,
+        }
+    }
+}
+
+/// The first byte and the complete length of a `Content-Range: bytes start-end/length` header.
+fn content_range_of(value: &str) -> Option<(u64, Option<u64>)> {
+    let range = value.trim().strip_prefix("bytes ")?;
+    let (range, total) = range.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let (start, end) = (start.parse::<u64>().ok()?, end.parse::<u64>().ok()?);
+    if end < start {
+        return None;
+    }
+    let total = match total {
+        "*" => None,
+        total => Some(total.parse::<u64>().ok().filter(|total| *total > end)?),
+    };
+    Some((start, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_ranges() {
+        assert_eq!(
+            content_range_of("bytes 100-199/1000"),
+            Some((100, Some(1000)))
+        );
+        assert_eq!(content_range_of("bytes 0-0/*"), Some((0, None)));
+        assert_eq!(content_range_of("bytes 100-99/1000"), None);
+        assert_eq!(content_range_of("bytes 100-1000/1000"), None);
+        assert_eq!(content_range_of("bytes */1000"), None);
+        assert_eq!(content_range_of("items 0-1/2"), None);
+    }
+}
@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+/// The essence of a media type, `None` if it isn't of the form `type/subtype`.
+pub(crate) fn media_type(value: &str) -> Option<String> {
+    let essence = value.split(';').next().unwrap_or_default().trim();
+    match essence.split_once('/') {
+        Some((type_, subtype)) if !type_.is_empty() && !subtype.is_empty() => {
+            Some(essence.to_ascii_lowercase())
+        }
+        _ => None,
+    }
+}
+
+/// The directives of a `Cache-Control` header, returned by
+/// [`Headers::cache_control`](crate::http::Headers::cache_control).
+///
+/// Unknown directives are ignored, as are invalid values of known ones.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CacheControl {
+    /// `max-age`: how long the response stays fresh.
+    pub max_age: Option<Duration>,
+    /// `s-maxage`: how long the response stays fresh in shared caches.
+    pub s_max_age: Option<Duration>,
+    /// `stale-while-revalidate`: how long a stale response may be used while it is revalidated.
+    pub stale_while_revalidate: Option<Duration>,
+    /// `no-cache`: the response has to be revalidated before every use.
+    pub no_cache: bool,
+    /// `no-store`: the response must not be stored.
+    pub no_store: bool,
+    /// `no-transform`: intermediaries must not change the response.
+    pub no_transform: bool,
+    /// `must-revalidate`: a stale response must not be used without revalidating it.
+    pub must_revalidate: bool,
+    /// `private`: only the browser may store the response, not shared caches.
+    pub private: bool,
+    /// `public`: any cache may store the response.
+    pub public: bool,
+    /// `immutable`: the response doesn't change while it is fresh.
+    pub immutable: bool,
+}
+
+impl CacheControl {
+    /// Parses the value of a `Cache-Control` header.
+    pub fn parse(value: &str) -> Self {
+        let mut cache_control = Self::default();
+        for directive in value.split(',') {
+            let directive = directive.trim().to_ascii_lowercase();
+            let (name, argument) = match directive.split_once('=') {
+                Some((name, argument)) => (name.trim(), Some(argument.trim().trim_matches('"'))),
+                None => (directive.as_str(), None),
+            };
+            let seconds = argument
+                .and_then(|seconds| seconds.parse().ok())
+                .map(Duration::from_secs);
+            match name {
+                "max-age" => cache_control.max_age = seconds,
+                "s-maxage" => cache_control.s_max_age = seconds,
+                "stale-while-revalidate" => cache_control.stale_while_revalidate = seconds,
+                "no-cache" => cache_control.no_cache = true,
+                "no-store" => cache_control.no_store = true,
+                "no-transform" => cache_control.no_transform = true,
+                "must-revalidate" => cache_control.must_revalidate = true,
+                "private" => cache_control.private = true,
+                "public" => cache_control.public = true,
+                "immutable" => cache_control.immutable = true,
+                _ => {}
+            }
+        }
+        cache_control
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn media_types() {
+        assert_eq!(
+            media_type("Application/JSON; charset=utf-8").as_deref(),
+            Some("application/json")
+        );
+        assert_eq!(media_type("text/plain").as_deref(), Some("text/plain"));
+        assert_eq!(media_type("text"), None);
+        assert_eq!(media_type("/plain"), None);
+    }
+
+    #[test]
+    fn cache_control() {
+        let cache_control =
+            CacheControl::parse("Public, max-age=\"600\", stale-while-revalidate=30, immutable");
+        assert_eq!(cache_control.max_age, Some(Duration::from_secs(600)));
+        assert_eq!(
+            cache_control.stale_while_revalidate,
+            Some(Duration::from_secs(30))
+        );
+        assert!(cache_control.public && cache_control.immutable);
+        assert!(!cache_control.no_store);
+
+        let cache_control = CacheControl::parse("no-store, max-age=soon");
+        assert!(cache_control.no_store);
+        assert_eq!(cache_control.max_age, None);
+        assert_eq!(CacheControl::parse(""), CacheControl::default());
+    }
+}
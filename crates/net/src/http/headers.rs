@@ -1,11 +1,18 @@
 use gloo_utils::iter::UncheckedIter;
 use js_sys::{Array, Map};
 use std::fmt;
+use std::iter::FromIterator;
 use wasm_bindgen::{JsCast, UnwrapThrowExt};
 
+use crate::http::header_values::media_type;
+use crate::http::CacheControl;
+
 // I experimented with using `js_sys::Object` for the headers, since this object is marked
 // experimental in MDN. However it's in the fetch spec, and it's necessary for appending headers.
 /// A wrapper around `web_sys::Headers`.
+///
+/// Header names are case-insensitive, and iterated over in lowercase. Besides the raw values,
+/// common headers can be read parsed, like [`content_length`](Self::content_length).
 pub struct Headers {
     raw: web_sys::Headers,
 }
@@ -91,6 +98,47 @@ impl Headers {
         let fake_map: &Map = self.raw.unchecked_ref();
         UncheckedIter::from(fake_map.values()).map(|v| v.as_string().unwrap_throw())
     }
+
+    /// The media type of the `Content-Type` header in lowercase, without parameters like the
+    /// charset, e.g. `application/json`.
+    pub fn content_type(&self) -> Option<String> {
+        self.get("Content-Type")
+            .and_then(|value| media_type(&value))
+    }
+
+    /// The `Content-Length` header, `None` if it is missing or invalid.
+    pub fn content_length(&self) -> Option<u64> {
+        self.get("Content-Length")?.trim().parse().ok()
+    }
+
+    /// The parsed `Cache-Control` header, `None` if it is missing.
+    pub fn cache_control(&self) -> Option<CacheControl> {
+        self.get("Cache-Control")
+            .map(|value| CacheControl::parse(&value))
+    }
+}
+
+impl From<web_sys::Headers> for Headers {
+    fn from(raw: web_sys::Headers) -> Self {
+        Self::from_raw(raw)
+    }
+}
+
+impl From<Headers> for web_sys::Headers {
+    fn from(headers: Headers) -> Self {
+        headers.into_raw()
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> FromIterator<(K, V)> for Headers {
+    /// Creates headers from name and value pairs, appending the values of repeated names.
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let headers = Headers::new();
+        for (name, value) in iter {
+            headers.append(name.as_ref(), value.as_ref());
+        }
+        headers
+    }
 }
 
 impl fmt::Debug for Headers {
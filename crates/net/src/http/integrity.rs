@@ -0,0 +1,139 @@
+use js_sys::{Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+use crate::{js_to_error, Error};
+
+/// Checks `body` against `integrity`, a
+/// [subresource integrity](https://developer.mozilla.org/en-US/docs/Web/Security/Subresource_Integrity)
+/// value like `sha384-<base64 digest>`, with `SubtleCrypto`.
+///
+/// This is the check browsers do for [`RequestBuilder::integrity`](crate::http::RequestBuilder::integrity),
+/// for bytes which don't come from a request with that option, e.g. a cached or streamed
+/// WebAssembly module about to be instantiated. Like browsers, only the strongest of the
+/// `sha256`, `sha384` and `sha512` digests listed is compared, and unknown algorithms are
+/// ignored.
+///
+/// Fails with [`Error::IntegrityMismatch`] if the digest doesn't match. `SubtleCrypto` is only
+/// available in secure contexts, served over HTTPS or from `localhost`.
+///
+/// # Example
+///
+/// ```
+/// # use gloo_net::http::{verify_integrity, Request};
+/// # async fn no_run() -> Result<(), gloo_net::Error> {
+/// let integrity = "sha384-oqVuAfXRKap7fdgcCY5uykM6+R9GqQ8K/uxy9rx7HNQlGYl1kPzQho1wx4JwY8wC";
+/// let module = Request::get("/plugin.wasm").send().await?.binary().await?;
+/// verify_integrity(&module, integrity).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn verify_integrity(body: &[u8], integrity: &str) -> Result<(), Error> {
+    let (algorithm, expected) = strongest_digests(integrity).ok_or_else(|| {
+        Error::GlooError(format!("no supported digest in integrity `{integrity}`"))
+    })?;
+    let crypto = Reflect::get(&js_sys::global(), &JsValue::from_str("crypto"))
+        .map_err(js_to_error)?
+        .dyn_into::<web_sys::Crypto>()
+        .map_err(|_| Error::GlooError("`crypto` is not available".to_string()))?;
+    let promise = crypto
+        .subtle()
+        .digest_with_str_and_u8_array(algorithm.name(), body)
+        .map_err(js_to_error)?;
+    let digest = JsFuture::from(promise).await.map_err(js_to_error)?;
+    let digest = base64(&Uint8Array::new(&digest).to_vec());
+    if expected.contains(&digest.as_str()) {
+        Ok(())
+    } else {
+        Err(Error::IntegrityMismatch {
+            integrity: integrity.to_string(),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Algorithm {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    fn name(self) -> &'static str {
+        match self {
+            Algorithm::Sha256 => "SHA-256",
+            Algorithm::Sha384 => "SHA-384",
+            Algorithm::Sha512 => "SHA-512",
+        }
+    }
+}
+
+/// The strongest algorithm in `integrity`, with the base64 digests given for it.
+fn strongest_digests(integrity: &str) -> Option<(Algorithm, Vec<&str>)> {
+    let mut strongest: Option<(Algorithm, Vec<&str>)> = None;
+    for token in integrity.split_ascii_whitespace() {
+        // Options after a `?` are reserved, and ignored.
+        let token = token.split('?').next().unwrap_or(token);
+        let (algorithm, digest) = match token.split_once('-') {
+            Some(("sha256", digest)) => (Algorithm::Sha256, digest),
+            Some(("sha384", digest)) => (Algorithm::Sha384, digest),
+            Some(("sha512", digest)) => (Algorithm::Sha512, digest),
+            _ => continue,
+        };
+        match &mut strongest {
+            Some((strongest, digests)) if *strongest == algorithm => digests.push(digest),
+            Some((strongest, _)) if *strongest > algorithm => {}
+            _ => strongest = Some((algorithm, vec![digest])),
+        }
+    }
+    strongest
+}
+
+/// Standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | u32::from(byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(char::from(ALPHABET[(n >> (18 - 6 * i) & 63) as usize]));
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_the_strongest_digests() {
+        assert_eq!(
+            strongest_digests("sha256-a sha512-b md5-c sha512-d?opt sha384-e"),
+            Some((Algorithm::Sha512, vec!["b", "d"]))
+        );
+        assert_eq!(
+            strongest_digests(" sha256-a\tsha256-b "),
+            Some((Algorithm::Sha256, vec!["a", "b"]))
+        );
+        assert_eq!(strongest_digests("md5-a sha1-b"), None);
+        assert_eq!(strongest_digests(""), None);
+    }
+
+    #[test]
+    fn encodes_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(base64(&[0xfb, 0xff]), "+/8=");
+    }
+}
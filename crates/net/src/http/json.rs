@@ -0,0 +1,75 @@
+use crate::Error;
+use serde::de::DeserializeOwned;
+
+/// Parses `text` as JSON, reporting where parsing failed with the `json-path` feature.
+pub(crate) fn from_json<T: DeserializeOwned>(text: &str) -> Result<T, Error> {
+    #[cfg(feature = "json-path")]
+    {
+        let deserializer = &mut serde_json::Deserializer::from_str(text);
+        serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let path = e.path().to_string();
+            let source = e.into_inner();
+            Error::JsonPathError {
+                path,
+                snippet: snippet(text, source.line(), source.column()),
+                source,
+            }
+        })
+    }
+    #[cfg(not(feature = "json-path"))]
+    serde_json::from_str(text).map_err(Error::from)
+}
+
+/// Up to 20 characters of `text` on each side of the 1-based `line` and `column`.
+#[cfg(feature = "json-path")]
+fn snippet(text: &str, line: usize, column: usize) -> String {
+    const CONTEXT: usize = 20;
+    let line = text.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+    let mut column = column.min(line.len());
+    while !line.is_char_boundary(column) {
+        column -= 1;
+    }
+    let start = line[..column]
+        .char_indices()
+        .rev()
+        .nth(CONTEXT - 1)
+        .map_or(0, |(index, _)| index);
+    let end = line[column..]
+        .char_indices()
+        .nth(CONTEXT)
+        .map_or(line.len(), |(index, _)| column + index);
+    line[start..end].to_string()
+}
+
+#[cfg(all(test, feature = "json-path"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_path_errors() {
+        #[derive(serde::Deserialize, Debug)]
+        #[allow(dead_code)]
+        struct User {
+            name: String,
+            age: u8,
+        }
+
+        let json = r#"{"users": [{"name": "a", "age": 1}, {"name": "b", "age": "two"}]}"#;
+        let error = from_json::<std::collections::HashMap<String, Vec<User>>>(json).unwrap_err();
+        match error {
+            Error::JsonPathError { path, snippet, .. } => {
+                assert_eq!(path, "users[1].age");
+                assert_eq!(snippet, r#"": "b", "age": "two"}]}"#);
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+    }
+
+    #[test]
+    fn snippets() {
+        assert_eq!(snippet("{\n  \"a\": x\n}", 2, 8), "  \"a\": x");
+        assert_eq!(snippet("", 1, 1), "");
+        let long = "é".repeat(50);
+        assert_eq!(snippet(&long, 1, 50), "é".repeat(40));
+    }
+}
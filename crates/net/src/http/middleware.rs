@@ -0,0 +1,95 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use crate::http::browser::{Request, Response};
+use crate::http::request::fetch;
+use crate::Error;
+
+/// The future returned by a [`Middleware`].
+pub type MiddlewareFuture = Pin<Box<dyn Future<Output = Result<Response, Error>>>>;
+
+/// Code running around sending a [`Request`], e.g. to add headers, log requests or retry them.
+///
+/// A middleware receives the request and the [`Next`] part of the chain, which it sends the
+/// request to in order to get a response. It can change the request before and the response
+/// after, answer without sending the request, or send it more than once. Middlewares run in the
+/// order they were added, the last one is closest to `fetch`.
+///
+/// This is implemented for `async` closures taking a `Request` and a `Next`.
+///
+/// # Example
+///
+/// ```
+/// # use gloo_net::http::Next;
+/// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+/// # use gloo_net::http::browser::Request;
+/// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+/// # use gloo_net::http::Request;
+/// # async fn no_run() {
+/// let resp = Request::get("/api/users")
+///     .middleware(|request: Request, next: Next| async move {
+///         request.headers().set("Authorization", "Bearer token");
+///         next.run(request).await
+///     })
+///     .send()
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+pub trait Middleware {
+    /// Handles `request`, usually by passing it on to `next`.
+    fn handle(&self, request: Request, next: Next) -> MiddlewareFuture;
+}
+
+impl<F, Fut> Middleware for F
+where
+    F: Fn(Request, Next) -> Fut,
+    Fut: Future<Output = Result<Response, Error>> + 'static,
+{
+    fn handle(&self, request: Request, next: Next) -> MiddlewareFuture {
+        Box::pin(self(request, next))
+    }
+}
+
+/// The rest of a middleware chain, ending with sending the request with `fetch`.
+///
+/// It can be cloned to run the rest of the chain more than once.
+#[derive(Clone)]
+pub struct Next {
+    middlewares: Rc<[Rc<dyn Middleware>]>,
+    index: usize,
+}
+
+impl Next {
+    pub(crate) fn new(middlewares: Rc<[Rc<dyn Middleware>]>) -> Self {
+        Self {
+            middlewares,
+            index: 0,
+        }
+    }
+
+    /// Runs the rest of the chain for `request`.
+    pub async fn run(self, request: Request) -> Result<Response, Error> {
+        match self.middlewares.get(self.index) {
+            Some(middleware) => {
+                let middleware = Rc::clone(middleware);
+                let next = Next {
+                    middlewares: self.middlewares,
+                    index: self.index + 1,
+                };
+                middleware.handle(request, next).await
+            }
+            None => fetch(request.into()).await,
+        }
+    }
+}
+
+impl fmt::Debug for Next {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("remaining", &(self.middlewares.len() - self.index))
+            .finish()
+    }
+}
@@ -0,0 +1,247 @@
+//! Canned responses in place of `fetch`, for testing code sending [`Request`]s without a server.
+//!
+//! While a [`MockServer`] is installed, every request sent on the current thread is answered
+//! by the first of its routes matching the request's method and URL, instead of being sent over
+//! the network. Requests matching no route fail. Middlewares, timeouts and aborting still apply.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::http::mock::{MockResponse, MockServer};
+//! use gloo_net::http::{Method, Request};
+//! use std::time::Duration;
+//!
+//! # async fn no_run() {
+//! let server = MockServer::install();
+//! server
+//!     .on(Method::GET, "/api/users", MockResponse::new(200).text("[]"))
+//!     .on(Method::POST, "/api/users", MockResponse::network_error())
+//!     .on(Method::GET, "/api/slow", MockResponse::new(204).delay(Duration::from_secs(1)));
+//!
+//! let resp = Request::get("/api/users").send().await.unwrap();
+//! assert_eq!(resp.text().await.unwrap(), "[]");
+//! assert!(Request::post("/api/users").send().await.is_err());
+//! assert_eq!(server.requests().len(), 2);
+//! # }
+//! ```
+//!
+//! [`Request`]: crate::http::Request
+
+use std::cell::RefCell;
+use std::convert::TryInto;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::http::browser::Response;
+use crate::http::Method;
+use crate::{js_to_error, Error};
+use gloo_timers::future::TimeoutFuture;
+
+thread_local! {
+    static INSTALLED: RefCell<Option<Rc<RefCell<Routes>>>> = const { RefCell::new(None) };
+}
+
+#[derive(Default)]
+struct Routes {
+    routes: Vec<(Method, String, MockResponse)>,
+    requests: Vec<(Method, String)>,
+}
+
+/// Answers requests with canned responses while it is alive, see the [module docs](self).
+///
+/// Installing a server replaces the one installed before. Dropping it restores `fetch`.
+pub struct MockServer {
+    routes: Rc<RefCell<Routes>>,
+}
+
+impl MockServer {
+    /// Installs a server without routes, making all requests fail until routes are added.
+    pub fn install() -> Self {
+        let routes = Rc::new(RefCell::new(Routes::default()));
+        INSTALLED.with(|installed| *installed.borrow_mut() = Some(Rc::clone(&routes)));
+        Self { routes }
+    }
+
+    /// Answers requests with `method` to `url` with `response`.
+    ///
+    /// `url` is either absolute or a path. A URL without a query matches requests with any
+    /// query. Routes added first take precedence.
+    pub fn on(&self, method: Method, url: &str, response: MockResponse) -> &Self {
+        self.routes
+            .borrow_mut()
+            .routes
+            .push((method, url.to_string(), response));
+        self
+    }
+
+    /// The method and URL of every request received so far, in order.
+    pub fn requests(&self) -> Vec<(Method, String)> {
+        self.routes.borrow().requests.clone()
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        INSTALLED.with(|installed| {
+            let mut installed = installed.borrow_mut();
+            // A server installed after this one stays.
+            if installed
+                .as_ref()
+                .is_some_and(|routes| Rc::ptr_eq(routes, &self.routes))
+            {
+                *installed = None;
+            }
+        });
+    }
+}
+
+impl fmt::Debug for MockServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let routes = self.routes.borrow();
+        f.debug_struct("MockServer")
+            .field("routes", &routes.routes.len())
+            .field("requests", &routes.requests.len())
+            .finish()
+    }
+}
+
+/// A canned response for a [`MockServer`] route.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    delay: Option<Duration>,
+    network_error: bool,
+}
+
+impl MockResponse {
+    /// A response with `status` and an empty body.
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+            delay: None,
+            network_error: false,
+        }
+    }
+
+    /// Fails the request like `fetch` does when the server can't be reached.
+    pub fn network_error() -> Self {
+        Self {
+            network_error: true,
+            ..Self::new(0)
+        }
+    }
+
+    /// Adds a header.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Sets a text body.
+    pub fn text(self, text: &str) -> Self {
+        self.body(text)
+    }
+
+    /// Sets a JSON body and the `Content-Type` header to `application/json`.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json<T: serde::Serialize + ?Sized>(self, value: &T) -> Result<Self, Error> {
+        let json = serde_json::to_vec(value)?;
+        Ok(self.header("Content-Type", "application/json").body(json))
+    }
+
+    /// Waits for `delay` before answering, e.g. to test timeouts or loading states.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    async fn respond(mut self, request: &web_sys::Request) -> Result<Response, Error> {
+        if let Some(delay) = self.delay {
+            TimeoutFuture::new(delay.as_millis().try_into().unwrap_or(u32::MAX)).await;
+        }
+        if request.signal().aborted() {
+            return Err(Error::Aborted);
+        }
+        if self.network_error {
+            return Err(js_to_error(
+                js_sys::TypeError::new("Failed to fetch (mocked network error)").into(),
+            ));
+        }
+        let mut builder = Response::builder().status(self.status);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        let body = Some(self.body.as_mut_slice()).filter(|body| !body.is_empty());
+        builder.body(body)
+    }
+}
+
+/// The installed server's response to `request`, `None` if no server is installed.
+pub(crate) async fn respond(request: &web_sys::Request) -> Option<Result<Response, Error>> {
+    let routes = INSTALLED.with(|installed| installed.borrow().clone())?;
+    let method = Method::from_str(&request.method()).unwrap_or_default();
+    let url = request.url();
+    let response = {
+        let mut routes = routes.borrow_mut();
+        routes.requests.push((method.clone(), url.clone()));
+        routes
+            .routes
+            .iter()
+            .find(|(route_method, route, _)| *route_method == method && matches(route, &url))
+            .map(|(_, _, response)| response.clone())
+    };
+    Some(match response {
+        Some(response) => response.respond(request).await,
+        None => Err(Error::GlooError(format!(
+            "no mock response for {method} {url}"
+        ))),
+    })
+}
+
+/// Whether the route `route` matches the absolute URL `url`.
+fn matches(route: &str, url: &str) -> bool {
+    let url = url.split('#').next().unwrap_or_default();
+    let path = match url.split_once("://") {
+        Some((_, rest)) => rest.find('/').map_or("/", |start| &rest[start..]),
+        None => url,
+    };
+    let candidates = [url, path];
+    if route.contains('?') {
+        candidates.contains(&route)
+    } else {
+        candidates
+            .iter()
+            .any(|candidate| candidate.split('?').next() == Some(route))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_routes() {
+        let url = "http://localhost:8000/api/users?page=2";
+        assert!(matches("/api/users", url));
+        assert!(matches("/api/users?page=2", url));
+        assert!(matches("http://localhost:8000/api/users", url));
+        assert!(matches(url, url));
+        assert!(!matches("/api/users?page=3", url));
+        assert!(!matches("/api", url));
+        assert!(!matches("/api/users/1", url));
+        assert!(matches("/", "http://localhost:8000"));
+    }
+}
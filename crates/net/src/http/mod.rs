@@ -1,5 +1,18 @@
 //! Wrapper around the `fetch` API.
 //!
+//! Requests can be sent from windows as well as from dedicated, shared and service workers, e.g.
+//! the ones of `gloo-worker`, using the `fetch` of the global scope.
+//!
+//! With the `native-http` feature, a [`reqwest`](https://docs.rs/reqwest) based implementation
+//! replaces the browser one on targets other than `wasm32`, so crates sharing code between the
+//! browser and native binaries, desktop apps or tests don't need to `cfg` their requests. Only
+//! the core API of [`Request`], [`RequestBuilder`], [`Response`], [`Headers`] and [`Client`] is
+//! native, the rest of this module is still compiled but built on their browser counterparts,
+//! which are moved to `http::browser`. Native requests need to be sent from within a tokio
+//! runtime. `https://` URLs
+//! require one of the TLS features of `reqwest` to be enabled, e.g. `native-tls` or
+//! `rustls-tls`.
+//!
 //! # Example
 //!
 //! ```
@@ -13,15 +26,114 @@
 //! # }
 //! ```
 
+mod abort;
+#[cfg(feature = "macros")]
+mod api;
+mod auth;
+mod batch;
+mod body;
+mod cache;
+mod client;
+mod conditional;
+pub mod cookies;
+mod download;
+mod header_values;
 mod headers;
+mod integrity;
+#[cfg(feature = "json")]
+mod json;
+mod middleware;
+#[cfg(feature = "test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub mod mock;
+mod multipart;
+#[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+mod native;
+#[cfg(feature = "json")]
+mod paginate;
 mod query;
+mod rate_limit;
 mod request;
 mod response;
+mod retry;
+mod sign;
+mod sse;
+#[cfg(feature = "json")]
+mod swr;
+#[cfg(feature = "tower")]
+mod tower;
+mod url;
+mod xhr;
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private {
+    pub use super::api::*;
+}
 
+/// The browser [`Client`], [`Request`], [`Response`] and [`Headers`], which the native backend
+/// replaces.
+///
+/// The other items of this module, e.g. [`Retry`] or [`Middleware`], work with these and stay
+/// available with the `native-http` feature, so code naming them still compiles, but their
+/// requests are only sent in browsers.
+#[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "native-http")))]
+pub mod browser {
+    pub use super::client::{Client, ClientBuilder};
+    pub use super::headers::Headers;
+    pub use super::request::{Request, RequestBuilder};
+    pub use super::response::Response;
+}
+#[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+pub(crate) mod browser {
+    pub use super::client::Client;
+    pub use super::headers::Headers;
+    pub use super::request::{Request, RequestBuilder};
+    pub use super::response::Response;
+}
+
+pub use abort::{AbortHandle, PendingRequest};
+pub use auth::Auth;
+pub use batch::SendAll;
+#[cfg(feature = "json")]
+pub use body::NdJson;
+pub use body::{BodyStream, Compression, TextLines};
+pub use cache::HttpCache;
+#[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+pub use client::{Client, ClientBuilder};
+#[cfg(feature = "storage")]
+pub use conditional::StorageStore;
+pub use conditional::{CachedResponse, MemoryStore, ValidatorStore};
+pub use download::{Download, DownloadProgress};
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use gloo_net_macros::gloo_api;
+pub use header_values::CacheControl;
+#[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
 pub use headers::Headers;
 #[doc(inline)]
 pub use http::Method;
+pub use integrity::verify_integrity;
+pub use middleware::{Middleware, MiddlewareFuture, Next};
+pub use multipart::Multipart;
+#[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+pub use native::{Client, ClientBuilder, Headers, Request, RequestBuilder, Response};
+#[cfg(feature = "json")]
+pub use paginate::Paginated;
 pub use query::QueryParams;
-
-pub use request::Request;
-pub use response::{IntoRawResponse, Response};
+pub use rate_limit::RateLimit;
+pub use request::RequestPriority;
+#[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+pub use request::{Request, RequestBuilder};
+#[cfg(feature = "json")]
+pub use response::ApiError;
+#[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+pub use response::Response;
+pub use response::{IntoRawResponse, OpaqueResponse};
+pub use retry::Retry;
+pub use sign::{Signer, SigningRequest};
+pub use sse::{SseEvent, SseStream};
+#[cfg(feature = "json")]
+pub use swr::StaleWhileRevalidate;
+pub use xhr::UploadProgress;
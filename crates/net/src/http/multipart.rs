@@ -0,0 +1,103 @@
+use std::fmt;
+
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::UnwrapThrowExt;
+use web_sys::{Blob, BlobPropertyBag, File, FormData};
+
+/// A `multipart/form-data` body, sent with
+/// [`RequestBuilder::multipart`](crate::http::RequestBuilder::multipart).
+///
+/// The browser encodes the parts and sets the `Content-Type` header with the boundary. `Blob`
+/// and `File` parts are read while the request is sent, so a large file picked by the user is
+/// uploaded without being loaded into memory.
+///
+/// # Example
+///
+/// ```
+/// # use gloo_net::http::Multipart;
+/// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+/// # use gloo_net::http::browser::Request;
+/// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+/// # use gloo_net::http::Request;
+/// # async fn no_run(file: web_sys::File) {
+/// let form = Multipart::new()
+///     .text("title", "Holiday")
+///     .file("photo", &file)
+///     .bytes("meta", br#"{"tags":[]}"#, "meta.json", "application/json");
+/// let resp = Request::post("/upload").multipart(form).unwrap().send().await.unwrap();
+/// # }
+/// ```
+pub struct Multipart {
+    form: FormData,
+}
+
+impl Default for Multipart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Multipart {
+    /// Creates an empty body.
+    pub fn new() -> Self {
+        Self {
+            // The constructor only throws for invalid form elements.
+            form: FormData::new().unwrap_throw(),
+        }
+    }
+
+    /// Adds a text field.
+    pub fn text(self, name: &str, value: &str) -> Self {
+        self.form.append_with_str(name, value).unwrap_throw();
+        self
+    }
+
+    /// Adds a file part with the name and content type of `file`.
+    pub fn file(self, name: &str, file: &File) -> Self {
+        self.form.append_with_blob(name, file).unwrap_throw();
+        self
+    }
+
+    /// Adds a file part with the content of `blob`, e.g. a slice of a file.
+    ///
+    /// The part's content type is the one of the blob, `application/octet-stream` if it has
+    /// none.
+    pub fn blob(self, name: &str, blob: &Blob, filename: &str) -> Self {
+        self.form
+            .append_with_blob_and_filename(name, blob, filename)
+            .unwrap_throw();
+        self
+    }
+
+    /// Adds a file part with the given content and content type.
+    pub fn bytes(self, name: &str, bytes: &[u8], filename: &str, content_type: &str) -> Self {
+        let options = BlobPropertyBag::new();
+        options.set_type(content_type);
+        let parts = Array::of1(&Uint8Array::from(bytes));
+        let blob = Blob::new_with_u8_array_sequence_and_options(&parts, &options).unwrap_throw();
+        self.blob(name, &blob, filename)
+    }
+
+    /// The underlying `web_sys::FormData`.
+    pub fn as_raw(&self) -> &FormData {
+        &self.form
+    }
+}
+
+impl From<FormData> for Multipart {
+    fn from(form: FormData) -> Self {
+        Self { form }
+    }
+}
+
+impl From<Multipart> for FormData {
+    fn from(multipart: Multipart) -> Self {
+        multipart.form
+    }
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart").finish_non_exhaustive()
+    }
+}
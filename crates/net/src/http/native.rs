@@ -0,0 +1,773 @@
+//! A `reqwest` backed implementation of the HTTP API for native targets, see the
+//! [module docs](crate::http).
+use std::cell::RefCell;
+use std::fmt;
+use std::iter::FromIterator;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::http::header_values::media_type;
+use crate::http::url::join_url;
+use crate::http::{CacheControl, Method};
+use crate::Error;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+#[cfg(feature = "json")]
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The headers of a request or response, with the same interface as the browser based
+/// `Headers`.
+///
+/// Header names are case-insensitive, and iterated over in lowercase.
+#[derive(Default)]
+pub struct Headers {
+    raw: RefCell<HeaderMap>,
+}
+
+impl Headers {
+    /// Create a new empty headers object.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Build [Headers] from a `reqwest` [`HeaderMap`].
+    pub fn from_raw(raw: HeaderMap) -> Self {
+        Self {
+            raw: RefCell::new(raw),
+        }
+    }
+
+    /// Covert [Headers] to a `reqwest` [`HeaderMap`].
+    pub fn into_raw(self) -> HeaderMap {
+        self.raw.into_inner()
+    }
+
+    /// This method appends a new value onto an existing header, or adds the header if it does not
+    /// already exist.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` or `value` isn't valid in a header, like in the browser.
+    pub fn append(&self, name: &str, value: &str) {
+        let (name, value) = header(name, value);
+        self.raw.borrow_mut().append(name, value);
+    }
+
+    /// Deletes a header if it is present.
+    pub fn delete(&self, name: &str) {
+        self.raw.borrow_mut().remove(name);
+    }
+
+    /// Gets a header if it is present, with the values of repeated headers joined by `, `.
+    pub fn get(&self, name: &str) -> Option<String> {
+        let raw = self.raw.borrow();
+        let values: Vec<_> = raw.get_all(name).iter().map(value_string).collect();
+        Some(values.join(", ")).filter(|_| !values.is_empty())
+    }
+
+    /// Whether a header with the given name exists.
+    pub fn has(&self, name: &str) -> bool {
+        self.raw.borrow().contains_key(name)
+    }
+
+    /// Overwrites a header with the given name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` or `value` isn't valid in a header, like in the browser.
+    pub fn set(&self, name: &str, value: &str) {
+        let (name, value) = header(name, value);
+        self.raw.borrow_mut().insert(name, value);
+    }
+
+    /// Iterate over (header name, header value) pairs, sorted by name like in the browser.
+    pub fn entries(&self) -> impl Iterator<Item = (String, String)> {
+        let entries: Vec<_> = self
+            .keys()
+            .map(|name| {
+                let value = self.get(&name).unwrap_or_default();
+                (name, value)
+            })
+            .collect();
+        entries.into_iter()
+    }
+
+    /// Iterate over the names of the headers.
+    pub fn keys(&self) -> impl Iterator<Item = String> {
+        let mut keys: Vec<_> = self
+            .raw
+            .borrow()
+            .keys()
+            .map(|name| name.as_str().to_string())
+            .collect();
+        keys.sort();
+        keys.into_iter()
+    }
+
+    /// Iterate over the values of the headers.
+    pub fn values(&self) -> impl Iterator<Item = String> {
+        self.entries().map(|(_, value)| value)
+    }
+
+    /// The media type of the `Content-Type` header in lowercase, without parameters like the
+    /// charset, e.g. `application/json`.
+    pub fn content_type(&self) -> Option<String> {
+        self.get("Content-Type")
+            .and_then(|value| media_type(&value))
+    }
+
+    /// The `Content-Length` header, `None` if it is missing or invalid.
+    pub fn content_length(&self) -> Option<u64> {
+        self.get("Content-Length")?.trim().parse().ok()
+    }
+
+    /// The parsed `Cache-Control` header, `None` if it is missing.
+    pub fn cache_control(&self) -> Option<CacheControl> {
+        self.get("Cache-Control")
+            .map(|value| CacheControl::parse(&value))
+    }
+}
+
+impl From<HeaderMap> for Headers {
+    fn from(raw: HeaderMap) -> Self {
+        Self::from_raw(raw)
+    }
+}
+
+impl From<Headers> for HeaderMap {
+    fn from(headers: Headers) -> Self {
+        headers.into_raw()
+    }
+}
+
+impl<K: AsRef<str>, V: AsRef<str>> FromIterator<(K, V)> for Headers {
+    /// Creates headers from name and value pairs, appending the values of repeated names.
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let headers = Headers::new();
+        for (name, value) in iter {
+            headers.append(name.as_ref(), value.as_ref());
+        }
+        headers
+    }
+}
+
+impl fmt::Debug for Headers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut dbg = f.debug_struct("Headers");
+        for (key, value) in self.entries() {
+            dbg.field(&key, &value);
+        }
+        dbg.finish()
+    }
+}
+
+fn header(name: &str, value: &str) -> (HeaderName, HeaderValue) {
+    let name =
+        HeaderName::from_str(name).unwrap_or_else(|_| panic!("invalid header name {:?}", name));
+    let value = HeaderValue::from_str(value)
+        .unwrap_or_else(|_| panic!("invalid value {:?} of header {}", value, name));
+    (name, value)
+}
+
+fn value_string(value: &HeaderValue) -> String {
+    String::from_utf8_lossy(value.as_bytes()).into_owned()
+}
+
+/// A builder for a [`Request`], with the same interface as the browser based `RequestBuilder`.
+pub struct RequestBuilder {
+    url: String,
+    method: Method,
+    headers: Headers,
+    query: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+    timeout: Option<Duration>,
+    client: Option<reqwest::Client>,
+}
+
+impl RequestBuilder {
+    /// Creates a new request that will be sent to `url`.
+    ///
+    /// Uses `GET` by default. Unlike in the browser, `url` has to be absolute.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            method: Method::GET,
+            headers: Headers::new(),
+            query: Vec::new(),
+            body: None,
+            timeout: None,
+            client: None,
+        }
+    }
+
+    /// Set the body for this request.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Result<Request, Error> {
+        self.body = Some(body.into());
+        self.build()
+    }
+
+    /// Replace _all_ the headers.
+    pub fn headers(mut self, headers: Headers) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Sets a header.
+    pub fn header(self, key: &str, value: &str) -> Self {
+        self.headers.set(key, value);
+        self
+    }
+
+    /// Append query parameters to the url, given as `(name, value)` tuples.
+    ///
+    /// It is possible to append the same parameters with the same name multiple times, so
+    /// `.query([("a", "1"), ("a", "2")])` results in the query string `a=1&a=2`.
+    pub fn query<'a, T, V>(mut self, params: T) -> Self
+    where
+        T: IntoIterator<Item = (&'a str, V)>,
+        V: AsRef<str>,
+    {
+        for (name, value) in params {
+            self.query
+                .push((name.to_string(), value.as_ref().to_string()));
+        }
+        self
+    }
+
+    /// A convenience method to set JSON as request body
+    ///
+    /// # Note
+    ///
+    /// This method also sets the `Content-Type` header to `application/json`
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn json<T: serde::Serialize + ?Sized>(self, value: &T) -> Result<Request, Error> {
+        let json = serde_json::to_vec(value)?;
+        self.header("Content-Type", "application/json").body(json)
+    }
+
+    /// A convenience method to set a URL-encoded form as request body
+    ///
+    /// # Note
+    ///
+    /// This method also sets the `Content-Type` header to `application/x-www-form-urlencoded`
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn form<T: serde::Serialize + ?Sized>(self, value: &T) -> Result<Request, Error> {
+        let form =
+            serde_urlencoded::to_string(value).map_err(|e| Error::GlooError(e.to_string()))?;
+        self.header("Content-Type", "application/x-www-form-urlencoded")
+            .body(form)
+    }
+
+    /// The request method, e.g. GET, POST.
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Fails the request with [`Error::Timeout`] if it doesn't complete within `timeout`.
+    ///
+    /// Unlike in the browser, the timeout also covers reading the body of the response.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    fn client(mut self, client: &reqwest::Client) -> Self {
+        self.client = Some(client.clone());
+        self
+    }
+
+    /// Builds the request and send it to the server, returning the received response.
+    pub async fn send(self) -> Result<Response, Error> {
+        self.build()?.send().await
+    }
+
+    /// Builds the request.
+    pub fn build(self) -> Result<Request, Error> {
+        let mut url = reqwest::Url::parse(&self.url)
+            .map_err(|e| Error::GlooError(format!("invalid URL {}: {e}", self.url)))?;
+        if !self.query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&self.query);
+        }
+        let mut raw = reqwest::Request::new(method_to_reqwest(&self.method)?, url);
+        *raw.headers_mut() = self.headers.into_raw();
+        *raw.body_mut() = self.body.map(reqwest::Body::from);
+        *raw.timeout_mut() = self.timeout;
+        Ok(Request {
+            raw,
+            client: self.client,
+        })
+    }
+}
+
+impl fmt::Debug for RequestBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestBuilder")
+            .field("url", &self.url)
+            .field("method", &self.method)
+            .field("headers", &self.headers)
+            .field("query", &self.query)
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An HTTP request sent with `reqwest`, with the same interface as the browser based `Request`.
+///
+/// Requests not created by a [`Client`] are sent without reusing connections.
+pub struct Request {
+    raw: reqwest::Request,
+    client: Option<reqwest::Client>,
+}
+
+impl Request {
+    /// Creates a new [`GET`][Method::GET] `Request` with url.
+    pub fn get(url: &str) -> RequestBuilder {
+        RequestBuilder::new(url).method(Method::GET)
+    }
+
+    /// Creates a new [`POST`][Method::POST] `Request` with url.
+    pub fn post(url: &str) -> RequestBuilder {
+        RequestBuilder::new(url).method(Method::POST)
+    }
+
+    /// Creates a new [`PUT`][Method::PUT] `Request` with url.
+    pub fn put(url: &str) -> RequestBuilder {
+        RequestBuilder::new(url).method(Method::PUT)
+    }
+
+    /// Creates a new [`DELETE`][Method::DELETE] `Request` with url.
+    pub fn delete(url: &str) -> RequestBuilder {
+        RequestBuilder::new(url).method(Method::DELETE)
+    }
+
+    /// Creates a new [`PATCH`][Method::PATCH] `Request` with url.
+    pub fn patch(url: &str) -> RequestBuilder {
+        RequestBuilder::new(url).method(Method::PATCH)
+    }
+
+    /// The URL of the request, including the query.
+    pub fn url(&self) -> String {
+        self.raw.url().to_string()
+    }
+
+    /// The HTTP request method.
+    pub fn method(&self) -> Method {
+        Method::from_str(self.raw.method().as_str()).unwrap_or_default()
+    }
+
+    /// Gets the headers.
+    pub fn headers(&self) -> Headers {
+        Headers::from_raw(self.raw.headers().clone())
+    }
+
+    /// Creates a copy of the request, e.g. to send it again.
+    pub fn try_clone(&self) -> Result<Self, Error> {
+        let raw = self.raw.try_clone().ok_or_else(|| {
+            Error::GlooError("the body of the request can't be cloned".to_string())
+        })?;
+        Ok(Self {
+            raw,
+            client: self.client.clone(),
+        })
+    }
+
+    /// Send the request, returning the response.
+    ///
+    /// This needs to be awaited from within a tokio runtime.
+    pub async fn send(self) -> Result<Response, Error> {
+        let client = self.client.unwrap_or_default();
+        let response = client.execute(self.raw).await.map_err(reqwest_to_error)?;
+        Ok(Response::new(response))
+    }
+}
+
+impl fmt::Debug for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Request")
+            .field("method", self.raw.method())
+            .field("url", &self.raw.url().as_str())
+            .field("headers", self.raw.headers())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The response to a [`Request`], with the same interface as the browser based `Response`.
+pub struct Response {
+    status: u16,
+    url: String,
+    headers: HeaderMap,
+    body: Mutex<Option<reqwest::Response>>,
+}
+
+impl Response {
+    fn new(response: reqwest::Response) -> Self {
+        Self {
+            status: response.status().as_u16(),
+            url: response.url().to_string(),
+            headers: response.headers().clone(),
+            body: Mutex::new(Some(response)),
+        }
+    }
+
+    /// The URL of the response, after redirects.
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// the [HTTP status code](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status) of the
+    /// response.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Whether the [HTTP status code](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status)
+    /// was a success code (in the range `200 - 299`).
+    pub fn ok(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Turns a response with an unsuccessful status into an [`Error::Status`].
+    pub fn error_for_status(self) -> Result<Self, Error> {
+        if self.ok() {
+            Ok(self)
+        } else {
+            Err(Error::Status {
+                status: self.status(),
+                url: self.url(),
+            })
+        }
+    }
+
+    /// The standard status message of the
+    /// [HTTP status code](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status), e.g.
+    /// 'Not Found' for 404.
+    ///
+    /// Unlike in the browser, this isn't the message sent by the server.
+    pub fn status_text(&self) -> String {
+        reqwest::StatusCode::from_u16(self.status)
+            .ok()
+            .and_then(|status| status.canonical_reason())
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Gets the headers.
+    pub fn headers(&self) -> Headers {
+        Headers::from_raw(self.headers.clone())
+    }
+
+    /// Has the response body been consumed?
+    ///
+    /// If true, then any future attempts to consume the body will error.
+    pub fn body_used(&self) -> bool {
+        self.body.lock().map_or(true, |body| body.is_none())
+    }
+
+    /// Gets and parses the json.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        crate::http::json::from_json(&self.text().await?)
+    }
+
+    /// Gets the response text.
+    pub async fn text(&self) -> Result<String, Error> {
+        self.take_body()?.text().await.map_err(reqwest_to_error)
+    }
+
+    /// Gets the binary response
+    pub async fn binary(&self) -> Result<Vec<u8>, Error> {
+        let bytes = self.take_body()?.bytes().await.map_err(reqwest_to_error)?;
+        Ok(bytes.to_vec())
+    }
+
+    fn take_body(&self) -> Result<reqwest::Response, Error> {
+        self.body
+            .lock()
+            .ok()
+            .and_then(|mut body| body.take())
+            .ok_or_else(|| {
+                Error::GlooError("the body of the response was already read".to_string())
+            })
+    }
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("url", &self.url)
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .field("body_used", &self.body_used())
+            .finish()
+    }
+}
+
+/// A reusable configuration for requests, with the same interface as the browser based
+/// `Client`: a base URL and default headers.
+///
+/// Requests created by the same client share their connections.
+#[derive(Clone, Debug, Default)]
+pub struct Client {
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+    client: reqwest::Client,
+}
+
+impl Client {
+    /// Creates a client without base URL or defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a builder for a client.
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    /// The base URL relative URLs are resolved against.
+    pub fn base_url(&self) -> Option<&str> {
+        self.base_url.as_deref()
+    }
+
+    /// Creates a request with the given method to `url`, resolved against the base URL.
+    pub fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let url = match &self.base_url {
+            Some(base_url) => join_url(base_url, url),
+            None => url.to_string(),
+        };
+        let mut builder = RequestBuilder::new(&url)
+            .method(method)
+            .client(&self.client);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        builder
+    }
+
+    /// Creates a [`GET`][Method::GET] request to `url`.
+    pub fn get(&self, url: &str) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
+
+    /// Creates a [`POST`][Method::POST] request to `url`.
+    pub fn post(&self, url: &str) -> RequestBuilder {
+        self.request(Method::POST, url)
+    }
+
+    /// Creates a [`PUT`][Method::PUT] request to `url`.
+    pub fn put(&self, url: &str) -> RequestBuilder {
+        self.request(Method::PUT, url)
+    }
+
+    /// Creates a [`DELETE`][Method::DELETE] request to `url`.
+    pub fn delete(&self, url: &str) -> RequestBuilder {
+        self.request(Method::DELETE, url)
+    }
+
+    /// Creates a [`PATCH`][Method::PATCH] request to `url`.
+    pub fn patch(&self, url: &str) -> RequestBuilder {
+        self.request(Method::PATCH, url)
+    }
+
+    /// Sends a [`GET`][Method::GET] request to `url`, parsing the JSON response as `T`.
+    ///
+    /// Responses with an unsuccessful status fail with [`Error::Status`].
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn get_json<T: DeserializeOwned>(&self, url: &str) -> Result<T, Error> {
+        let request = self.get(url).header("Accept", "application/json");
+        let response = request.send().await?.error_for_status()?;
+        response.json().await
+    }
+
+    /// Sends `body` as JSON in a [`POST`][Method::POST] request to `url`, parsing the JSON
+    /// response as `T`.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn post_json<B, T>(&self, url: &str, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send_json(self.post(url), body).await
+    }
+
+    /// Sends `body` as JSON in a [`PUT`][Method::PUT] request to `url`, parsing the JSON
+    /// response as `T`.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn put_json<B, T>(&self, url: &str, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send_json(self.put(url), body).await
+    }
+
+    /// Sends `body` as JSON in a [`PATCH`][Method::PATCH] request to `url`, parsing the JSON
+    /// response as `T`.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn patch_json<B, T>(&self, url: &str, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.send_json(self.patch(url), body).await
+    }
+
+    #[cfg(feature = "json")]
+    async fn send_json<B, T>(&self, request: RequestBuilder, body: &B) -> Result<T, Error>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        let request = request.header("Accept", "application/json").json(body)?;
+        let response = request.send().await?.error_for_status()?;
+        response.json().await
+    }
+}
+
+/// A builder for a [`Client`].
+#[derive(Clone, Debug, Default)]
+pub struct ClientBuilder {
+    base_url: Option<String>,
+    headers: Vec<(String, String)>,
+}
+
+impl ClientBuilder {
+    /// Sets the base URL relative URLs are resolved against, e.g. `https://api.example.com/v1`.
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.base_url = Some(base_url.to_string());
+        self
+    }
+
+    /// Sets a header sent with every request, unless the request sets it itself.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Builds the client.
+    pub fn build(self) -> Client {
+        Client {
+            base_url: self.base_url,
+            headers: self.headers,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+fn method_to_reqwest(method: &Method) -> Result<reqwest::Method, Error> {
+    reqwest::Method::from_bytes(method.as_str().as_bytes())
+        .map_err(|e| Error::GlooError(e.to_string()))
+}
+
+fn reqwest_to_error(error: reqwest::Error) -> Error {
+    if error.is_timeout() {
+        Error::Timeout
+    } else {
+        Error::GlooError(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serves one connection with `response`, returning the request it received, which has to
+    /// end with `hello`.
+    async fn serve(response: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"hello") {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+            }
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (format!("http://{addr}"), server)
+    }
+
+    #[tokio::test]
+    async fn send() {
+        let (url, server) = serve(
+            "HTTP/1.1 201 Created\r\n\
+             Content-Type: text/plain; charset=utf-8\r\n\
+             Content-Length: 5\r\n\
+             Connection: close\r\n\
+             \r\n\
+             world",
+        )
+        .await;
+        let client = Client::builder()
+            .base_url(&url)
+            .default_header("X-Client", "gloo")
+            .build();
+        let request = client
+            .post("/greet")
+            .query([("to", "all")])
+            .body("hello")
+            .unwrap();
+        assert_eq!(request.method(), Method::POST);
+        let response = request.send().await.unwrap();
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /greet?to=all HTTP/1.1\r\n"));
+        assert!(request.contains("x-client: gloo\r\n"));
+        assert!(request.ends_with("\r\n\r\nhello"));
+
+        assert_eq!(response.status(), 201);
+        assert_eq!(response.status_text(), "Created");
+        assert!(response.ok());
+        assert_eq!(
+            response.headers().content_type().as_deref(),
+            Some("text/plain")
+        );
+        assert!(!response.body_used());
+        assert_eq!(response.text().await.unwrap(), "world");
+        assert!(response.body_used());
+        assert!(response.text().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let result = Request::get(&url)
+            .timeout(Duration::from_millis(50))
+            .send()
+            .await;
+        assert!(matches!(result, Err(Error::Timeout)));
+        drop(listener);
+    }
+
+    #[test]
+    fn headers() {
+        let headers: Headers = vec![("B", "1"), ("a", "2"), ("b", "3")]
+            .into_iter()
+            .collect();
+        assert_eq!(headers.get("b").as_deref(), Some("1, 3"));
+        assert_eq!(
+            headers.entries().collect::<Vec<_>>(),
+            [
+                ("a".to_string(), "2".to_string()),
+                ("b".to_string(), "1, 3".to_string())
+            ]
+        );
+        headers.delete("B");
+        assert!(!headers.has("b"));
+        assert_eq!(headers.get("c"), None);
+    }
+}
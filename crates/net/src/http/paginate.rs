@@ -0,0 +1,199 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+use crate::http::browser::{Client, Headers};
+use crate::{js_to_error, Error};
+use futures_core::Stream;
+use serde::de::DeserializeOwned;
+
+type PageFuture<T> = Pin<Box<dyn Future<Output = Result<(T, Option<String>), Error>>>>;
+type Extractor<T> = Rc<dyn Fn(&Headers, &T) -> Option<String>>;
+
+/// A [`Stream`] of the pages of a paginated JSON API, created by
+/// [`Client::get_paginated`](crate::http::Client::get_paginated).
+///
+/// Pages are requested one after the other as the stream is polled. By default, the URL of the
+/// next page is taken from the `Link` header with `rel="next"`, as with the GitHub API; APIs
+/// which put it elsewhere can set [`next_page`](Self::next_page). The stream ends after the last
+/// page, or after the first request which fails.
+#[must_use = "streams do nothing unless polled"]
+pub struct Paginated<T> {
+    client: Client,
+    next: Option<String>,
+    pending: Option<PageFuture<T>>,
+    extractor: Option<Extractor<T>>,
+}
+
+impl<T: DeserializeOwned + 'static> Paginated<T> {
+    pub(crate) fn new(client: Client, url: &str) -> Self {
+        Self {
+            client,
+            next: Some(url.to_string()),
+            pending: None,
+            extractor: None,
+        }
+    }
+
+    /// Finds the URL of the next page with `next`, from the headers of the response and the
+    /// page, instead of the `Link` header. `None` ends the stream.
+    ///
+    /// Relative URLs are resolved against the base URL of the client.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use futures::StreamExt;
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Client;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Client;
+    /// # use serde::Deserialize;
+    /// #[derive(Deserialize)]
+    /// struct Page {
+    ///     items: Vec<String>,
+    ///     next_cursor: Option<String>,
+    /// }
+    ///
+    /// # async fn no_run() {
+    /// let client = Client::builder().base_url("/api").build();
+    /// let mut pages = client
+    ///     .get_paginated::<Page>("/items")
+    ///     .next_page(|_, page| {
+    ///         let cursor = page.next_cursor.as_ref()?;
+    ///         Some(format!("/items?cursor={cursor}"))
+    ///     });
+    /// while let Some(page) = pages.next().await {
+    ///     let items = page.unwrap().items;
+    /// }
+    /// # }
+    /// ```
+    pub fn next_page<F>(mut self, next: F) -> Self
+    where
+        F: Fn(&Headers, &T) -> Option<String> + 'static,
+    {
+        self.extractor = Some(Rc::new(next));
+        self
+    }
+
+    fn fetch(&self, url: String) -> PageFuture<T> {
+        let client = self.client.clone();
+        let extractor = self.extractor.clone();
+        Box::pin(async move {
+            let response = client
+                .get(&url)
+                .header("Accept", "application/json")
+                .send()
+                .await?
+                .error_for_status()?;
+            let headers = response.headers();
+            let page = response.json().await?;
+            let next = match extractor {
+                Some(extractor) => extractor(&headers, &page),
+                None => match headers.get("Link").as_deref().and_then(next_link) {
+                    // Links are relative to the URL of the response.
+                    Some(link) => Some(
+                        web_sys::Url::new_with_base(link, &response.url())
+                            .map_err(js_to_error)?
+                            .href(),
+                    ),
+                    None => None,
+                },
+            };
+            Ok((page, next))
+        })
+    }
+}
+
+impl<T: DeserializeOwned + 'static> Stream for Paginated<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(pending) = &mut this.pending {
+                let result = match pending.as_mut().poll(cx) {
+                    Poll::Ready(result) => result,
+                    Poll::Pending => return Poll::Pending,
+                };
+                this.pending = None;
+                return Poll::Ready(Some(result.map(|(page, next)| {
+                    this.next = next;
+                    page
+                })));
+            }
+            match this.next.take() {
+                Some(url) => this.pending = Some(this.fetch(url)),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for Paginated<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Paginated")
+            .field("next", &self.next)
+            .field("pending", &self.pending.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The target of the link with the `next` relation in a `Link` header, see
+/// [RFC 8288](https://www.rfc-editor.org/rfc/rfc8288).
+fn next_link(header: &str) -> Option<&str> {
+    let mut rest = header;
+    loop {
+        let start = rest.find('<')?;
+        let end = start + rest[start..].find('>')?;
+        let target = &rest[start + 1..end];
+        rest = &rest[end + 1..];
+        // The parameters run until the next link, which starts after a comma outside of quotes.
+        let mut quoted = false;
+        let params_end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    quoted = !quoted;
+                }
+                c == ',' && !quoted
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        let is_next = rest[..params_end]
+            .split(';')
+            .any(|param| match param.split_once('=') {
+                Some((name, value)) if name.trim().eq_ignore_ascii_case("rel") => value
+                    .trim()
+                    .trim_matches('"')
+                    .split_ascii_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("next")),
+                _ => false,
+            });
+        if is_next {
+            return Some(target);
+        }
+        rest = &rest[params_end..];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_next_links() {
+        let github = r#"<https://api.github.com/repositories/1/issues?page=2>; rel="next", <https://api.github.com/repositories/1/issues?page=5>; rel="last""#;
+        assert_eq!(
+            next_link(github),
+            Some("https://api.github.com/repositories/1/issues?page=2")
+        );
+        let reordered =
+            r#"</items?page=1>; rel="prev first"; title="a, b", </items?page=3>; REL=next"#;
+        assert_eq!(next_link(reordered), Some("/items?page=3"));
+        assert_eq!(next_link(r#"</a,b>; rel="prefetch next""#), Some("/a,b"));
+        assert_eq!(next_link(r#"</items?page=1>; rel="prev""#), None);
+        assert_eq!(next_link(""), None);
+    }
+}
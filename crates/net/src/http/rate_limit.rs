@@ -0,0 +1,222 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::http::browser::{Request, Response};
+use crate::http::middleware::{Middleware, MiddlewareFuture, Next};
+use crate::Error;
+use gloo_timers::future::TimeoutFuture;
+
+/// Limits how often requests are sent, e.g. to stay within the quota of a third-party API.
+///
+/// This is a token bucket: up to `burst` requests are sent right away, after which requests are
+/// delayed so that no more than `requests` are sent `per` period on average. Requests which would
+/// be delayed for longer than [`max_delay`](Self::max_delay) fail with
+/// [`Error::RateLimited`] without being sent instead.
+///
+/// Clones share their buckets, so a rate limit can be shared between clients and requests. By
+/// default, all requests share the same bucket; with [`per_host`](Self::per_host), every host
+/// has its own.
+///
+/// Added to a [`Client`](crate::http::Client) after a [`Retry`](crate::http::Retry), the retries
+/// are limited as well.
+///
+/// # Example
+///
+/// ```
+/// # use gloo_net::http::RateLimit;
+/// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+/// # use gloo_net::http::browser::Client;
+/// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+/// # use gloo_net::http::Client;
+/// # use std::time::Duration;
+/// # async fn no_run() {
+/// let client = Client::builder()
+///     .rate_limit(RateLimit::new(10, Duration::from_secs(1)).per_host(true))
+///     .build();
+/// let resp = client.get("https://api.example.com/items").send().await.unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RateLimit {
+    requests: u32,
+    per: Duration,
+    burst: u32,
+    max_delay: Option<Duration>,
+    per_host: bool,
+    buckets: Rc<RefCell<HashMap<String, Bucket>>>,
+}
+
+impl RateLimit {
+    /// Allows `requests` requests `per` period, and bursts of as many requests.
+    ///
+    /// `requests` is at least 1.
+    pub fn new(requests: u32, per: Duration) -> Self {
+        let requests = requests.max(1);
+        Self {
+            requests,
+            per,
+            burst: requests,
+            max_delay: None,
+            per_host: false,
+            buckets: Rc::default(),
+        }
+    }
+
+    /// Sets how many requests can be sent right away after a quiet period, `requests` by
+    /// default.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    /// Fails requests which would be delayed for longer than `max_delay`, instead of waiting.
+    ///
+    /// With `Duration::ZERO`, requests beyond the rate are rejected right away.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = Some(max_delay);
+        self
+    }
+
+    /// Sets whether every host has its own bucket, instead of all requests sharing one.
+    pub fn per_host(mut self, per_host: bool) -> Self {
+        self.per_host = per_host;
+        self
+    }
+
+    /// Takes a token for a request to `url` at `now`, in milliseconds, returning how long the
+    /// request has to wait.
+    fn acquire(&self, url: &str, now: f64) -> Result<Duration, Error> {
+        let key = if self.per_host { host_of(url) } else { "" };
+        let mut buckets = self.buckets.borrow_mut();
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: f64::from(self.burst),
+            updated: now,
+        });
+        let rate = f64::from(self.requests) / (self.per.as_secs_f64() * 1000.0);
+        bucket.refill(now, rate, f64::from(self.burst));
+        let wait_ms = ((1.0 - bucket.tokens) / rate).max(0.0);
+        let wait = Duration::from_micros((wait_ms * 1000.0).ceil() as u64);
+        if self.max_delay.is_some_and(|max_delay| wait > max_delay) {
+            return Err(Error::RateLimited { retry_after: wait });
+        }
+        // Going below zero reserves the next tokens, so that waiting requests are spaced out.
+        bucket.tokens -= 1.0;
+        Ok(wait)
+    }
+
+    async fn send(self, request: Request, next: Next) -> Result<Response, Error> {
+        let wait = self.acquire(&request.url(), js_sys::Date::now())?;
+        if !wait.is_zero() {
+            trace_event!(
+                debug,
+                url = %request.url(),
+                delay_ms = wait.as_millis() as u64,
+                "rate limiting request"
+            );
+            TimeoutFuture::new(u32::try_from(wait.as_millis()).unwrap_or(u32::MAX)).await;
+        }
+        next.run(request).await
+    }
+}
+
+impl Middleware for RateLimit {
+    fn handle(&self, request: Request, next: Next) -> MiddlewareFuture {
+        Box::pin(self.clone().send(request, next))
+    }
+}
+
+impl fmt::Debug for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("requests", &self.requests)
+            .field("per", &self.per)
+            .field("burst", &self.burst)
+            .field("max_delay", &self.max_delay)
+            .field("per_host", &self.per_host)
+            .finish_non_exhaustive()
+    }
+}
+
+struct Bucket {
+    /// The tokens left, negative when requests are waiting for tokens.
+    tokens: f64,
+    /// When `tokens` was last refilled, in milliseconds.
+    updated: f64,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: f64, rate: f64, capacity: f64) {
+        let elapsed = (now - self.updated).max(0.0);
+        self.tokens = (self.tokens + elapsed * rate).min(capacity);
+        self.updated = now;
+    }
+}
+
+/// The host and port of `url`, empty for relative URLs.
+fn host_of(url: &str) -> &str {
+    match url.split_once("://") {
+        Some((_, rest)) => {
+            let end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+            let authority = &rest[..end];
+            authority.rsplit('@').next().unwrap_or(authority)
+        }
+        None => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn waits(limit: &RateLimit, url: &str, now: f64) -> Option<u128> {
+        limit.acquire(url, now).ok().map(|wait| wait.as_millis())
+    }
+
+    #[test]
+    fn token_bucket() {
+        let limit = RateLimit::new(2, Duration::from_secs(1));
+        let url = "https://api.example.com/a";
+        assert_eq!(waits(&limit, url, 0.0), Some(0));
+        assert_eq!(waits(&limit, url, 0.0), Some(0));
+        assert_eq!(waits(&limit, url, 0.0), Some(500));
+        assert_eq!(waits(&limit, url, 0.0), Some(1000));
+        assert_eq!(waits(&limit, url, 1000.0), Some(500));
+        // Another host shares the bucket.
+        assert_eq!(waits(&limit, "https://b.example.com", 1000.0), Some(1000));
+        // Tokens don't pile up beyond the burst.
+        assert_eq!(waits(&limit, url, 60_000.0), Some(0));
+        assert_eq!(waits(&limit, url, 60_000.0), Some(0));
+        assert_eq!(waits(&limit, url, 60_000.0), Some(500));
+    }
+
+    #[test]
+    fn rejects_and_separates_hosts() {
+        let limit = RateLimit::new(1, Duration::from_secs(1))
+            .max_delay(Duration::ZERO)
+            .per_host(true);
+        assert_eq!(waits(&limit, "https://a.example.com/1", 0.0), Some(0));
+        assert_eq!(waits(&limit, "https://b.example.com/1", 0.0), Some(0));
+        match limit.acquire("https://a.example.com/2", 250.0) {
+            Err(Error::RateLimited { retry_after }) => {
+                assert_eq!(retry_after, Duration::from_millis(750))
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        // Rejected requests don't take tokens.
+        assert_eq!(waits(&limit, "https://a.example.com/3", 1000.0), Some(0));
+    }
+
+    #[test]
+    fn hosts() {
+        assert_eq!(
+            host_of("https://api.example.com:8443/v1?a"),
+            "api.example.com:8443"
+        );
+        assert_eq!(host_of("http://user:pw@example.com#top"), "example.com");
+        assert_eq!(host_of("/relative"), "");
+    }
+}
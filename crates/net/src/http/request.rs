@@ -1,11 +1,23 @@
-use crate::http::{Headers, QueryParams, Response};
+use crate::http::abort::Abort;
+use crate::http::body::readable_stream;
+use crate::http::browser::{Headers, Response};
+#[cfg(feature = "json")]
+use crate::http::json::from_json;
+use crate::http::middleware::{Middleware, Next};
+use crate::http::xhr;
+use crate::http::{Multipart, OpaqueResponse, PendingRequest, QueryParams, Retry, UploadProgress};
 use crate::{js_to_error, Error};
+use futures_core::Stream;
 use http::Method;
 use js_sys::{ArrayBuffer, Reflect, Uint8Array};
 use std::convert::{From, TryFrom, TryInto};
 use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
 use std::str::FromStr;
-use wasm_bindgen::{JsCast, JsValue};
+use std::time::Duration;
+use wasm_bindgen::{JsCast, JsValue, UnwrapThrowExt};
 use wasm_bindgen_futures::JsFuture;
 use web_sys::{
     AbortSignal, FormData, ObserverCallback, ReadableStream, ReferrerPolicy, RequestCache,
@@ -22,6 +34,9 @@ pub struct RequestBuilder {
     headers: Headers,
     query: QueryParams,
     url: String,
+    signal: Option<AbortSignal>,
+    timeout: Option<Duration>,
+    middlewares: Vec<Rc<dyn Middleware>>,
 }
 
 impl RequestBuilder {
@@ -34,29 +49,68 @@ impl RequestBuilder {
             headers: Headers::new(),
             query: QueryParams::new(),
             url: url.into(),
+            signal: None,
+            timeout: None,
+            middlewares: Vec::new(),
         }
     }
 
     /// Set the body for this request.
-    pub fn body(mut self, body: impl Into<JsValue>) -> Result<Request, Error> {
-        self.options.body(Some(&body.into()));
+    ///
+    /// Besides strings and buffers, the body can be a `Blob` or a `File`, from `web-sys` or
+    /// `gloo-file`, which the browser streams from disk instead of copying it into memory. Unless
+    /// a `Content-Type` header is set, the request is then sent with the MIME type of the blob.
+    pub fn body(self, body: impl Into<JsValue>) -> Result<Request, Error> {
+        self.options.set_body(&body.into());
+
+        self.try_into()
+    }
+
+    /// Set a body streamed from `stream` for this request, e.g. to upload a file in chunks
+    /// without holding it in memory.
+    ///
+    /// The request is sent with `duplex: "half"`, which browsers require for streamed bodies:
+    /// the response is only available once the whole body is sent. Browsers only stream
+    /// request bodies over HTTP/2 and later, and fail the request otherwise.
+    pub fn body_stream<S>(self, stream: S) -> Result<Request, Error>
+    where
+        S: Stream<Item = Vec<u8>> + 'static,
+    {
+        let body = readable_stream(stream)?;
+        self.options.set_body(&body);
+        Reflect::set(
+            &self.options,
+            &JsValue::from_str("duplex"),
+            &JsValue::from_str("half"),
+        )
+        .map_err(js_to_error)?;
 
         self.try_into()
     }
 
     /// A string indicating how the request will interact with the browser’s HTTP cache.
-    pub fn cache(mut self, cache: RequestCache) -> Self {
-        self.options.cache(cache);
+    pub fn cache(self, cache: RequestCache) -> Self {
+        self.options.set_cache(cache);
         self
     }
 
     /// Controls what browsers do with credentials (cookies, HTTP authentication entries, and TLS
     /// client certificates).
-    pub fn credentials(mut self, credentials: RequestCredentials) -> Self {
-        self.options.credentials(credentials);
+    pub fn credentials(self, credentials: RequestCredentials) -> Self {
+        self.options.set_credentials(credentials);
         self
     }
 
+    /// Sends cookies with the request and stores the ones in the response, also for
+    /// cross-origin requests.
+    ///
+    /// This is [`credentials`](Self::credentials) with `RequestCredentials::Include`, for
+    /// session-based authentication against an API on another origin. The server has to allow
+    /// it with `Access-Control-Allow-Credentials: true`.
+    pub fn with_credentials(self) -> Self {
+        self.credentials(RequestCredentials::Include)
+    }
+
     /// Replace _all_ the headers.
     pub fn headers(mut self, headers: Headers) -> Self {
         self.headers = headers;
@@ -82,6 +136,9 @@ impl RequestBuilder {
     /// ```
     /// # fn no_run() {
     /// use std::collections::HashMap;
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Request;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
     /// use gloo_net::http::Request;
     ///
     /// let slice_params = [("key", "value")];
@@ -109,8 +166,12 @@ impl RequestBuilder {
 
     /// The subresource integrity value of the request (e.g.,
     /// `sha256-BpfBw7ivV8q2jLiT13fxDYAe2tJllusRSZ273h2nFSE=`).
-    pub fn integrity(mut self, integrity: &str) -> Self {
-        self.options.integrity(integrity);
+    ///
+    /// The browser fails the request if the body of the response doesn't match it. Bytes which
+    /// don't come from such a request can be checked with
+    /// [`verify_integrity`](crate::http::verify_integrity).
+    pub fn integrity(self, integrity: &str) -> Self {
+        self.options.set_integrity(integrity);
         self
     }
 
@@ -126,21 +187,107 @@ impl RequestBuilder {
         self.header("Content-Type", "application/json").body(json)
     }
 
+    /// A convenience method to set a URL-encoded form as request body
+    ///
+    /// # Note
+    ///
+    /// This method also sets the `Content-Type` header to `application/x-www-form-urlencoded`
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn form<T: serde::Serialize + ?Sized>(self, value: &T) -> Result<Request, Error> {
+        let form =
+            serde_urlencoded::to_string(value).map_err(|e| Error::GlooError(e.to_string()))?;
+        self.header("Content-Type", "application/x-www-form-urlencoded")
+            .body(form)
+    }
+
+    /// A convenience method to set [CBOR](https://cbor.io) as request body
+    ///
+    /// # Note
+    ///
+    /// This method also sets the `Content-Type` header to `application/cbor`
+    #[cfg(feature = "cbor")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+    pub fn cbor<T: serde::Serialize + ?Sized>(self, value: &T) -> Result<Request, Error> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(value, &mut bytes)
+            .map_err(|e| Error::GlooError(e.to_string()))?;
+        self.header("Content-Type", "application/cbor")
+            .body(Uint8Array::from(bytes.as_slice()))
+    }
+
+    /// A convenience method to set [MessagePack](https://msgpack.org) as request body, with
+    /// structs encoded as maps
+    ///
+    /// # Note
+    ///
+    /// This method also sets the `Content-Type` header to `application/msgpack`
+    #[cfg(feature = "rmp")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rmp")))]
+    pub fn msgpack<T: serde::Serialize + ?Sized>(self, value: &T) -> Result<Request, Error> {
+        let bytes = rmp_serde::to_vec_named(value).map_err(|e| Error::GlooError(e.to_string()))?;
+        self.header("Content-Type", "application/msgpack")
+            .body(Uint8Array::from(bytes.as_slice()))
+    }
+
+    /// A convenience method to set a `multipart/form-data` request body
+    ///
+    /// # Note
+    ///
+    /// This method removes a `Content-Type` header set before, e.g. by a
+    /// [`Client`](crate::http::Client) default, so that the browser can set it with the boundary
+    /// of the parts.
+    pub fn multipart(self, multipart: Multipart) -> Result<Request, Error> {
+        self.headers.delete("Content-Type");
+        self.body(FormData::from(multipart))
+    }
+
     /// The request method, e.g., GET, POST.
-    pub fn method(mut self, method: Method) -> Self {
-        self.options.method(method.as_ref());
+    pub fn method(self, method: Method) -> Self {
+        self.options.set_method(method.as_ref());
         self
     }
 
     /// The mode you want to use for the request.
-    pub fn mode(mut self, mode: RequestMode) -> Self {
-        self.options.mode(mode);
+    pub fn mode(self, mode: RequestMode) -> Self {
+        self.options.set_mode(mode);
+        self
+    }
+
+    /// Keeps sending the request when the page is closed or navigated away from, e.g. to report
+    /// analytics on unload.
+    ///
+    /// Browsers limit the total size of the bodies of pending keepalive requests to 64 KiB. Also
+    /// see [`Request::beacon`].
+    pub fn keepalive(self, keepalive: bool) -> Self {
+        // Setting a property of a plain object doesn't throw.
+        Reflect::set(
+            &self.options,
+            &JsValue::from_str("keepalive"),
+            &JsValue::from_bool(keepalive),
+        )
+        .unwrap_throw();
+        self
+    }
+
+    /// Hints how important the request is compared to other requests of the page, e.g. to
+    /// send a prefetch with [`RequestPriority::Low`].
+    ///
+    /// Browsers without support for priorities ignore it.
+    pub fn priority(self, priority: RequestPriority) -> Self {
+        // Setting a property of a plain object doesn't throw.
+        Reflect::set(
+            &self.options,
+            &JsValue::from_str("priority"),
+            &JsValue::from_str(priority.as_str()),
+        )
+        .unwrap_throw();
         self
     }
 
     /// Sets the observer callback.
-    pub fn observe(mut self, observe: &ObserverCallback) -> Self {
-        self.options.observe(observe);
+    pub fn observe(self, observe: &ObserverCallback) -> Self {
+        self.options.set_observe(observe);
         self
     }
 
@@ -151,37 +298,145 @@ impl RequestBuilder {
     /// - *error*: Abort with an error if a redirect occurs.
     /// - *manual*: Caller intends to process the response in another context. See [WHATWG fetch
     ///   standard](https://fetch.spec.whatwg.org/#requests) for more information.
-    pub fn redirect(mut self, redirect: RequestRedirect) -> Self {
-        self.options.redirect(redirect);
+    ///
+    /// With *manual*, a redirect resolves to a response for which
+    /// [`Response::is_opaque_redirect`] is `true`. Browsers hide its status and `Location`,
+    /// so the redirect can't be followed by hand, only detected, e.g. to notice that a session
+    /// expired and the server redirects to a login page.
+    pub fn redirect(self, redirect: RequestRedirect) -> Self {
+        self.options.set_redirect(redirect);
         self
     }
 
     /// The referrer of the request.
     ///
     /// This can be a same-origin URL, `about:client`, or an empty string.
-    pub fn referrer(mut self, referrer: &str) -> Self {
-        self.options.referrer(referrer);
+    pub fn referrer(self, referrer: &str) -> Self {
+        self.options.set_referrer(referrer);
         self
     }
 
     /// Specifies the
     /// [referrer policy](https://w3c.github.io/webappsec-referrer-policy/#referrer-policies) to
     /// use for the request.
-    pub fn referrer_policy(mut self, referrer_policy: ReferrerPolicy) -> Self {
-        self.options.referrer_policy(referrer_policy);
+    pub fn referrer_policy(self, referrer_policy: ReferrerPolicy) -> Self {
+        self.options.set_referrer_policy(referrer_policy);
         self
     }
 
     /// Sets the request abort signal.
+    ///
+    /// The request is also aborted through the [`AbortHandle`](crate::http::AbortHandle) of
+    /// its [`PendingRequest`], and fails with [`Error::Aborted`] either way.
     pub fn abort_signal(mut self, signal: Option<&AbortSignal>) -> Self {
-        self.options.signal(signal);
+        self.signal = signal.cloned();
+        self
+    }
+
+    /// Fails the request with [`Error::Timeout`] if no response is received within `timeout`.
+    ///
+    /// The request is aborted when the timeout expires. Reading the body of the response is not
+    /// covered by the timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retries the request according to `retry` when it fails.
+    ///
+    /// This adds `retry` as a [`middleware`](Self::middleware). A [`timeout`](Self::timeout)
+    /// covers all attempts and the delays between them.
+    pub fn retry(self, retry: Retry) -> Self {
+        self.middleware(retry)
+    }
+
+    /// Adds a [`Middleware`] the request is sent through, after the ones added before.
+    pub fn middleware(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middlewares.push(Rc::new(middleware));
+        self
+    }
+
+    pub(crate) fn middlewares(mut self, middlewares: &[Rc<dyn Middleware>]) -> Self {
+        self.middlewares.extend(middlewares.iter().cloned());
         self
     }
+
     /// Builds the request and send it to the server, returning the received response.
-    pub async fn send(self) -> Result<Response, Error> {
-        let req: Request = self.try_into()?;
-        req.send().await
+    ///
+    /// See [`Request::send`].
+    pub fn send(self) -> PendingRequest {
+        match Request::try_from(self) {
+            Ok(req) => req.send(),
+            Err(e) => PendingRequest::failed(e),
+        }
+    }
+
+    /// Sends the request in [`no-cors`](RequestMode::NoCors) mode, e.g. to prefetch or cache a
+    /// cross-origin resource whose server doesn't allow CORS.
+    ///
+    /// The browser only allows `GET`, `HEAD` and `POST` requests with simple headers in this
+    /// mode, and the response to a cross-origin request is opaque, see [`OpaqueResponse`].
+    /// Requests with a body can be sent with [`mode`](Self::mode), converting their response.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Request;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Request;
+    /// # async fn no_run() {
+    /// let resp = Request::get("https://cdn.example.com/font.woff2")
+    ///     .send_no_cors()
+    ///     .await
+    ///     .unwrap();
+    /// assert!(resp.is_opaque());
+    /// # }
+    /// ```
+    pub async fn send_no_cors(self) -> Result<OpaqueResponse, Error> {
+        let response = self.mode(RequestMode::NoCors).send().await?;
+        Ok(OpaqueResponse::from(response))
+    }
+
+    /// Sends the request with `XMLHttpRequest` instead of `fetch`, calling `on_progress` while
+    /// its body is uploaded, e.g. to show the progress of a file upload.
+    ///
+    /// `fetch` can't report upload progress. The response is the same as with
+    /// [`send`](Self::send), the timeout and abort signal apply as well, but middlewares and
+    /// options other than the method, headers and credentials are not used. A body which is a
+    /// stream is read in full before sending it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Request;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Request;
+    /// # async fn no_run(file: web_sys::File) {
+    /// let resp = Request::post("/upload")
+    ///     .body(file)
+    ///     .unwrap()
+    ///     .send_with_upload_progress(|progress| {
+    ///         if let Some(total) = progress.total {
+    ///             log(progress.sent * 100 / total);
+    ///         }
+    ///     })
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// # fn log(_: u64) {}
+    /// ```
+    pub fn send_with_upload_progress(
+        self,
+        on_progress: impl Fn(UploadProgress) + 'static,
+    ) -> PendingRequest {
+        match Request::try_from(self) {
+            Ok(req) => req.send_with_upload_progress(on_progress),
+            Err(e) => PendingRequest::failed(e),
+        }
     }
+
     /// Builds the request.
     pub fn build(self) -> Result<Request, crate::error::Error> {
         self.try_into()
@@ -191,7 +446,7 @@ impl RequestBuilder {
 impl TryFrom<RequestBuilder> for Request {
     type Error = crate::error::Error;
 
-    fn try_from(mut value: RequestBuilder) -> Result<Self, Self::Error> {
+    fn try_from(value: RequestBuilder) -> Result<Self, Self::Error> {
         // To preserve existing query parameters of self.url, it must be parsed and extended with
         // self.query's parameters. As web_sys::Url just accepts absolute URLs, retrieve the
         // absolute URL through creating a web_sys::Request object.
@@ -204,11 +459,17 @@ impl TryFrom<RequestBuilder> for Request {
         url.set_search(&combined_query);
 
         let final_url = String::from(url.to_string());
-        value.options.headers(&value.headers.into_raw());
+        value.options.set_headers(&value.headers.into_raw());
+        let abort = Abort::new(value.signal, value.timeout)?;
+        value.options.set_signal(Some(&abort.signal()));
         let request = web_sys::Request::new_with_str_and_init(&final_url, &value.options)
             .map_err(js_to_error)?;
 
-        Ok(request.into())
+        Ok(Request {
+            raw: request,
+            abort: Some(abort),
+            middlewares: value.middlewares,
+        })
     }
 }
 
@@ -218,8 +479,34 @@ impl fmt::Debug for RequestBuilder {
     }
 }
 
+/// The priority of a request relative to other requests of the page, set with
+/// [`RequestBuilder::priority`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RequestPriority {
+    /// More important than other requests of the same kind.
+    High,
+    /// Less important than other requests of the same kind.
+    Low,
+    /// The browser's default priority.
+    Auto,
+}
+
+impl RequestPriority {
+    fn as_str(self) -> &'static str {
+        match self {
+            RequestPriority::High => "high",
+            RequestPriority::Low => "low",
+            RequestPriority::Auto => "auto",
+        }
+    }
+}
+
 /// The [`Request`] sent to the server
-pub struct Request(web_sys::Request);
+pub struct Request {
+    raw: web_sys::Request,
+    abort: Option<Abort>,
+    middlewares: Vec<Rc<dyn Middleware>>,
+}
 
 impl Request {
     /// Creates a new [`GET`][Method::GET] `Request` with url.
@@ -249,29 +536,29 @@ impl Request {
 
     /// The URL of the request.
     pub fn url(&self) -> String {
-        self.0.url()
+        self.raw.url()
     }
 
     /// Gets the headers.
     pub fn headers(&self) -> Headers {
-        Headers::from_raw(self.0.headers())
+        Headers::from_raw(self.raw.headers())
     }
 
     /// Has the request body been consumed?
     ///
     /// If true, then any future attempts to consume the body will error.
     pub fn body_used(&self) -> bool {
-        self.0.body_used()
+        self.raw.body_used()
     }
 
     /// Gets the body.
     pub fn body(&self) -> Option<ReadableStream> {
-        self.0.body()
+        self.raw.body()
     }
 
     /// Reads the request to completion, returning it as `FormData`.
     pub async fn form_data(&self) -> Result<FormData, Error> {
-        let promise = self.0.form_data().map_err(js_to_error)?;
+        let promise = self.raw.form_data().map_err(js_to_error)?;
         let val = JsFuture::from(promise).await.map_err(js_to_error)?;
         Ok(FormData::from(val))
     }
@@ -280,12 +567,12 @@ impl Request {
     #[cfg(feature = "json")]
     #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
     pub async fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
-        serde_json::from_str::<T>(&self.text().await?).map_err(Error::from)
+        from_json(&self.text().await?)
     }
 
     /// Reads the reqeust as a String.
     pub async fn text(&self) -> Result<String, Error> {
-        let promise = self.0.text().unwrap();
+        let promise = self.raw.text().unwrap();
         let val = JsFuture::from(promise).await.map_err(js_to_error)?;
         let string = js_sys::JsString::from(val);
         Ok(String::from(&string))
@@ -296,7 +583,7 @@ impl Request {
     /// This works by obtaining the response as an `ArrayBuffer`, creating a `Uint8Array` from it
     /// and then converting it to `Vec<u8>`
     pub async fn binary(&self) -> Result<Vec<u8>, Error> {
-        let promise = self.0.array_buffer().map_err(js_to_error)?;
+        let promise = self.raw.array_buffer().map_err(js_to_error)?;
         let array_buffer: ArrayBuffer = JsFuture::from(promise)
             .await
             .map_err(js_to_error)?
@@ -307,53 +594,245 @@ impl Request {
         Ok(body)
     }
 
+    /// Queues a `POST` of `body` to `url` with `navigator.sendBeacon`, which the browser sends
+    /// even when the page is closed.
+    ///
+    /// The body can be anything [`RequestBuilder::body`] accepts, such as a string or
+    /// `FormData`. The response can't be read. Fails if the browser refuses to queue the beacon,
+    /// e.g. because the body is too large, or outside of a window.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Request;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Request;
+    /// # fn no_run() {
+    /// Request::beacon("/analytics", r#"{"event":"unload"}"#).unwrap();
+    /// # }
+    /// ```
+    pub fn beacon(url: &str, body: impl Into<JsValue>) -> Result<(), Error> {
+        let window = js_sys::global()
+            .dyn_into::<web_sys::Window>()
+            .map_err(|_| Error::GlooError("beacons can only be sent from a window".to_string()))?;
+        let body = body.into();
+        // `sendBeacon` takes the same bodies as `fetch`, the binding's type is just one of them.
+        let queued = window
+            .navigator()
+            .send_beacon_with_opt_blob(url, Some(body.unchecked_ref()))
+            .map_err(js_to_error)?;
+        if queued {
+            Ok(())
+        } else {
+            Err(Error::GlooError(
+                "the browser refused to queue the beacon".to_string(),
+            ))
+        }
+    }
+
     /// Return the read only mode for the request
     pub fn mode(&self) -> RequestMode {
-        self.0.mode()
+        self.raw.mode()
+    }
+
+    /// How the request interacts with the browser's HTTP cache.
+    pub fn cache(&self) -> RequestCache {
+        self.raw.cache()
+    }
+
+    /// Whether credentials are sent with the request.
+    pub fn credentials(&self) -> RequestCredentials {
+        self.raw.credentials()
+    }
+
+    /// The subresource integrity value of the request, empty if it has none.
+    pub fn integrity(&self) -> String {
+        self.raw.integrity()
+    }
+
+    /// The referrer of the request, `about:client` for the default one and empty for none.
+    pub fn referrer(&self) -> String {
+        self.raw.referrer()
+    }
+
+    /// The referrer policy of the request.
+    pub fn referrer_policy(&self) -> ReferrerPolicy {
+        self.raw.referrer_policy()
+    }
+
+    /// How the request handles redirects.
+    pub fn redirect(&self) -> RequestRedirect {
+        self.raw.redirect()
     }
 
     /// Return the parsed method for the request
     pub fn method(&self) -> Method {
-        Method::from_str(self.0.method().as_str()).unwrap()
+        Method::from_str(self.raw.method().as_str()).unwrap()
+    }
+
+    /// A copy of this request, which can be sent separately, e.g. by a [`Middleware`] resending
+    /// a request.
+    ///
+    /// The body is teed, so both requests can be sent. The copy is aborted along with this
+    /// request, but has no timeout of its own. Fails if the body was already read, e.g. because
+    /// this request was sent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Request;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Request;
+    /// # async fn no_run() -> Result<(), gloo_net::Error> {
+    /// let request = Request::post("/api/events").body("ping")?;
+    /// let copy = request.try_clone()?;
+    /// let first = request.send().await?;
+    /// let second = copy.send().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn try_clone(&self) -> Result<Self, Error> {
+        // `web_sys::Request::clone` is the JS `clone()`, not `Clone::clone`.
+        let raw = web_sys::Request::clone(&self.raw).map_err(js_to_error)?;
+        Ok(Request {
+            raw,
+            abort: None,
+            middlewares: self.middlewares.clone(),
+        })
     }
 
     /// Executes the request.
-    pub async fn send(self) -> Result<Response, Error> {
-        let request = self.0;
-        let global = js_sys::global();
-        let maybe_window =
-            Reflect::get(&global, &JsValue::from_str("Window")).map_err(js_to_error)?;
-        let promise = if !maybe_window.is_undefined() {
-            let window = global.dyn_into::<web_sys::Window>().unwrap();
-            window.fetch_with_request(&request)
+    ///
+    /// The request is aborted when the returned [`PendingRequest`] is dropped before the
+    /// response is received, or through its [`abort_handle`](PendingRequest::abort_handle).
+    pub fn send(self) -> PendingRequest {
+        let Request {
+            raw,
+            abort,
+            middlewares,
+        } = self;
+        let request = Request::from(raw);
+        let future: Pin<Box<dyn Future<Output = _>>> = if middlewares.is_empty() {
+            Box::pin(fetch(request.raw))
         } else {
-            let maybe_worker = Reflect::get(&global, &JsValue::from_str("WorkerGlobalScope"))
-                .map_err(js_to_error)?;
-            if !maybe_worker.is_undefined() {
-                let worker = global.dyn_into::<web_sys::WorkerGlobalScope>().unwrap();
-                worker.fetch_with_request(&request)
-            } else {
-                panic!("Unsupported JavaScript global context");
-            }
+            Box::pin(Next::new(middlewares.into()).run(request))
+        };
+        let future = match &abort {
+            Some(abort) => abort.with_timeout(future),
+            None => future,
+        };
+        PendingRequest::new(future, abort)
+    }
+
+    /// Executes the request with `XMLHttpRequest`, calling `on_progress` while its body is
+    /// uploaded.
+    ///
+    /// See [`RequestBuilder::send_with_upload_progress`].
+    pub fn send_with_upload_progress(
+        self,
+        on_progress: impl Fn(UploadProgress) + 'static,
+    ) -> PendingRequest {
+        let future: Pin<Box<dyn Future<Output = _>>> =
+            Box::pin(xhr::send(self.raw, Box::new(on_progress)));
+        let future = match &self.abort {
+            Some(abort) => abort.with_timeout(future),
+            None => future,
         };
+        PendingRequest::new(future, self.abort)
+    }
+}
 
-        let response = JsFuture::from(promise).await.map_err(js_to_error)?;
-        response
-            .dyn_into::<web_sys::Response>()
-            .map_err(|e| panic!("fetch returned {:?}, not `Response` - this is a bug", e))
-            .map(Response::from)
+/// Starts `request` with the `fetch` of the global scope: a window, a dedicated, shared or
+/// service worker, or any other environment with a global `fetch` function, like Node.js.
+fn global_fetch(request: &web_sys::Request) -> Result<js_sys::Promise, Error> {
+    let global = js_sys::global();
+    if let Some(window) = global.dyn_ref::<web_sys::Window>() {
+        return Ok(window.fetch_with_request(request));
+    }
+    // Service workers are worker scopes as well.
+    if let Some(worker) = global.dyn_ref::<web_sys::WorkerGlobalScope>() {
+        return Ok(worker.fetch_with_request(request));
+    }
+    let fetch = Reflect::get(&global, &JsValue::from_str("fetch")).map_err(js_to_error)?;
+    match fetch.dyn_ref::<js_sys::Function>() {
+        Some(fetch) => Ok(fetch
+            .call1(&global, request)
+            .map_err(js_to_error)?
+            .unchecked_into()),
+        None => Err(Error::GlooError(
+            "`fetch` is not available in this JavaScript environment".to_string(),
+        )),
     }
 }
 
+pub(crate) async fn fetch(request: web_sys::Request) -> Result<Response, Error> {
+    #[cfg(feature = "test-utils")]
+    if let Some(response) = crate::http::mock::respond(&request).await {
+        return response;
+    }
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!("fetch", method = %request.method(), url = %request.url());
+    trace_event!(debug, parent: &span, "request sent");
+    let promise = global_fetch(&request)?;
+    let response = JsFuture::from(promise).await.map_err(|e| {
+        let e = js_to_error(e);
+        trace_event!(warn, parent: &span, error = %e, "request failed");
+        e
+    })?;
+    let response = response
+        .dyn_into::<web_sys::Response>()
+        .map(Response::from)
+        .unwrap_or_else(|e| panic!("fetch returned {:?}, not `Response` - this is a bug", e));
+    trace_event!(
+        debug,
+        parent: &span,
+        status = response.status(),
+        "response received"
+    );
+    Ok(response)
+}
+
 impl From<web_sys::Request> for Request {
     fn from(raw: web_sys::Request) -> Self {
-        Request(raw)
+        Request {
+            raw,
+            abort: None,
+            middlewares: Vec::new(),
+        }
     }
 }
 
 impl From<Request> for web_sys::Request {
     fn from(val: Request) -> Self {
-        val.0
+        val.raw
+    }
+}
+
+#[cfg(feature = "http-types")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http-types")))]
+impl TryFrom<http::Request<Vec<u8>>> for Request {
+    type Error = Error;
+
+    /// Converts a request of the `http` crate, with its method, URI, headers and body.
+    ///
+    /// Fails for header values which aren't UTF-8, and for requests the browser rejects, such
+    /// as a `GET` request with a body.
+    fn try_from(request: http::Request<Vec<u8>>) -> Result<Self, Error> {
+        let (parts, body) = request.into_parts();
+        let builder = RequestBuilder::new(&parts.uri.to_string()).method(parts.method);
+        for (name, value) in &parts.headers {
+            let value = value.to_str().map_err(|_| {
+                Error::GlooError(format!("the value of header {name} is not valid UTF-8"))
+            })?;
+            builder.headers.append(name.as_str(), value);
+        }
+        if body.is_empty() {
+            builder.build()
+        } else {
+            builder.body(Uint8Array::from(body.as_slice()))
+        }
     }
 }
 
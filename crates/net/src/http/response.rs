@@ -6,9 +6,14 @@ use wasm_bindgen::{JsCast, JsValue};
 use wasm_bindgen_futures::JsFuture;
 use web_sys::ResponseInit;
 
-use crate::http::Headers;
+use crate::http::body::decompress;
+use crate::http::browser::Headers;
 #[cfg(feature = "json")]
-#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+use crate::http::json::from_json;
+#[cfg(feature = "json")]
+use crate::http::NdJson;
+use crate::http::{BodyStream, Compression, SseStream, TextLines};
+#[cfg(any(feature = "json", feature = "cbor", feature = "rmp"))]
 use serde::de::DeserializeOwned;
 
 /// The [`Request`]'s response
@@ -49,6 +54,15 @@ impl Response {
         self.0.redirected()
     }
 
+    /// Whether this is the redirect response of a request sent with
+    /// [`RequestRedirect::Manual`](web_sys::RequestRedirect::Manual).
+    ///
+    /// Such a response has status `0`, no headers and no body, the redirect's target isn't
+    /// exposed.
+    pub fn is_opaque_redirect(&self) -> bool {
+        self.0.type_() == web_sys::ResponseType::Opaqueredirect
+    }
+
     /// the [HTTP status code](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status) of the
     /// response.
     pub fn status(&self) -> u16 {
@@ -61,6 +75,36 @@ impl Response {
         self.0.ok()
     }
 
+    /// Turns a response with an unsuccessful status into an [`Error::Status`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Request;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Request;
+    /// # async fn no_run() -> Result<(), gloo_net::Error> {
+    /// let text = Request::get("/path")
+    ///     .send()
+    ///     .await?
+    ///     .error_for_status()?
+    ///     .text()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn error_for_status(self) -> Result<Self, Error> {
+        if self.ok() {
+            Ok(self)
+        } else {
+            Err(Error::Status {
+                status: self.status(),
+                url: self.url(),
+            })
+        }
+    }
+
     /// The status message corresponding to the
     /// [HTTP status code](https://developer.mozilla.org/en-US/docs/Web/HTTP/Status) from
     /// `Response::status`.
@@ -71,6 +115,10 @@ impl Response {
         self.0.status_text()
     }
 
+    pub(crate) fn as_raw(&self) -> &web_sys::Response {
+        &self.0
+    }
+
     /// Gets the headers.
     pub fn headers(&self) -> Headers {
         Headers::from_raw(self.0.headers())
@@ -88,7 +136,152 @@ impl Response {
         self.0.body()
     }
 
+    /// Streams the body in chunks as they are received, instead of buffering it like
+    /// [`binary`](Self::binary).
+    ///
+    /// A response without a body yields an empty stream. If the body was already consumed, the
+    /// stream yields a single error.
+    pub fn body_stream(&self) -> BodyStream {
+        BodyStream::new(self.0.body())
+    }
+
+    /// Splits the body into two independent streams of the same chunks, e.g. to hash the body
+    /// or to show a progress while another consumer stores it.
+    ///
+    /// Chunks are buffered until both streams have read them, so a stream which isn't polled,
+    /// or is polled slower than the other, holds the rest of the body in memory. Dropping one of
+    /// the streams doesn't cancel the other. Fails if the body was already consumed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use futures::StreamExt;
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Request;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Request;
+    /// # async fn no_run() {
+    /// let resp = Request::get("/large.bin").send().await.unwrap();
+    /// let (mut counted, stored) = resp.tee_body_stream().unwrap();
+    /// let mut len = 0;
+    /// while let Some(chunk) = counted.next().await {
+    ///     len += chunk.unwrap().len();
+    /// }
+    /// # drop(stored);
+    /// # }
+    /// ```
+    pub fn tee_body_stream(&self) -> Result<(BodyStream, BodyStream), Error> {
+        let body = match self.0.body() {
+            Some(body) => body,
+            None => return Ok((BodyStream::new(None), BodyStream::new(None))),
+        };
+        // `tee` throws for locked streams, which the binding can't catch.
+        if body.locked() || self.0.body_used() {
+            return Err(Error::GlooError(
+                "the body was already consumed".to_string(),
+            ));
+        }
+        let branches = body.tee();
+        Ok((
+            BodyStream::new(Some(branches.get(0).unchecked_into())),
+            BodyStream::new(Some(branches.get(1).unchecked_into())),
+        ))
+    }
+
+    /// Streams the body as lines of text as they are received, e.g. to follow a log or a
+    /// streaming text API.
+    pub fn text_lines(&self) -> Result<TextLines, Error> {
+        TextLines::new(self.body_stream())
+    }
+
+    /// Streams a newline-delimited JSON body, parsing every line as a `T` as it is received.
+    ///
+    /// This is the format of many log and LLM streaming endpoints.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use futures::StreamExt;
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Request;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Request;
+    /// # use serde::Deserialize;
+    /// #[derive(Deserialize)]
+    /// struct Event {
+    ///     message: String,
+    /// }
+    ///
+    /// # async fn no_run() {
+    /// let resp = Request::get("/api/events").send().await.unwrap();
+    /// let mut events = resp.ndjson::<Event>().unwrap();
+    /// while let Some(event) = events.next().await {
+    ///     log(&event.unwrap().message);
+    /// }
+    /// # }
+    /// # fn log(_: &str) {}
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub fn ndjson<T: DeserializeOwned>(&self) -> Result<NdJson<T>, Error> {
+        Ok(NdJson::new(self.text_lines()?))
+    }
+
+    /// Streams a `text/event-stream` body as [`SseEvent`](crate::http::SseEvent)s, for servers
+    /// which need a request `EventSource` can't send, like a `POST` with a body.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use futures::StreamExt;
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Request;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Request;
+    /// # async fn no_run() {
+    /// let resp = Request::post("/api/chat")
+    ///     .header("Accept", "text/event-stream")
+    ///     .body(r#"{"prompt":"Hello"}"#)
+    ///     .unwrap()
+    ///     .send()
+    ///     .await
+    ///     .unwrap();
+    /// let mut events = resp.sse().unwrap();
+    /// while let Some(event) = events.next().await {
+    ///     let event = event.unwrap();
+    ///     log(&event.event, &event.data);
+    /// }
+    /// # }
+    /// # fn log(_: &str, _: &str) {}
+    /// ```
+    pub fn sse(&self) -> Result<SseStream, Error> {
+        Ok(SseStream::new(self.text_lines()?))
+    }
+
+    /// Like [`body_stream`](Self::body_stream), decompressing the body from `format`.
+    ///
+    /// Browsers decompress bodies sent with a `Content-Encoding` header themselves. This is for
+    /// compressed files, like a `.json.gz` file. Brotli is not supported by browsers'
+    /// `DecompressionStream`. Fails if the body was already consumed, or if the browser has no
+    /// `DecompressionStream`. Invalid data is reported as an error of the stream.
+    pub fn body_stream_decompressed(&self, format: Compression) -> Result<BodyStream, Error> {
+        if self.0.body_used() {
+            return Err(Error::GlooError(
+                "the body was already consumed".to_string(),
+            ));
+        }
+        let body = self
+            .0
+            .body()
+            .map(|body| decompress(&body, format))
+            .transpose()?;
+        Ok(BodyStream::new(body))
+    }
+
     /// Reads the response to completion, returning it as `FormData`.
+    ///
+    /// The body has to be `multipart/form-data` or `application/x-www-form-urlencoded`, as
+    /// told by its `Content-Type`, files of multipart bodies are returned as `File`s.
     pub async fn form_data(&self) -> Result<web_sys::FormData, Error> {
         let promise = self.0.form_data().map_err(js_to_error)?;
         let val = JsFuture::from(promise).await.map_err(js_to_error)?;
@@ -99,7 +292,70 @@ impl Response {
     #[cfg(feature = "json")]
     #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
     pub async fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
-        serde_json::from_str::<T>(&self.text().await?).map_err(Error::from)
+        from_json(&self.text().await?)
+    }
+
+    /// Reads the response to completion, parsing it as [CBOR](https://cbor.io).
+    #[cfg(feature = "cbor")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+    pub async fn cbor<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        ciborium::de::from_reader(self.binary().await?.as_slice())
+            .map_err(|e| Error::GlooError(e.to_string()))
+    }
+
+    /// Reads the response to completion, parsing it as [MessagePack](https://msgpack.org).
+    #[cfg(feature = "rmp")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "rmp")))]
+    pub async fn msgpack<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        rmp_serde::from_slice(&self.binary().await?).map_err(|e| Error::GlooError(e.to_string()))
+    }
+
+    /// Reads the response to completion, parsing it as `T` if the status is a success code and
+    /// as the application's error type `E` otherwise.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use gloo_net::http::ApiError;
+    /// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+    /// # use gloo_net::http::browser::Request;
+    /// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+    /// # use gloo_net::http::Request;
+    /// # use serde::Deserialize;
+    /// #[derive(Deserialize)]
+    /// struct User {
+    ///     name: String,
+    /// }
+    ///
+    /// #[derive(Debug, Deserialize)]
+    /// struct Problem {
+    ///     message: String,
+    /// }
+    ///
+    /// # async fn no_run() {
+    /// let resp = Request::get("/api/users/1").send().await.unwrap();
+    /// match resp.json_or_error::<User, Problem>().await {
+    ///     Ok(user) => {}
+    ///     Err(ApiError::Status { status: 404, .. }) => {}
+    ///     Err(e) => {}
+    /// }
+    /// # }
+    /// ```
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    pub async fn json_or_error<T, E>(&self) -> Result<T, ApiError<E>>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned,
+    {
+        if self.ok() {
+            Ok(self.json().await?)
+        } else {
+            Err(ApiError::Status {
+                status: self.status(),
+                body: self.json().await?,
+            })
+        }
     }
 
     /// Reads the response as a String.
@@ -110,6 +366,30 @@ impl Response {
         Ok(String::from(&string))
     }
 
+    /// Reads the response to completion into a `Blob` of its content type, returning an object
+    /// URL for it, e.g. to show a fetched image or to offer it as a download.
+    ///
+    /// The URL is revoked when the returned [`ObjectUrl`](gloo_file::ObjectUrl) and its clones
+    /// are dropped.
+    #[cfg(feature = "file")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "file")))]
+    pub async fn object_url(&self) -> Result<gloo_file::ObjectUrl, Error> {
+        Ok(gloo_file::ObjectUrl::from(self.blob().await?))
+    }
+
+    /// Reads the response to completion into a `Blob` of its content type, e.g. to store a
+    /// download or to read it with a `FileReader`.
+    #[cfg(feature = "file")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "file")))]
+    pub async fn blob(&self) -> Result<gloo_file::Blob, Error> {
+        let promise = self.0.blob().map_err(js_to_error)?;
+        let blob: web_sys::Blob = JsFuture::from(promise)
+            .await
+            .map_err(js_to_error)?
+            .unchecked_into();
+        Ok(gloo_file::Blob::from(blob))
+    }
+
     /// Gets the binary response
     ///
     /// This works by obtaining the response as an `ArrayBuffer`, creating a `Uint8Array` from it
@@ -127,6 +407,125 @@ impl Response {
     }
 }
 
+/// The error of [`Response::json_or_error`].
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ApiError<E> {
+    /// The server responded with an unsuccessful status and an error body.
+    Status {
+        /// The status of the response.
+        status: u16,
+        /// The deserialized body.
+        body: E,
+    },
+    /// The body couldn't be read or deserialized.
+    Error(Error),
+}
+
+#[cfg(feature = "json")]
+impl<E> From<Error> for ApiError<E> {
+    fn from(error: Error) -> Self {
+        ApiError::Error(error)
+    }
+}
+
+#[cfg(feature = "json")]
+impl<E: fmt::Debug> fmt::Display for ApiError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Status { status, body } => write!(f, "HTTP status {status}: {body:?}"),
+            ApiError::Error(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<E: fmt::Debug> std::error::Error for ApiError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ApiError::Status { .. } => None,
+            ApiError::Error(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "http-types")]
+#[cfg_attr(docsrs, doc(cfg(feature = "http-types")))]
+impl Response {
+    /// Reads the response to completion, converting it to a response of the `http` crate.
+    ///
+    /// Fails for responses without a valid status, like
+    /// [opaque redirects](Self::is_opaque_redirect).
+    pub async fn into_http(self) -> Result<http::Response<Vec<u8>>, Error> {
+        let mut builder = http::Response::builder().status(self.status());
+        for (name, value) in self.headers().entries() {
+            builder = builder.header(name, value);
+        }
+        let body = self.binary().await?;
+        builder
+            .body(body)
+            .map_err(|e| Error::GlooError(e.to_string()))
+    }
+}
+
+/// The response to a [`no-cors`](web_sys::RequestMode::NoCors) request, see
+/// [`RequestBuilder::send_no_cors`](crate::http::RequestBuilder::send_no_cors).
+///
+/// Responses to cross-origin `no-cors` requests are opaque: their status is 0, and their
+/// headers and body can't be read. They can still be stored in a `Cache`, or passed on to the
+/// browser, e.g. as the response of a service worker. Responses from the same origin are
+/// readable, see [`into_readable`](Self::into_readable).
+pub struct OpaqueResponse(Response);
+
+impl OpaqueResponse {
+    /// Whether the response is opaque, instead of a same-origin response.
+    pub fn is_opaque(&self) -> bool {
+        self.0.type_() == web_sys::ResponseType::Opaque
+    }
+
+    /// The URL of the request, or the one redirected to.
+    pub fn url(&self) -> String {
+        self.0.url()
+    }
+
+    /// The response, if it isn't opaque.
+    pub fn into_readable(self) -> Result<Response, Self> {
+        if self.is_opaque() {
+            Err(self)
+        } else {
+            Ok(self.0)
+        }
+    }
+
+    /// The underlying `web_sys::Response`, e.g. to put it in a `Cache`.
+    pub fn as_raw(&self) -> &web_sys::Response {
+        self.0.as_raw()
+    }
+}
+
+impl From<Response> for OpaqueResponse {
+    fn from(response: Response) -> Self {
+        Self(response)
+    }
+}
+
+impl From<OpaqueResponse> for web_sys::Response {
+    fn from(response: OpaqueResponse) -> Self {
+        response.0 .0
+    }
+}
+
+impl fmt::Debug for OpaqueResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpaqueResponse")
+            .field("url", &self.url())
+            .field("opaque", &self.is_opaque())
+            .finish_non_exhaustive()
+    }
+}
+
 impl From<web_sys::Response> for Response {
     fn from(raw: web_sys::Response) -> Self {
         Self(raw)
@@ -178,14 +577,14 @@ impl ResponseBuilder {
     }
 
     /// Set the status code
-    pub fn status(mut self, status: u16) -> Self {
-        self.options.status(status);
+    pub fn status(self, status: u16) -> Self {
+        self.options.set_status(status);
         self
     }
 
     /// Set the status text
-    pub fn status_text(mut self, status_text: &str) -> Self {
-        self.options.status_text(status_text);
+    pub fn status_text(self, status_text: &str) -> Self {
+        self.options.set_status_text(status_text);
         self
     }
 
@@ -203,11 +602,11 @@ impl ResponseBuilder {
     }
 
     /// Set the response body and return the response
-    pub fn body<T>(mut self, data: T) -> Result<Response, Error>
+    pub fn body<T>(self, data: T) -> Result<Response, Error>
     where
         T: IntoRawResponse,
     {
-        self.options.headers(&self.headers.into_raw());
+        self.options.set_headers(&self.headers.into_raw());
         let init = self.options;
 
         data.into_raw(init).map(Response).map_err(js_to_error)
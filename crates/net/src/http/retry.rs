@@ -0,0 +1,243 @@
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::http::browser::{Request, Response};
+use crate::http::middleware::{Middleware, MiddlewareFuture, Next};
+use crate::Error;
+use gloo_timers::future::TimeoutFuture;
+
+/// Retries failed requests with exponential backoff.
+///
+/// By default, requests are sent up to 3 times when the server responds with a `5xx` status or
+/// `429 Too Many Requests`, or when the request fails because of a network error. Requests
+/// which were [aborted](Error::Aborted) or [timed out](Error::Timeout) are not retried.
+///
+/// The delay before retry `n` (starting at `0`) is `initial_delay * multiplier^n`, capped at
+/// `max_delay`. With jitter, a random part of up to half of the delay is subtracted so that
+/// clients failing at the same time don't retry at the same time. A `Retry-After` header of a
+/// `429` or `503` response takes precedence, capped at `max_delay` as well.
+///
+/// # Example
+///
+/// ```
+/// # use gloo_net::http::Retry;
+/// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+/// # use gloo_net::http::browser::Request;
+/// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+/// # use gloo_net::http::Request;
+/// # use std::time::Duration;
+/// # async fn no_run() {
+/// let resp = Request::get("/api/flaky")
+///     .retry(Retry::new().max_attempts(5).initial_delay(Duration::from_millis(100)))
+///     .send()
+///     .await
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Retry {
+    max_attempts: u32,
+    initial_delay: Duration,
+    max_delay: Duration,
+    multiplier: u32,
+    jitter: bool,
+    server_errors: bool,
+    too_many_requests: bool,
+    network_errors: bool,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2,
+            jitter: true,
+            server_errors: true,
+            too_many_requests: true,
+            network_errors: true,
+        }
+    }
+}
+
+impl Retry {
+    /// Creates the default retry policy.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how many times a request is sent at most, including the first attempt.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts;
+        self
+    }
+
+    /// Sets the delay before the first retry, 500ms by default.
+    pub fn initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    /// Sets the upper bound for the delay between two attempts, 30s by default.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Sets the factor the delay is multiplied by after every retry, 2 by default.
+    pub fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Sets whether the delays are randomized, enabled by default.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Sets whether responses with a `5xx` status are retried.
+    pub fn retry_server_errors(mut self, retry: bool) -> Self {
+        self.server_errors = retry;
+        self
+    }
+
+    /// Sets whether `429 Too Many Requests` responses are retried.
+    pub fn retry_too_many_requests(mut self, retry: bool) -> Self {
+        self.too_many_requests = retry;
+        self
+    }
+
+    /// Sets whether requests failing because of a network error are retried.
+    pub fn retry_network_errors(mut self, retry: bool) -> Self {
+        self.network_errors = retry;
+        self
+    }
+
+    /// The backoff delay before the given (zero based) retry. `random` is in `[0, 1)`.
+    fn delay(&self, retry: u32, random: f64) -> Duration {
+        let delay = self
+            .initial_delay
+            .checked_mul(self.multiplier.saturating_pow(retry))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        if self.jitter {
+            delay - delay.mul_f64(random / 2.0)
+        } else {
+            delay
+        }
+    }
+
+    /// The delay before trying again after `failures` failed attempts, `None` once the attempts
+    /// are exhausted.
+    pub(crate) fn backoff(&self, failures: u32) -> Option<Duration> {
+        if failures >= self.max_attempts {
+            return None;
+        }
+        Some(self.delay(failures.saturating_sub(1), js_sys::Math::random()))
+    }
+
+    /// Whether a response with the given status is retried.
+    pub(crate) fn retries_status(&self, status: u16) -> bool {
+        match status {
+            429 => self.too_many_requests,
+            500..=599 => self.server_errors,
+            _ => false,
+        }
+    }
+
+    /// Sends `request` until it succeeds or the attempts are exhausted, returning the last
+    /// result.
+    async fn send(self, request: Request, next: Next) -> Result<Response, Error> {
+        let mut retry = 0;
+        loop {
+            let result = next.clone().run(request.try_clone()?).await;
+            if retry + 1 >= self.max_attempts {
+                return result;
+            }
+            let delay = match &result {
+                Ok(response) if self.retries_status(response.status()) => {
+                    let retry_after = match response.status() {
+                        429 | 503 => response
+                            .headers()
+                            .get("Retry-After")
+                            .and_then(|value| retry_after(&value)),
+                        _ => None,
+                    };
+                    retry_after.map_or_else(
+                        || self.delay(retry, js_sys::Math::random()),
+                        |delay| delay.min(self.max_delay),
+                    )
+                }
+                Err(Error::JsError(_)) if self.network_errors => {
+                    self.delay(retry, js_sys::Math::random())
+                }
+                _ => return result,
+            };
+            trace_event!(
+                debug,
+                retry = retry + 1,
+                delay_ms = delay.as_millis() as u64,
+                "retrying request"
+            );
+            let millis = u32::try_from(delay.as_millis()).unwrap_or(u32::MAX);
+            TimeoutFuture::new(millis).await;
+            retry += 1;
+        }
+    }
+}
+
+impl Middleware for Retry {
+    fn handle(&self, request: Request, next: Next) -> MiddlewareFuture {
+        Box::pin(self.send(request, next))
+    }
+}
+
+/// The delay requested by a `Retry-After` header, in seconds or as an HTTP date.
+fn retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = js_sys::Date::parse(value);
+    if date.is_nan() {
+        return None;
+    }
+    let millis = (date - js_sys::Date::now()).max(0.0);
+    Some(Duration::from_millis(millis as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays() {
+        let retry = Retry::new()
+            .initial_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_secs(1))
+            .jitter(false);
+        assert_eq!(retry.delay(0, 0.5), Duration::from_millis(100));
+        assert_eq!(retry.delay(2, 0.5), Duration::from_millis(400));
+        assert_eq!(retry.delay(u32::MAX, 0.5), Duration::from_secs(1));
+
+        let retry = retry.jitter(true);
+        assert_eq!(retry.delay(0, 0.0), Duration::from_millis(100));
+        assert_eq!(retry.delay(0, 0.5), Duration::from_millis(75));
+    }
+
+    #[test]
+    fn retried_statuses() {
+        let retry = Retry::new();
+        assert!(retry.retries_status(503));
+        assert!(retry.retries_status(429));
+        assert!(!retry.retries_status(404));
+        assert!(!retry.retries_status(200));
+        assert!(!retry.retry_too_many_requests(false).retries_status(429));
+    }
+
+    #[test]
+    fn retry_after_seconds() {
+        assert_eq!(retry_after(" 120 "), Some(Duration::from_secs(120)));
+    }
+}
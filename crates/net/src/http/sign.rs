@@ -0,0 +1,102 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+
+use crate::http::browser::{Headers, Request, Response};
+use crate::http::middleware::{Middleware, MiddlewareFuture, Next};
+use crate::http::Method;
+use crate::Error;
+
+type SignFuture = Pin<Box<dyn Future<Output = Result<(), Error>>>>;
+
+/// Signs requests right before they are sent, from their final method, URL, headers and body.
+///
+/// APIs authenticating requests with a signature, like an HMAC of the request or AWS Signature
+/// Version 4, need the bytes of the body. The closure gets a [`SigningRequest`] with a copy of
+/// them, and adds the signature to its [`headers`](SigningRequest::headers), which are the ones
+/// the request is sent with. The body of the request itself is left untouched.
+///
+/// Added to a [`Client`](crate::http::Client) before a [`Retry`](crate::http::Retry), retries are
+/// sent with the signature of the first attempt; added after it, every attempt is signed again,
+/// e.g. for signatures including a timestamp.
+///
+/// # Example
+///
+/// ```
+/// # use gloo_net::http::Signer;
+/// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+/// # use gloo_net::http::browser::Client;
+/// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+/// # use gloo_net::http::Client;
+/// # fn hmac_sha256(key: &[u8], message: &[u8]) -> String { String::new() }
+/// let signer = Signer::new(|request| async move {
+///     let timestamp = js_sys::Date::now().to_string();
+///     let mut message = format!("{}\n{}\n{}\n", request.method, request.url, timestamp).into_bytes();
+///     message.extend_from_slice(&request.body);
+///     request.headers.set("X-Timestamp", &timestamp);
+///     request.headers.set("X-Signature", &hmac_sha256(b"secret", &message));
+///     Ok(())
+/// });
+/// let client = Client::builder().sign(signer).build();
+/// ```
+#[derive(Clone)]
+pub struct Signer {
+    sign: Rc<dyn Fn(SigningRequest) -> SignFuture>,
+}
+
+/// A request about to be signed by a [`Signer`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct SigningRequest {
+    /// The method of the request.
+    pub method: Method,
+    /// The URL of the request, resolved against the base URL of the client.
+    pub url: String,
+    /// The headers of the request, the signature is added to them.
+    pub headers: Headers,
+    /// The body of the request, empty if it has none.
+    pub body: Vec<u8>,
+}
+
+impl Signer {
+    /// Creates the middleware, signing requests with `sign`.
+    pub fn new<F, Fut>(sign: F) -> Self
+    where
+        F: Fn(SigningRequest) -> Fut + 'static,
+        Fut: Future<Output = Result<(), Error>> + 'static,
+    {
+        Self {
+            sign: Rc::new(move |request| Box::pin(sign(request))),
+        }
+    }
+
+    async fn send(self, request: Request, next: Next) -> Result<Response, Error> {
+        // Reading a copy leaves the body of the request to be sent.
+        let body = match request.body() {
+            Some(_) => request.try_clone()?.binary().await?,
+            None => Vec::new(),
+        };
+        let signing = SigningRequest {
+            method: request.method(),
+            url: request.url(),
+            headers: request.headers(),
+            body,
+        };
+        trace_event!(debug, url = %signing.url, "signing request");
+        (self.sign)(signing).await?;
+        next.run(request).await
+    }
+}
+
+impl Middleware for Signer {
+    fn handle(&self, request: Request, next: Next) -> MiddlewareFuture {
+        Box::pin(self.clone().send(request, next))
+    }
+}
+
+impl fmt::Debug for Signer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Signer").finish_non_exhaustive()
+    }
+}
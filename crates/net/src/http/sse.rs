@@ -0,0 +1,182 @@
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use crate::http::TextLines;
+use crate::Error;
+use futures_core::{ready, Stream};
+
+/// An event of a `text/event-stream` body, yielded by [`SseStream`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The event type, `message` unless the event has an `event` field.
+    pub event: String,
+    /// The `data` fields of the event, joined by newlines.
+    pub data: String,
+    /// The last event ID received so far, which is sent in a `Last-Event-ID` header when
+    /// reconnecting.
+    pub id: Option<String>,
+    /// The reconnection time last requested by the server with a `retry` field.
+    pub retry: Option<Duration>,
+}
+
+/// A [`Stream`] of the events of a `text/event-stream` body, created by
+/// [`Response::sse`](crate::http::Response::sse).
+///
+/// Unlike `EventSource`, this works with any request, e.g. a `POST` with a body or custom
+/// headers. It doesn't reconnect, the stream ends with the body. An event which isn't
+/// terminated by a blank line when the body ends is dropped.
+pub struct SseStream {
+    lines: TextLines,
+    parser: Parser,
+}
+
+impl SseStream {
+    pub(crate) fn new(lines: TextLines) -> Self {
+        Self {
+            lines,
+            parser: Parser::default(),
+        }
+    }
+
+    /// The last event ID received so far.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.parser.id.as_deref()
+    }
+
+    /// The reconnection time last requested by the server.
+    pub fn retry(&self) -> Option<Duration> {
+        self.parser.retry
+    }
+}
+
+impl Stream for SseStream {
+    type Item = Result<SseEvent, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match ready!(Pin::new(&mut this.lines).poll_next(cx)) {
+                Some(Ok(line)) => {
+                    if let Some(event) = this.parser.line(&line) {
+                        return Poll::Ready(Some(Ok(event)));
+                    }
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl fmt::Debug for SseStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseStream")
+            .field("last_event_id", &self.parser.id)
+            .field("retry", &self.parser.retry)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The `text/event-stream` parser, fed a line at a time.
+#[derive(Default)]
+struct Parser {
+    event: Option<String>,
+    data: Option<String>,
+    id: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl Parser {
+    /// Handles `line`, returning the event it completes, if any.
+    fn line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        let (field, value) = match line.split_once(':') {
+            Some(("", _)) => return None,
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = Some(value.to_string()),
+            "data" => match &mut self.data {
+                Some(data) => {
+                    data.push('\n');
+                    data.push_str(value);
+                }
+                None => self.data = Some(value.to_string()),
+            },
+            "id" if !value.contains('\0') => {
+                self.id = Some(value.to_string()).filter(|id| !id.is_empty())
+            }
+            "retry" => {
+                if let Ok(millis) = value.parse::<u64>() {
+                    self.retry = Some(Duration::from_millis(millis));
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = self.event.take();
+        let data = self.data.take()?;
+        Some(SseEvent {
+            event: event
+                .filter(|event| !event.is_empty())
+                .unwrap_or_else(|| "message".to_string()),
+            data,
+            id: self.id.clone(),
+            retry: self.retry,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(body: &str) -> Vec<SseEvent> {
+        let mut parser = Parser::default();
+        body.lines().filter_map(|line| parser.line(line)).collect()
+    }
+
+    #[test]
+    fn parses_events() {
+        let events = parse(
+            ": comment\n\
+             data: first\n\
+             \n\
+             event: update\n\
+             id: 7\n\
+             retry: 3000\n\
+             data:a\n\
+             data\n\
+             data:  b\n\
+             \n\
+             id: 8\n\
+             \n\
+             data: unterminated\n",
+        );
+        assert_eq!(
+            events,
+            [
+                SseEvent {
+                    event: "message".to_string(),
+                    data: "first".to_string(),
+                    id: None,
+                    retry: None,
+                },
+                SseEvent {
+                    event: "update".to_string(),
+                    data: "a\n\n b".to_string(),
+                    id: Some("7".to_string()),
+                    retry: Some(Duration::from_secs(3)),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use crate::http::browser::Client;
+use crate::http::conditional::CachedResponse;
+use crate::Error;
+use futures_core::Stream;
+use serde::de::DeserializeOwned;
+
+/// The cached value of a JSON API, and a [`Stream`] of the fresh one, created by
+/// [`Client::get_json_swr`](crate::http::Client::get_json_swr).
+///
+/// The stream yields the fresh value once it is received, if it differs from the cached one,
+/// and ends. It yields nothing if the cached value is still current, and the error if the
+/// refresh fails. The refresh runs in a task of its own, so it completes and updates the cache
+/// even if the stream is never polled or dropped.
+#[must_use = "the fresh value is only received by polling the stream"]
+pub struct StaleWhileRevalidate<T> {
+    cached: Option<T>,
+    refresh: Rc<RefCell<Refresh<T>>>,
+}
+
+struct Refresh<T> {
+    result: Option<Result<T, Error>>,
+    done: bool,
+    waker: Option<Waker>,
+}
+
+impl<T: DeserializeOwned + 'static> StaleWhileRevalidate<T> {
+    pub(crate) fn new(client: Client, url: &str) -> Self {
+        let key = client.resolve(url);
+        let stored = client.validator_store().get(&key);
+        let cached = stored
+            .as_ref()
+            .and_then(|stored| serde_json::from_slice(&stored.body).ok());
+        let refresh = Rc::new(RefCell::new(Refresh {
+            result: None,
+            done: false,
+            waker: None,
+        }));
+
+        let shared = Rc::clone(&refresh);
+        let url = url.to_string();
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = match revalidate(&client, &key, &url, stored).await {
+                Ok(Some(body)) => Some(serde_json::from_slice(&body).map_err(Error::from)),
+                Ok(None) => None,
+                Err(e) => Some(Err(e)),
+            };
+            let mut refresh = shared.borrow_mut();
+            refresh.result = result;
+            refresh.done = true;
+            if let Some(waker) = refresh.waker.take() {
+                waker.wake();
+            }
+        });
+
+        Self { cached, refresh }
+    }
+}
+
+impl<T> StaleWhileRevalidate<T> {
+    /// The value cached by a previous request, if any.
+    pub fn cached(&self) -> Option<&T> {
+        self.cached.as_ref()
+    }
+
+    /// Takes the cached value out, e.g. to render it while waiting for the fresh one.
+    pub fn take_cached(&mut self) -> Option<T> {
+        self.cached.take()
+    }
+}
+
+/// Requests `url`, storing its body under `key` and returning it if it differs from `stored`.
+async fn revalidate(
+    client: &Client,
+    key: &str,
+    url: &str,
+    stored: Option<CachedResponse>,
+) -> Result<Option<Vec<u8>>, Error> {
+    let mut builder = client.get(url).header("Accept", "application/json");
+    if let Some(stored) = &stored {
+        for (name, value) in stored.conditional_headers() {
+            builder = builder.header(name, value);
+        }
+    }
+    let response = builder.send().await?;
+    if response.status() == 304 && stored.is_some() {
+        trace_event!(debug, url = %key, "not modified");
+        return Ok(None);
+    }
+    let response = response.error_for_status()?;
+    let (etag, last_modified) = CachedResponse::validators(&response).unwrap_or_default();
+    let content_type = response.headers().get("Content-Type");
+    let body = response.binary().await?;
+    if stored.is_some_and(|stored| stored.body == body) {
+        return Ok(None);
+    }
+    client.validator_store().set(
+        key,
+        CachedResponse {
+            etag,
+            last_modified,
+            content_type,
+            body: body.clone(),
+        },
+    );
+    Ok(Some(body))
+}
+
+impl<T> Stream for StaleWhileRevalidate<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut refresh = self.refresh.borrow_mut();
+        if refresh.done {
+            Poll::Ready(refresh.result.take())
+        } else {
+            refresh.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl<T> Unpin for StaleWhileRevalidate<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for StaleWhileRevalidate<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StaleWhileRevalidate")
+            .field("cached", &self.cached)
+            .field("done", &self.refresh.borrow().done)
+            .finish_non_exhaustive()
+    }
+}
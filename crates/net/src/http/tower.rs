@@ -0,0 +1,77 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use js_sys::Uint8Array;
+use tower_service::Service;
+
+use crate::http::browser::Client;
+use crate::Error;
+
+type ServiceFuture = Pin<Box<dyn Future<Output = Result<http::Response<Bytes>, Error>>>>;
+
+/// Sends requests of the `http` crate with the client, so that `tower` middleware can be
+/// stacked on top of it.
+///
+/// URIs are resolved against the base URL of the client, and requests get its default headers
+/// and go through its [`Middleware`](crate::http::Middleware)s. Bodies are sent and received
+/// whole. The futures aren't `Send`, like everything in the browser, so the client works with
+/// layers polling their inner service in place, like `tower`'s `retry` and `limit` layers, but not
+/// with the ones spawning tasks, like `buffer`.
+///
+/// # Example
+///
+/// ```
+/// # use bytes::Bytes;
+/// # #[cfg(all(feature = "native-http", not(target_arch = "wasm32")))]
+/// # use gloo_net::http::browser::Client;
+/// # #[cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+/// # use gloo_net::http::Client;
+/// # use tower_service::Service;
+/// # async fn no_run() {
+/// let mut client = Client::builder().base_url("https://api.example.com").build();
+/// let request = http::Request::get("/users").body(Bytes::new()).unwrap();
+/// let response = client.call(request).await.unwrap();
+/// assert_eq!(response.status(), 200);
+/// # }
+/// ```
+impl Service<http::Request<Bytes>> for Client {
+    type Response = http::Response<Bytes>;
+    type Error = Error;
+    type Future = ServiceFuture;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<Bytes>) -> Self::Future {
+        let client = self.clone();
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let mut builder = client.request(parts.method, &parts.uri.to_string());
+            for name in parts.headers.keys() {
+                let values = parts
+                    .headers
+                    .get_all(name)
+                    .iter()
+                    .map(|value| {
+                        value.to_str().map_err(|_| {
+                            Error::GlooError(format!(
+                                "the value of header {name} is not valid UTF-8"
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                builder = builder.header(name.as_str(), &values.join(", "));
+            }
+            let request = if body.is_empty() {
+                builder.build()?
+            } else {
+                builder.body(Uint8Array::from(&body[..]))?
+            };
+            let response = request.send().await?.into_http().await?;
+            Ok(response.map(Bytes::from))
+        })
+    }
+}
@@ -0,0 +1,44 @@
+/// `url` appended to `base`, unless it has a scheme.
+pub(crate) fn join_url(base: &str, url: &str) -> String {
+    let has_scheme = url.split_once(':').is_some_and(|(scheme, _)| {
+        !scheme.is_empty()
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    if has_scheme || url.starts_with("//") {
+        return url.to_string();
+    }
+    match (base.ends_with('/'), url.starts_with('/')) {
+        (true, true) => format!("{}{}", base, &url[1..]),
+        (false, false) if !url.is_empty() && !url.starts_with('?') => format!("{base}/{url}"),
+        _ => format!("{base}{url}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn joins_urls() {
+        let base = "https://api.example.com/v1";
+        assert_eq!(join_url(base, "/users"), "https://api.example.com/v1/users");
+        assert_eq!(join_url(base, "users"), "https://api.example.com/v1/users");
+        assert_eq!(join_url("/api/", "/users"), "/api/users");
+        assert_eq!(
+            join_url(base, "?page=2"),
+            "https://api.example.com/v1?page=2"
+        );
+        assert_eq!(join_url(base, ""), base);
+        assert_eq!(
+            join_url(base, "https://cdn.example.com/a"),
+            "https://cdn.example.com/a"
+        );
+        assert_eq!(join_url(base, "//cdn.example.com/a"), "//cdn.example.com/a");
+        assert_eq!(
+            join_url(base, "/users?q=a:b"),
+            "https://api.example.com/v1/users?q=a:b"
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use js_sys::{Object, Promise, Reflect, Uint8Array};
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    AbortSignal, ProgressEvent, RequestCredentials, XmlHttpRequest, XmlHttpRequestResponseType,
+};
+
+use crate::http::browser::{Headers, Response};
+use crate::{js_to_error, Error};
+
+/// How much of the body of a request was sent, passed to the callback of
+/// [`RequestBuilder::send_with_upload_progress`](crate::http::RequestBuilder::send_with_upload_progress).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct UploadProgress {
+    /// The bytes of the body sent so far.
+    pub sent: u64,
+    /// The size of the body, if the browser knows it.
+    pub total: Option<u64>,
+}
+
+/// The listeners of a request in flight, removed when it completes or is dropped.
+struct InFlight {
+    xhr: XmlHttpRequest,
+    signal: AbortSignal,
+    abort: Closure<dyn FnMut()>,
+    _progress: Closure<dyn FnMut(ProgressEvent)>,
+    done: bool,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let _ = self
+            .signal
+            .remove_event_listener_with_callback("abort", self.abort.as_ref().unchecked_ref());
+        if let Ok(upload) = self.xhr.upload() {
+            upload.set_onprogress(None);
+        }
+        if !self.done {
+            let _ = self.xhr.abort();
+        }
+    }
+}
+
+/// Sends `request` with `XMLHttpRequest`, calling `on_progress` while its body is uploaded.
+pub(crate) async fn send(
+    request: web_sys::Request,
+    on_progress: Box<dyn Fn(UploadProgress)>,
+) -> Result<Response, Error> {
+    let signal = request.signal();
+    if signal.aborted() {
+        return Err(Error::Aborted);
+    }
+    #[cfg(feature = "tracing")]
+    let span = tracing::debug_span!("xhr", method = %request.method(), url = %request.url());
+    // `XMLHttpRequest` can't send streams, and reading the body turns any other kind into bytes
+    // sent with the `Content-Type` the `Request` computed for it.
+    let body = match request.body() {
+        Some(_) => {
+            let buffer = request.array_buffer().map_err(js_to_error)?;
+            Some(JsFuture::from(buffer).await.map_err(js_to_error)?)
+        }
+        None => None,
+    };
+
+    let xhr = XmlHttpRequest::new().map_err(js_to_error)?;
+    xhr.open_with_async(&request.method(), &request.url(), true)
+        .map_err(js_to_error)?;
+    for (name, value) in Headers::from_raw(request.headers()).entries() {
+        xhr.set_request_header(&name, &value).map_err(js_to_error)?;
+    }
+    xhr.set_with_credentials(request.credentials() == RequestCredentials::Include);
+    xhr.set_response_type(XmlHttpRequestResponseType::Arraybuffer);
+
+    let progress = Closure::wrap(Box::new(move |event: ProgressEvent| {
+        on_progress(UploadProgress {
+            sent: event.loaded() as u64,
+            total: Some(event.total() as u64).filter(|_| event.length_computable()),
+        })
+    }) as Box<dyn FnMut(ProgressEvent)>);
+    // Upload events are only fired if a listener is registered before sending.
+    xhr.upload()
+        .map_err(js_to_error)?
+        .set_onprogress(Some(progress.as_ref().unchecked_ref()));
+    let target = xhr.clone();
+    let abort = Closure::wrap(Box::new(move || {
+        let _ = target.abort();
+    }) as Box<dyn FnMut()>);
+    signal
+        .add_event_listener_with_callback("abort", abort.as_ref().unchecked_ref())
+        .map_err(js_to_error)?;
+    let mut in_flight = InFlight {
+        xhr: xhr.clone(),
+        signal,
+        abort,
+        _progress: progress,
+        done: false,
+    };
+
+    let loaded = Promise::new(&mut |resolve, reject| {
+        xhr.set_onload(Some(&resolve));
+        xhr.set_onerror(Some(&reject));
+        xhr.set_onabort(Some(&reject));
+    });
+    match &body {
+        Some(body) => xhr.send_with_opt_buffer_source(Some(body.unchecked_ref())),
+        None => xhr.send(),
+    }
+    .map_err(js_to_error)?;
+    trace_event!(debug, parent: &span, "request sent");
+    let result = JsFuture::from(loaded).await;
+    in_flight.done = true;
+    drop(in_flight);
+    result.map_err(|event| {
+        let aborted = event
+            .dyn_ref::<ProgressEvent>()
+            .is_some_and(|event| event.type_() == "abort");
+        let e = if aborted {
+            Error::Aborted
+        } else {
+            // Like `fetch`, network errors are reported as a `TypeError`.
+            js_to_error(js_sys::TypeError::new("XMLHttpRequest failed").into())
+        };
+        trace_event!(warn, parent: &span, error = %e, "request failed");
+        e
+    })?;
+
+    let headers = Headers::new();
+    for line in xhr.get_all_response_headers().map_err(js_to_error)?.lines() {
+        if let Some((name, value)) = line.split_once(':') {
+            headers.append(name.trim(), value.trim());
+        }
+    }
+    let body = xhr.response().map_err(js_to_error)?;
+    let mut body = if body.is_null() {
+        Vec::new()
+    } else {
+        Uint8Array::new(&body).to_vec()
+    };
+    let response = Response::builder()
+        .status(xhr.status().map_err(js_to_error)?)
+        .status_text(&xhr.status_text().map_err(js_to_error)?)
+        .headers(headers)
+        .body(Some(body.as_mut_slice()).filter(|body| !body.is_empty()))?;
+    // Constructed responses have an empty URL, shadow it with the one the request ended at.
+    let descriptor = Object::new();
+    Reflect::set(&descriptor, &"value".into(), &xhr.response_url().into()).map_err(js_to_error)?;
+    Object::define_property(response.as_raw(), &JsValue::from_str("url"), &descriptor);
+    trace_event!(
+        debug,
+        parent: &span,
+        status = response.status(),
+        "response received"
+    );
+    Ok(response)
+}
@@ -1,7 +1,19 @@
 //! HTTP requests library for WASM apps. It provides idiomatic Rust bindings for the `web_sys`
 //! `fetch` and `WebSocket` API.
 //!
-//! See module level documentation for [`http`] and [`websocket`] to learn more.
+//! See module level documentation for [`http`] and [`websocket`] to learn more. The `webrtc`
+//! feature adds peer-to-peer data channels, see the `webrtc` module, and the `realtime` feature a
+//! transport falling back from WebSockets to Server-Sent Events and
+//! long-polling.
+//!
+//! The [`graphql`] module runs GraphQL queries and mutations over HTTP, and subscriptions over
+//! WebSockets with `websocket::graphql`.
+//!
+//! [`UrlBuilder`] escapes the path segments and query parameters of the URLs requested or
+//! connected to.
+//!
+//! With the `tracing` feature, connections, messages and fetch requests are reported as
+//! [`tracing`](https://docs.rs/tracing) spans and events, e.g. to `tracing-wasm`.
 
 #![deny(
     missing_docs,
@@ -10,15 +22,36 @@
 )]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+/// Emits a `tracing` event at `$level` (`trace`, `debug`, ...) with the `tracing` feature, and
+/// nothing otherwise. The arguments are only evaluated when the feature is enabled.
+#[allow(unused_macros)]
+macro_rules! trace_event {
+    ($level:ident, $($args:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($args)+);
+    };
+}
+
 mod error;
 #[cfg(feature = "eventsource")]
 #[cfg_attr(docsrs, doc(cfg(feature = "eventsource")))]
 pub mod eventsource;
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub mod graphql;
 #[cfg(feature = "http")]
 #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
 pub mod http;
+#[cfg(feature = "realtime")]
+#[cfg_attr(docsrs, doc(cfg(feature = "realtime")))]
+pub mod realtime;
+mod url;
+#[cfg(feature = "webrtc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "webrtc")))]
+pub mod webrtc;
 #[cfg(feature = "websocket")]
 #[cfg_attr(docsrs, doc(cfg(feature = "websocket")))]
 pub mod websocket;
 
 pub use error::*;
+pub use url::UrlBuilder;
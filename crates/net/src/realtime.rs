@@ -0,0 +1,492 @@
+//! One interface for WebSockets, Server-Sent Events and HTTP long-polling.
+//!
+//! Applications written against [`Transport`] work over whichever connection is available.
+//! `<dyn Transport>::connect_best` tries a WebSocket first, which
+//! corporate proxies sometimes block, then falls back to an `EventSource` and finally to
+//! long-polling.
+//!
+//! The fallbacks need the server to speak a simple protocol on the same URL:
+//!
+//! - **Server-Sent Events**: `GET` with `Accept: text/event-stream` streams the messages as
+//!   events without an `event` field, i.e. `message` events. Messages are sent as `POST`s.
+//! - **Long-polling**: `GET` waits for the next message and returns it as the body, with
+//!   `Content-Type: application/octet-stream` for binary ones, or returns `204 No Content` when
+//!   there is none before a timeout. Messages are sent as `POST`s.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::realtime::{Transport, TransportKind};
+//! use gloo_net::websocket::Message;
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::{SinkExt, StreamExt};
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! spawn_local(async move {
+//!     let mut transport = <dyn Transport>::connect_best("https://example.com/live")
+//!         .await
+//!         .unwrap();
+//!     console_log!(format!("connected over {:?}", transport.kind()));
+//!     transport.send(Message::Text("hello".to_string())).await.unwrap();
+//!     while let Some(msg) = transport.next().await {
+//!         console_log!(format!("1. {:?}", msg))
+//!     }
+//! })
+//! # }
+//! ```
+use crate::eventsource::futures::{EventSource, EventSourceSubscription};
+use crate::eventsource::EventSourceError;
+use crate::http::browser::Request;
+use crate::websocket::browser::WebSocket;
+use crate::websocket::{Message, WebSocketError};
+use futures_core::{ready, Stream};
+use futures_sink::Sink;
+use futures_util::future::{select, Either};
+use gloo_timers::future::TimeoutFuture;
+use gloo_utils::errors::JsError;
+use std::convert::TryFrom;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use thiserror::Error as ThisError;
+
+/// How long `connect_best` waits for a WebSocket or an `EventSource` to open.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The kind of connection behind a [`Transport`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TransportKind {
+    /// A [`WsTransport`]
+    WebSocket,
+    /// A [`SseTransport`]
+    EventSource,
+    /// A [`LongPollTransport`]
+    LongPolling,
+}
+
+/// Errors of a [`Transport`].
+#[derive(Debug, ThisError)]
+#[non_exhaustive]
+pub enum TransportError {
+    /// The WebSocket failed or was closed.
+    #[error("{0}")]
+    WebSocket(WebSocketError),
+    /// The `EventSource` failed.
+    #[error("{0}")]
+    EventSource(EventSourceError),
+    /// A request failed.
+    #[error("{0}")]
+    Http(crate::Error),
+    /// The server answered a request with an unsuccessful status.
+    #[error("unexpected HTTP status {0}")]
+    Status(u16),
+    /// The connection couldn't be created.
+    #[error("{0}")]
+    Js(JsError),
+}
+
+/// A bidirectional stream of [`Message`]s, implemented by all realtime transports.
+pub trait Transport:
+    Stream<Item = Result<Message, TransportError>> + Sink<Message, Error = TransportError> + Unpin
+{
+    /// The kind of the connection.
+    fn kind(&self) -> TransportKind;
+}
+
+impl dyn Transport {
+    /// Connects to `url` over the best transport which works, see the [module
+    /// documentation](self).
+    ///
+    /// `url` may use the `http(s)` or the `ws(s)` scheme, the other one is derived from it. Every
+    /// transport which needs a connection gets 5 seconds to open it. Long-polling is only chosen
+    /// if its first poll doesn't fail within that time, otherwise the error of the poll is
+    /// returned.
+    pub async fn connect_best(url: &str) -> Result<Box<dyn Transport>, TransportError> {
+        Self::connect_best_with_timeout(url, CONNECT_TIMEOUT).await
+    }
+
+    /// Like `connect_best`, with the time every transport gets to open a
+    /// connection.
+    pub async fn connect_best_with_timeout(
+        url: &str,
+        timeout: Duration,
+    ) -> Result<Box<dyn Transport>, TransportError> {
+        if let Ok(ws) = WebSocket::builder(&ws_url(url))
+            .open_timeout(timeout)
+            .open()
+        {
+            if ws.connected().await.is_ok() {
+                return Ok(Box::new(WsTransport::new(ws)));
+            }
+        }
+
+        let url = http_url(url);
+        let millis = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+        if let Ok(sse) = SseTransport::open(&url) {
+            let connected = Box::pin(sse.es.connected());
+            if let Either::Left((Ok(()), _)) = select(connected, TimeoutFuture::new(millis)).await {
+                return Ok(Box::new(sse));
+            }
+        }
+
+        // A poll still waiting after the timeout is kept, the server may hold it until there is
+        // a message.
+        let mut long_poll = LongPollTransport::new(&url);
+        let first: ReceiveFuture = Box::pin(receive(url));
+        match select(first, TimeoutFuture::new(millis)).await {
+            Either::Left((Ok(msg), _)) => long_poll.received = msg,
+            Either::Left((Err(e), _)) => return Err(e),
+            Either::Right((_, first)) => long_poll.receiving = Some(first),
+        }
+        Ok(Box::new(long_poll))
+    }
+}
+
+/// A [`Transport`] over a [`WebSocket`].
+pub struct WsTransport {
+    ws: WebSocket,
+}
+
+impl WsTransport {
+    /// Wraps the socket.
+    pub fn new(ws: WebSocket) -> Self {
+        Self { ws }
+    }
+
+    /// Unwraps the socket.
+    pub fn into_inner(self) -> WebSocket {
+        self.ws
+    }
+}
+
+impl fmt::Debug for WsTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WsTransport")
+            .field("state", &self.ws.state())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Transport for WsTransport {
+    fn kind(&self) -> TransportKind {
+        TransportKind::WebSocket
+    }
+}
+
+impl Stream for WsTransport {
+    type Item = Result<Message, TransportError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.ws)
+            .poll_next(cx)
+            .map(|msg| msg.map(|msg| msg.map_err(TransportError::WebSocket)))
+    }
+}
+
+impl Sink<Message> for WsTransport {
+    type Error = TransportError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Message>::poll_ready(Pin::new(&mut self.ws), cx).map_err(TransportError::WebSocket)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        Pin::new(&mut self.ws)
+            .start_send(item)
+            .map_err(TransportError::WebSocket)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Message>::poll_flush(Pin::new(&mut self.ws), cx).map_err(TransportError::WebSocket)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Message>::poll_close(Pin::new(&mut self.ws), cx).map_err(TransportError::WebSocket)
+    }
+}
+
+/// A [`Transport`] receiving `message` events from an [`EventSource`] and sending messages as
+/// `POST` requests to the same URL.
+///
+/// All received messages are [`Message::Text`].
+pub struct SseTransport {
+    es: EventSource,
+    subscription: EventSourceSubscription,
+    sender: Poster,
+}
+
+impl SseTransport {
+    /// Opens an `EventSource` for `url`.
+    ///
+    /// This fails under the same conditions as [`EventSource::new`].
+    pub fn open(url: &str) -> Result<Self, JsError> {
+        let mut es = EventSource::new(url)?;
+        let subscription = es.subscribe("message")?;
+        Ok(Self {
+            es,
+            subscription,
+            sender: Poster::new(url),
+        })
+    }
+}
+
+impl Transport for SseTransport {
+    fn kind(&self) -> TransportKind {
+        TransportKind::EventSource
+    }
+}
+
+impl Stream for SseTransport {
+    type Item = Result<Message, TransportError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let event = ready!(Pin::new(&mut self.subscription).poll_next(cx));
+        Poll::Ready(event.map(|event| match event {
+            Ok((_, msg)) => Ok(Message::Text(msg.data().as_string().unwrap_or_default())),
+            Err(e) => Err(TransportError::EventSource(e)),
+        }))
+    }
+}
+
+impl Sink<Message> for SseTransport {
+    type Error = TransportError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.sender.poll_flush(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        self.sender.start_send(item);
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.sender.poll_flush(cx)
+    }
+
+    /// Waits for the last message to be sent. The `EventSource` is closed when this is dropped.
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.sender.poll_flush(cx)
+    }
+}
+
+impl fmt::Debug for SseTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SseTransport")
+            .field("es", &self.es)
+            .finish_non_exhaustive()
+    }
+}
+
+type ReceiveFuture = Pin<Box<dyn Future<Output = Result<Option<Message>, TransportError>>>>;
+
+/// A [`Transport`] polling `url` with `GET` requests for messages and sending messages as `POST`
+/// requests, see the [module documentation](self).
+///
+/// A failed poll is yielded as an error, polling the stream again retries it.
+pub struct LongPollTransport {
+    url: String,
+    receiving: Option<ReceiveFuture>,
+    /// A message received while probing the server in `connect_best`.
+    received: Option<Message>,
+    sender: Poster,
+    closed: bool,
+}
+
+impl LongPollTransport {
+    /// Creates a transport polling `url`. The first request is sent when the stream is polled.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            receiving: None,
+            received: None,
+            sender: Poster::new(url),
+            closed: false,
+        }
+    }
+}
+
+impl Transport for LongPollTransport {
+    fn kind(&self) -> TransportKind {
+        TransportKind::LongPolling
+    }
+}
+
+async fn receive(url: String) -> Result<Option<Message>, TransportError> {
+    let response = Request::get(&url)
+        .send()
+        .await
+        .map_err(TransportError::Http)?;
+    if response.status() == 204 {
+        return Ok(None);
+    }
+    if !response.ok() {
+        return Err(TransportError::Status(response.status()));
+    }
+    let binary = response
+        .headers()
+        .get("Content-Type")
+        .is_some_and(|ty| ty.starts_with("application/octet-stream"));
+    let msg = if binary {
+        Message::Bytes(response.binary().await.map_err(TransportError::Http)?)
+    } else {
+        Message::Text(response.text().await.map_err(TransportError::Http)?)
+    };
+    Ok(Some(msg))
+}
+
+impl Stream for LongPollTransport {
+    type Item = Result<Message, TransportError>;
+
+    /// The stream ends once the sink is closed.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if this.closed {
+                return Poll::Ready(None);
+            }
+            if let Some(msg) = this.received.take() {
+                return Poll::Ready(Some(Ok(msg)));
+            }
+            let url = &this.url;
+            let receiving = this
+                .receiving
+                .get_or_insert_with(|| Box::pin(receive(url.clone())));
+            let result = ready!(receiving.as_mut().poll(cx));
+            this.receiving = None;
+            match result {
+                Ok(Some(msg)) => return Poll::Ready(Some(Ok(msg))),
+                Ok(None) => {}
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+        }
+    }
+}
+
+impl Sink<Message> for LongPollTransport {
+    type Error = TransportError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.sender.poll_flush(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        self.sender.start_send(item);
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.sender.poll_flush(cx)
+    }
+
+    /// Waits for the last message to be sent and stops polling.
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.sender.poll_flush(cx))?;
+        self.closed = true;
+        self.receiving = None;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl fmt::Debug for LongPollTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LongPollTransport")
+            .field("url", &self.url)
+            .field("closed", &self.closed)
+            .finish_non_exhaustive()
+    }
+}
+
+type SendFuture = Pin<Box<dyn Future<Output = Result<(), TransportError>>>>;
+
+/// Sends messages as `POST` requests, one at a time.
+struct Poster {
+    url: String,
+    sending: Option<SendFuture>,
+}
+
+impl Poster {
+    fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            sending: None,
+        }
+    }
+
+    fn start_send(&mut self, msg: Message) {
+        let request = Request::post(&self.url);
+        let request = match msg {
+            Message::Text(text) => request
+                .header("Content-Type", "text/plain;charset=UTF-8")
+                .body(text),
+            Message::Bytes(bytes) => request
+                .header("Content-Type", "application/octet-stream")
+                .body(js_sys::Uint8Array::from(bytes.as_slice())),
+        };
+        self.sending = Some(Box::pin(async move {
+            let response = request
+                .map_err(TransportError::Http)?
+                .send()
+                .await
+                .map_err(TransportError::Http)?;
+            if response.ok() {
+                Ok(())
+            } else {
+                Err(TransportError::Status(response.status()))
+            }
+        }));
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), TransportError>> {
+        if let Some(sending) = &mut self.sending {
+            let result = ready!(sending.as_mut().poll(cx));
+            self.sending = None;
+            result?;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// `url` with an `http(s)` scheme replaced by the matching `ws(s)` one.
+fn ws_url(url: &str) -> String {
+    replace_scheme(url, &[("https:", "wss:"), ("http:", "ws:")])
+}
+
+/// `url` with a `ws(s)` scheme replaced by the matching `http(s)` one.
+fn http_url(url: &str) -> String {
+    replace_scheme(url, &[("wss:", "https:"), ("ws:", "http:")])
+}
+
+fn replace_scheme(url: &str, schemes: &[(&str, &str)]) -> String {
+    for (from, to) in schemes {
+        if let Some(scheme) = url.get(..from.len()) {
+            if scheme.eq_ignore_ascii_case(from) {
+                return format!("{to}{}", &url[from.len()..]);
+            }
+        }
+    }
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_urls() {
+        assert_eq!(ws_url("https://example.com/live"), "wss://example.com/live");
+        assert_eq!(ws_url("HTTP://example.com"), "ws://example.com");
+        assert_eq!(ws_url("wss://example.com"), "wss://example.com");
+        assert_eq!(
+            http_url("wss://example.com/live"),
+            "https://example.com/live"
+        );
+        assert_eq!(http_url("ws://example.com"), "http://example.com");
+        assert_eq!(http_url("/live"), "/live");
+    }
+}
@@ -0,0 +1,186 @@
+use std::fmt::{self, Write};
+
+/// Builds URLs from a base, path segments, query parameters and a fragment, escaping each part.
+///
+/// Formatting URLs by hand breaks as soon as a value contains a `/`, `?`, `&`, `#` or a space.
+/// The builder percent-encodes path segments, query names and values, and the fragment, so they
+/// reach the server as they were given. The base is used as it is, and may already contain a
+/// path and query parameters, which are kept.
+///
+/// The URL can be passed to the `http` requests and to `WebSocket::open` with
+/// [`build`](Self::build), or formatted with [`Display`](fmt::Display).
+///
+/// # Example
+///
+/// ```
+/// use gloo_net::UrlBuilder;
+///
+/// let url = UrlBuilder::new("https://api.example.com/v1")
+///     .segment("users")
+///     .segment("ferris/crab")
+///     .query("q", "rust & wasm")
+///     .query("page", 2)
+///     .fragment("top")
+///     .build();
+/// assert_eq!(
+///     url,
+///     "https://api.example.com/v1/users/ferris%2Fcrab?q=rust%20%26%20wasm&page=2#top"
+/// );
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UrlBuilder {
+    base: String,
+    segments: Vec<String>,
+    query: Vec<(String, String)>,
+    fragment: Option<String>,
+}
+
+impl UrlBuilder {
+    /// Starts from `base`, an absolute or relative URL which isn't escaped.
+    pub fn new(base: &str) -> Self {
+        Self {
+            base: base.to_string(),
+            segments: Vec::new(),
+            query: Vec::new(),
+            fragment: None,
+        }
+    }
+
+    /// Appends a path segment, escaping `/` and any other reserved character in it.
+    pub fn segment(mut self, segment: impl fmt::Display) -> Self {
+        self.segments.push(encode(&segment.to_string(), b""));
+        self
+    }
+
+    /// Appends path segments, see [`segment`](Self::segment).
+    pub fn segments<I>(self, segments: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: fmt::Display,
+    {
+        segments.into_iter().fold(self, Self::segment)
+    }
+
+    /// Appends a query parameter. Parameters can be repeated.
+    pub fn query(mut self, name: &str, value: impl fmt::Display) -> Self {
+        self.query
+            .push((encode(name, b""), encode(&value.to_string(), b"")));
+        self
+    }
+
+    /// Appends a query parameter if `value` is `Some`.
+    pub fn query_opt(self, name: &str, value: Option<impl fmt::Display>) -> Self {
+        match value {
+            Some(value) => self.query(name, value),
+            None => self,
+        }
+    }
+
+    /// Sets the fragment, replacing the one of the base, if any.
+    pub fn fragment(mut self, fragment: &str) -> Self {
+        self.fragment = Some(encode(fragment, b"/?:@!$&'()*+,;="));
+        self
+    }
+
+    /// Builds the URL.
+    pub fn build(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for UrlBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (base, base_fragment) = match self.base.split_once('#') {
+            Some((base, fragment)) => (base, Some(fragment)),
+            None => (self.base.as_str(), None),
+        };
+        let (path, base_query) = match base.split_once('?') {
+            Some((path, query)) => (path, Some(query).filter(|query| !query.is_empty())),
+            None => (base, None),
+        };
+
+        f.write_str(path)?;
+        let mut ends_with_slash = path.ends_with('/');
+        for segment in &self.segments {
+            if !ends_with_slash {
+                f.write_char('/')?;
+            }
+            f.write_str(segment)?;
+            ends_with_slash = false;
+        }
+
+        let mut separator = '?';
+        if let Some(query) = base_query {
+            write!(f, "?{query}")?;
+            separator = '&';
+        }
+        for (name, value) in &self.query {
+            write!(f, "{separator}{name}={value}")?;
+            separator = '&';
+        }
+
+        match self.fragment.as_deref().or(base_fragment) {
+            Some(fragment) => write!(f, "#{fragment}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl From<UrlBuilder> for String {
+    fn from(url: UrlBuilder) -> Self {
+        url.build()
+    }
+}
+
+/// Percent-encodes `value`, leaving unreserved characters and the `allowed` ones as they are.
+pub(crate) fn encode(value: &str, allowed: &[u8]) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) || allowed.contains(&byte) {
+            encoded.push(char::from(byte));
+        } else {
+            let _ = write!(encoded, "%{byte:02X}");
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_urls() {
+        let url = UrlBuilder::new("/api/")
+            .segments(["a b", "ü", "x?y#z"])
+            .query("k&v", "1=2")
+            .query_opt("none", None::<u32>)
+            .query_opt("some", Some(true));
+        assert_eq!(
+            url.build(),
+            "/api/a%20b/%C3%BC/x%3Fy%23z?k%26v=1%3D2&some=true"
+        );
+        assert_eq!(UrlBuilder::new("").segment(1).build(), "/1");
+        assert_eq!(
+            UrlBuilder::new("https://example.com").build(),
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn keeps_the_base_query_and_fragment() {
+        let base = "https://example.com/search?lang=en#results";
+        assert_eq!(
+            UrlBuilder::new(base).query("q", "a").build(),
+            "https://example.com/search?lang=en&q=a#results"
+        );
+        assert_eq!(
+            UrlBuilder::new(base)
+                .segment("more")
+                .fragment("page 2/3")
+                .build(),
+            "https://example.com/search/more?lang=en#page%202/3"
+        );
+        assert_eq!(String::from(UrlBuilder::new("/a?").query("b", "")), "/a?b=");
+    }
+}
@@ -0,0 +1,298 @@
+//! The wrapper around the `RTCDataChannel` API using the Futures API to be used in async rust
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::webrtc::{DataChannel, Message};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::{SinkExt, StreamExt};
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run(pc: web_sys::RtcPeerConnection) {
+//! let channel = DataChannel::create(&pc, "chat").unwrap();
+//! let (mut write, mut read) = channel.split();
+//!
+//! spawn_local(async move {
+//!     // Waits until the channel is open.
+//!     write.send(Message::Text(String::from("hello"))).await.unwrap();
+//! });
+//!
+//! spawn_local(async move {
+//!     while let Some(msg) = read.next().await {
+//!         console_log!(format!("1. {:?}", msg))
+//!     }
+//!     console_log!("DataChannel Closed")
+//! })
+//! # }
+//! ```
+use crate::js_to_js_error;
+use crate::webrtc::{DataChannelError, Message, State};
+use futures_channel::mpsc;
+use futures_core::{ready, Stream};
+use futures_sink::Sink;
+use gloo_utils::errors::JsError;
+use pin_project::{pin_project, pinned_drop};
+use std::cell::RefCell;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{MessageEvent, RtcDataChannelState, RtcDataChannelType};
+
+/// Wrapper around browser's `RTCDataChannel` API.
+///
+/// Dropping it closes the channel.
+#[pin_project(PinnedDrop)]
+pub struct DataChannel {
+    channel: web_sys::RtcDataChannel,
+    sink_waker: Rc<RefCell<Option<Waker>>>,
+    #[pin]
+    message_receiver: mpsc::UnboundedReceiver<StreamMessage>,
+    #[allow(clippy::type_complexity)]
+    closures: (
+        Closure<dyn FnMut()>,
+        Closure<dyn FnMut(MessageEvent)>,
+        Closure<dyn FnMut(web_sys::Event)>,
+        Closure<dyn FnMut()>,
+    ),
+}
+
+impl DataChannel {
+    /// Creates a data channel with the given label on a peer connection.
+    ///
+    /// The channel opens once the peer connection is established.
+    pub fn create(pc: &web_sys::RtcPeerConnection, label: &str) -> Result<Self, JsError> {
+        Self::new(pc.create_data_channel(label))
+    }
+
+    /// Creates a data channel with the given label and options, e.g. unordered delivery, on a
+    /// peer connection.
+    ///
+    /// See the [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/RTCPeerConnection/createDataChannel#options)
+    /// for the options.
+    pub fn create_with_init(
+        pc: &web_sys::RtcPeerConnection,
+        label: &str,
+        init: &web_sys::RtcDataChannelInit,
+    ) -> Result<Self, JsError> {
+        Self::new(pc.create_data_channel_with_data_channel_dict(label, init))
+    }
+
+    /// Wraps an existing data channel, e.g. one received through the `datachannel` event of a
+    /// peer connection.
+    ///
+    /// Messages received before this is called are not yielded by the [`Stream`].
+    pub fn new(channel: web_sys::RtcDataChannel) -> Result<Self, JsError> {
+        let waker: Rc<RefCell<Option<Waker>>> = Rc::new(RefCell::new(None));
+
+        // Blobs can only be read through a promise, see `WebSocket::setup`.
+        channel.set_binary_type(RtcDataChannelType::Arraybuffer);
+
+        let (sender, receiver) = mpsc::unbounded();
+
+        let open_callback: Closure<dyn FnMut()> = {
+            let waker = Rc::clone(&waker);
+            Closure::wrap(Box::new(move || {
+                if let Some(waker) = waker.borrow_mut().take() {
+                    waker.wake();
+                }
+            }) as Box<dyn FnMut()>)
+        };
+
+        channel
+            .add_event_listener_with_callback("open", open_callback.as_ref().unchecked_ref())
+            .map_err(js_to_js_error)?;
+
+        let message_callback: Closure<dyn FnMut(MessageEvent)> = {
+            let sender = sender.clone();
+            Closure::wrap(Box::new(move |e: MessageEvent| {
+                let _ = sender.unbounded_send(StreamMessage::Message(parse_message(e)));
+            }) as Box<dyn FnMut(MessageEvent)>)
+        };
+
+        channel
+            .add_event_listener_with_callback("message", message_callback.as_ref().unchecked_ref())
+            .map_err(js_to_js_error)?;
+
+        let error_callback: Closure<dyn FnMut(web_sys::Event)> = {
+            let sender = sender.clone();
+            let waker = Rc::clone(&waker);
+            Closure::wrap(Box::new(move |e: web_sys::Event| {
+                if let Some(waker) = waker.borrow_mut().take() {
+                    waker.wake();
+                }
+                let _ = sender.unbounded_send(StreamMessage::ErrorEvent(error_message(&e)));
+            }) as Box<dyn FnMut(web_sys::Event)>)
+        };
+
+        channel
+            .add_event_listener_with_callback("error", error_callback.as_ref().unchecked_ref())
+            .map_err(js_to_js_error)?;
+
+        let close_callback: Closure<dyn FnMut()> = {
+            let waker = Rc::clone(&waker);
+            Closure::wrap(Box::new(move || {
+                if let Some(waker) = waker.borrow_mut().take() {
+                    waker.wake();
+                }
+                let _ = sender.unbounded_send(StreamMessage::CloseEvent);
+                // Nothing happens after the close event, end the stream.
+                sender.close_channel();
+            }) as Box<dyn FnMut()>)
+        };
+
+        channel
+            .add_event_listener_with_callback("close", close_callback.as_ref().unchecked_ref())
+            .map_err(js_to_js_error)?;
+
+        Ok(Self {
+            channel,
+            sink_waker: waker,
+            message_receiver: receiver,
+            closures: (
+                open_callback,
+                message_callback,
+                error_callback,
+                close_callback,
+            ),
+        })
+    }
+
+    /// Closes the data channel.
+    ///
+    /// See the [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/RTCDataChannel/close)
+    /// to learn more.
+    pub fn close(self) {
+        self.channel.close();
+    }
+
+    /// The current state of the data channel.
+    pub fn state(&self) -> State {
+        match self.channel.ready_state() {
+            RtcDataChannelState::Connecting => State::Connecting,
+            RtcDataChannelState::Open => State::Open,
+            RtcDataChannelState::Closing => State::Closing,
+            _ => State::Closed,
+        }
+    }
+
+    /// The label the channel was created with.
+    pub fn label(&self) -> String {
+        self.channel.label()
+    }
+
+    /// The number of bytes queued for sending.
+    pub fn buffered_amount(&self) -> u32 {
+        self.channel.buffered_amount()
+    }
+
+    /// The underlying `web_sys::RtcDataChannel`.
+    pub fn as_raw(&self) -> &web_sys::RtcDataChannel {
+        &self.channel
+    }
+}
+
+impl fmt::Debug for DataChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DataChannel")
+            .field("label", &self.label())
+            .field("state", &self.state())
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone)]
+enum StreamMessage {
+    ErrorEvent(Option<String>),
+    CloseEvent,
+    Message(Message),
+}
+
+fn parse_message(event: MessageEvent) -> Message {
+    if let Ok(array_buffer) = event.data().dyn_into::<js_sys::ArrayBuffer>() {
+        let array = js_sys::Uint8Array::new(&array_buffer);
+        Message::Bytes(array.to_vec())
+    } else if let Ok(txt) = event.data().dyn_into::<js_sys::JsString>() {
+        Message::Text(String::from(&txt))
+    } else {
+        unreachable!("message event, received Unknown: {:?}", event.data());
+    }
+}
+
+/// The message of the `RTCError` carried by an `RTCErrorEvent`.
+fn error_message(event: &web_sys::Event) -> Option<String> {
+    let error = js_sys::Reflect::get(event, &JsValue::from_str("error")).ok()?;
+    js_sys::Reflect::get(&error, &JsValue::from_str("message"))
+        .ok()?
+        .as_string()
+}
+
+impl<T: Into<Message>> Sink<T> for DataChannel {
+    type Error = DataChannelError;
+
+    /// Waits until the channel is open.
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match self.channel.ready_state() {
+            RtcDataChannelState::Connecting => {
+                *self.sink_waker.borrow_mut() = Some(cx.waker().clone());
+                Poll::Pending
+            }
+            RtcDataChannelState::Open => Poll::Ready(Ok(())),
+            _ => Poll::Ready(Err(DataChannelError::ConnectionClose)),
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let result = match item.into() {
+            Message::Bytes(bytes) => self.channel.send_with_u8_array(&bytes),
+            Message::Text(message) => self.channel.send_with_str(&message),
+        };
+        result.map_err(|e| DataChannelError::MessageSendError(js_to_js_error(e)))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.channel.close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Stream for DataChannel {
+    type Item = Result<Message, DataChannelError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let msg = ready!(self.project().message_receiver.poll_next(cx));
+        Poll::Ready(msg.map(|msg| match msg {
+            StreamMessage::Message(msg) => Ok(msg),
+            StreamMessage::ErrorEvent(message) => {
+                Err(DataChannelError::ConnectionError { message })
+            }
+            StreamMessage::CloseEvent => Err(DataChannelError::ConnectionClose),
+        }))
+    }
+}
+
+#[pinned_drop]
+impl PinnedDrop for DataChannel {
+    fn drop(self: Pin<&mut Self>) {
+        self.channel.close();
+
+        for (ty, cb) in [
+            ("open", self.closures.0.as_ref()),
+            ("message", self.closures.1.as_ref()),
+            ("error", self.closures.2.as_ref()),
+            ("close", self.closures.3.as_ref()),
+        ] {
+            let _ = self
+                .channel
+                .remove_event_listener_with_callback(ty, cb.unchecked_ref());
+        }
+    }
+}
@@ -0,0 +1,104 @@
+//! Wrapper around the WebRTC `RTCDataChannel` API
+//!
+//! This API is provided in the following flavors:
+//! - [Futures API][futures]
+//!
+//! A [`DataChannel`] exchanges messages directly with a peer, with the same [`Stream`] and
+//! [`Sink`] design as a [WebSocket](crate::websocket::futures::WebSocket). With the `websocket`
+//! and `json` features, the [`signaling`] helpers connect two peers by exchanging the SDP offer
+//! and answer over an existing WebSocket.
+//!
+//! [`Stream`]: futures_core::Stream
+//! [`Sink`]: futures_sink::Sink
+
+pub mod futures;
+#[cfg(all(feature = "websocket", feature = "json"))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "websocket", feature = "json"))))]
+pub mod signaling;
+
+pub use self::futures::DataChannel;
+
+use gloo_utils::errors::JsError;
+use std::fmt;
+
+/// Message sent to and received from a data channel.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Message {
+    /// String message
+    Text(String),
+    /// ArrayBuffer parsed into bytes
+    Bytes(Vec<u8>),
+}
+
+impl From<String> for Message {
+    fn from(text: String) -> Self {
+        Message::Text(text)
+    }
+}
+
+impl From<&str> for Message {
+    fn from(text: &str) -> Self {
+        Message::Text(text.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Message {
+    fn from(bytes: Vec<u8>) -> Self {
+        Message::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Message {
+    fn from(bytes: &[u8]) -> Self {
+        Message::Bytes(bytes.to_vec())
+    }
+}
+
+/// The state of the data channel.
+///
+/// See [`RTCDataChannel.readyState` on MDN](https://developer.mozilla.org/en-US/docs/Web/API/RTCDataChannel/readyState)
+/// to learn more.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum State {
+    /// The underlying connection has not yet been established.
+    Connecting,
+    /// The data channel is open and communication is possible.
+    Open,
+    /// The data channel is being closed.
+    Closing,
+    /// The data channel has been closed or could not be opened.
+    Closed,
+}
+
+/// Error returned by a data channel
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DataChannelError {
+    /// The `error` event
+    ConnectionError {
+        /// The message of the error, if the browser provided one
+        message: Option<String>,
+    },
+    /// The `close` event
+    ConnectionClose,
+    /// Message failed to send.
+    MessageSendError(JsError),
+}
+
+impl fmt::Display for DataChannelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataChannelError::ConnectionError { message } => {
+                write!(f, "data channel failed")?;
+                if let Some(message) = message {
+                    write!(f, ": {message}")?;
+                }
+                Ok(())
+            }
+            DataChannelError::ConnectionClose => write!(f, "data channel closed"),
+            DataChannelError::MessageSendError(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for DataChannelError {}
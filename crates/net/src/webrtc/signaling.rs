@@ -0,0 +1,273 @@
+//! Connecting [`DataChannel`]s through a signaling WebSocket.
+//!
+//! Both peers need a WebSocket to a server relaying their messages to each other, e.g. a room
+//! on a chat server. One peer calls [`offer`], the other [`answer`]. ICE candidates are gathered
+//! before a description is sent, so only two JSON text messages are exchanged:
+//!
+//! ```json
+//! {"type": "offer", "sdp": "v=0\r\n..."}
+//! {"type": "answer", "sdp": "v=0\r\n..."}
+//! ```
+//!
+//! Other messages received on the signaling socket while waiting are ignored.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::webrtc::signaling;
+//! use gloo_net::websocket::futures::WebSocket;
+//! use futures::{SinkExt, StreamExt};
+//!
+//! # async fn no_run() {
+//! let mut ws = WebSocket::open("wss://signaling.example.com/room/1").unwrap();
+//! let (_pc, mut channel) = signaling::offer(&mut ws, &web_sys::RtcConfiguration::new(), "chat")
+//!     .await
+//!     .unwrap();
+//! // The signaling socket isn't needed anymore.
+//! drop(ws);
+//! channel.send("hello").await.unwrap();
+//! let reply = channel.next().await;
+//! # }
+//! ```
+use crate::js_to_js_error;
+use crate::webrtc::DataChannel;
+use crate::websocket::transport::WebSocketTransport;
+use crate::websocket::{Message, WebSocketError};
+use futures_channel::oneshot;
+use futures_util::{SinkExt, StreamExt};
+use gloo_utils::errors::JsError;
+use serde_json::{json, Value};
+use thiserror::Error as ThisError;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    RtcConfiguration, RtcDataChannelEvent, RtcIceGatheringState, RtcPeerConnection, RtcSdpType,
+    RtcSessionDescriptionInit,
+};
+
+/// Errors of the signaling helpers.
+#[derive(Debug, ThisError)]
+#[non_exhaustive]
+pub enum SignalingError {
+    /// The signaling socket failed.
+    #[error("{0}")]
+    Socket(WebSocketError),
+    /// The signaling socket ended before the peer's description arrived.
+    #[error("signaling connection closed")]
+    Closed,
+    /// The browser rejected a description, or couldn't create the peer connection.
+    #[error("{0}")]
+    Js(JsError),
+}
+
+impl From<JsError> for SignalingError {
+    fn from(error: JsError) -> Self {
+        SignalingError::Js(error)
+    }
+}
+
+/// Creates a peer connection with a data channel, sends the offer over `signaling` and
+/// completes the connection with the answer received on it.
+///
+/// The peer connection is returned along with the channel, which opens once the peers are
+/// connected. It is closed when connecting fails.
+pub async fn offer<T>(
+    signaling: &mut T,
+    config: &RtcConfiguration,
+    label: &str,
+) -> Result<(RtcPeerConnection, DataChannel), SignalingError>
+where
+    T: WebSocketTransport + Unpin,
+{
+    let pc = RtcPeerConnection::new_with_configuration(config).map_err(js_to_js_error)?;
+    let result = async {
+        let channel = DataChannel::create(&pc, label)?;
+        let offer = promise(pc.create_offer()).await?;
+        promise(pc.set_local_description(offer.unchecked_ref())).await?;
+        send_description(signaling, &pc).await?;
+
+        let sdp = receive_description(signaling, "answer").await?;
+        let answer = RtcSessionDescriptionInit::new(RtcSdpType::Answer);
+        answer.set_sdp(&sdp);
+        promise(pc.set_remote_description(&answer)).await?;
+        Ok(channel)
+    }
+    .await;
+    close_on_error(pc, result)
+}
+
+/// Waits for an offer on `signaling`, answers it and returns the peer connection with the
+/// first data channel the offering peer created.
+///
+/// The peer connection is closed when connecting fails.
+pub async fn answer<T>(
+    signaling: &mut T,
+    config: &RtcConfiguration,
+) -> Result<(RtcPeerConnection, DataChannel), SignalingError>
+where
+    T: WebSocketTransport + Unpin,
+{
+    let pc = RtcPeerConnection::new_with_configuration(config).map_err(js_to_js_error)?;
+    let result = async {
+        // The channel is wrapped in the event callback so that no message is missed.
+        let data_channel = Once::new(&pc, "datachannel", |e| {
+            Some(DataChannel::new(
+                e.unchecked_into::<RtcDataChannelEvent>().channel(),
+            ))
+        })?;
+
+        let sdp = receive_description(signaling, "offer").await?;
+        let offer = RtcSessionDescriptionInit::new(RtcSdpType::Offer);
+        offer.set_sdp(&sdp);
+        promise(pc.set_remote_description(&offer)).await?;
+        let answer = promise(pc.create_answer()).await?;
+        promise(pc.set_local_description(answer.unchecked_ref())).await?;
+        send_description(signaling, &pc).await?;
+
+        Ok(data_channel.wait().await?)
+    }
+    .await;
+    close_on_error(pc, result)
+}
+
+fn close_on_error(
+    pc: RtcPeerConnection,
+    result: Result<DataChannel, SignalingError>,
+) -> Result<(RtcPeerConnection, DataChannel), SignalingError> {
+    match result {
+        Ok(channel) => Ok((pc, channel)),
+        Err(e) => {
+            pc.close();
+            Err(e)
+        }
+    }
+}
+
+async fn promise(promise: js_sys::Promise) -> Result<JsValue, JsError> {
+    JsFuture::from(promise).await.map_err(js_to_js_error)
+}
+
+/// Sends the local description once all ICE candidates are part of it.
+async fn send_description<T>(
+    signaling: &mut T,
+    pc: &RtcPeerConnection,
+) -> Result<(), SignalingError>
+where
+    T: WebSocketTransport + Unpin,
+{
+    if pc.ice_gathering_state() != RtcIceGatheringState::Complete {
+        let target = pc.clone();
+        Once::new(pc, "icegatheringstatechange", move |_| {
+            (target.ice_gathering_state() == RtcIceGatheringState::Complete).then_some(())
+        })?
+        .wait()
+        .await;
+    }
+    let description = pc
+        .local_description()
+        .expect("the local description was set");
+    let kind = match description.type_() {
+        RtcSdpType::Offer => "offer",
+        _ => "answer",
+    };
+    signaling
+        .send(Message::Text(encode_description(kind, &description.sdp())))
+        .await
+        .map_err(SignalingError::Socket)
+}
+
+async fn receive_description<T>(signaling: &mut T, kind: &str) -> Result<String, SignalingError>
+where
+    T: WebSocketTransport + Unpin,
+{
+    while let Some(msg) = signaling.next().await {
+        if let Message::Text(text) = msg.map_err(SignalingError::Socket)? {
+            if let Some(sdp) = decode_description(&text, kind) {
+                return Ok(sdp);
+            }
+        }
+    }
+    Err(SignalingError::Closed)
+}
+
+fn encode_description(kind: &str, sdp: &str) -> String {
+    json!({ "type": kind, "sdp": sdp }).to_string()
+}
+
+/// The SDP of a description of the given kind, `None` for other messages.
+fn decode_description(text: &str, kind: &str) -> Option<String> {
+    let value = serde_json::from_str::<Value>(text).ok()?;
+    if value.get("type")?.as_str()? != kind {
+        return None;
+    }
+    Some(value.get("sdp")?.as_str()?.to_string())
+}
+
+/// An event listener keeping the first value `f` returns, removed when dropped.
+struct Once<T> {
+    target: web_sys::EventTarget,
+    event_type: &'static str,
+    callback: Closure<dyn FnMut(web_sys::Event)>,
+    receiver: oneshot::Receiver<T>,
+}
+
+impl<T: 'static> Once<T> {
+    fn new(
+        target: &web_sys::EventTarget,
+        event_type: &'static str,
+        mut f: impl FnMut(web_sys::Event) -> Option<T> + 'static,
+    ) -> Result<Self, JsError> {
+        let (sender, receiver) = oneshot::channel();
+        let mut sender = Some(sender);
+        let callback: Closure<dyn FnMut(web_sys::Event)> =
+            Closure::wrap(Box::new(move |e: web_sys::Event| {
+                if sender.is_some() {
+                    if let Some(value) = f(e) {
+                        let _ = sender.take().unwrap().send(value);
+                    }
+                }
+            }) as Box<dyn FnMut(web_sys::Event)>);
+        target
+            .add_event_listener_with_callback(event_type, callback.as_ref().unchecked_ref())
+            .map_err(js_to_js_error)?;
+        Ok(Self {
+            target: target.clone(),
+            event_type,
+            callback,
+            receiver,
+        })
+    }
+
+    async fn wait(mut self) -> T {
+        (&mut self.receiver)
+            .await
+            .expect("the sender is owned by the listener")
+    }
+}
+
+impl<T> Drop for Once<T> {
+    fn drop(&mut self) {
+        let _ = self.target.remove_event_listener_with_callback(
+            self.event_type,
+            self.callback.as_ref().unchecked_ref(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn descriptions() {
+        let text = encode_description("offer", "v=0\r\n");
+        assert_eq!(
+            decode_description(&text, "offer").as_deref(),
+            Some("v=0\r\n")
+        );
+        assert_eq!(decode_description(&text, "answer"), None);
+        assert_eq!(decode_description(r#"{"type":"offer"}"#, "offer"), None);
+        assert_eq!(decode_description("hello", "offer"), None);
+    }
+}
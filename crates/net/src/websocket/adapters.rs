@@ -0,0 +1,187 @@
+//! Adapters for protocols which only use a single kind of message.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::futures::WebSocket;
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::{SinkExt, StreamExt};
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! let mut ws = WebSocket::open("wss://echo.websocket.org")
+//!     .unwrap()
+//!     .into_text_stream();
+//!
+//! spawn_local(async move {
+//!     ws.send("hello".to_string()).await.unwrap();
+//!     while let Some(text) = ws.next().await {
+//!         console_log!(format!("{:?}", text))
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::{futures::WebSocket, Message, WebSocketError};
+use futures_core::{ready, Stream};
+use futures_sink::Sink;
+use pin_project::pin_project;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+macro_rules! adapter {
+    ($(#[$attr:meta])* $name:ident, $item:ty, $variant:ident) => {
+        $(#[$attr])*
+        #[pin_project]
+        pub struct $name<S = WebSocket> {
+            #[pin]
+            inner: S,
+        }
+
+        impl<S> $name<S> {
+            /// Wraps a socket.
+            pub fn new(inner: S) -> Self {
+                Self { inner }
+            }
+
+            /// A reference to the wrapped socket.
+            pub fn get_ref(&self) -> &S {
+                &self.inner
+            }
+
+            /// Unwraps the socket.
+            pub fn into_inner(self) -> S {
+                self.inner
+            }
+        }
+
+        impl<S> Stream for $name<S>
+        where
+            S: Stream<Item = Result<Message, WebSocketError>>,
+        {
+            type Item = Result<$item, WebSocketError>;
+
+            fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                let msg = ready!(self.project().inner.poll_next(cx));
+                Poll::Ready(msg.map(|msg| match msg? {
+                    Message::$variant(item) => Ok(item),
+                    msg => Err(WebSocketError::UnexpectedMessage(msg)),
+                }))
+            }
+        }
+
+        impl<S> Sink<$item> for $name<S>
+        where
+            S: Sink<Message, Error = WebSocketError>,
+        {
+            type Error = WebSocketError;
+
+            fn poll_ready(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Result<(), Self::Error>> {
+                self.project().inner.poll_ready(cx)
+            }
+
+            fn start_send(self: Pin<&mut Self>, item: $item) -> Result<(), Self::Error> {
+                self.project().inner.start_send(Message::$variant(item))
+            }
+
+            fn poll_flush(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Result<(), Self::Error>> {
+                self.project().inner.poll_flush(cx)
+            }
+
+            fn poll_close(
+                self: Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<Result<(), Self::Error>> {
+                self.project().inner.poll_close(cx)
+            }
+        }
+
+        impl<S> fmt::Debug for $name<S> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name)).finish_non_exhaustive()
+            }
+        }
+    };
+}
+
+adapter!(
+    /// Yields and sends text messages as `String`s, obtained from
+    /// [`WebSocket::into_text_stream`].
+    ///
+    /// Binary messages are yielded as [`WebSocketError::UnexpectedMessage`] without ending the
+    /// stream.
+    TextWebSocket,
+    String,
+    Text
+);
+
+adapter!(
+    /// Yields and sends binary messages as `Vec<u8>`s, obtained from
+    /// [`WebSocket::into_bytes_stream`].
+    ///
+    /// Text messages are yielded as [`WebSocketError::UnexpectedMessage`] without ending the
+    /// stream.
+    BytesWebSocket,
+    Vec<u8>,
+    Bytes
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::browser::WebSocket;
+    use futures::{SinkExt, StreamExt};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[allow(clippy::option_env_unwrap)]
+    async fn open_echo() -> WebSocket {
+        let ws_echo_server_url =
+            option_env!("WS_ECHO_SERVER_URL").expect("Did you set WS_ECHO_SERVER_URL?");
+        let mut ws = WebSocket::open(ws_echo_server_url).unwrap();
+        // The echo server sends its info in the first message.
+        let _ = ws.next().await;
+        ws
+    }
+
+    #[wasm_bindgen_test]
+    async fn text_stream() {
+        let mut ws = open_echo().await.into_text_stream();
+
+        ws.send("hello".to_string()).await.unwrap();
+        assert_eq!(ws.next().await.unwrap().unwrap(), "hello");
+
+        ws.get_ref().try_send(vec![1, 2, 3]).unwrap();
+        ws.send("still open".to_string()).await.unwrap();
+        assert!(matches!(
+            ws.next().await,
+            Some(Err(WebSocketError::UnexpectedMessage(Message::Bytes(_))))
+        ));
+        assert_eq!(ws.next().await.unwrap().unwrap(), "still open");
+    }
+
+    #[wasm_bindgen_test]
+    async fn bytes_stream() {
+        let mut ws = open_echo().await.into_bytes_stream();
+
+        ws.send(vec![1, 2, 3]).await.unwrap();
+        assert_eq!(ws.next().await.unwrap().unwrap(), [1, 2, 3]);
+
+        ws.get_ref().try_send("hello").unwrap();
+        ws.send(vec![4]).await.unwrap();
+        assert!(matches!(
+            ws.next().await,
+            Some(Err(WebSocketError::UnexpectedMessage(Message::Text(_))))
+        ));
+        assert_eq!(ws.next().await.unwrap().unwrap(), [4]);
+    }
+}
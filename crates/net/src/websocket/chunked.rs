@@ -0,0 +1,346 @@
+//! Splitting large binary messages into chunks.
+//!
+//! Some proxies and servers limit the size of WebSocket frames. [`ChunkedWebSocket`] splits
+//! outgoing binary messages into chunks of at most a configured size and reassembles incoming
+//! chunks, so both ends need to use it (or implement the same framing).
+//!
+//! Every binary message is prefixed with a 12 byte header made of three big endian `u32`s: the
+//! message id, the index of the chunk and the number of chunks in the message. Text messages are
+//! passed through unchanged.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::{chunked::ChunkedWebSocket, futures::WebSocket};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::SinkExt;
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! let ws = WebSocket::open("wss://example.com/upload").unwrap();
+//! let mut ws = ChunkedWebSocket::new(ws, 64 * 1024).on_send_progress(|progress| {
+//!     console_log!(format!("{}/{}", progress.chunks_done, progress.chunk_count))
+//! });
+//!
+//! spawn_local(async move {
+//!     ws.send(vec![0; 10 * 1024 * 1024]).await.unwrap();
+//! })
+//! # }
+//! ```
+use crate::websocket::{futures::WebSocket, Message, WebSocketError};
+use futures_core::{ready, Stream};
+use futures_sink::Sink;
+use pin_project::pin_project;
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryInto;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+
+const HEADER_LEN: usize = 12;
+
+/// Progress of a chunked message, passed to the callbacks of a [`ChunkedWebSocket`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// The id of the message, unique among the messages in flight from the same sender.
+    pub message_id: u32,
+    /// The number of chunks sent or received so far, including the current one.
+    pub chunks_done: u32,
+    /// The total number of chunks.
+    pub chunk_count: u32,
+    /// The payload bytes sent or received so far.
+    pub bytes_done: usize,
+}
+
+type ProgressCallback = Rc<dyn Fn(Progress)>;
+
+/// Fragments outgoing and reassembles incoming binary messages.
+///
+/// See the [module documentation](self) for the framing used. Receiving a binary message
+/// without a valid header yields [`WebSocketError::InvalidChunk`] without ending the stream.
+#[pin_project]
+pub struct ChunkedWebSocket<S = WebSocket> {
+    #[pin]
+    inner: S,
+    chunk_size: usize,
+    next_id: u32,
+    outgoing: VecDeque<(Vec<u8>, Progress)>,
+    reassembler: Reassembler,
+    on_send_progress: Option<ProgressCallback>,
+    on_receive_progress: Option<ProgressCallback>,
+}
+
+impl<S> ChunkedWebSocket<S> {
+    /// Wraps a socket, splitting binary messages into chunks of at most `chunk_size` payload
+    /// bytes.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn new(inner: S, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "chunk size must not be zero");
+        Self {
+            inner,
+            chunk_size,
+            next_id: 0,
+            outgoing: VecDeque::new(),
+            reassembler: Reassembler::default(),
+            on_send_progress: None,
+            on_receive_progress: None,
+        }
+    }
+
+    /// Calls `callback` every time a chunk has been handed to the socket.
+    pub fn on_send_progress(mut self, callback: impl Fn(Progress) + 'static) -> Self {
+        self.on_send_progress = Some(Rc::new(callback));
+        self
+    }
+
+    /// Calls `callback` every time a chunk has been received.
+    pub fn on_receive_progress(mut self, callback: impl Fn(Progress) + 'static) -> Self {
+        self.on_receive_progress = Some(Rc::new(callback));
+        self
+    }
+
+    /// A reference to the wrapped socket.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwraps the socket.
+    ///
+    /// Chunks which haven't been sent yet and partially received messages are lost.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> ChunkedWebSocket<S>
+where
+    S: Sink<Message, Error = WebSocketError>,
+{
+    /// Hands the queued chunks to the socket.
+    fn poll_send_chunks(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), WebSocketError>> {
+        let mut this = self.project();
+        while !this.outgoing.is_empty() {
+            ready!(this.inner.as_mut().poll_ready(cx))?;
+            let (chunk, progress) = this.outgoing.pop_front().expect("checked above");
+            this.inner.as_mut().start_send(Message::Bytes(chunk))?;
+            if let Some(callback) = this.on_send_progress {
+                callback(progress);
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> Stream for ChunkedWebSocket<S>
+where
+    S: Stream<Item = Result<Message, WebSocketError>>,
+{
+    type Item = Result<Message, WebSocketError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+        loop {
+            let frame = match ready!(this.inner.as_mut().poll_next(cx)) {
+                Some(Ok(Message::Bytes(frame))) => frame,
+                other => return Poll::Ready(other),
+            };
+            match this.reassembler.push(&frame) {
+                Ok((progress, message)) => {
+                    if let Some(callback) = this.on_receive_progress {
+                        callback(progress);
+                    }
+                    if let Some(message) = message {
+                        return Poll::Ready(Some(Ok(Message::Bytes(message))));
+                    }
+                }
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+        }
+    }
+}
+
+impl<S, T> Sink<T> for ChunkedWebSocket<S>
+where
+    S: Sink<Message, Error = WebSocketError>,
+    T: Into<Message>,
+{
+    type Error = WebSocketError;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_send_chunks(cx))?;
+        self.project().inner.poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let this = self.project();
+        let bytes = match item.into() {
+            Message::Bytes(bytes) => bytes,
+            text => return this.inner.start_send(text),
+        };
+        let message_id = *this.next_id;
+        *this.next_id = this.next_id.wrapping_add(1);
+        this.outgoing
+            .extend(split(message_id, &bytes, *this.chunk_size)?);
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_send_chunks(cx))?;
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_send_chunks(cx))?;
+        self.project().inner.poll_close(cx)
+    }
+}
+
+impl<S> fmt::Debug for ChunkedWebSocket<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkedWebSocket")
+            .field("chunk_size", &self.chunk_size)
+            .field("queued_chunks", &self.outgoing.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Splits `payload` into framed chunks.
+fn split(
+    message_id: u32,
+    payload: &[u8],
+    chunk_size: usize,
+) -> Result<Vec<(Vec<u8>, Progress)>, WebSocketError> {
+    let chunk_count = payload.len().max(1).div_ceil(chunk_size);
+    let chunk_count: u32 = chunk_count
+        .try_into()
+        .map_err(|_| WebSocketError::InvalidChunk)?;
+    let mut bytes_done = 0;
+    let chunks = (0..chunk_count)
+        .map(|index| {
+            let start = index as usize * chunk_size;
+            let end = (start + chunk_size).min(payload.len());
+            let data = &payload[start.min(end)..end];
+            bytes_done += data.len();
+
+            let mut chunk = Vec::with_capacity(HEADER_LEN + data.len());
+            chunk.extend_from_slice(&message_id.to_be_bytes());
+            chunk.extend_from_slice(&index.to_be_bytes());
+            chunk.extend_from_slice(&chunk_count.to_be_bytes());
+            chunk.extend_from_slice(data);
+            let progress = Progress {
+                message_id,
+                chunks_done: index + 1,
+                chunk_count,
+                bytes_done,
+            };
+            (chunk, progress)
+        })
+        .collect();
+    Ok(chunks)
+}
+
+struct Partial {
+    data: Vec<u8>,
+    chunks_done: u32,
+    chunk_count: u32,
+}
+
+/// Collects the chunks of incoming messages.
+#[derive(Default)]
+struct Reassembler {
+    partial: HashMap<u32, Partial>,
+}
+
+impl Reassembler {
+    /// Adds a chunk, returning the complete message once its last chunk arrived.
+    ///
+    /// Chunks of a message must arrive in order, but chunks of different messages may be
+    /// interleaved.
+    fn push(&mut self, frame: &[u8]) -> Result<(Progress, Option<Vec<u8>>), WebSocketError> {
+        if frame.len() < HEADER_LEN {
+            return Err(WebSocketError::InvalidChunk);
+        }
+        let field = |i: usize| u32::from_be_bytes(frame[i * 4..i * 4 + 4].try_into().unwrap());
+        let (message_id, index, chunk_count) = (field(0), field(1), field(2));
+        if chunk_count == 0 {
+            return Err(WebSocketError::InvalidChunk);
+        }
+        let data = &frame[HEADER_LEN..];
+
+        let partial = self.partial.entry(message_id).or_insert(Partial {
+            data: Vec::new(),
+            chunks_done: 0,
+            chunk_count,
+        });
+        if index != partial.chunks_done || chunk_count != partial.chunk_count {
+            self.partial.remove(&message_id);
+            return Err(WebSocketError::InvalidChunk);
+        }
+        partial.data.extend_from_slice(data);
+        partial.chunks_done += 1;
+
+        let progress = Progress {
+            message_id,
+            chunks_done: partial.chunks_done,
+            chunk_count,
+            bytes_done: partial.data.len(),
+        };
+        if partial.chunks_done < chunk_count {
+            return Ok((progress, None));
+        }
+        let message = self.partial.remove(&message_id).map(|partial| partial.data);
+        Ok((progress, message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(payload: &[u8], chunk_size: usize) -> Vec<u8> {
+        let mut reassembler = Reassembler::default();
+        let chunks = split(7, payload, chunk_size).unwrap();
+        let count = chunks.len();
+        let mut message = None;
+        for (i, (chunk, progress)) in chunks.into_iter().enumerate() {
+            assert!(chunk.len() <= HEADER_LEN + chunk_size);
+            let (received, complete) = reassembler.push(&chunk).unwrap();
+            assert_eq!(received, progress);
+            assert_eq!(complete.is_some(), i + 1 == count);
+            message = complete;
+        }
+        message.unwrap()
+    }
+
+    #[test]
+    fn splits_and_reassembles() {
+        let payload = (0..=255).collect::<Vec<u8>>();
+        assert_eq!(split(0, &payload, 100).unwrap().len(), 3);
+        assert_eq!(roundtrip(&payload, 100), payload);
+        assert_eq!(roundtrip(&payload, 256), payload);
+        assert_eq!(roundtrip(&payload, 1), payload);
+        assert_eq!(roundtrip(&[], 16), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn rejects_invalid_chunks() {
+        let mut reassembler = Reassembler::default();
+        assert!(reassembler.push(&[0; 4]).is_err());
+
+        let chunks = split(1, &[1, 2, 3], 1).unwrap();
+        reassembler.push(&chunks[0].0).unwrap();
+        // chunk 1 is missing
+        assert!(reassembler.push(&chunks[2].0).is_err());
+        // the partial message was discarded, so the next chunk 0 starts over
+        assert_eq!(reassembler.push(&chunks[0].0).unwrap().1, None);
+    }
+}
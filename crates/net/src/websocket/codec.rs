@@ -0,0 +1,359 @@
+//! Pluggable serialization formats for WebSocket messages.
+//!
+//! A [`Codec`] turns serializable values into [`Message`]s and back. [`CodecWebSocket`] uses one
+//! to carry typed values over a socket, and [`WorkerCodec`] adapts one for `gloo-worker`
+//! bridges with the `worker` feature, for the formats `gloo-worker` has no codec of its own for.
+//!
+//! | Codec           | Feature   | Messages |
+//! |-----------------|-----------|----------|
+//! | [`Json`]        | `json`    | text     |
+//! | [`Bincode`]     | `bincode` | binary   |
+//! | [`Cbor`]        | `cbor`    | binary   |
+//! | [`MessagePack`] | `rmp`     | binary   |
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(feature = "cbor")]
+//! # fn no_run() {
+//! use gloo_net::websocket::{codec::{Cbor, CodecWebSocket}, futures::WebSocket};
+//! use serde::{Deserialize, Serialize};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::{SinkExt, StreamExt};
+//!
+//! #[derive(Serialize)]
+//! struct Subscribe {
+//!     channel: String,
+//! }
+//!
+//! #[derive(Deserialize, Debug)]
+//! struct Update {
+//!     price: f64,
+//! }
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! let ws = WebSocket::open("wss://example.com/prices").unwrap();
+//! let mut ws = CodecWebSocket::<Cbor, Subscribe, Update>::new(ws);
+//!
+//! spawn_local(async move {
+//!     ws.send(Subscribe { channel: "BTC".to_string() }).await.unwrap();
+//!     while let Some(update) = ws.next().await {
+//!         console_log!(format!("{:?}", update))
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::{futures::WebSocket, Message, WebSocketError};
+use futures_core::{ready, Stream};
+use futures_sink::Sink;
+use pin_project::pin_project;
+use serde::{de::DeserializeOwned, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A serialization format for [`Message`]s.
+pub trait Codec {
+    /// Serializes `value` into a message.
+    fn encode<T: Serialize>(value: &T) -> Result<Message, CodecError>;
+
+    /// Deserializes a message.
+    fn decode<T: DeserializeOwned>(message: Message) -> Result<T, CodecError>;
+}
+
+/// A value couldn't be encoded, or a message couldn't be decoded.
+#[derive(Debug)]
+pub struct CodecError(Box<dyn Error + Send + Sync>);
+
+impl CodecError {
+    /// Wraps the error of a format, or a description of what went wrong.
+    pub fn new(error: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self(error.into())
+    }
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Error for CodecError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+/// The payload of a message for binary formats, which reject text messages.
+#[cfg(any(feature = "bincode", feature = "cbor", feature = "rmp"))]
+fn binary(message: Message) -> Result<Vec<u8>, CodecError> {
+    match message {
+        Message::Bytes(bytes) => Ok(bytes),
+        Message::Text(_) => Err(CodecError::new("expected a binary message")),
+    }
+}
+
+/// JSON in text messages. Binary messages are decoded as well.
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl Codec for Json {
+    fn encode<T: Serialize>(value: &T) -> Result<Message, CodecError> {
+        serde_json::to_string(value)
+            .map(Message::Text)
+            .map_err(CodecError::new)
+    }
+
+    fn decode<T: DeserializeOwned>(message: Message) -> Result<T, CodecError> {
+        match message {
+            Message::Text(text) => serde_json::from_str(&text),
+            Message::Bytes(bytes) => serde_json::from_slice(&bytes),
+        }
+        .map_err(CodecError::new)
+    }
+}
+
+/// [bincode](https://docs.rs/bincode) in binary messages, the format used by `gloo-worker`.
+#[cfg(feature = "bincode")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bincode")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Bincode;
+
+#[cfg(feature = "bincode")]
+impl Codec for Bincode {
+    fn encode<T: Serialize>(value: &T) -> Result<Message, CodecError> {
+        bincode::serialize(value)
+            .map(Message::Bytes)
+            .map_err(CodecError::new)
+    }
+
+    fn decode<T: DeserializeOwned>(message: Message) -> Result<T, CodecError> {
+        bincode::deserialize(&binary(message)?).map_err(CodecError::new)
+    }
+}
+
+/// [CBOR](https://cbor.io) in binary messages.
+#[cfg(feature = "cbor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Codec for Cbor {
+    fn encode<T: Serialize>(value: &T) -> Result<Message, CodecError> {
+        let mut bytes = Vec::new();
+        ciborium::ser::into_writer(value, &mut bytes).map_err(CodecError::new)?;
+        Ok(Message::Bytes(bytes))
+    }
+
+    fn decode<T: DeserializeOwned>(message: Message) -> Result<T, CodecError> {
+        ciborium::de::from_reader(binary(message)?.as_slice()).map_err(CodecError::new)
+    }
+}
+
+/// [MessagePack](https://msgpack.org) in binary messages, with structs encoded as maps.
+#[cfg(feature = "rmp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rmp")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MessagePack;
+
+#[cfg(feature = "rmp")]
+impl Codec for MessagePack {
+    fn encode<T: Serialize>(value: &T) -> Result<Message, CodecError> {
+        rmp_serde::to_vec_named(value)
+            .map(Message::Bytes)
+            .map_err(CodecError::new)
+    }
+
+    fn decode<T: DeserializeOwned>(message: Message) -> Result<T, CodecError> {
+        rmp_serde::from_slice(&binary(message)?).map_err(CodecError::new)
+    }
+}
+
+/// Encodes outgoing `Tx` values and decodes incoming messages into `Rx` values with `C`.
+///
+/// Messages which fail to decode are yielded as [`WebSocketError::CodecError`] without ending
+/// the stream.
+///
+/// `S` can be any [`Message`] based socket, such as a
+/// [`ReconnectingWebSocket`](crate::websocket::reconnect::ReconnectingWebSocket).
+#[pin_project]
+pub struct CodecWebSocket<C, Tx, Rx, S = WebSocket> {
+    #[pin]
+    inner: S,
+    _marker: PhantomData<fn(C, Tx) -> Rx>,
+}
+
+impl<C, Tx, Rx, S> CodecWebSocket<C, Tx, Rx, S> {
+    /// Wraps a socket.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    /// A reference to the wrapped socket.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwraps the socket.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<C, Tx, Rx, S> Stream for CodecWebSocket<C, Tx, Rx, S>
+where
+    C: Codec,
+    Rx: DeserializeOwned,
+    S: Stream<Item = Result<Message, WebSocketError>>,
+{
+    type Item = Result<Rx, WebSocketError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let msg = ready!(self.project().inner.poll_next(cx));
+        Poll::Ready(msg.map(|msg| C::decode(msg?).map_err(WebSocketError::CodecError)))
+    }
+}
+
+impl<C, Tx, Rx, S> Sink<Tx> for CodecWebSocket<C, Tx, Rx, S>
+where
+    C: Codec,
+    Tx: Serialize,
+    S: Sink<Message, Error = WebSocketError>,
+{
+    type Error = WebSocketError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Tx) -> Result<(), Self::Error> {
+        let msg = C::encode(&item).map_err(WebSocketError::CodecError)?;
+        self.project().inner.start_send(msg)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+impl<C, Tx, Rx, S> fmt::Debug for CodecWebSocket<C, Tx, Rx, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CodecWebSocket").finish_non_exhaustive()
+    }
+}
+
+/// Uses a [`Codec`] for the messages of `gloo-worker` bridges, e.g.
+/// `MyWorker::spawner().encoding::<WorkerCodec<MessagePack>>()`.
+///
+/// This is meant for the formats `gloo-worker` doesn't have. For bincode and CBOR, use
+/// [`gloo_worker::Bincode`] and `gloo_worker::Cbor` (with its `cbor` feature) instead: they encode
+/// messages the same way as `WorkerCodec<Bincode>` and `WorkerCodec<Cbor>`.
+///
+/// Like the codecs of `gloo-worker`, this panics if a message can't be encoded or decoded, except
+/// in [`try_decode`](gloo_worker::Codec::try_decode).
+#[cfg(feature = "worker")]
+#[cfg_attr(docsrs, doc(cfg(feature = "worker")))]
+pub struct WorkerCodec<C>(PhantomData<C>);
+
+#[cfg(feature = "worker")]
+impl<C: Codec> gloo_worker::Codec for WorkerCodec<C> {
+    fn encode<I>(input: I) -> wasm_bindgen::JsValue
+    where
+        I: Serialize,
+    {
+        match C::encode(&input).expect("can't encode a worker message") {
+            Message::Text(text) => text.into(),
+            Message::Bytes(bytes) => js_sys::Uint8Array::from(bytes.as_slice()).into(),
+        }
+    }
+
+    fn decode<O>(input: wasm_bindgen::JsValue) -> O
+    where
+        O: for<'de> serde::Deserialize<'de>,
+    {
+        let message = match input.as_string() {
+            Some(text) => Message::Text(text),
+            None => Message::Bytes(js_sys::Uint8Array::from(input).to_vec()),
+        };
+        C::decode(message).expect("can't decode a worker message")
+    }
+
+    fn try_decode<O>(input: wasm_bindgen::JsValue) -> Option<O>
+    where
+        O: for<'de> serde::Deserialize<'de>,
+    {
+        use wasm_bindgen::JsCast;
+
+        let message = match input.as_string() {
+            Some(text) => Message::Text(text),
+            None => Message::Bytes(input.dyn_into::<js_sys::Uint8Array>().ok()?.to_vec()),
+        };
+        C::decode(message).ok()
+    }
+}
+
+#[cfg(feature = "worker")]
+impl<C> fmt::Debug for WorkerCodec<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WorkerCodec")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Update {
+        channel: String,
+        price: f64,
+        tags: Vec<u8>,
+    }
+
+    #[allow(dead_code)]
+    fn round_trip<C: Codec>() -> Message {
+        let update = Update {
+            channel: "BTC".to_string(),
+            price: 1.5,
+            tags: vec![1, 2],
+        };
+        let message = C::encode(&update).unwrap();
+        assert_eq!(C::decode::<Update>(message.clone()).unwrap(), update);
+        assert!(C::decode::<Update>(Message::Bytes(vec![0xff, 0x00])).is_err());
+        message
+    }
+
+    #[test]
+    fn codecs_round_trip() {
+        #[cfg(feature = "json")]
+        assert!(matches!(round_trip::<Json>(), Message::Text(_)));
+        #[cfg(feature = "bincode")]
+        assert!(matches!(round_trip::<Bincode>(), Message::Bytes(_)));
+        #[cfg(feature = "cbor")]
+        assert!(matches!(round_trip::<Cbor>(), Message::Bytes(_)));
+        #[cfg(feature = "rmp")]
+        assert!(matches!(round_trip::<MessagePack>(), Message::Bytes(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "cbor")]
+    fn binary_codecs_reject_text() {
+        assert!(Cbor::decode::<u8>(Message::Text("1".to_string())).is_err());
+    }
+}
@@ -1,12 +1,186 @@
 //! WebSocket Events
 
+use crate::websocket::State;
+use gloo_utils::errors::JsError;
+use std::fmt;
+
+/// The status code of a closing handshake.
+///
+/// See [RFC 6455](https://www.rfc-editor.org/rfc/rfc6455#section-7.4.1) for the meaning of the
+/// codes. Browsers only allow sending [`Normal`](Self::Normal) and [`Custom`](Self::Custom)
+/// codes, the others are mostly received from servers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "worker",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "u16", into = "u16")
+)]
+pub enum CloseCode {
+    /// `1000`, the purpose of the connection has been fulfilled
+    Normal,
+    /// `1001`, the endpoint is going away, e.g. a server shutting down
+    GoingAway,
+    /// `1002`, the endpoint received a frame violating the protocol
+    ProtocolError,
+    /// `1003`, the endpoint received a kind of message it can't handle
+    UnsupportedData,
+    /// `1005`, the close frame had no code. Never sent.
+    NoStatus,
+    /// `1006`, the connection ended without a closing handshake. Never sent.
+    Abnormal,
+    /// `1007`, a message contained invalid data, e.g. non UTF-8 text
+    InvalidPayload,
+    /// `1008`, a message violated the policy of the endpoint
+    PolicyViolation,
+    /// `1009`, a message was too large to process
+    MessageTooBig,
+    /// `1010`, the server didn't negotiate an extension required by the client
+    MandatoryExtension,
+    /// `1011`, the server encountered an unexpected condition
+    InternalError,
+    /// `1012`, the server is restarting
+    ServiceRestart,
+    /// `1013`, the server is overloaded, the client should reconnect later
+    TryAgainLater,
+    /// `1014`, a gateway received an invalid response from upstream
+    BadGateway,
+    /// `1015`, the TLS handshake failed. Never sent.
+    TlsHandshake,
+    /// `3000`–`3999` for libraries and frameworks, `4000`–`4999` for applications
+    Custom(u16),
+    /// Any other code, reserved by the protocol
+    Reserved(u16),
+}
+
+impl CloseCode {
+    /// Whether the code may be sent in a close frame.
+    ///
+    /// Codes which only describe how a connection ended, like [`Abnormal`](Self::Abnormal), and
+    /// reserved codes can't be sent.
+    pub fn can_be_sent(self) -> bool {
+        matches!(u16::from(self), 1000..=1003 | 1007..=1014 | 3000..=4999)
+    }
+
+    /// Whether browsers accept the code in `WebSocket.close()`, i.e. it is `1000` or in
+    /// `3000`–`4999`.
+    pub fn can_be_sent_by_browsers(self) -> bool {
+        matches!(u16::from(self), 1000 | 3000..=4999)
+    }
+}
+
+impl From<u16> for CloseCode {
+    fn from(code: u16) -> Self {
+        match code {
+            1000 => CloseCode::Normal,
+            1001 => CloseCode::GoingAway,
+            1002 => CloseCode::ProtocolError,
+            1003 => CloseCode::UnsupportedData,
+            1005 => CloseCode::NoStatus,
+            1006 => CloseCode::Abnormal,
+            1007 => CloseCode::InvalidPayload,
+            1008 => CloseCode::PolicyViolation,
+            1009 => CloseCode::MessageTooBig,
+            1010 => CloseCode::MandatoryExtension,
+            1011 => CloseCode::InternalError,
+            1012 => CloseCode::ServiceRestart,
+            1013 => CloseCode::TryAgainLater,
+            1014 => CloseCode::BadGateway,
+            1015 => CloseCode::TlsHandshake,
+            3000..=4999 => CloseCode::Custom(code),
+            _ => CloseCode::Reserved(code),
+        }
+    }
+}
+
+impl From<CloseCode> for u16 {
+    fn from(code: CloseCode) -> Self {
+        match code {
+            CloseCode::Normal => 1000,
+            CloseCode::GoingAway => 1001,
+            CloseCode::ProtocolError => 1002,
+            CloseCode::UnsupportedData => 1003,
+            CloseCode::NoStatus => 1005,
+            CloseCode::Abnormal => 1006,
+            CloseCode::InvalidPayload => 1007,
+            CloseCode::PolicyViolation => 1008,
+            CloseCode::MessageTooBig => 1009,
+            CloseCode::MandatoryExtension => 1010,
+            CloseCode::InternalError => 1011,
+            CloseCode::ServiceRestart => 1012,
+            CloseCode::TryAgainLater => 1013,
+            CloseCode::BadGateway => 1014,
+            CloseCode::TlsHandshake => 1015,
+            CloseCode::Custom(code) | CloseCode::Reserved(code) => code,
+        }
+    }
+}
+
+impl fmt::Display for CloseCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", u16::from(*self))
+    }
+}
+
 /// Data emitted by `onclose` event
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "worker", derive(serde::Serialize, serde::Deserialize))]
 pub struct CloseEvent {
     /// Close code
-    pub code: u16,
+    pub code: CloseCode,
     /// Close reason
     pub reason: String,
     /// If the websockets was closed cleanly
     pub was_clean: bool,
 }
+
+/// Data emitted by `onerror` event
+///
+/// Browsers don't expose why a connection failed for security reasons, so this mostly helps
+/// telling failures apart by when they happened.
+#[derive(Clone, Debug)]
+pub struct ErrorEvent {
+    /// The type of the event, usually `error`
+    pub event_type: String,
+    /// The error message, only available if the environment dispatched an `ErrorEvent`
+    pub message: Option<String>,
+    /// The state of the websocket when the error occurred
+    pub ready_state: State,
+    /// The code of the close event following the error, if it was received by the time the
+    /// error was yielded
+    pub close_code: Option<CloseCode>,
+}
+
+/// A change in the lifecycle of a connection, yielded by
+/// [`WebSocket::events`](crate::websocket::futures::WebSocket::events).
+#[derive(Debug)]
+pub enum WebSocketEvent {
+    /// The `open` event
+    Open,
+    /// The `error` event
+    Error(JsError),
+    /// The `close` event. This is always the last event.
+    Close(CloseEvent),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close_codes() {
+        for code in 0..=u16::MAX {
+            assert_eq!(u16::from(CloseCode::from(code)), code);
+        }
+        assert_eq!(CloseCode::from(4000), CloseCode::Custom(4000));
+        assert_eq!(CloseCode::from(2000), CloseCode::Reserved(2000));
+
+        assert!(CloseCode::GoingAway.can_be_sent());
+        assert!(!CloseCode::GoingAway.can_be_sent_by_browsers());
+        assert!(CloseCode::Normal.can_be_sent_by_browsers());
+        assert!(CloseCode::Custom(4000).can_be_sent_by_browsers());
+        assert!(!CloseCode::Abnormal.can_be_sent());
+        assert!(!CloseCode::Reserved(2000).can_be_sent());
+        // Built directly with a code outside of its range.
+        assert!(!CloseCode::Custom(1006).can_be_sent());
+    }
+}
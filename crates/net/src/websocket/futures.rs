@@ -28,28 +28,56 @@
 //! # }
 //! ```
 use crate::js_to_js_error;
-use crate::websocket::{events::CloseEvent, Message, State, WebSocketError};
+use crate::websocket::adapters::{BytesWebSocket, TextWebSocket};
+use crate::websocket::events::{CloseCode, CloseEvent, ErrorEvent, WebSocketEvent};
+use crate::websocket::heartbeat::{Heartbeat, HeartbeatTask};
+use crate::websocket::io::WebSocketIo;
+#[cfg(feature = "tracing")]
+use crate::websocket::metrics::payload_len;
+use crate::websocket::metrics::{Metrics, MetricsStream, SharedCounters};
+use crate::websocket::queue::MessageQueue;
+use crate::websocket::raw::{RawMessage, RawWebSocket};
+use crate::websocket::{Message, State, WebSocketError};
 use futures_channel::mpsc;
 use futures_core::{ready, Stream};
 use futures_sink::Sink;
+use gloo_timers::callback::Timeout;
+use gloo_timers::future::TimeoutFuture;
 use gloo_utils::errors::JsError;
 use pin_project::{pin_project, pinned_drop};
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::convert::TryFrom;
+use std::fmt;
+use std::future::Future;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::task::{Context, Poll, Waker};
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::{spawn_local, JsFuture};
 use web_sys::{BinaryType, MessageEvent};
 
 /// Wrapper around browser's WebSocket API.
+///
+/// The [`Sink`] accepts anything convertible into a [`Message`], so `ws.send("hello")` sends a
+/// text message and `ws.send(vec![1, 2, 3])` a binary one.
 #[allow(missing_debug_implementations)]
 #[pin_project(PinnedDrop)]
 pub struct WebSocket {
     ws: web_sys::WebSocket,
     sink_waker: Rc<RefCell<Option<Waker>>>,
-    #[pin]
-    message_receiver: mpsc::UnboundedReceiver<StreamMessage>,
+    lifecycle: Rc<RefCell<Lifecycle>>,
+    high_water_mark: Option<u32>,
+    drain_timer: Option<TimeoutFuture>,
+    on_drop: CloseBehavior,
+    close_frame: Option<(CloseCode, String)>,
+    _open_timeout: Option<Timeout>,
+    heartbeat: Option<Rc<HeartbeatTask>>,
+    message_queue: MessageQueue<StreamMessage>,
+    counters: SharedCounters,
+    /// Whether binary messages are queued as `ArrayBuffer`s, see [`WebSocket::into_raw_stream`].
+    raw: Rc<Cell<bool>>,
     #[allow(clippy::type_complexity)]
     closures: (
         Closure<dyn FnMut()>,
@@ -66,11 +94,25 @@ impl WebSocket {
     /// - The port to which the connection is being attempted is being blocked.
     /// - The URL is invalid.
     ///
-    /// The error returned is [`JsError`]. See the
+    /// The error returned is [`WebSocketError::OpenError`], holding the exception thrown. See the
     /// [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/WebSocket#exceptions_thrown)
     /// to learn more.
-    pub fn open(url: &str) -> Result<Self, JsError> {
-        Self::setup(web_sys::WebSocket::new(url))
+    pub fn open(url: &str) -> Result<Self, WebSocketError> {
+        Self::builder(url).open()
+    }
+
+    /// Establish a WebSocket connection which fails with [`WebSocketError::ConnectTimeout`] if
+    /// it isn't open within `timeout`.
+    ///
+    /// This is a shorthand for [`WebSocketBuilder::open_timeout`], see [`WebSocket::open`] for
+    /// the errors returned.
+    pub fn open_with_timeout(url: &str, timeout: Duration) -> Result<Self, WebSocketError> {
+        Self::builder(url).open_timeout(timeout).open()
+    }
+
+    /// Creates a [`WebSocketBuilder`] to configure the connection before opening it.
+    pub fn builder(url: &str) -> WebSocketBuilder {
+        WebSocketBuilder::new(url)
     }
 
     /// Establish a WebSocket connection.
@@ -80,11 +122,11 @@ impl WebSocket {
     /// - The URL is invalid.
     /// - The specified protocol is not supported
     ///
-    /// The error returned is [`JsError`]. See the
+    /// The error returned is [`WebSocketError::OpenError`], holding the exception thrown. See the
     /// [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/WebSocket#exceptions_thrown)
     /// to learn more.
-    pub fn open_with_protocol(url: &str, protocol: &str) -> Result<Self, JsError> {
-        Self::setup(web_sys::WebSocket::new_with_str(url, protocol))
+    pub fn open_with_protocol(url: &str, protocol: &str) -> Result<Self, WebSocketError> {
+        Self::builder(url).protocol(protocol).open()
     }
 
     /// Establish a WebSocket connection.
@@ -93,103 +135,246 @@ impl WebSocket {
     /// - The port to which the connection is being attempted is being blocked.
     /// - The URL is invalid.
     /// - The specified protocols are not supported
-    /// - The protocols cannot be converted to a JSON string list
     ///
-    /// The error returned is [`JsError`]. See the
+    /// The error returned is [`WebSocketError::OpenError`], holding the exception thrown. See the
     /// [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/WebSocket#exceptions_thrown)
     /// to learn more.
-    ///
-    /// This function requires `json` features because protocols are parsed by `serde` into `JsValue`.
-    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
-    #[cfg(feature = "json")]
-    pub fn open_with_protocols<S: AsRef<str> + serde::Serialize>(
+    pub fn open_with_protocols<S: AsRef<str>>(
         url: &str,
         protocols: &[S],
-    ) -> Result<Self, JsError> {
-        let json = <JsValue as gloo_utils::format::JsValueSerdeExt>::from_serde(protocols)
-            .map_err(|err| {
-                js_sys::Error::new(&format!(
-                    "Failed to convert protocols to Javascript value: {err}"
-                ))
-            })?;
-        Self::setup(web_sys::WebSocket::new_with_str_sequence(url, &json))
+    ) -> Result<Self, WebSocketError> {
+        Self::builder(url).protocols(protocols).open()
     }
 
-    fn setup(ws: Result<web_sys::WebSocket, JsValue>) -> Result<Self, JsError> {
+    fn setup(
+        ws: Result<web_sys::WebSocket, JsValue>,
+        config: &WebSocketBuilder,
+    ) -> Result<Self, WebSocketError> {
         let waker: Rc<RefCell<Option<Waker>>> = Rc::new(RefCell::new(None));
-        let ws = ws.map_err(js_to_js_error)?;
+        let ws = ws.map_err(|e| WebSocketError::OpenError(js_to_js_error(e)))?;
+        let lifecycle = Rc::new(RefCell::new(Lifecycle::new(&ws)));
+        trace_event!(debug, parent: &lifecycle.borrow().span, "connecting");
 
-        // We rely on this because the other type Blob can be converted to Vec<u8> only through a
-        // promise which makes it awkward to use in our event callbacks where we want to guarantee
-        // the order of the events stays the same.
-        ws.set_binary_type(BinaryType::Arraybuffer);
+        ws.set_binary_type(config.binary_type);
 
-        let (sender, receiver) = mpsc::unbounded();
+        let queue = MessageQueue::new(config.queue_capacity, config.overflow_policy);
+        let counters = SharedCounters::default();
+        let raw = Rc::new(Cell::new(false));
+
+        let once_options = web_sys::AddEventListenerOptions::new();
+        once_options.set_once(true);
 
         let open_callback: Closure<dyn FnMut()> = {
             let waker = Rc::clone(&waker);
+            let lifecycle = Rc::clone(&lifecycle);
             Closure::wrap(Box::new(move || {
                 if let Some(waker) = waker.borrow_mut().take() {
                     waker.wake();
                 }
+                let mut lifecycle = lifecycle.borrow_mut();
+                lifecycle.wake_all();
+                lifecycle.set_state(State::Open);
+                lifecycle.emit(|| WebSocketEvent::Open);
+                trace_event!(debug, parent: &lifecycle.span, "connection open");
             }) as Box<dyn FnMut()>)
         };
 
         ws.add_event_listener_with_callback_and_add_event_listener_options(
             "open",
             open_callback.as_ref().unchecked_ref(),
-            web_sys::AddEventListenerOptions::new().once(true),
+            &once_options,
         )
-        .map_err(js_to_js_error)?;
+        .map_err(|e| WebSocketError::OpenError(js_to_js_error(e)))?;
+
+        let heartbeat = config.heartbeat.clone().map(|heartbeat| {
+            Rc::new(HeartbeatTask::start(
+                heartbeat,
+                ws.clone(),
+                queue.clone(),
+                Rc::clone(&counters),
+                || StreamMessage::KeepAliveTimeout,
+            ))
+        });
 
         let message_callback: Closure<dyn FnMut(MessageEvent)> = {
-            let sender = sender.clone();
+            let queue = queue.clone();
+            let heartbeat = heartbeat.clone();
+            let counters = Rc::clone(&counters);
+            let raw = Rc::clone(&raw);
+            let max_message_size = config.max_message_size;
+            #[cfg(feature = "tracing")]
+            let span = lifecycle.borrow().span.clone();
             Closure::wrap(Box::new(move |e: MessageEvent| {
+                if let Some(heartbeat) = &heartbeat {
+                    heartbeat.received();
+                }
+                if let Some(limit) = max_message_size {
+                    if let Some(size) = oversized_message(&e.data(), limit) {
+                        counters.borrow_mut().received_bytes(size as u64);
+                        trace_event!(warn, parent: &span, size, limit, "message too large");
+                        queue.push_control(StreamMessage::MessageTooLarge { size, limit });
+                        return;
+                    }
+                }
+                // Blobs can only be read through a promise. Their place in the queue is reserved
+                // so that the messages and events after them keep their order.
+                if let Some(blob) = e.data().dyn_ref::<web_sys::Blob>() {
+                    counters.borrow_mut().received_bytes(blob.size() as u64);
+                    trace_event!(trace, parent: &span, binary = true, len = blob.size(), "message received");
+                    if let Some(reservation) = queue.reserve(|| StreamMessage::QueueOverflow) {
+                        let array_buffer = JsFuture::from(blob.array_buffer());
+                        let queue = queue.clone();
+                        let raw = Rc::clone(&raw);
+                        spawn_local(async move {
+                            let msg = array_buffer.await.ok().map(|array_buffer| {
+                                binary_message(array_buffer.unchecked_into(), raw.get())
+                            });
+                            queue.fulfil(reservation, msg);
+                        });
+                    }
+                    return;
+                }
+                if let Ok(array_buffer) = e.data().dyn_into::<js_sys::ArrayBuffer>() {
+                    counters
+                        .borrow_mut()
+                        .received_bytes(u64::from(array_buffer.byte_length()));
+                    trace_event!(
+                        trace,
+                        parent: &span,
+                        binary = true,
+                        len = array_buffer.byte_length(),
+                        "message received"
+                    );
+                    queue.push_data(binary_message(array_buffer, raw.get()), || {
+                        StreamMessage::QueueOverflow
+                    });
+                    return;
+                }
                 let msg = parse_message(e);
-                let _ = sender.unbounded_send(StreamMessage::Message(msg));
+                counters.borrow_mut().received(&msg);
+                trace_event!(
+                    trace,
+                    parent: &span,
+                    binary = matches!(msg, Message::Bytes(_)),
+                    len = payload_len(&msg),
+                    "message received"
+                );
+                queue.push_data(StreamMessage::Message(msg), || StreamMessage::QueueOverflow);
             }) as Box<dyn FnMut(MessageEvent)>)
         };
 
         ws.add_event_listener_with_callback("message", message_callback.as_ref().unchecked_ref())
-            .map_err(js_to_js_error)?;
+            .map_err(|e| WebSocketError::OpenError(js_to_js_error(e)))?;
 
         let error_callback: Closure<dyn FnMut(web_sys::Event)> = {
-            let sender = sender.clone();
+            let ws = ws.clone();
+            let queue = queue.clone();
             let waker = Rc::clone(&waker);
-            Closure::wrap(Box::new(move |_e: web_sys::Event| {
+            let lifecycle = Rc::clone(&lifecycle);
+            Closure::wrap(Box::new(move |e: web_sys::Event| {
                 if let Some(waker) = waker.borrow_mut().take() {
                     waker.wake();
                 }
-                let _ = sender.unbounded_send(StreamMessage::ErrorEvent);
+                let mut lifecycle = lifecycle.borrow_mut();
+                lifecycle.wake_all();
+                // The event doesn't carry any details for security reasons, but some
+                // environments dispatch an `ErrorEvent` instead.
+                let error_event = e.dyn_ref::<web_sys::ErrorEvent>();
+                let error = error_event
+                    .and_then(|e| e.error().dyn_into::<js_sys::Error>().ok())
+                    .unwrap_or_else(|| js_sys::Error::new("WebSocket connection failed"));
+                let details = ErrorEvent {
+                    event_type: e.type_(),
+                    message: error_event.map(|e| e.message()),
+                    ready_state: ready_state_to_state(ws.ready_state()),
+                    close_code: None,
+                };
+                lifecycle.emit(|| WebSocketEvent::Error(JsError::from(error.clone())));
+                lifecycle.set_state(details.ready_state);
+                trace_event!(warn, parent: &lifecycle.span, message = ?details.message, "connection error");
+                lifecycle.last_error = Some(details.clone());
+                if !lifecycle.timed_out {
+                    queue.push_control(StreamMessage::ErrorEvent(details));
+                }
             }) as Box<dyn FnMut(web_sys::Event)>)
         };
 
         ws.add_event_listener_with_callback("error", error_callback.as_ref().unchecked_ref())
-            .map_err(js_to_js_error)?;
+            .map_err(|e| WebSocketError::OpenError(js_to_js_error(e)))?;
 
         let close_callback: Closure<dyn FnMut(web_sys::CloseEvent)> = {
+            let queue = queue.clone();
+            let lifecycle = Rc::clone(&lifecycle);
             Closure::wrap(Box::new(move |e: web_sys::CloseEvent| {
                 let close_event = CloseEvent {
-                    code: e.code(),
+                    code: e.code().into(),
                     reason: e.reason(),
                     was_clean: e.was_clean(),
                 };
-                let _ = sender.unbounded_send(StreamMessage::CloseEvent(close_event));
-                let _ = sender.unbounded_send(StreamMessage::ConnectionClose);
+                {
+                    let mut lifecycle = lifecycle.borrow_mut();
+                    lifecycle.close_event = Some(close_event.clone());
+                    lifecycle.wake_all();
+                    lifecycle.emit(|| WebSocketEvent::Close(close_event.clone()));
+                    lifecycle.set_state(State::Closed);
+                    trace_event!(
+                        debug,
+                        parent: &lifecycle.span,
+                        code = u16::from(close_event.code),
+                        reason = %close_event.reason,
+                        was_clean = close_event.was_clean,
+                        "connection closed"
+                    );
+                    // Nothing happens after the close event, end the event streams.
+                    lifecycle.listeners.clear();
+                    lifecycle.state_listeners.clear();
+                }
+                queue.push_control(StreamMessage::CloseEvent(close_event));
+                queue.push_control(StreamMessage::ConnectionClose);
             }) as Box<dyn FnMut(web_sys::CloseEvent)>)
         };
 
         ws.add_event_listener_with_callback_and_add_event_listener_options(
             "close",
             close_callback.as_ref().unchecked_ref(),
-            web_sys::AddEventListenerOptions::new().once(true),
+            &once_options,
         )
-        .map_err(js_to_js_error)?;
+        .map_err(|e| WebSocketError::OpenError(js_to_js_error(e)))?;
+
+        // Browsers wait a long time before giving up on unreachable hosts. Closing the socket
+        // while it is still connecting fires the `error` and `close` events, the former is
+        // replaced by `ConnectTimeout`.
+        let open_timeout = config.open_timeout.map(|timeout| {
+            let ws = ws.clone();
+            let queue = queue.clone();
+            let lifecycle = Rc::clone(&lifecycle);
+            let millis = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+            Timeout::new(millis, move || {
+                if ws.ready_state() == web_sys::WebSocket::CONNECTING {
+                    lifecycle.borrow_mut().timed_out = true;
+                    trace_event!(warn, parent: &lifecycle.borrow().span, "connection timed out");
+                    queue.push_control(StreamMessage::ConnectTimeout);
+                    let _ = ws.close();
+                    lifecycle
+                        .borrow_mut()
+                        .set_state(ready_state_to_state(ws.ready_state()));
+                }
+            })
+        });
 
         Ok(Self {
             ws,
             sink_waker: waker,
-            message_receiver: receiver,
+            lifecycle,
+            high_water_mark: config.high_water_mark,
+            drain_timer: None,
+            on_drop: config.on_drop.clone(),
+            close_frame: config.close_frame.clone(),
+            _open_timeout: open_timeout,
+            heartbeat,
+            message_queue: queue,
+            counters,
+            raw,
             closures: (
                 open_callback,
                 message_callback,
@@ -202,31 +387,347 @@ impl WebSocket {
     /// Closes the websocket.
     ///
     /// See the [MDN Documentation](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/close#parameters)
-    /// to learn about parameters passed to this function and when it can return an `Err(_)`
-    pub fn close(self, code: Option<u16>, reason: Option<&str>) -> Result<(), JsError> {
-        let result = match (code, reason) {
-            (None, None) => self.ws.close(),
-            (Some(code), None) => self.ws.close_with_code(code),
-            (Some(code), Some(reason)) => self.ws.close_with_code_and_reason(code, reason),
-            // default code is 1005 so we use it,
-            // see: https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/close#parameters
-            (None, Some(reason)) => self.ws.close_with_code_and_reason(1005, reason),
+    /// to learn about parameters passed to this function and when it can return an `Err(_)`.
+    /// Codes browsers don't accept, see [`CloseCode::can_be_sent_by_browsers`], are rejected
+    /// with [`WebSocketError::InvalidCloseCode`] before reaching the browser, other exceptions
+    /// thrown by it are returned as [`WebSocketError::CloseError`].
+    pub fn close(
+        self,
+        code: Option<CloseCode>,
+        reason: Option<&str>,
+    ) -> Result<(), WebSocketError> {
+        self.close_handle().close(code, reason)
+    }
+
+    /// Returns a handle which can close the websocket without owning it.
+    ///
+    /// This is useful after splitting the socket with `StreamExt::split`, where
+    /// [`close`](Self::close) can no longer be called.
+    pub fn close_handle(&self) -> CloseHandle {
+        CloseHandle {
+            ws: self.ws.clone(),
+            lifecycle: Rc::clone(&self.lifecycle),
+        }
+    }
+
+    /// Waits until the connection is open.
+    ///
+    /// Resolves immediately if the socket is already open. If the connection fails or is
+    /// closed before reaching the `Open` state, the future resolves to
+    /// [`WebSocketError::ConnectionClose`] (or [`WebSocketError::ConnectionError`] if the close
+    /// event hasn't been received yet). If the [open timeout](WebSocketBuilder::open_timeout)
+    /// elapses, it resolves to [`WebSocketError::ConnectTimeout`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+    /// # use gloo_net::websocket::browser::WebSocket;
+    /// # #[cfg(not(all(feature = "native", not(target_arch = "wasm32"))))]
+    /// use gloo_net::websocket::futures::WebSocket;
+    ///
+    /// # async fn no_run() {
+    /// let ws = WebSocket::open("wss://echo.websocket.org").unwrap();
+    /// ws.connected().await.unwrap();
+    /// // spawn the send and receive tasks...
+    /// # }
+    /// ```
+    pub fn connected(&self) -> impl Future<Output = Result<(), WebSocketError>> {
+        Connected {
+            ws: self.ws.clone(),
+            lifecycle: Rc::clone(&self.lifecycle),
+        }
+    }
+
+    /// Returns a stream of connection lifecycle events, separate from the messages yielded by
+    /// the [`Stream`] implementation.
+    ///
+    /// Only events which happen after this call are yielded, except that the close event is
+    /// replayed if the connection is already closed. The stream ends after the close event or
+    /// when the [`WebSocket`] is dropped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use gloo_net::websocket::events::WebSocketEvent;
+    /// # #[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+    /// # use gloo_net::websocket::browser::WebSocket;
+    /// # #[cfg(not(all(feature = "native", not(target_arch = "wasm32"))))]
+    /// use gloo_net::websocket::futures::WebSocket;
+    /// use wasm_bindgen_futures::spawn_local;
+    /// use futures::StreamExt;
+    ///
+    /// # macro_rules! console_log {
+    /// #    ($($expr:expr),*) => {{}};
+    /// # }
+    /// # fn no_run() {
+    /// let ws = WebSocket::open("wss://echo.websocket.org").unwrap();
+    /// let mut events = ws.events();
+    ///
+    /// spawn_local(async move {
+    ///     while let Some(event) = events.next().await {
+    ///         match event {
+    ///             WebSocketEvent::Open => console_log!("connected"),
+    ///             WebSocketEvent::Error(_) => console_log!("connection error"),
+    ///             WebSocketEvent::Close(_) => console_log!("disconnected"),
+    ///         }
+    ///     }
+    /// });
+    /// # }
+    /// ```
+    pub fn events(&self) -> impl Stream<Item = WebSocketEvent> {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut lifecycle = self.lifecycle.borrow_mut();
+        match lifecycle.close_event.clone() {
+            Some(close_event) => {
+                let _ = sender.unbounded_send(WebSocketEvent::Close(close_event));
+            }
+            None => lifecycle.listeners.push(sender),
+        }
+        receiver
+    }
+
+    /// Returns a stream of the [`State`]s the connection goes through.
+    ///
+    /// The current state is yielded first, followed by every transition. `Closing` is only
+    /// observed when the connection is closed by this side, e.g. through [`close`](Self::close)
+    /// or a [`CloseHandle`], since browsers don't notify about the server starting the closing
+    /// handshake. The stream ends once `Closed` has been yielded or the [`WebSocket`] is dropped.
+    pub fn state_stream(&self) -> impl Stream<Item = State> {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut lifecycle = self.lifecycle.borrow_mut();
+        let _ = sender.unbounded_send(lifecycle.state);
+        if lifecycle.state != State::Closed {
+            lifecycle.state_listeners.push(sender);
+        }
+        receiver
+    }
+
+    /// Sets what happens to the connection when this [`WebSocket`] is dropped.
+    ///
+    /// By default the connection is closed without a code. Failures to close the connection
+    /// (e.g. because of an invalid code) are ignored.
+    pub fn set_on_drop(&mut self, on_drop: CloseBehavior) {
+        self.on_drop = on_drop;
+    }
+
+    /// Sets the code and reason sent when the socket is closed through [`Sink::poll_close`],
+    /// e.g. by `SinkExt::close`.
+    ///
+    /// `None`, the default, closes the connection without a code. Only
+    /// [`CloseCode::Normal`] and [`CloseCode::Custom`] codes can be sent by browsers.
+    pub fn set_close_frame(&mut self, code: CloseCode, reason: &str) {
+        self.close_frame = Some((code, reason.to_string()));
+    }
+
+    /// Converts this into the underlying `web_sys::WebSocket`, leaving the connection open.
+    ///
+    /// Messages which have been received but not yet yielded by the [`Stream`] are lost.
+    pub fn into_raw(mut self) -> web_sys::WebSocket {
+        self.on_drop = CloseBehavior::Forget;
+        self.ws.clone()
+    }
+
+    /// Converts this into a byte stream implementing `AsyncRead` and `AsyncWrite`.
+    ///
+    /// See [`WebSocketIo`] for how bytes map to messages.
+    pub fn into_io(self) -> WebSocketIo<Self> {
+        WebSocketIo::new(self)
+    }
+
+    /// Converts this into a stream and sink of text messages.
+    ///
+    /// See [`TextWebSocket`].
+    pub fn into_text_stream(self) -> TextWebSocket<Self> {
+        TextWebSocket::new(self)
+    }
+
+    /// Converts this into a stream and sink of binary messages.
+    ///
+    /// See [`BytesWebSocket`].
+    pub fn into_bytes_stream(self) -> BytesWebSocket<Self> {
+        BytesWebSocket::new(self)
+    }
+
+    /// Converts this into a stream and sink of messages whose binary payloads stay in the
+    /// JavaScript heap.
+    ///
+    /// See [`RawWebSocket`].
+    pub fn into_raw_stream(self) -> RawWebSocket {
+        self.raw.set(true);
+        RawWebSocket::new(self)
+    }
+
+    /// Stops queueing binary messages as `ArrayBuffer`s, when a [`RawWebSocket`] is unwrapped.
+    pub(crate) fn set_raw(&self, raw: bool) {
+        self.raw.set(raw);
+    }
+
+    /// Sends a message right away, failing with [`WebSocketError::NotOpen`] unless the
+    /// connection is open.
+    ///
+    /// Unlike the [`Sink`], this doesn't wait for the connection to be established or for the
+    /// [high-water mark](Self::set_high_water_mark) to be reached, which makes it convenient for
+    /// fire-and-forget messages sent from event handlers.
+    pub fn try_send(&self, msg: impl Into<Message>) -> Result<(), WebSocketError> {
+        match ready_state_to_state(self.ws.ready_state()) {
+            State::Open => self.send_message(msg.into()),
+            state => Err(WebSocketError::NotOpen(state)),
+        }
+    }
+
+    fn send_message(&self, msg: Message) -> Result<(), WebSocketError> {
+        if let Some(heartbeat) = &self.heartbeat {
+            heartbeat.sent();
+        }
+        let result = match &msg {
+            Message::Bytes(bytes) => self.ws.send_with_u8_array(bytes),
+            Message::Text(message) => self.ws.send_with_str(message),
         };
-        result.map_err(js_to_js_error)
+        match result {
+            Ok(_) => {
+                self.counters.borrow_mut().sent(&msg);
+                trace_event!(
+                    trace,
+                    parent: &self.lifecycle.borrow().span,
+                    binary = matches!(msg, Message::Bytes(_)),
+                    len = payload_len(&msg),
+                    "message sent"
+                );
+                Ok(())
+            }
+            Err(e) => {
+                let e = js_to_js_error(e);
+                trace_event!(warn, parent: &self.lifecycle.borrow().span, error = %e, "send failed");
+                Err(WebSocketError::MessageSendError(e))
+            }
+        }
+    }
+
+    /// Registers the current task to be woken when the connection opens, fails or closes.
+    pub(crate) fn register_lifecycle_waker(&self, waker: &Waker) {
+        self.lifecycle.borrow_mut().register(waker);
+    }
+
+    /// Sends the bytes viewed by `array` without copying them into wasm memory first.
+    pub(crate) fn send_array(&self, array: &js_sys::Uint8Array) -> Result<(), WebSocketError> {
+        if let Some(heartbeat) = &self.heartbeat {
+            heartbeat.sent();
+        }
+        self.ws
+            .send_with_array_buffer_view(array)
+            .map_err(|e| WebSocketError::MessageSendError(js_to_js_error(e)))?;
+        self.counters
+            .borrow_mut()
+            .sent_bytes(u64::from(array.byte_length()));
+        trace_event!(
+            trace,
+            parent: &self.lifecycle.borrow().span,
+            binary = true,
+            len = array.byte_length(),
+            "message sent"
+        );
+        Ok(())
+    }
+
+    /// Polls the queue like the [`Stream`], with binary messages as `Uint8Array`s.
+    pub(crate) fn poll_next_raw(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<RawMessage, WebSocketError>>> {
+        Poll::Ready(match ready!(self.message_queue.poll_next(cx)) {
+            StreamMessage::Message(Message::Text(text)) => Some(Ok(RawMessage::Text(text))),
+            // Queued before the socket was converted.
+            StreamMessage::Message(Message::Bytes(bytes)) => {
+                Some(Ok(RawMessage::Bytes(js_sys::Uint8Array::from(&bytes[..]))))
+            }
+            StreamMessage::Buffer(array_buffer) => Some(Ok(RawMessage::Bytes(
+                js_sys::Uint8Array::new(&array_buffer),
+            ))),
+            msg => self.control_item(msg),
+        })
+    }
+
+    /// The stream item of a control message.
+    fn control_item<T>(&self, msg: StreamMessage) -> Option<Result<T, WebSocketError>> {
+        match msg {
+            StreamMessage::ErrorEvent(mut e) => {
+                e.close_code = self.lifecycle.borrow().close_event.as_ref().map(|e| e.code);
+                Some(Err(WebSocketError::ConnectionError(e)))
+            }
+            StreamMessage::ConnectTimeout => Some(Err(WebSocketError::ConnectTimeout)),
+            StreamMessage::KeepAliveTimeout => Some(Err(WebSocketError::KeepAliveTimeout)),
+            StreamMessage::QueueOverflow => Some(Err(WebSocketError::QueueOverflow)),
+            StreamMessage::MessageTooLarge { size, limit } => {
+                Some(Err(WebSocketError::MessageTooLarge { size, limit }))
+            }
+            StreamMessage::CloseEvent(e) => Some(Err(WebSocketError::ConnectionClose(e))),
+            StreamMessage::ConnectionClose => None,
+            StreamMessage::Message(_) | StreamMessage::Buffer(_) => {
+                unreachable!("data items are handled by the caller")
+            }
+        }
     }
 
     /// The current state of the websocket.
     pub fn state(&self) -> State {
-        let ready_state = self.ws.ready_state();
-        match ready_state {
-            0 => State::Connecting,
-            1 => State::Open,
-            2 => State::Closing,
-            3 => State::Closed,
-            _ => unreachable!(),
+        ready_state_to_state(self.ws.ready_state())
+    }
+
+    /// The number of bytes of data that have been queued using calls to `send` but not yet
+    /// transmitted to the network.
+    ///
+    /// See [`WebSocket.bufferedAmount` on MDN](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/bufferedAmount)
+    /// to learn more.
+    pub fn buffered_amount(&self) -> u32 {
+        self.ws.buffered_amount()
+    }
+
+    /// Sets the high-water mark, in bytes, used to apply backpressure to senders.
+    ///
+    /// When set, [`Sink::poll_ready`] and [`Sink::poll_flush`] return `Pending` while
+    /// [`buffered_amount`](Self::buffered_amount) is above the mark, so fast producers wait for
+    /// the browser to transmit queued data instead of growing the buffer without bound. The
+    /// browser does not signal when the buffer drains, so it is polled every
+    /// [`DRAIN_POLL_INTERVAL_MS`] milliseconds.
+    ///
+    /// `None`, the default, disables backpressure.
+    pub fn set_high_water_mark(&mut self, high_water_mark: Option<u32>) {
+        self.high_water_mark = high_water_mark;
+    }
+
+    /// The number of received messages waiting to be yielded by the [`Stream`].
+    pub fn queue_len(&self) -> usize {
+        self.message_queue.len()
+    }
+
+    /// A snapshot of the traffic statistics of the connection.
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            queue_len: self.message_queue.len(),
+            buffered_amount: self.ws.buffered_amount(),
+            ..self.counters.borrow().snapshot()
         }
     }
 
+    /// Returns a stream yielding a [`metrics`](Self::metrics) snapshot every `interval`.
+    ///
+    /// See [`MetricsStream`] for when the stream ends.
+    pub fn metrics_stream(&self, interval: Duration) -> MetricsStream {
+        let ws = self.ws.clone();
+        let queue = self.message_queue.clone();
+        let counters = Rc::downgrade(&self.counters);
+        MetricsStream::new(interval, move || {
+            let counters = counters.upgrade()?;
+            let metrics = Metrics {
+                queue_len: queue.len(),
+                buffered_amount: ws.buffered_amount(),
+                ..counters.borrow().snapshot()
+            };
+            Some((metrics, ws.ready_state() == web_sys::WebSocket::CLOSED))
+        })
+    }
+
     /// The extensions in use.
     pub fn extensions(&self) -> String {
         self.ws.extensions()
@@ -238,55 +739,525 @@ impl WebSocket {
     }
 }
 
+/// Connection state shared with the event callbacks.
+struct Lifecycle {
+    wakers: Vec<Waker>,
+    listeners: Vec<mpsc::UnboundedSender<WebSocketEvent>>,
+    state: State,
+    state_listeners: Vec<mpsc::UnboundedSender<State>>,
+    close_event: Option<CloseEvent>,
+    last_error: Option<ErrorEvent>,
+    timed_out: bool,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl Lifecycle {
+    fn new(ws: &web_sys::WebSocket) -> Self {
+        Self {
+            wakers: Vec::new(),
+            listeners: Vec::new(),
+            state: ready_state_to_state(ws.ready_state()),
+            state_listeners: Vec::new(),
+            close_event: None,
+            last_error: None,
+            timed_out: false,
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!("websocket", url = %ws.url()),
+        }
+    }
+
+    /// Records the new state, notifying `state_stream` listeners if it changed.
+    fn set_state(&mut self, state: State) {
+        if self.state == state {
+            return;
+        }
+        self.state = state;
+        self.state_listeners
+            .retain(|listener| listener.unbounded_send(state).is_ok());
+    }
+
+    fn register(&mut self, waker: &Waker) {
+        if !self.wakers.iter().any(|w| w.will_wake(waker)) {
+            self.wakers.push(waker.clone());
+        }
+    }
+
+    fn wake_all(&mut self) {
+        for waker in self.wakers.drain(..) {
+            waker.wake();
+        }
+    }
+
+    fn emit(&mut self, event: impl Fn() -> WebSocketEvent) {
+        self.listeners
+            .retain(|listener| listener.unbounded_send(event()).is_ok());
+    }
+}
+
+/// Future returned by [`WebSocket::connected`].
+struct Connected {
+    ws: web_sys::WebSocket,
+    lifecycle: Rc<RefCell<Lifecycle>>,
+}
+
+impl Future for Connected {
+    type Output = Result<(), WebSocketError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut lifecycle = self.lifecycle.borrow_mut();
+        match self.ws.ready_state() {
+            web_sys::WebSocket::CONNECTING => {
+                lifecycle.register(cx.waker());
+                Poll::Pending
+            }
+            web_sys::WebSocket::OPEN => Poll::Ready(Ok(())),
+            _ if lifecycle.timed_out => Poll::Ready(Err(WebSocketError::ConnectTimeout)),
+            ready_state => Poll::Ready(Err(match lifecycle.close_event.clone() {
+                Some(close_event) => WebSocketError::ConnectionClose(close_event),
+                None => WebSocketError::ConnectionError(lifecycle.last_error.clone().unwrap_or(
+                    ErrorEvent {
+                        event_type: "error".to_string(),
+                        message: None,
+                        ready_state: ready_state_to_state(ready_state),
+                        close_code: None,
+                    },
+                )),
+            })),
+        }
+    }
+}
+
+/// What happens to the underlying connection when a [`WebSocket`] is dropped.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CloseBehavior {
+    /// Close the connection with the given code and reason.
+    ///
+    /// Only [`CloseCode::Normal`] and [`CloseCode::Custom`] codes can be sent by browsers.
+    /// Without a code, the browser sends `1005` (no status received).
+    Close {
+        /// Close code
+        code: Option<CloseCode>,
+        /// Close reason
+        reason: Option<String>,
+    },
+    /// Leave the connection open. Only the event listeners installed by this crate are removed.
+    Forget,
+}
+
+impl Default for CloseBehavior {
+    fn default() -> Self {
+        CloseBehavior::Close {
+            code: None,
+            reason: None,
+        }
+    }
+}
+
+/// What happens to messages received while the queue set by
+/// [`WebSocketBuilder::queue_capacity`] is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Discard the oldest queued message to make room for the new one.
+    DropOldest,
+    /// Discard the new message. This is the default.
+    #[default]
+    DropNewest,
+    /// Discard the new message and yield [`WebSocketError::QueueOverflow`] from the stream.
+    ///
+    /// The error is yielded once per burst, after the messages queued before it. The connection
+    /// stays open.
+    Error,
+}
+
+/// A cloneable handle to close a [`WebSocket`], obtained from [`WebSocket::close_handle`].
+#[derive(Clone)]
+pub struct CloseHandle {
+    ws: web_sys::WebSocket,
+    lifecycle: Rc<RefCell<Lifecycle>>,
+}
+
+impl CloseHandle {
+    /// Closes the websocket.
+    ///
+    /// See [`WebSocket::close`]. Closing an already closed websocket has no effect.
+    pub fn close(
+        &self,
+        code: Option<CloseCode>,
+        reason: Option<&str>,
+    ) -> Result<(), WebSocketError> {
+        close_socket(&self.ws, code, reason)?;
+        let mut lifecycle = self.lifecycle.borrow_mut();
+        trace_event!(debug, parent: &lifecycle.span, code = ?code, "closing");
+        lifecycle.set_state(ready_state_to_state(self.ws.ready_state()));
+        Ok(())
+    }
+
+    /// The current state of the websocket.
+    pub fn state(&self) -> State {
+        ready_state_to_state(self.ws.ready_state())
+    }
+}
+
+impl fmt::Debug for CloseHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CloseHandle")
+            .field("ws", &self.ws)
+            .finish_non_exhaustive()
+    }
+}
+
+fn close_socket(
+    ws: &web_sys::WebSocket,
+    code: Option<CloseCode>,
+    reason: Option<&str>,
+) -> Result<(), WebSocketError> {
+    if let Some(code) = code.filter(|code| !code.can_be_sent_by_browsers()) {
+        return Err(WebSocketError::InvalidCloseCode(code));
+    }
+    let result = match (code, reason) {
+        (None, None) => ws.close(),
+        (Some(code), None) => ws.close_with_code(code.into()),
+        (Some(code), Some(reason)) => ws.close_with_code_and_reason(code.into(), reason),
+        // A reason requires a code, and browsers reject `1005`, the code sent without one.
+        (None, Some(reason)) => ws.close_with_code_and_reason(CloseCode::Normal.into(), reason),
+    };
+    result.map_err(|e| WebSocketError::CloseError(js_to_js_error(e)))
+}
+
+fn ready_state_to_state(ready_state: u16) -> State {
+    match ready_state {
+        0 => State::Connecting,
+        1 => State::Open,
+        2 => State::Closing,
+        3 => State::Closed,
+        _ => unreachable!(),
+    }
+}
+
+/// A builder to configure a [`WebSocket`] before the connection is opened.
+///
+/// # Example
+///
+/// ```rust
+/// use gloo_net::websocket::events::CloseCode;
+/// use gloo_net::websocket::futures::{CloseBehavior, OverflowPolicy};
+/// # #[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+/// # use gloo_net::websocket::browser::WebSocket;
+/// # #[cfg(not(all(feature = "native", not(target_arch = "wasm32"))))]
+/// use gloo_net::websocket::futures::WebSocket;
+/// use std::time::Duration;
+///
+/// # fn no_run() {
+/// let ws = WebSocket::builder("wss://echo.websocket.org")
+///     .protocols(["graphql-transport-ws", "graphql-ws"])
+///     .open_timeout(Duration::from_secs(5))
+///     .queue_capacity(1024)
+///     .overflow_policy(OverflowPolicy::DropOldest)
+///     .on_drop(CloseBehavior::Close {
+///         code: Some(CloseCode::Custom(4001)),
+///         reason: Some("navigating away".to_string()),
+///     })
+///     .open()
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct WebSocketBuilder {
+    url: String,
+    protocols: Vec<String>,
+    high_water_mark: Option<u32>,
+    binary_type: BinaryType,
+    open_timeout: Option<Duration>,
+    queue_capacity: Option<usize>,
+    overflow_policy: OverflowPolicy,
+    max_message_size: Option<usize>,
+    heartbeat: Option<Heartbeat>,
+    on_drop: CloseBehavior,
+    close_frame: Option<(CloseCode, String)>,
+}
+
+impl WebSocketBuilder {
+    /// Creates a new builder for a connection to `url`.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            protocols: Vec::new(),
+            high_water_mark: None,
+            binary_type: BinaryType::Arraybuffer,
+            open_timeout: None,
+            queue_capacity: None,
+            overflow_policy: OverflowPolicy::default(),
+            max_message_size: None,
+            heartbeat: None,
+            on_drop: CloseBehavior::default(),
+            close_frame: None,
+        }
+    }
+
+    /// Adds a sub-protocol to offer to the server.
+    pub fn protocol(mut self, protocol: &str) -> Self {
+        self.protocols.push(protocol.to_string());
+        self
+    }
+
+    /// Adds sub-protocols to offer to the server, in order of preference.
+    pub fn protocols<I, S>(mut self, protocols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.protocols
+            .extend(protocols.into_iter().map(|p| p.as_ref().to_string()));
+        self
+    }
+
+    /// Sets the high-water mark used to apply backpressure to senders.
+    ///
+    /// See [`WebSocket::set_high_water_mark`].
+    pub fn high_water_mark(mut self, bytes: u32) -> Self {
+        self.high_water_mark = Some(bytes);
+        self
+    }
+
+    /// Sets how the browser delivers binary messages, which are always yielded as
+    /// [`Message::Bytes`].
+    ///
+    /// The default, [`BinaryType::Arraybuffer`], copies every binary frame into an `ArrayBuffer`
+    /// as soon as it arrives. With [`BinaryType::Blob`] the browser may keep large frames out of
+    /// the JavaScript heap until they are read, at the cost of an asynchronous conversion per
+    /// message. Messages are yielded in the order they were received in either mode.
+    pub fn binary_type(mut self, binary_type: BinaryType) -> Self {
+        self.binary_type = binary_type;
+        self
+    }
+
+    /// Closes the socket if it hasn't been opened within `timeout`.
+    ///
+    /// Browsers can stay in the `Connecting` state for a very long time against dead hosts. When
+    /// the timeout elapses, the stream yields [`WebSocketError::ConnectTimeout`] instead of
+    /// [`WebSocketError::ConnectionError`], followed by the close event.
+    pub fn open_timeout(mut self, timeout: Duration) -> Self {
+        self.open_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits the number of received messages buffered until the stream is polled.
+    ///
+    /// Messages received while the queue is full are handled according to the
+    /// [overflow policy](Self::overflow_policy). By default the queue is unbounded.
+    pub fn queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets what happens to messages received while the queue is full.
+    ///
+    /// Has no effect without a [`queue_capacity`](Self::queue_capacity).
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
+
+    /// Drops received messages larger than `bytes`, before their payload is copied out of the
+    /// JavaScript heap.
+    ///
+    /// The stream yields [`WebSocketError::MessageTooLarge`] in place of each dropped message and
+    /// the connection stays open. Text messages are measured in UTF-8 bytes. By default the size
+    /// of messages is unlimited.
+    pub fn max_message_size(mut self, bytes: usize) -> Self {
+        self.max_message_size = Some(bytes);
+        self
+    }
+
+    /// Enables application level keep-alive messages.
+    ///
+    /// See [`Heartbeat`] for details.
+    pub fn heartbeat(mut self, heartbeat: Heartbeat) -> Self {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+
+    /// Sets what happens to the connection when the [`WebSocket`] is dropped.
+    ///
+    /// See [`WebSocket::set_on_drop`].
+    pub fn on_drop(mut self, on_drop: CloseBehavior) -> Self {
+        self.on_drop = on_drop;
+        self
+    }
+
+    /// Sets the code and reason sent by [`Sink::poll_close`].
+    ///
+    /// See [`WebSocket::set_close_frame`].
+    pub fn close_frame(mut self, code: CloseCode, reason: &str) -> Self {
+        self.close_frame = Some((code, reason.to_string()));
+        self
+    }
+
+    /// The URL the connection will be opened to.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Establish the WebSocket connection.
+    ///
+    /// This fails under the same conditions as [`WebSocket::open_with_protocol`].
+    pub fn open(&self) -> Result<WebSocket, WebSocketError> {
+        let ws = match self.protocols.as_slice() {
+            [] => web_sys::WebSocket::new(&self.url),
+            [protocol] => web_sys::WebSocket::new_with_str(&self.url, protocol),
+            protocols => {
+                let array = protocols
+                    .iter()
+                    .map(|p| JsValue::from_str(p))
+                    .collect::<js_sys::Array>();
+                web_sys::WebSocket::new_with_str_sequence(&self.url, &array)
+            }
+        };
+        WebSocket::setup(ws, self)
+    }
+}
+
+/// How often, in milliseconds, `bufferedAmount` is checked while waiting for it to drop below
+/// the [high-water mark](WebSocket::set_high_water_mark).
+pub const DRAIN_POLL_INTERVAL_MS: u32 = 16;
+
+/// Waits until `bufferedAmount` is at or below the configured high-water mark.
+fn poll_drained(
+    ws: &web_sys::WebSocket,
+    high_water_mark: Option<u32>,
+    drain_timer: &mut Option<TimeoutFuture>,
+    cx: &mut Context<'_>,
+) -> Poll<()> {
+    let high_water_mark = match high_water_mark {
+        Some(high_water_mark) => high_water_mark,
+        None => return Poll::Ready(()),
+    };
+    loop {
+        // A closing or closed socket never drains, data sent to it is simply discarded.
+        if ws.buffered_amount() <= high_water_mark || ws.ready_state() > 1 {
+            *drain_timer = None;
+            return Poll::Ready(());
+        }
+        let timer = drain_timer.get_or_insert_with(|| TimeoutFuture::new(DRAIN_POLL_INTERVAL_MS));
+        ready!(Pin::new(timer).poll(cx));
+        *drain_timer = None;
+    }
+}
+
 #[derive(Clone)]
 enum StreamMessage {
-    ErrorEvent,
+    ErrorEvent(ErrorEvent),
+    ConnectTimeout,
+    KeepAliveTimeout,
+    QueueOverflow,
+    MessageTooLarge {
+        size: usize,
+        limit: usize,
+    },
     CloseEvent(CloseEvent),
     Message(Message),
+    /// A binary message received by a [`RawWebSocket`], not copied into wasm memory.
+    Buffer(js_sys::ArrayBuffer),
     ConnectionClose,
 }
 
-fn parse_message(event: MessageEvent) -> Message {
-    if let Ok(array_buffer) = event.data().dyn_into::<js_sys::ArrayBuffer>() {
+/// The queue item of a received `ArrayBuffer`, which is only copied for [`Message::Bytes`]
+/// when the socket isn't [raw](WebSocket::into_raw_stream).
+fn binary_message(array_buffer: js_sys::ArrayBuffer, raw: bool) -> StreamMessage {
+    if raw {
+        StreamMessage::Buffer(array_buffer)
+    } else {
         let array = js_sys::Uint8Array::new(&array_buffer);
-        Message::Bytes(array.to_vec())
-    } else if let Ok(txt) = event.data().dyn_into::<js_sys::JsString>() {
+        StreamMessage::Message(Message::Bytes(array.to_vec()))
+    }
+}
+
+/// Returns the size of a received message if it is larger than `limit` bytes.
+///
+/// Text is only decoded when its length in UTF-16 code units, which never exceeds its UTF-8
+/// length, is within the limit. Otherwise the code unit count is returned as the size.
+fn oversized_message(data: &JsValue, limit: usize) -> Option<usize> {
+    let size = if let Some(blob) = data.dyn_ref::<web_sys::Blob>() {
+        blob.size() as usize
+    } else if let Some(array_buffer) = data.dyn_ref::<js_sys::ArrayBuffer>() {
+        array_buffer.byte_length() as usize
+    } else if let Some(text) = data.dyn_ref::<js_sys::JsString>() {
+        match text.length() as usize {
+            units if units > limit => units,
+            _ => String::from(text).len(),
+        }
+    } else {
+        return None;
+    };
+    Some(size).filter(|size| *size > limit)
+}
+
+fn parse_message(event: MessageEvent) -> Message {
+    if let Ok(txt) = event.data().dyn_into::<js_sys::JsString>() {
         Message::Text(String::from(&txt))
     } else {
         unreachable!("message event, received Unknown: {:?}", event.data());
     }
 }
 
-impl Sink<Message> for WebSocket {
+impl<T: Into<Message>> Sink<T> for WebSocket {
     type Error = WebSocketError;
 
     fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        let ready_state = self.ws.ready_state();
+        let this = self.project();
+        let ready_state = this.ws.ready_state();
         if ready_state == 0 {
-            *self.sink_waker.borrow_mut() = Some(cx.waker().clone());
+            *this.sink_waker.borrow_mut() = Some(cx.waker().clone());
             Poll::Pending
         } else {
+            ready!(poll_drained(
+                this.ws,
+                *this.high_water_mark,
+                this.drain_timer,
+                cx
+            ));
             Poll::Ready(Ok(()))
         }
     }
 
-    fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
-        let result = match item {
-            Message::Bytes(bytes) => self.ws.send_with_u8_array(&bytes),
-            Message::Text(message) => self.ws.send_with_str(&message),
-        };
-        match result {
-            Ok(_) => Ok(()),
-            Err(e) => Err(WebSocketError::MessageSendError(js_to_js_error(e))),
-        }
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        self.send_message(item.into())
     }
 
-    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.project();
+        ready!(poll_drained(
+            this.ws,
+            *this.high_water_mark,
+            this.drain_timer,
+            cx
+        ));
         Poll::Ready(Ok(()))
     }
 
-    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
+    /// Starts the closing handshake and waits until the connection is fully closed, i.e. the
+    /// `close` event has fired.
+    ///
+    /// The code and reason can be set with [`WebSocket::set_close_frame`]. Messages received
+    /// in the meantime are still yielded by the [`Stream`].
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.project();
+        let mut lifecycle = this.lifecycle.borrow_mut();
+        if lifecycle.close_event.is_some() || this.ws.ready_state() == web_sys::WebSocket::CLOSED {
+            return Poll::Ready(Ok(()));
+        }
+        lifecycle.register(cx.waker());
+        if this.ws.ready_state() < web_sys::WebSocket::CLOSING {
+            let (code, reason) = match this.close_frame {
+                Some((code, reason)) => (Some(*code), Some(reason.as_str())),
+                None => (None, None),
+            };
+            close_socket(this.ws, code, reason)?;
+            trace_event!(debug, parent: &lifecycle.span, code = ?code, "closing");
+            lifecycle.set_state(ready_state_to_state(this.ws.ready_state()));
+        }
+        Poll::Pending
     }
 }
 
@@ -294,25 +1265,23 @@ impl Stream for WebSocket {
     type Item = Result<Message, WebSocketError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let msg = ready!(self.project().message_receiver.poll_next(cx));
-        match msg {
-            Some(StreamMessage::Message(msg)) => Poll::Ready(Some(Ok(msg))),
-            Some(StreamMessage::ErrorEvent) => {
-                Poll::Ready(Some(Err(WebSocketError::ConnectionError)))
-            }
-            Some(StreamMessage::CloseEvent(e)) => {
-                Poll::Ready(Some(Err(WebSocketError::ConnectionClose(e))))
-            }
-            Some(StreamMessage::ConnectionClose) => Poll::Ready(None),
-            None => Poll::Ready(None),
-        }
+        Poll::Ready(match ready!(self.message_queue.poll_next(cx)) {
+            StreamMessage::Message(msg) => Some(Ok(msg)),
+            // Queued before a `RawWebSocket` was unwrapped.
+            StreamMessage::Buffer(array_buffer) => Some(Ok(Message::Bytes(
+                js_sys::Uint8Array::new(&array_buffer).to_vec(),
+            ))),
+            msg => self.control_item(msg),
+        })
     }
 }
 
 #[pinned_drop]
 impl PinnedDrop for WebSocket {
     fn drop(self: Pin<&mut Self>) {
-        self.ws.close().unwrap();
+        if let CloseBehavior::Close { code, reason } = &self.on_drop {
+            let _ = close_socket(&self.ws, *code, reason.as_deref());
+        }
 
         for (ty, cb) in [
             ("open", self.closures.0.as_ref()),
@@ -324,12 +1293,22 @@ impl PinnedDrop for WebSocket {
                 .remove_event_listener_with_callback(ty, cb.unchecked_ref());
         }
 
-        if let Ok(close_event) = web_sys::CloseEvent::new_with_event_init_dict(
-            "close",
-            web_sys::CloseEventInit::new()
-                .code(1000)
-                .reason("client dropped"),
-        ) {
+        if self.on_drop == CloseBehavior::Forget {
+            // The socket stays open, so the `close` listener can't be consumed by the synthetic
+            // event below without confusing other listeners.
+            let _ = self.ws.remove_event_listener_with_callback(
+                "close",
+                self.closures.3.as_ref().unchecked_ref(),
+            );
+            return;
+        }
+
+        let close_event_init = web_sys::CloseEventInit::new();
+        close_event_init.set_code(CloseCode::Normal.into());
+        close_event_init.set_reason("client dropped");
+        if let Ok(close_event) =
+            web_sys::CloseEvent::new_with_event_init_dict("close", &close_event_init)
+        {
             let _ = self.ws.dispatch_event(&close_event);
         }
     }
@@ -345,6 +1324,7 @@ mod tests {
     wasm_bindgen_test_configure!(run_in_browser);
 
     #[wasm_bindgen_test]
+    #[allow(clippy::option_env_unwrap)]
     fn websocket_works() {
         let ws_echo_server_url =
             option_env!("WS_ECHO_SERVER_URL").expect("Did you set WS_ECHO_SERVER_URL?");
@@ -378,4 +1358,172 @@ mod tests {
             );
         });
     }
+
+    #[allow(clippy::option_env_unwrap)]
+    fn echo_server_url() -> &'static str {
+        option_env!("WS_ECHO_SERVER_URL").expect("Did you set WS_ECHO_SERVER_URL?")
+    }
+
+    /// Opens a socket to the echo server, after the message it sends its info in.
+    async fn open_echo(builder: WebSocketBuilder) -> WebSocket {
+        let mut ws = builder.open().unwrap();
+        let _ = ws.next().await;
+        ws
+    }
+
+    #[wasm_bindgen_test]
+    async fn connected_resolves_once_open() {
+        let ws = WebSocket::open(echo_server_url()).unwrap();
+        assert_eq!(ws.state(), State::Connecting);
+
+        ws.connected().await.unwrap();
+        assert_eq!(ws.state(), State::Open);
+        // Resolves right away once open.
+        ws.connected().await.unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    async fn connected_fails_if_the_connection_fails() {
+        let ws = WebSocket::open("ws://127.0.0.1:1").unwrap();
+
+        assert!(ws.connected().await.is_err());
+    }
+
+    #[wasm_bindgen_test]
+    async fn try_send_fails_unless_open() {
+        let ws = WebSocket::open(echo_server_url()).unwrap();
+        assert!(matches!(
+            ws.try_send("too early"),
+            Err(WebSocketError::NotOpen(State::Connecting))
+        ));
+
+        ws.connected().await.unwrap();
+        ws.try_send("on time").unwrap();
+
+        ws.close_handle().close(None, None).unwrap();
+        assert!(matches!(
+            ws.try_send("too late"),
+            Err(WebSocketError::NotOpen(State::Closing))
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    async fn builder_binary_type_blob() {
+        let mut ws =
+            open_echo(WebSocket::builder(echo_server_url()).binary_type(BinaryType::Blob)).await;
+
+        ws.send(vec![1, 2, 3]).await.unwrap();
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            Message::Bytes(vec![1, 2, 3])
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn builder_queue_capacity() {
+        let mut ws = WebSocket::builder(echo_server_url())
+            .queue_capacity(1)
+            .overflow_policy(OverflowPolicy::Error)
+            .open()
+            .unwrap();
+        ws.connected().await.unwrap();
+        ws.try_send("1").unwrap();
+        ws.try_send("2").unwrap();
+        // Let the info message and the echoes arrive while the stream isn't polled.
+        TimeoutFuture::new(500).await;
+
+        assert!(matches!(ws.next().await, Some(Ok(Message::Text(_)))));
+        assert!(matches!(
+            ws.next().await,
+            Some(Err(WebSocketError::QueueOverflow))
+        ));
+    }
+
+    #[wasm_bindgen_test]
+    async fn max_message_size() {
+        let mut ws = WebSocket::builder(echo_server_url())
+            .max_message_size(4)
+            .open()
+            .unwrap();
+        // The info message is too large too.
+        assert!(matches!(
+            ws.next().await,
+            Some(Err(WebSocketError::MessageTooLarge { limit: 4, .. }))
+        ));
+
+        ws.send("fits").await.unwrap();
+        ws.send("too large").await.unwrap();
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            Message::Text("fits".to_string())
+        );
+        assert!(matches!(
+            ws.next().await,
+            Some(Err(WebSocketError::MessageTooLarge { size: 9, limit: 4 }))
+        ));
+        // The connection stays open.
+        assert_eq!(ws.state(), State::Open);
+    }
+
+    #[wasm_bindgen_test]
+    async fn high_water_mark_waits_for_the_buffer_to_drain() {
+        let mut ws = open_echo(WebSocket::builder(echo_server_url()).high_water_mark(1024)).await;
+
+        ws.send(vec![0; 64 * 1024]).await.unwrap();
+        assert!(ws.buffered_amount() <= 1024);
+    }
+
+    #[wasm_bindgen_test]
+    async fn heartbeat_sends_the_payload_when_idle() {
+        let heartbeat = Heartbeat::new(
+            Duration::from_millis(50),
+            Message::Text("ping".to_string()),
+            Duration::from_secs(5),
+        );
+        let mut ws = open_echo(WebSocket::builder(echo_server_url()).heartbeat(heartbeat)).await;
+
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            Message::Text("ping".to_string())
+        );
+        // The echo counts as the reply.
+        TimeoutFuture::new(100).await;
+        assert!(ws.metrics().round_trip.is_some());
+        assert_eq!(ws.state(), State::Open);
+    }
+
+    #[wasm_bindgen_test]
+    async fn close_handle_closes_a_split_socket() {
+        let ws = open_echo(WebSocket::builder(echo_server_url())).await;
+        let handle = ws.close_handle();
+        let (_sink, mut stream) = ws.split::<Message>();
+
+        assert!(matches!(
+            handle.close(Some(CloseCode::Abnormal), None),
+            Err(WebSocketError::InvalidCloseCode(CloseCode::Abnormal))
+        ));
+        handle.close(Some(CloseCode::Normal), Some("bye")).unwrap();
+        assert_eq!(handle.state(), State::Closing);
+
+        match stream.next().await {
+            Some(Err(WebSocketError::ConnectionClose(event))) => {
+                assert_eq!(event.code, CloseCode::Normal)
+            }
+            item => panic!("expected the close event, got {:?}", item),
+        }
+        assert!(stream.next().await.is_none());
+        assert_eq!(handle.state(), State::Closed);
+        // Closing again has no effect.
+        handle.close(None, None).unwrap();
+    }
+
+    #[wasm_bindgen_test]
+    async fn poll_close_waits_for_the_close_event() {
+        let mut ws = open_echo(WebSocket::builder(echo_server_url())).await;
+        ws.set_close_frame(CloseCode::Normal, "done");
+        let handle = ws.close_handle();
+
+        SinkExt::<Message>::close(&mut ws).await.unwrap();
+        assert_eq!(handle.state(), State::Closed);
+    }
 }
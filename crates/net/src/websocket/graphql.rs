@@ -0,0 +1,405 @@
+//! A client for the [`graphql-transport-ws`](https://github.com/enisdenjo/graphql-ws/blob/master/PROTOCOL.md)
+//! protocol, used by most GraphQL servers for subscriptions.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::graphql::{GraphQlClient, GraphQlRequest};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::StreamExt;
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! spawn_local(async {
+//!     let client = GraphQlClient::open("wss://example.com/graphql", None)
+//!         .await
+//!         .unwrap();
+//!     let mut prices = client.subscribe(GraphQlRequest::new(
+//!         "subscription { price(symbol: \"BTC\") }",
+//!     ));
+//!     while let Some(result) = prices.next().await {
+//!         console_log!(format!("{:?}", result))
+//!     }
+//! })
+//! # }
+//! ```
+pub use crate::graphql::{GraphQlRequest, Location, ServerError};
+use crate::websocket::{browser::WebSocket, events::CloseEvent, Message, WebSocketError};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use thiserror::Error as ThisError;
+use wasm_bindgen_futures::spawn_local;
+
+/// The sub-protocol offered by [`GraphQlClient::open`].
+pub const PROTOCOL: &str = "graphql-transport-ws";
+
+/// Errors of a [`GraphQlClient`] and its subscriptions.
+#[derive(Debug, ThisError)]
+#[non_exhaustive]
+pub enum GraphQlError {
+    /// The socket failed.
+    #[error("{0}")]
+    Socket(WebSocketError),
+    /// The connection was closed, with the close event if one was received.
+    #[error("GraphQL connection closed")]
+    Closed(Option<CloseEvent>),
+    /// The server sent an `error` message for the operation, containing GraphQL errors.
+    #[error("GraphQL operation failed")]
+    Operation(Vec<ServerError>),
+    /// The server sent a message which doesn't follow the protocol.
+    #[error("invalid GraphQL message: {0}")]
+    Protocol(String),
+}
+
+/// Messages sent by the server.
+#[derive(Debug, PartialEq)]
+enum ServerMessage {
+    ConnectionAck,
+    Ping(Option<Value>),
+    Pong,
+    Next {
+        id: String,
+        payload: Value,
+    },
+    Error {
+        id: String,
+        payload: Vec<ServerError>,
+    },
+    Complete {
+        id: String,
+    },
+}
+
+impl ServerMessage {
+    fn parse(text: &str) -> Result<Self, GraphQlError> {
+        let invalid = || GraphQlError::Protocol(text.to_string());
+        let mut value: Value = serde_json::from_str(text).map_err(|_| invalid())?;
+        let id = || value["id"].as_str().map(str::to_string).ok_or_else(invalid);
+        let message = match value["type"].as_str().ok_or_else(invalid)? {
+            "connection_ack" => ServerMessage::ConnectionAck,
+            "ping" => ServerMessage::Ping(value.get("payload").cloned()),
+            "pong" => ServerMessage::Pong,
+            "next" => ServerMessage::Next {
+                id: id()?,
+                payload: value["payload"].take(),
+            },
+            "error" => ServerMessage::Error {
+                id: id()?,
+                payload: serde_json::from_value(value["payload"].take()).map_err(|_| invalid())?,
+            },
+            "complete" => ServerMessage::Complete { id: id()? },
+            _ => return Err(invalid()),
+        };
+        Ok(message)
+    }
+}
+
+fn client_message(ty: &str, id: Option<&str>, payload: Option<Value>) -> Message {
+    let mut message = json!({ "type": ty });
+    if let Some(id) = id {
+        message["id"] = json!(id);
+    }
+    if let Some(payload) = payload {
+        message["payload"] = payload;
+    }
+    Message::Text(message.to_string())
+}
+
+type Subscriber = mpsc::UnboundedSender<Result<Value, GraphQlError>>;
+
+struct Shared {
+    outgoing: mpsc::UnboundedSender<Message>,
+    subscriptions: HashMap<String, Subscriber>,
+    next_id: u64,
+    closed: Option<Option<CloseEvent>>,
+}
+
+/// A GraphQL client multiplexing operations over a WebSocket.
+///
+/// The connection is driven by tasks spawned with `spawn_local`. Clones share the connection,
+/// which is closed once the client, all its clones and all subscriptions have been dropped.
+#[derive(Clone)]
+pub struct GraphQlClient {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl GraphQlClient {
+    /// Opens a socket offering the [`PROTOCOL`] and initializes the connection.
+    ///
+    /// `payload` is sent with the `connection_init` message, it usually carries credentials.
+    pub async fn open(url: &str, payload: Option<Value>) -> Result<Self, GraphQlError> {
+        let ws = WebSocket::open_with_protocol(url, PROTOCOL).map_err(GraphQlError::Socket)?;
+        Self::connect(ws, payload).await
+    }
+
+    /// Initializes the connection over an already open socket and waits for the server to
+    /// acknowledge it.
+    pub async fn connect(ws: WebSocket, payload: Option<Value>) -> Result<Self, GraphQlError> {
+        let (mut write, mut read) = ws.split();
+        write
+            .send(client_message("connection_init", None, payload))
+            .await
+            .map_err(GraphQlError::Socket)?;
+        loop {
+            match read_message(read.next().await)? {
+                ServerMessage::ConnectionAck => break,
+                ServerMessage::Ping(payload) => write
+                    .send(client_message("pong", None, payload))
+                    .await
+                    .map_err(GraphQlError::Socket)?,
+                ServerMessage::Pong => {}
+                message => return Err(GraphQlError::Protocol(format!("{message:?}"))),
+            }
+        }
+
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded();
+        spawn_local(async move {
+            while let Some(msg) = outgoing_rx.next().await {
+                if write.send(msg).await.is_err() {
+                    break;
+                }
+            }
+            let _ = SinkExt::<Message>::close(&mut write).await;
+        });
+
+        let shared = Rc::new(RefCell::new(Shared {
+            outgoing,
+            subscriptions: HashMap::new(),
+            next_id: 0,
+            closed: None,
+        }));
+
+        let weak = Rc::downgrade(&shared);
+        spawn_local(async move {
+            let mut close_event = None;
+            while let Some(msg) = read.next().await {
+                let shared = match weak.upgrade() {
+                    Some(shared) => shared,
+                    None => return,
+                };
+                let mut shared = shared.borrow_mut();
+                let msg = match read_message(Some(msg)) {
+                    Ok(msg) => msg,
+                    Err(GraphQlError::Closed(e)) => {
+                        close_event = e;
+                        continue;
+                    }
+                    // A broken message can't be attributed to an operation.
+                    Err(_) => continue,
+                };
+                match msg {
+                    ServerMessage::Next { id, payload } => {
+                        if let Some(subscriber) = shared.subscriptions.get(&id) {
+                            let _ = subscriber.unbounded_send(Ok(payload));
+                        }
+                    }
+                    ServerMessage::Error { id, payload } => {
+                        if let Some(subscriber) = shared.subscriptions.remove(&id) {
+                            let _ =
+                                subscriber.unbounded_send(Err(GraphQlError::Operation(payload)));
+                        }
+                    }
+                    ServerMessage::Complete { id } => {
+                        shared.subscriptions.remove(&id);
+                    }
+                    ServerMessage::Ping(payload) => {
+                        let _ = shared
+                            .outgoing
+                            .unbounded_send(client_message("pong", None, payload));
+                    }
+                    ServerMessage::ConnectionAck | ServerMessage::Pong => {}
+                }
+            }
+
+            if let Some(shared) = weak.upgrade() {
+                let mut shared = shared.borrow_mut();
+                for (_, subscriber) in shared.subscriptions.drain() {
+                    let _ =
+                        subscriber.unbounded_send(Err(GraphQlError::Closed(close_event.clone())));
+                }
+                shared.closed = Some(close_event);
+            }
+        });
+
+        Ok(Self { shared })
+    }
+
+    /// Starts an operation, yielding its results.
+    ///
+    /// The stream ends when the server completes the operation. Dropping it before that stops
+    /// the operation.
+    pub fn subscribe(&self, request: GraphQlRequest) -> Subscription {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut shared = self.shared.borrow_mut();
+        let id = shared.next_id.to_string();
+        shared.next_id += 1;
+
+        if let Some(close_event) = shared.closed.clone() {
+            let _ = sender.unbounded_send(Err(GraphQlError::Closed(close_event)));
+        } else {
+            let _ = shared.outgoing.unbounded_send(client_message(
+                "subscribe",
+                Some(&id),
+                Some(request.to_json()),
+            ));
+            shared.subscriptions.insert(id.clone(), sender);
+        }
+
+        Subscription {
+            id,
+            receiver,
+            shared: Rc::clone(&self.shared),
+        }
+    }
+
+    /// Runs a query or mutation, resolving to its only result.
+    pub async fn execute(&self, request: GraphQlRequest) -> Result<Value, GraphQlError> {
+        match self.subscribe(request).next().await {
+            Some(result) => result,
+            None => Err(GraphQlError::Protocol(
+                "operation completed without a result".to_string(),
+            )),
+        }
+    }
+
+    /// Sends a `ping`, e.g. to keep the connection alive through proxies.
+    pub fn ping(&self) {
+        let _ = self
+            .shared
+            .borrow()
+            .outgoing
+            .unbounded_send(client_message("ping", None, None));
+    }
+}
+
+impl fmt::Debug for GraphQlClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shared = self.shared.borrow();
+        f.debug_struct("GraphQlClient")
+            .field("subscriptions", &shared.subscriptions.len())
+            .field("closed", &shared.closed.is_some())
+            .finish()
+    }
+}
+
+fn read_message(
+    msg: Option<Result<Message, WebSocketError>>,
+) -> Result<ServerMessage, GraphQlError> {
+    match msg {
+        Some(Ok(Message::Text(text))) => ServerMessage::parse(&text),
+        Some(Ok(Message::Bytes(_))) => Err(GraphQlError::Protocol(
+            "unexpected binary message".to_string(),
+        )),
+        Some(Err(WebSocketError::ConnectionClose(e))) => Err(GraphQlError::Closed(Some(e))),
+        Some(Err(e)) => Err(GraphQlError::Socket(e)),
+        None => Err(GraphQlError::Closed(None)),
+    }
+}
+
+/// The results of an operation started with [`GraphQlClient::subscribe`].
+pub struct Subscription {
+    id: String,
+    receiver: mpsc::UnboundedReceiver<Result<Value, GraphQlError>>,
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Subscription {
+    /// The id of the operation.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Result<Value, GraphQlError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        if shared.subscriptions.remove(&self.id).is_some() {
+            let _ =
+                shared
+                    .outgoing
+                    .unbounded_send(client_message("complete", Some(&self.id), None));
+        }
+    }
+}
+
+impl fmt::Debug for Subscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Subscription")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_server_messages() {
+        assert_eq!(
+            ServerMessage::parse(r#"{"type":"connection_ack"}"#).unwrap(),
+            ServerMessage::ConnectionAck
+        );
+        assert_eq!(
+            ServerMessage::parse(r#"{"type":"next","id":"1","payload":{"data":{"a":1}}}"#).unwrap(),
+            ServerMessage::Next {
+                id: "1".to_string(),
+                payload: json!({ "data": { "a": 1 } }),
+            }
+        );
+        assert_eq!(
+            ServerMessage::parse(r#"{"type":"error","id":"2","payload":[{"message":"no"}]}"#)
+                .unwrap(),
+            ServerMessage::Error {
+                id: "2".to_string(),
+                payload: vec![ServerError {
+                    message: "no".to_string(),
+                    locations: Vec::new(),
+                    path: Vec::new(),
+                    extensions: None,
+                }],
+            }
+        );
+        assert!(ServerMessage::parse(r#"{"type":"next","payload":{}}"#).is_err());
+        assert!(ServerMessage::parse(r#"{"type":"unknown"}"#).is_err());
+        assert!(ServerMessage::parse("not json").is_err());
+    }
+
+    #[test]
+    fn encodes_subscribe() {
+        let request = GraphQlRequest::new("query { a }")
+            .operation_name("A")
+            .variables(json!({ "x": 1 }));
+        let msg = client_message("subscribe", Some("0"), Some(request.to_json()));
+        let text = match msg {
+            Message::Text(text) => text,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            serde_json::from_str::<Value>(&text).unwrap(),
+            json!({
+                "type": "subscribe",
+                "id": "0",
+                "payload": { "query": "query { a }", "operationName": "A", "variables": { "x": 1 } },
+            })
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Application level keep-alive for WebSockets.
+//!
+//! Browsers don't expose WebSocket ping frames, so dead connections behind proxies can go
+//! unnoticed for a long time. A [`Heartbeat`] sends a payload of your choice whenever nothing
+//! has been sent for a while and expects the server to reply with any message.
+
+use crate::websocket::metrics::SharedCounters;
+use crate::websocket::queue::MessageQueue;
+use crate::websocket::Message;
+use gloo_timers::callback::{Interval, Timeout};
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+/// Configuration of the keep-alive messages sent by a
+/// [`WebSocket`](crate::websocket::futures::WebSocket).
+///
+/// Enabled through [`WebSocketBuilder::heartbeat`](crate::websocket::futures::WebSocketBuilder::heartbeat).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Heartbeat {
+    /// How long the socket may be idle, i.e. without sending anything, before `payload` is sent.
+    pub interval: Duration,
+    /// The message sent to the server.
+    pub payload: Message,
+    /// How long to wait for any message from the server after sending `payload`. If nothing
+    /// arrives, the stream yields
+    /// [`WebSocketError::KeepAliveTimeout`](crate::websocket::WebSocketError::KeepAliveTimeout)
+    /// and the connection is closed.
+    pub timeout: Duration,
+}
+
+impl Heartbeat {
+    /// Sends `payload` after `interval` of idleness and gives up if the server doesn't respond
+    /// within `timeout`.
+    pub fn new(interval: Duration, payload: Message, timeout: Duration) -> Self {
+        Self {
+            interval,
+            payload,
+            timeout,
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    last_sent: f64,
+    /// When the heartbeat waiting for a reply was sent
+    ping_sent: f64,
+    pong_deadline: Option<Timeout>,
+    counters: SharedCounters,
+}
+
+/// Drives the heartbeat of a single socket. Dropping it stops the heartbeat.
+pub(crate) struct HeartbeatTask {
+    state: Rc<RefCell<State>>,
+    _interval: Interval,
+}
+
+impl HeartbeatTask {
+    pub(crate) fn start<T: 'static>(
+        config: Heartbeat,
+        ws: web_sys::WebSocket,
+        queue: MessageQueue<T>,
+        counters: SharedCounters,
+        timeout_message: impl Fn() -> T + 'static,
+    ) -> Self {
+        let state = Rc::new(RefCell::new(State {
+            counters,
+            ..State::default()
+        }));
+        let interval_millis = millis(config.interval);
+        let timeout_message = Rc::new(timeout_message);
+
+        let interval = {
+            let state = Rc::downgrade(&state);
+            Interval::new(interval_millis, move || {
+                let state = match Weak::upgrade(&state) {
+                    Some(state) => state,
+                    None => return,
+                };
+                let mut state_ref = state.borrow_mut();
+                let idle = js_sys::Date::now() - state_ref.last_sent;
+                if ws.ready_state() != web_sys::WebSocket::OPEN
+                    || idle < f64::from(interval_millis)
+                    || state_ref.pong_deadline.is_some()
+                {
+                    return;
+                }
+
+                let result = match &config.payload {
+                    Message::Text(text) => ws.send_with_str(text),
+                    Message::Bytes(bytes) => ws.send_with_u8_array(bytes),
+                };
+                if result.is_err() {
+                    return;
+                }
+                state_ref.counters.borrow_mut().sent(&config.payload);
+                state_ref.last_sent = js_sys::Date::now();
+                state_ref.ping_sent = state_ref.last_sent;
+
+                let ws = ws.clone();
+                let queue = queue.clone();
+                let timeout_message = Rc::clone(&timeout_message);
+                state_ref.pong_deadline = Some(Timeout::new(millis(config.timeout), move || {
+                    queue.push_control(timeout_message());
+                    let _ = ws.close();
+                }));
+            })
+        };
+
+        Self {
+            state,
+            _interval: interval,
+        }
+    }
+
+    /// Records that a message was sent, postponing the next heartbeat.
+    pub(crate) fn sent(&self) {
+        self.state.borrow_mut().last_sent = js_sys::Date::now();
+    }
+
+    /// Records that a message was received, which counts as the reply to a heartbeat.
+    pub(crate) fn received(&self) {
+        let mut state = self.state.borrow_mut();
+        if state.pong_deadline.take().is_some() {
+            let round_trip = (js_sys::Date::now() - state.ping_sent).max(0.0);
+            state
+                .counters
+                .borrow_mut()
+                .set_round_trip(Duration::from_micros((round_trip * 1000.0) as u64));
+        }
+    }
+}
+
+fn millis(duration: Duration) -> u32 {
+    u32::try_from(duration.as_millis()).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::browser::OverflowPolicy;
+    use std::future::poll_fn;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[wasm_bindgen_test]
+    #[allow(clippy::option_env_unwrap)]
+    async fn closes_the_socket_without_a_reply() {
+        let ws_echo_server_url =
+            option_env!("WS_ECHO_SERVER_URL").expect("Did you set WS_ECHO_SERVER_URL?");
+        let ws = web_sys::WebSocket::new(ws_echo_server_url).unwrap();
+        let queue = MessageQueue::new(None, OverflowPolicy::default());
+        let heartbeat = Heartbeat::new(
+            Duration::from_millis(50),
+            Message::Text("ping".to_string()),
+            Duration::from_millis(100),
+        );
+
+        // The echoes of the server never reach the task, so they don't count as replies.
+        let _task = HeartbeatTask::start(
+            heartbeat,
+            ws.clone(),
+            queue.clone(),
+            SharedCounters::default(),
+            || "timed out",
+        );
+
+        assert_eq!(poll_fn(|cx| queue.poll_next(cx)).await, "timed out");
+        assert!(ws.ready_state() >= web_sys::WebSocket::CLOSING);
+    }
+}
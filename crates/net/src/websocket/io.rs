@@ -0,0 +1,195 @@
+//! A byte stream over a WebSocket.
+//!
+//! This allows protocols written against `futures::io`, such as multiplexers or custom RPC
+//! framing, to be tunneled through a WebSocket unchanged.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::futures::WebSocket;
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::{AsyncReadExt, AsyncWriteExt};
+//!
+//! # fn no_run() {
+//! let mut io = WebSocket::open("wss://echo.websocket.org").unwrap().into_io();
+//!
+//! spawn_local(async move {
+//!     io.write_all(b"ping").await.unwrap();
+//!     let mut buf = [0; 4];
+//!     io.read_exact(&mut buf).await.unwrap();
+//! })
+//! # }
+//! ```
+use crate::websocket::{futures::WebSocket, Message, WebSocketError};
+use futures_core::{ready, Stream};
+use futures_io::{AsyncRead, AsyncWrite};
+use futures_sink::Sink;
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Adapter implementing [`AsyncRead`] and [`AsyncWrite`] over a [`WebSocket`], obtained from
+/// [`WebSocket::into_io`].
+///
+/// Every write is sent as one binary message and reads consume the payloads of incoming binary
+/// messages. Message boundaries are not preserved. Receiving a text message is an
+/// [`InvalidData`](io::ErrorKind::InvalidData) error, and a clean close of the connection
+/// is the end of the stream.
+pub struct WebSocketIo<S = WebSocket> {
+    inner: S,
+    read_buf: Vec<u8>,
+    read_pos: usize,
+}
+
+impl<S> WebSocketIo<S> {
+    pub(crate) fn new(inner: S) -> Self {
+        Self {
+            inner,
+            read_buf: Vec::new(),
+            read_pos: 0,
+        }
+    }
+
+    /// A reference to the wrapped socket.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwraps the socket.
+    ///
+    /// Bytes which have been received but not yet read are lost.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+fn into_io_error(error: WebSocketError) -> io::Error {
+    let kind = match &error {
+        WebSocketError::ConnectionError(_) => io::ErrorKind::ConnectionReset,
+        WebSocketError::ConnectionClose(_) => io::ErrorKind::ConnectionAborted,
+        WebSocketError::MessageSendError(_) => io::ErrorKind::BrokenPipe,
+        WebSocketError::NotOpen(_) => io::ErrorKind::NotConnected,
+        WebSocketError::ConnectTimeout | WebSocketError::KeepAliveTimeout => {
+            io::ErrorKind::TimedOut
+        }
+        WebSocketError::MessageTooLarge { .. } => io::ErrorKind::InvalidData,
+        WebSocketError::InvalidCloseCode(_) => io::ErrorKind::InvalidInput,
+        _ => io::ErrorKind::Other,
+    };
+    io::Error::new(kind, error.to_string())
+}
+
+impl<S> AsyncRead for WebSocketIo<S>
+where
+    S: Stream<Item = Result<Message, WebSocketError>> + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        while this.read_pos >= this.read_buf.len() {
+            match ready!(Pin::new(&mut this.inner).poll_next(cx)) {
+                Some(Ok(Message::Bytes(bytes))) => {
+                    this.read_buf = bytes;
+                    this.read_pos = 0;
+                }
+                Some(Ok(Message::Text(_))) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "received a text message",
+                    )))
+                }
+                // The stream ends right after a clean close.
+                Some(Err(WebSocketError::ConnectionClose(e))) if e.was_clean => {}
+                Some(Err(e)) => return Poll::Ready(Err(into_io_error(e))),
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+
+        let available = &this.read_buf[this.read_pos..];
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        this.read_pos += len;
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl<S> AsyncWrite for WebSocketIo<S>
+where
+    S: Sink<Message, Error = WebSocketError> + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut inner = Pin::new(&mut self.get_mut().inner);
+        ready!(Sink::<Message>::poll_ready(inner.as_mut(), cx)).map_err(into_io_error)?;
+        inner
+            .start_send(Message::Bytes(buf.to_vec()))
+            .map_err(into_io_error)?;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Sink::<Message>::poll_flush(Pin::new(&mut self.get_mut().inner), cx).map_err(into_io_error)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Sink::<Message>::poll_close(Pin::new(&mut self.get_mut().inner), cx).map_err(into_io_error)
+    }
+}
+
+impl<S> fmt::Debug for WebSocketIo<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketIo")
+            .field("buffered", &(self.read_buf.len() - self.read_pos))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::browser::WebSocket;
+    use futures::{AsyncReadExt, AsyncWriteExt, StreamExt};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[allow(clippy::option_env_unwrap)]
+    async fn open_echo() -> WebSocket {
+        let ws_echo_server_url =
+            option_env!("WS_ECHO_SERVER_URL").expect("Did you set WS_ECHO_SERVER_URL?");
+        let mut ws = WebSocket::open(ws_echo_server_url).unwrap();
+        // The echo server sends its info in the first message.
+        let _ = ws.next().await;
+        ws
+    }
+
+    #[wasm_bindgen_test]
+    async fn reads_and_writes_bytes() {
+        let mut io = open_echo().await.into_io();
+
+        io.write_all(b"ping").await.unwrap();
+        io.write_all(b"pong").await.unwrap();
+        // Message boundaries aren't preserved.
+        let mut buf = [0; 6];
+        io.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"pingpo");
+        let mut buf = [0; 2];
+        io.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ng");
+
+        io.get_ref().try_send("text").unwrap();
+        let error = io.read(&mut buf).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        // A clean close is the end of the stream.
+        io.close().await.unwrap();
+        assert_eq!(io.read(&mut buf).await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,171 @@
+//! A typed JSON layer over a WebSocket.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::{futures::WebSocket, json::JsonWebSocket};
+//! use serde::{Deserialize, Serialize};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::{SinkExt, StreamExt};
+//!
+//! #[derive(Serialize)]
+//! struct Subscribe {
+//!     channel: String,
+//! }
+//!
+//! #[derive(Deserialize, Debug)]
+//! struct Update {
+//!     price: f64,
+//! }
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! let ws = WebSocket::open("wss://example.com/prices").unwrap();
+//! let mut ws = JsonWebSocket::<Subscribe, Update>::new(ws);
+//!
+//! spawn_local(async move {
+//!     ws.send(Subscribe { channel: "BTC".to_string() }).await.unwrap();
+//!     while let Some(update) = ws.next().await {
+//!         console_log!(format!("{:?}", update))
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::{futures::WebSocket, Message, WebSocketError};
+use futures_core::{ready, Stream};
+use futures_sink::Sink;
+use pin_project::pin_project;
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Serializes outgoing `Tx` values to JSON text messages and deserializes incoming messages
+/// into `Rx` values.
+///
+/// Both text and binary messages are deserialized. Messages which fail to deserialize are
+/// yielded as [`WebSocketError::JsonError`] without ending the stream.
+///
+/// `S` can be any [`Message`] based socket, such as a
+/// [`ReconnectingWebSocket`](crate::websocket::reconnect::ReconnectingWebSocket).
+#[pin_project]
+pub struct JsonWebSocket<Tx, Rx, S = WebSocket> {
+    #[pin]
+    inner: S,
+    _marker: PhantomData<fn(Tx) -> Rx>,
+}
+
+impl<Tx, Rx, S> JsonWebSocket<Tx, Rx, S> {
+    /// Wraps a socket.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    /// A reference to the wrapped socket.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwraps the socket.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<Tx, Rx, S> Stream for JsonWebSocket<Tx, Rx, S>
+where
+    Rx: DeserializeOwned,
+    S: Stream<Item = Result<Message, WebSocketError>>,
+{
+    type Item = Result<Rx, WebSocketError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let msg = ready!(self.project().inner.poll_next(cx));
+        Poll::Ready(msg.map(|msg| {
+            let value = match msg? {
+                Message::Text(text) => serde_json::from_str(&text),
+                Message::Bytes(bytes) => serde_json::from_slice(&bytes),
+            };
+            value.map_err(WebSocketError::JsonError)
+        }))
+    }
+}
+
+impl<Tx, Rx, S> Sink<Tx> for JsonWebSocket<Tx, Rx, S>
+where
+    Tx: Serialize,
+    S: Sink<Message, Error = WebSocketError>,
+{
+    type Error = WebSocketError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Tx) -> Result<(), Self::Error> {
+        let text = serde_json::to_string(&item).map_err(WebSocketError::JsonError)?;
+        self.project().inner.start_send(Message::Text(text))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+impl<Tx, Rx, S> fmt::Debug for JsonWebSocket<Tx, Rx, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonWebSocket").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::websocket::browser::WebSocket;
+    use futures::{SinkExt, StreamExt};
+    use serde::Deserialize;
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[allow(clippy::option_env_unwrap)]
+    async fn open_echo() -> WebSocket {
+        let ws_echo_server_url =
+            option_env!("WS_ECHO_SERVER_URL").expect("Did you set WS_ECHO_SERVER_URL?");
+        let mut ws = WebSocket::open(ws_echo_server_url).unwrap();
+        // The echo server sends its info in the first message.
+        let _ = ws.next().await;
+        ws
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Ping {
+        id: u32,
+    }
+
+    #[wasm_bindgen_test]
+    async fn sends_and_receives_values() {
+        let mut ws = JsonWebSocket::<Ping, Ping, WebSocket>::new(open_echo().await);
+
+        ws.send(Ping { id: 1 }).await.unwrap();
+        assert_eq!(ws.next().await.unwrap().unwrap(), Ping { id: 1 });
+
+        ws.get_ref().try_send("not json").unwrap();
+        ws.send(Ping { id: 2 }).await.unwrap();
+        assert!(matches!(
+            ws.next().await,
+            Some(Err(WebSocketError::JsonError(_)))
+        ));
+        assert_eq!(ws.next().await.unwrap().unwrap(), Ping { id: 2 });
+    }
+}
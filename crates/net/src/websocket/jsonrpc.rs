@@ -0,0 +1,379 @@
+//! A [JSON-RPC 2.0](https://www.jsonrpc.org/specification) client over a WebSocket.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::jsonrpc::Client;
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::StreamExt;
+//! use serde_json::{json, Value};
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! let client = Client::open("wss://example.com/rpc").unwrap();
+//! spawn_local(async move {
+//!     let block: String = client.call("eth_blockNumber", json!([])).await.unwrap();
+//!     console_log!(block);
+//!
+//!     let _: Value = client.call("eth_subscribe", json!(["newHeads"])).await.unwrap();
+//!     let mut notifications = client.notifications();
+//!     while let Some(notification) = notifications.next().await {
+//!         console_log!(format!("{}: {}", notification.method, notification.params));
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::{browser::WebSocket, events::CloseEvent, Message, WebSocketError};
+use futures_channel::{mpsc, oneshot};
+use futures_core::Stream;
+use futures_util::{SinkExt, StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use thiserror::Error as ThisError;
+use wasm_bindgen_futures::spawn_local;
+
+/// The error object of a failed call.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ErrorObject {
+    /// The error code
+    pub code: i64,
+    /// A short description of the error
+    pub message: String,
+    /// Additional information about the error
+    pub data: Option<Value>,
+}
+
+/// Errors of a JSON-RPC [`Client`].
+#[derive(Debug, ThisError)]
+#[non_exhaustive]
+pub enum RpcError {
+    /// The socket failed.
+    #[error("{0}")]
+    Socket(WebSocketError),
+    /// The connection was closed before the response arrived, with the close event if one was
+    /// received.
+    #[error("JSON-RPC connection closed")]
+    Closed(Option<CloseEvent>),
+    /// The server answered with an error.
+    #[error("JSON-RPC error {}: {}", .0.code, .0.message)]
+    Call(ErrorObject),
+    /// The parameters couldn't be serialized, or the result couldn't be deserialized.
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
+    /// The server sent a message which isn't valid JSON-RPC.
+    #[error("invalid JSON-RPC message: {0}")]
+    Protocol(String),
+}
+
+/// A notification sent by the server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Notification {
+    /// The method of the notification
+    pub method: String,
+    /// The parameters, `null` if there are none
+    pub params: Value,
+}
+
+/// Messages sent by the server.
+#[derive(Debug, PartialEq)]
+enum Incoming {
+    Response {
+        id: u64,
+        result: Result<Value, ErrorObject>,
+    },
+    Notification(Notification),
+    /// A call from the server, which this client doesn't serve.
+    Request {
+        id: Value,
+    },
+}
+
+impl Incoming {
+    fn parse(value: Value) -> Result<Self, RpcError> {
+        let invalid = |value: &Value| RpcError::Protocol(value.to_string());
+        if value["jsonrpc"] != "2.0" {
+            return Err(invalid(&value));
+        }
+        if let Some(method) = value["method"].as_str() {
+            return Ok(match value.get("id") {
+                Some(id) => Incoming::Request { id: id.clone() },
+                None => Incoming::Notification(Notification {
+                    method: method.to_string(),
+                    params: value.get("params").cloned().unwrap_or(Value::Null),
+                }),
+            });
+        }
+
+        let id = value["id"].as_u64().ok_or_else(|| invalid(&value))?;
+        let result = match (value.get("result"), value.get("error")) {
+            (Some(result), None) => Ok(result.clone()),
+            (None, Some(error)) => Err(ErrorObject {
+                code: error["code"].as_i64().ok_or_else(|| invalid(&value))?,
+                message: error["message"].as_str().unwrap_or_default().to_string(),
+                data: error.get("data").cloned(),
+            }),
+            _ => return Err(invalid(&value)),
+        };
+        Ok(Incoming::Response { id, result })
+    }
+}
+
+type Pending = oneshot::Sender<Result<Value, RpcError>>;
+
+struct Shared {
+    outgoing: mpsc::UnboundedSender<Message>,
+    pending: HashMap<u64, Pending>,
+    listeners: Vec<mpsc::UnboundedSender<Notification>>,
+    next_id: u64,
+    closed: Option<Option<CloseEvent>>,
+}
+
+impl Shared {
+    fn send(&self, message: &Value) {
+        let _ = self
+            .outgoing
+            .unbounded_send(Message::Text(message.to_string()));
+    }
+
+    fn dispatch(&mut self, incoming: Incoming) {
+        match incoming {
+            Incoming::Response { id, result } => {
+                if let Some(pending) = self.pending.remove(&id) {
+                    let _ = pending.send(result.map_err(RpcError::Call));
+                }
+            }
+            Incoming::Notification(notification) => self
+                .listeners
+                .retain(|listener| listener.unbounded_send(notification.clone()).is_ok()),
+            Incoming::Request { id } => self.send(&json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": "Method not found" },
+            })),
+        }
+    }
+}
+
+/// A JSON-RPC client correlating calls and responses over a WebSocket.
+///
+/// The connection is driven by tasks spawned with `spawn_local`. Clones share the connection.
+/// Calls the server makes to the client are answered with a "method not found" error.
+#[derive(Clone)]
+pub struct Client {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Client {
+    /// Opens a socket to `url` and wraps it.
+    pub fn open(url: &str) -> Result<Self, WebSocketError> {
+        WebSocket::open(url).map(Self::new)
+    }
+
+    /// Wraps a socket. Calls made before the connection is open are sent once it is.
+    pub fn new(ws: WebSocket) -> Self {
+        let (mut write, mut read) = ws.split();
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded();
+        spawn_local(async move {
+            while let Some(msg) = outgoing_rx.next().await {
+                if write.send(msg).await.is_err() {
+                    break;
+                }
+            }
+            let _ = SinkExt::<Message>::close(&mut write).await;
+        });
+
+        let shared = Rc::new(RefCell::new(Shared {
+            outgoing,
+            pending: HashMap::new(),
+            listeners: Vec::new(),
+            next_id: 0,
+            closed: None,
+        }));
+
+        let weak = Rc::downgrade(&shared);
+        spawn_local(async move {
+            let mut close_event = None;
+            while let Some(msg) = read.next().await {
+                let shared = match weak.upgrade() {
+                    Some(shared) => shared,
+                    None => return,
+                };
+                let text = match msg {
+                    Ok(Message::Text(text)) => text,
+                    // Binary messages are not part of the protocol.
+                    Ok(Message::Bytes(_)) => continue,
+                    Err(WebSocketError::ConnectionClose(e)) => {
+                        close_event = Some(e);
+                        continue;
+                    }
+                    Err(_) => continue,
+                };
+                let messages = match serde_json::from_str(&text) {
+                    Ok(Value::Array(batch)) => batch,
+                    Ok(value) => vec![value],
+                    Err(_) => continue,
+                };
+                let mut shared = shared.borrow_mut();
+                // Messages which can't be attributed to a call are dropped.
+                for incoming in messages.into_iter().filter_map(|m| Incoming::parse(m).ok()) {
+                    shared.dispatch(incoming);
+                }
+            }
+
+            if let Some(shared) = weak.upgrade() {
+                let mut shared = shared.borrow_mut();
+                for (_, pending) in shared.pending.drain() {
+                    let _ = pending.send(Err(RpcError::Closed(close_event.clone())));
+                }
+                shared.listeners.clear();
+                shared.closed = Some(close_event);
+            }
+        });
+
+        Self { shared }
+    }
+
+    /// Calls `method` and waits for its result.
+    ///
+    /// `params` must serialize to an array or an object, or to `null` to omit them.
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R, RpcError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let params = serde_json::to_value(params)?;
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut shared = self.shared.borrow_mut();
+            if let Some(close_event) = shared.closed.clone() {
+                return Err(RpcError::Closed(close_event));
+            }
+            let id = shared.next_id;
+            shared.next_id += 1;
+            shared.pending.insert(id, sender);
+            shared.send(&request(Some(id), method, params));
+        }
+        let result = receiver.await.map_err(|_| RpcError::Closed(None))??;
+        Ok(serde_json::from_value(result)?)
+    }
+
+    /// Sends a notification, which the server doesn't answer.
+    pub fn notify<P: Serialize>(&self, method: &str, params: P) -> Result<(), RpcError> {
+        let params = serde_json::to_value(params)?;
+        self.shared.borrow().send(&request(None, method, params));
+        Ok(())
+    }
+
+    /// Returns a stream of the notifications the server sends from now on.
+    ///
+    /// The stream ends when the connection is closed.
+    pub fn notifications(&self) -> Notifications {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut shared = self.shared.borrow_mut();
+        if shared.closed.is_none() {
+            shared.listeners.push(sender);
+        }
+        Notifications { receiver }
+    }
+}
+
+fn request(id: Option<u64>, method: &str, params: Value) -> Value {
+    let mut request = json!({ "jsonrpc": "2.0", "method": method });
+    if let Some(id) = id {
+        request["id"] = json!(id);
+    }
+    if !params.is_null() {
+        request["params"] = params;
+    }
+    request
+}
+
+impl fmt::Debug for Client {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shared = self.shared.borrow();
+        f.debug_struct("Client")
+            .field("pending", &shared.pending.len())
+            .field("closed", &shared.closed.is_some())
+            .finish()
+    }
+}
+
+/// The notifications of the server, returned by [`Client::notifications`].
+#[derive(Debug)]
+pub struct Notifications {
+    receiver: mpsc::UnboundedReceiver<Notification>,
+}
+
+impl Stream for Notifications {
+    type Item = Notification;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_requests() {
+        assert_eq!(
+            request(Some(3), "sum", json!([1, 2])),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "sum", "params": [1, 2] })
+        );
+        assert_eq!(
+            request(None, "ping", Value::Null),
+            json!({ "jsonrpc": "2.0", "method": "ping" })
+        );
+    }
+
+    #[test]
+    fn parses_incoming_messages() {
+        assert_eq!(
+            Incoming::parse(json!({ "jsonrpc": "2.0", "id": 1, "result": 3 })).unwrap(),
+            Incoming::Response {
+                id: 1,
+                result: Ok(json!(3)),
+            }
+        );
+        assert_eq!(
+            Incoming::parse(json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "error": { "code": -32602, "message": "Invalid params" },
+            }))
+            .unwrap(),
+            Incoming::Response {
+                id: 2,
+                result: Err(ErrorObject {
+                    code: -32602,
+                    message: "Invalid params".to_string(),
+                    data: None,
+                }),
+            }
+        );
+        assert_eq!(
+            Incoming::parse(json!({ "jsonrpc": "2.0", "method": "update", "params": [1] }))
+                .unwrap(),
+            Incoming::Notification(Notification {
+                method: "update".to_string(),
+                params: json!([1]),
+            })
+        );
+        assert_eq!(
+            Incoming::parse(json!({ "jsonrpc": "2.0", "id": "a", "method": "ask" })).unwrap(),
+            Incoming::Request { id: json!("a") }
+        );
+        assert!(Incoming::parse(json!({ "id": 1, "result": 3 })).is_err());
+        assert!(Incoming::parse(json!({ "jsonrpc": "2.0", "id": 1 })).is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//! Connected in-memory sockets, for testing protocol logic without a server.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::{futures::WebSocket, Message};
+//! use futures::{SinkExt, StreamExt};
+//!
+//! # async fn no_run() {
+//! let (mut client, mut server) = WebSocket::pair();
+//! client.send(Message::Text("ping".to_string())).await.unwrap();
+//! assert_eq!(
+//!     server.next().await.unwrap().unwrap(),
+//!     Message::Text("ping".to_string())
+//! );
+//! # }
+//! ```
+use crate::websocket::events::{CloseCode, CloseEvent};
+use crate::websocket::futures::WebSocket;
+use crate::websocket::transport::WebSocketTransport;
+use crate::websocket::{Message, State, WebSocketError};
+use futures_channel::mpsc;
+use futures_core::{ready, Stream};
+use futures_sink::Sink;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+enum Frame {
+    Message(Message),
+    Close(CloseEvent),
+}
+
+impl WebSocket {
+    /// Creates two connected in-memory sockets, each receiving what the other sends.
+    ///
+    /// See [`MemoryWebSocket`].
+    pub fn pair() -> (MemoryWebSocket, MemoryWebSocket) {
+        let (a_sender, a_receiver) = mpsc::unbounded();
+        let (b_sender, b_receiver) = mpsc::unbounded();
+        (
+            MemoryWebSocket::new(a_sender, b_receiver),
+            MemoryWebSocket::new(b_sender, a_receiver),
+        )
+    }
+}
+
+/// One end of a pair created by [`WebSocket::pair`], with the same [`Stream`] and [`Sink`]
+/// behavior as a socket.
+///
+/// Both ends are open from the start. Closing one end sends a close frame which the other end
+/// answers, and both streams then yield [`WebSocketError::ConnectionClose`] followed by `None`.
+/// Dropping an end makes the other one yield an unclean close with code `1006`.
+pub struct MemoryWebSocket {
+    sender: mpsc::UnboundedSender<Frame>,
+    receiver: mpsc::UnboundedReceiver<Frame>,
+    close_sent: bool,
+    closed: bool,
+}
+
+impl MemoryWebSocket {
+    fn new(sender: mpsc::UnboundedSender<Frame>, receiver: mpsc::UnboundedReceiver<Frame>) -> Self {
+        Self {
+            sender,
+            receiver,
+            close_sent: false,
+            closed: false,
+        }
+    }
+
+    /// Starts the closing handshake with the given code and reason, `1000` without a code.
+    ///
+    /// Like the native socket, codes which can't be sent are rejected with
+    /// [`WebSocketError::InvalidCloseCode`].
+    pub fn close(
+        mut self,
+        code: Option<CloseCode>,
+        reason: Option<&str>,
+    ) -> Result<(), WebSocketError> {
+        if let Some(code) = code.filter(|code| !code.can_be_sent()) {
+            return Err(WebSocketError::InvalidCloseCode(code));
+        }
+        self.send_close(CloseEvent {
+            code: code.unwrap_or(CloseCode::Normal),
+            reason: reason.unwrap_or_default().to_string(),
+            was_clean: true,
+        });
+        Ok(())
+    }
+
+    /// The current state of the socket.
+    pub fn state(&self) -> State {
+        if self.closed {
+            State::Closed
+        } else if self.close_sent {
+            State::Closing
+        } else {
+            State::Open
+        }
+    }
+
+    fn send_close(&mut self, close_event: CloseEvent) {
+        if !self.close_sent {
+            self.close_sent = true;
+            // The peer may be gone already, which the stream reports.
+            let _ = self.sender.unbounded_send(Frame::Close(close_event));
+        }
+    }
+}
+
+impl<T: Into<Message>> Sink<T> for MemoryWebSocket {
+    type Error = WebSocketError;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        if self.close_sent || self.closed {
+            return Err(WebSocketError::NotOpen(self.state()));
+        }
+        // Like browsers, messages sent to a peer which went away are discarded.
+        let _ = self.sender.unbounded_send(Frame::Message(item.into()));
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Sends a close frame with code `1000`. Unlike a browser socket, this doesn't wait for the
+    /// peer to answer it.
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().send_close(CloseEvent {
+            code: CloseCode::Normal,
+            reason: String::new(),
+            was_clean: true,
+        });
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Stream for MemoryWebSocket {
+    type Item = Result<Message, WebSocketError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.closed {
+            return Poll::Ready(None);
+        }
+        match ready!(Pin::new(&mut this.receiver).poll_next(cx)) {
+            Some(Frame::Message(msg)) => Poll::Ready(Some(Ok(msg))),
+            Some(Frame::Close(close_event)) => {
+                // Answer a close frame initiated by the peer with the same code.
+                this.send_close(close_event.clone());
+                this.closed = true;
+                Poll::Ready(Some(Err(WebSocketError::ConnectionClose(close_event))))
+            }
+            None => {
+                this.closed = true;
+                Poll::Ready(Some(Err(WebSocketError::ConnectionClose(CloseEvent {
+                    code: CloseCode::Abnormal,
+                    reason: String::new(),
+                    was_clean: false,
+                }))))
+            }
+        }
+    }
+}
+
+impl WebSocketTransport for MemoryWebSocket {
+    fn state(&self) -> State {
+        self.state()
+    }
+}
+
+impl fmt::Debug for MemoryWebSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryWebSocket")
+            .field("state", &self.state())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use futures::{SinkExt, StreamExt};
+
+    #[test]
+    fn exchanges_messages_and_closes() {
+        block_on(async {
+            let (mut a, mut b) = WebSocket::pair();
+            a.send("ping").await.unwrap();
+            b.send(vec![1, 2]).await.unwrap();
+            assert_eq!(b.next().await.unwrap().unwrap(), Message::from("ping"));
+            assert_eq!(a.next().await.unwrap().unwrap(), Message::Bytes(vec![1, 2]));
+
+            SinkExt::<Message>::close(&mut a).await.unwrap();
+            assert_eq!(a.state(), State::Closing);
+            assert!(a.send("late").await.is_err());
+            for end in [&mut b, &mut a] {
+                assert!(matches!(
+                    end.next().await,
+                    Some(Err(WebSocketError::ConnectionClose(CloseEvent {
+                        code: CloseCode::Normal,
+                        was_clean: true,
+                        ..
+                    })))
+                ));
+                assert!(end.next().await.is_none());
+                assert_eq!(end.state(), State::Closed);
+            }
+        });
+    }
+
+    #[test]
+    fn rejects_unsendable_close_codes() {
+        let (a, _b) = WebSocket::pair();
+        assert!(matches!(
+            a.close(Some(CloseCode::Abnormal), None),
+            Err(WebSocketError::InvalidCloseCode(CloseCode::Abnormal))
+        ));
+    }
+
+    #[test]
+    fn dropped_peer_closes_uncleanly() {
+        let (mut a, b) = WebSocket::pair();
+        drop(b);
+        block_on(async {
+            assert!(matches!(
+                a.next().await,
+                Some(Err(WebSocketError::ConnectionClose(CloseEvent {
+                    code: CloseCode::Abnormal,
+                    was_clean: false,
+                    ..
+                })))
+            ));
+            assert!(a.next().await.is_none());
+        });
+    }
+}
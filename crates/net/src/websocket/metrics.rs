@@ -0,0 +1,200 @@
+//! Traffic statistics of a WebSocket connection, for diagnostics panels and logging.
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+//! # use gloo_net::websocket::browser::WebSocket;
+//! # #[cfg(not(all(feature = "native", not(target_arch = "wasm32"))))]
+//! use gloo_net::websocket::futures::WebSocket;
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::StreamExt;
+//! use std::time::Duration;
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! let ws = WebSocket::open("wss://echo.websocket.org").unwrap();
+//! let mut metrics = ws.metrics_stream(Duration::from_secs(1));
+//!
+//! spawn_local(async move {
+//!     while let Some(metrics) = metrics.next().await {
+//!         console_log!(format!("received {} bytes", metrics.bytes_received))
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::Message;
+use futures_core::{ready, Stream};
+use gloo_timers::future::IntervalStream;
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// A snapshot of the statistics of a connection.
+///
+/// Byte counts are payload sizes, i.e. UTF-8 bytes for text messages, without framing.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Metrics {
+    /// The number of messages handed to the browser
+    pub messages_sent: u64,
+    /// The payload bytes handed to the browser
+    pub bytes_sent: u64,
+    /// The number of messages received
+    pub messages_received: u64,
+    /// The payload bytes received
+    pub bytes_received: u64,
+    /// The number of received messages waiting to be yielded by the stream
+    pub queue_len: usize,
+    /// The bytes queued by the browser but not yet transmitted
+    pub buffered_amount: u32,
+    /// How many times the connection was re-established, only counted by
+    /// [`ReconnectingWebSocket`](crate::websocket::reconnect::ReconnectingWebSocket)
+    pub reconnects: u32,
+    /// The time between the last [heartbeat](crate::websocket::heartbeat::Heartbeat) and the
+    /// next message received, if a heartbeat has been answered yet
+    pub round_trip: Option<Duration>,
+}
+
+/// The counters updated by a socket and its callbacks.
+#[derive(Debug, Default)]
+pub(crate) struct Counters {
+    messages_sent: u64,
+    bytes_sent: u64,
+    messages_received: u64,
+    bytes_received: u64,
+    round_trip: Option<Duration>,
+}
+
+pub(crate) type SharedCounters = Rc<RefCell<Counters>>;
+
+pub(crate) fn payload_len(msg: &Message) -> u64 {
+    let len = match msg {
+        Message::Text(text) => text.len(),
+        Message::Bytes(bytes) => bytes.len(),
+    };
+    u64::try_from(len).unwrap_or(u64::MAX)
+}
+
+impl Counters {
+    pub(crate) fn sent(&mut self, msg: &Message) {
+        self.sent_bytes(payload_len(msg));
+    }
+
+    /// Records a sent message of `len` bytes, for payloads which aren't a [`Message`].
+    pub(crate) fn sent_bytes(&mut self, len: u64) {
+        self.messages_sent += 1;
+        self.bytes_sent += len;
+    }
+
+    pub(crate) fn received(&mut self, msg: &Message) {
+        self.received_bytes(payload_len(msg));
+    }
+
+    /// Records a received message of `len` bytes, for messages whose payload isn't read yet.
+    pub(crate) fn received_bytes(&mut self, len: u64) {
+        self.messages_received += 1;
+        self.bytes_received += len;
+    }
+
+    pub(crate) fn set_round_trip(&mut self, round_trip: Duration) {
+        self.round_trip = Some(round_trip);
+    }
+
+    /// A snapshot of the counters, with the fields the counters don't track left at zero.
+    pub(crate) fn snapshot(&self) -> Metrics {
+        Metrics {
+            messages_sent: self.messages_sent,
+            bytes_sent: self.bytes_sent,
+            messages_received: self.messages_received,
+            bytes_received: self.bytes_received,
+            round_trip: self.round_trip,
+            ..Metrics::default()
+        }
+    }
+}
+
+/// Periodic [`Metrics`] snapshots, returned by
+/// [`WebSocket::metrics_stream`](crate::websocket::futures::WebSocket::metrics_stream).
+///
+/// The stream ends once the connection is closed, after a final snapshot, or when the socket is
+/// dropped.
+pub struct MetricsStream {
+    ticks: IntervalStream,
+    /// Takes a snapshot and tells whether the connection is closed, `None` once the socket has
+    /// been dropped.
+    snapshot: Box<dyn Fn() -> Option<(Metrics, bool)>>,
+    done: bool,
+}
+
+impl MetricsStream {
+    pub(crate) fn new(
+        interval: Duration,
+        snapshot: impl Fn() -> Option<(Metrics, bool)> + 'static,
+    ) -> Self {
+        let millis = u32::try_from(interval.as_millis()).unwrap_or(u32::MAX);
+        Self {
+            ticks: IntervalStream::new(millis),
+            snapshot: Box::new(snapshot),
+            done: false,
+        }
+    }
+}
+
+impl Stream for MetricsStream {
+    type Item = Metrics;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        ready!(Pin::new(&mut self.ticks).poll_next(cx));
+        match (self.snapshot)() {
+            Some((metrics, closed)) => {
+                self.done = closed;
+                Poll::Ready(Some(metrics))
+            }
+            None => {
+                self.done = true;
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for MetricsStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetricsStream")
+            .field("done", &self.done)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_messages_and_bytes() {
+        let mut counters = Counters::default();
+        counters.sent(&Message::Text("héllo".to_string()));
+        counters.sent(&Message::Bytes(vec![0; 4]));
+        counters.received(&Message::Bytes(vec![0; 10]));
+        counters.received_bytes(5);
+        counters.set_round_trip(Duration::from_millis(20));
+
+        let metrics = counters.snapshot();
+        assert_eq!(metrics.messages_sent, 2);
+        assert_eq!(metrics.bytes_sent, 10);
+        assert_eq!(metrics.messages_received, 2);
+        assert_eq!(metrics.bytes_received, 15);
+        assert_eq!(metrics.round_trip, Some(Duration::from_millis(20)));
+        assert_eq!(metrics.reconnects, 0);
+    }
+}
@@ -2,16 +2,90 @@
 //!
 //! This API is provided in the following flavors:
 //! - [Futures API][futures]
+//!
+//! A [reconnecting][reconnect::ReconnectingWebSocket] wrapper is also available for long lived
+//! connections, as well as a [typed JSON][json::JsonWebSocket] layer with the `json` feature and
+//! a [Protocol Buffers][protobuf::ProtobufWebSocket] one with the `prost` feature.
+//!
+//! Sockets work in web workers as well, the `worker` feature adds a
+//! [worker][worker::WebSocketWorker] owning sockets on behalf of the main thread.
+//!
+//! With the `native` feature, a [`tokio-tungstenite`](https://docs.rs/tokio-tungstenite) based
+//! implementation replaces the browser one on targets other than `wasm32`. The modules built on
+//! the browser socket are still compiled there, the browser socket being moved to
+//! `websocket::browser`.
 
+pub mod adapters;
+/// The browser WebSocket, which [`futures`] refers to unless the native backend replaces it.
+///
+/// The modules built on it, e.g. [`reconnect`] or [`mux`], stay available with the `native`
+/// feature, so code naming them still compiles, but their sockets only connect in browsers.
+#[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "native")))]
+#[path = "futures.rs"]
+pub mod browser;
+pub mod chunked;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod codec;
 pub mod events;
+#[cfg(not(all(feature = "native", not(target_arch = "wasm32"))))]
 pub mod futures;
+#[cfg(not(all(feature = "native", not(target_arch = "wasm32"))))]
+pub(crate) use self::futures as browser;
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub mod graphql;
+pub mod heartbeat;
+pub mod io;
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub mod json;
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub mod jsonrpc;
+#[cfg(feature = "test-utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-utils")))]
+pub mod memory;
+pub mod metrics;
+#[cfg(feature = "mqtt")]
+#[cfg_attr(docsrs, doc(cfg(feature = "mqtt")))]
+pub mod mqtt;
+pub mod mux;
+#[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+#[cfg_attr(docsrs, doc(cfg(feature = "native")))]
+pub mod native;
+#[cfg(feature = "prost")]
+#[cfg_attr(docsrs, doc(cfg(feature = "prost")))]
+pub mod protobuf;
+mod queue;
+pub mod raw;
+pub mod reconnect;
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+pub mod socketio;
+pub mod stomp;
+pub mod transport;
+#[cfg(feature = "worker")]
+#[cfg_attr(docsrs, doc(cfg(feature = "worker")))]
+pub mod worker;
+
+/// The native WebSocket, exported under the same path as the browser one.
+///
+/// The other items are the ones of the [browser](super::browser) WebSocket.
+#[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+pub mod futures {
+    pub use super::browser::{CloseBehavior, CloseHandle, OverflowPolicy, WebSocketBuilder};
+    pub use super::native::WebSocket;
+}
 
-use events::CloseEvent;
+use events::{CloseCode, CloseEvent, ErrorEvent};
 use gloo_utils::errors::JsError;
 use std::fmt;
 
 /// Message sent to and received from WebSocket.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "worker", derive(serde::Serialize, serde::Deserialize))]
 pub enum Message {
     /// String message
     Text(String),
@@ -19,11 +93,35 @@ pub enum Message {
     Bytes(Vec<u8>),
 }
 
+impl From<String> for Message {
+    fn from(text: String) -> Self {
+        Message::Text(text)
+    }
+}
+
+impl From<&str> for Message {
+    fn from(text: &str) -> Self {
+        Message::Text(text.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Message {
+    fn from(bytes: Vec<u8>) -> Self {
+        Message::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Message {
+    fn from(bytes: &[u8]) -> Self {
+        Message::Bytes(bytes.to_vec())
+    }
+}
+
 /// The state of the websocket.
 ///
 /// See [`WebSocket.readyState` on MDN](https://developer.mozilla.org/en-US/docs/Web/API/WebSocket/readyState)
 /// to learn more.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum State {
     /// The connection has not yet been established.
     Connecting,
@@ -41,23 +139,114 @@ pub enum State {
 #[non_exhaustive]
 pub enum WebSocketError {
     /// The `error` event
-    ConnectionError,
+    ConnectionError(ErrorEvent),
     /// The `close` event
     ConnectionClose(CloseEvent),
+    /// The socket couldn't be opened, e.g. because the URL is invalid.
+    OpenError(JsError),
     /// Message failed to send.
     MessageSendError(JsError),
+    /// The browser refused to close the socket, e.g. because the reason is too long.
+    CloseError(JsError),
+    /// A message couldn't be sent right away because the connection is in the given state, see
+    /// [`WebSocket::try_send`](futures::WebSocket::try_send).
+    NotOpen(State),
+    /// The given code can't be sent in a close frame, see [`CloseCode::can_be_sent`].
+    InvalidCloseCode(CloseCode),
+    /// The connection wasn't established within the configured open timeout.
+    ConnectTimeout,
+    /// The server didn't respond to a [heartbeat](heartbeat::Heartbeat) in time.
+    KeepAliveTimeout,
+    /// Messages were discarded because the receive queue was full, see
+    /// [`OverflowPolicy::Error`](futures::OverflowPolicy::Error).
+    QueueOverflow,
+    /// A received message was larger than the
+    /// [maximum message size](futures::WebSocketBuilder::max_message_size) and was dropped.
+    ///
+    /// For text messages, `size` may be a lower bound of the size in bytes.
+    MessageTooLarge {
+        /// The size of the message
+        size: usize,
+        /// The configured maximum size
+        limit: usize,
+    },
+    /// A message of a kind the [adapter](adapters) doesn't handle was received.
+    UnexpectedMessage(Message),
+    /// A binary message received by a [`ChunkedWebSocket`](chunked::ChunkedWebSocket) isn't a
+    /// valid chunk, or a message is too large to be chunked.
+    InvalidChunk,
+    /// A message could not be serialized to or deserialized from JSON.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    JsonError(serde_json::Error),
+    /// A [codec](codec::Codec) couldn't encode or decode a message.
+    #[cfg(feature = "serde")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+    CodecError(codec::CodecError),
+    /// A binary message could not be decoded as a Protocol Buffers message.
+    #[cfg(feature = "prost")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "prost")))]
+    ProtobufError(prost::DecodeError),
+    /// An error of the native backend.
+    #[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "native")))]
+    Native(Box<tokio_tungstenite::tungstenite::Error>),
+}
+
+#[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+impl From<tokio_tungstenite::tungstenite::Error> for WebSocketError {
+    fn from(error: tokio_tungstenite::tungstenite::Error) -> Self {
+        WebSocketError::Native(Box::new(error))
+    }
 }
 
 impl fmt::Display for WebSocketError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            WebSocketError::ConnectionError => write!(f, "WebSocket connection failed"),
+            WebSocketError::ConnectionError(e) => {
+                write!(f, "WebSocket connection failed")?;
+                if let Some(message) = &e.message {
+                    write!(f, ": {message}")?;
+                }
+                if let Some(code) = e.close_code {
+                    write!(f, " (close code: {code})")?;
+                }
+                Ok(())
+            }
             WebSocketError::ConnectionClose(e) => write!(
                 f,
                 "WebSocket Closed: code: {}, reason: {}",
                 e.code, e.reason
             ),
+            WebSocketError::OpenError(e) => write!(f, "WebSocket couldn't be opened: {e}"),
             WebSocketError::MessageSendError(e) => write!(f, "{e}"),
+            WebSocketError::CloseError(e) => write!(f, "WebSocket couldn't be closed: {e}"),
+            WebSocketError::NotOpen(state) => write!(f, "WebSocket is not open: {state:?}"),
+            WebSocketError::InvalidCloseCode(code) => {
+                write!(f, "WebSocket close code {code} can't be sent")
+            }
+            WebSocketError::ConnectTimeout => write!(f, "WebSocket connection timed out"),
+            WebSocketError::KeepAliveTimeout => write!(f, "WebSocket heartbeat timed out"),
+            WebSocketError::QueueOverflow => write!(f, "WebSocket receive queue overflowed"),
+            WebSocketError::MessageTooLarge { size, limit } => write!(
+                f,
+                "WebSocket message of {size} bytes exceeds the limit of {limit} bytes"
+            ),
+            WebSocketError::InvalidChunk => write!(f, "invalid WebSocket message chunk"),
+            WebSocketError::UnexpectedMessage(Message::Text(_)) => {
+                write!(f, "unexpected WebSocket text message")
+            }
+            WebSocketError::UnexpectedMessage(Message::Bytes(_)) => {
+                write!(f, "unexpected WebSocket binary message")
+            }
+            #[cfg(feature = "json")]
+            WebSocketError::JsonError(e) => write!(f, "{e}"),
+            #[cfg(feature = "serde")]
+            WebSocketError::CodecError(e) => write!(f, "{e}"),
+            #[cfg(feature = "prost")]
+            WebSocketError::ProtobufError(e) => write!(f, "{e}"),
+            #[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+            WebSocketError::Native(e) => write!(f, "{e}"),
         }
     }
 }
@@ -0,0 +1,837 @@
+//! An [MQTT 3.1.1](https://docs.oasis-open.org/mqtt/mqtt/v3.1.1/mqtt-v3.1.1.html) client over
+//! binary WebSocket messages.
+//!
+//! Publishing and subscribing with QoS 0 and 1 are supported.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::mqtt::{MqttClient, MqttOptions, QoS};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::StreamExt;
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! spawn_local(async {
+//!     let client = MqttClient::open("wss://broker.example.com/mqtt", MqttOptions::new("dashboard"))
+//!         .await
+//!         .unwrap();
+//!     let mut readings = client.subscribe("sensors/+/temperature", QoS::AtLeastOnce);
+//!     client
+//!         .publish("sensors/dashboard/online", "1", QoS::AtLeastOnce, true)
+//!         .await
+//!         .unwrap();
+//!     while let Some(Ok(publish)) = readings.next().await {
+//!         console_log!(format!("{}: {:?}", publish.topic, publish.payload));
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::{browser::WebSocket, events::CloseEvent, Message, WebSocketError};
+use futures_channel::{mpsc, oneshot};
+use futures_core::Stream;
+use futures_util::{SinkExt, StreamExt};
+use gloo_timers::future::IntervalStream;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use thiserror::Error as ThisError;
+use wasm_bindgen_futures::spawn_local;
+
+/// The sub-protocol offered by [`MqttClient::open`].
+pub const PROTOCOL: &str = "mqtt";
+
+/// Errors of a [`MqttClient`] and its subscriptions.
+#[derive(Debug, ThisError)]
+#[non_exhaustive]
+pub enum MqttError {
+    /// The socket failed.
+    #[error("{0}")]
+    Socket(WebSocketError),
+    /// The connection was closed, with the close event if one was received.
+    #[error("MQTT connection closed")]
+    Closed(Option<CloseEvent>),
+    /// The broker refused the connection with the given `CONNACK` return code.
+    #[error("MQTT connection refused with code {0}")]
+    Refused(u8),
+    /// The broker rejected a subscription.
+    #[error("MQTT subscription rejected")]
+    SubscriptionRejected,
+    /// The broker didn't answer a `PINGREQ` within the keep alive interval.
+    #[error("MQTT keep alive timed out")]
+    KeepAliveTimeout,
+    /// The broker sent data which isn't a valid packet.
+    #[error("invalid MQTT packet")]
+    InvalidPacket,
+}
+
+/// The quality of service of a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum QoS {
+    /// The message is delivered at most once.
+    #[default]
+    AtMostOnce,
+    /// The message is delivered at least once, it is acknowledged by the receiver.
+    AtLeastOnce,
+}
+
+impl QoS {
+    fn from_bits(bits: u8) -> Result<Self, MqttError> {
+        match bits {
+            0 => Ok(QoS::AtMostOnce),
+            1 => Ok(QoS::AtLeastOnce),
+            _ => Err(MqttError::InvalidPacket),
+        }
+    }
+}
+
+/// A message received through a subscription.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Publish {
+    /// The topic the message was published to
+    pub topic: String,
+    /// The payload
+    pub payload: Vec<u8>,
+    /// The quality of service the message was delivered with
+    pub qos: QoS,
+    /// Whether this is a retained message
+    pub retain: bool,
+}
+
+/// Options of the `CONNECT` packet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MqttOptions {
+    /// The client identifier, empty to let the broker assign one
+    pub client_id: String,
+    /// The keep alive interval in seconds, zero to disable it
+    pub keep_alive: u16,
+    /// Whether the broker should discard the previous session of the client
+    pub clean_session: bool,
+    /// The user name
+    pub username: Option<String>,
+    /// The password
+    pub password: Option<Vec<u8>>,
+}
+
+impl MqttOptions {
+    /// Options for a clean session with a 30 second keep alive.
+    pub fn new(client_id: &str) -> Self {
+        Self {
+            client_id: client_id.to_string(),
+            keep_alive: 30,
+            clean_session: true,
+            username: None,
+            password: None,
+        }
+    }
+
+    /// Sets the credentials.
+    pub fn credentials(mut self, username: &str, password: impl Into<Vec<u8>>) -> Self {
+        self.username = Some(username.to_string());
+        self.password = Some(password.into());
+        self
+    }
+
+    /// Sets the keep alive interval in seconds.
+    pub fn keep_alive(mut self, keep_alive: u16) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Sets whether the broker should discard the previous session of the client.
+    pub fn clean_session(mut self, clean_session: bool) -> Self {
+        self.clean_session = clean_session;
+        self
+    }
+}
+
+/// The packets this client sends and receives.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Packet {
+    Connect(MqttOptions),
+    ConnAck {
+        code: u8,
+    },
+    Publish {
+        packet_id: Option<u16>,
+        publish: Publish,
+    },
+    PubAck(u16),
+    Subscribe {
+        packet_id: u16,
+        filter: String,
+        qos: QoS,
+    },
+    SubAck {
+        packet_id: u16,
+        codes: Vec<u8>,
+    },
+    Unsubscribe {
+        packet_id: u16,
+        filter: String,
+    },
+    UnsubAck(u16),
+    PingReq,
+    PingResp,
+    Disconnect,
+}
+
+fn put_str(out: &mut Vec<u8>, s: &[u8]) {
+    out.extend_from_slice(&(s.len() as u16).to_be_bytes());
+    out.extend_from_slice(s);
+}
+
+impl Packet {
+    fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        let header = match self {
+            Packet::Connect(options) => {
+                put_str(&mut body, b"MQTT");
+                body.push(4);
+                let mut flags = 0;
+                if options.clean_session {
+                    flags |= 0x02;
+                }
+                if options.password.is_some() {
+                    flags |= 0x40;
+                }
+                if options.username.is_some() {
+                    flags |= 0x80;
+                }
+                body.push(flags);
+                body.extend_from_slice(&options.keep_alive.to_be_bytes());
+                put_str(&mut body, options.client_id.as_bytes());
+                if let Some(username) = &options.username {
+                    put_str(&mut body, username.as_bytes());
+                }
+                if let Some(password) = &options.password {
+                    put_str(&mut body, password);
+                }
+                0x10
+            }
+            Packet::ConnAck { code } => {
+                body.extend_from_slice(&[0, *code]);
+                0x20
+            }
+            Packet::Publish { packet_id, publish } => {
+                put_str(&mut body, publish.topic.as_bytes());
+                if let Some(packet_id) = packet_id {
+                    body.extend_from_slice(&packet_id.to_be_bytes());
+                }
+                body.extend_from_slice(&publish.payload);
+                0x30 | (publish.qos as u8) << 1 | publish.retain as u8
+            }
+            Packet::PubAck(packet_id) => {
+                body.extend_from_slice(&packet_id.to_be_bytes());
+                0x40
+            }
+            Packet::Subscribe {
+                packet_id,
+                filter,
+                qos,
+            } => {
+                body.extend_from_slice(&packet_id.to_be_bytes());
+                put_str(&mut body, filter.as_bytes());
+                body.push(*qos as u8);
+                0x82
+            }
+            Packet::SubAck { packet_id, codes } => {
+                body.extend_from_slice(&packet_id.to_be_bytes());
+                body.extend_from_slice(codes);
+                0x90
+            }
+            Packet::Unsubscribe { packet_id, filter } => {
+                body.extend_from_slice(&packet_id.to_be_bytes());
+                put_str(&mut body, filter.as_bytes());
+                0xa2
+            }
+            Packet::UnsubAck(packet_id) => {
+                body.extend_from_slice(&packet_id.to_be_bytes());
+                0xb0
+            }
+            Packet::PingReq => 0xc0,
+            Packet::PingResp => 0xd0,
+            Packet::Disconnect => 0xe0,
+        };
+
+        let mut out = Vec::with_capacity(body.len() + 5);
+        out.push(header);
+        let mut len = body.len();
+        loop {
+            let byte = (len % 128) as u8;
+            len /= 128;
+            if len == 0 {
+                out.push(byte);
+                break;
+            }
+            out.push(byte | 0x80);
+        }
+        out.extend_from_slice(&body);
+        out
+    }
+
+    /// Decodes the packet at the start of `data`, returning `None` if it is incomplete.
+    ///
+    /// Only the packets a broker sends to a client are decoded.
+    fn decode(data: &[u8]) -> Result<Option<(Packet, usize)>, MqttError> {
+        let header = match data.first() {
+            Some(&header) => header,
+            None => return Ok(None),
+        };
+        let mut len = 0usize;
+        let mut offset = 1;
+        loop {
+            let byte = match data.get(offset) {
+                Some(&byte) => byte,
+                None => return Ok(None),
+            };
+            len += ((byte & 0x7f) as usize) << (7 * (offset - 1));
+            offset += 1;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            if offset > 4 {
+                return Err(MqttError::InvalidPacket);
+            }
+        }
+        let body = match data.get(offset..offset + len) {
+            Some(body) => body,
+            None => return Ok(None),
+        };
+
+        let u16_at = |i: usize| -> Result<u16, MqttError> {
+            body.get(i..i + 2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .ok_or(MqttError::InvalidPacket)
+        };
+        let packet = match header >> 4 {
+            2 => Packet::ConnAck {
+                code: *body.get(1).ok_or(MqttError::InvalidPacket)?,
+            },
+            3 => {
+                let qos = QoS::from_bits((header >> 1) & 0x03)?;
+                let topic_len = u16_at(0)? as usize;
+                let topic = body
+                    .get(2..2 + topic_len)
+                    .and_then(|t| std::str::from_utf8(t).ok())
+                    .ok_or(MqttError::InvalidPacket)?
+                    .to_string();
+                let mut rest = 2 + topic_len;
+                let packet_id = match qos {
+                    QoS::AtMostOnce => None,
+                    QoS::AtLeastOnce => {
+                        rest += 2;
+                        Some(u16_at(rest - 2)?)
+                    }
+                };
+                Packet::Publish {
+                    packet_id,
+                    publish: Publish {
+                        topic,
+                        payload: body.get(rest..).ok_or(MqttError::InvalidPacket)?.to_vec(),
+                        qos,
+                        retain: header & 0x01 != 0,
+                    },
+                }
+            }
+            4 => Packet::PubAck(u16_at(0)?),
+            9 => Packet::SubAck {
+                packet_id: u16_at(0)?,
+                codes: body[2.min(body.len())..].to_vec(),
+            },
+            11 => Packet::UnsubAck(u16_at(0)?),
+            13 => Packet::PingResp,
+            _ => return Err(MqttError::InvalidPacket),
+        };
+        Ok(Some((packet, offset + len)))
+    }
+}
+
+/// Whether `topic` matches the subscription `filter`, which may contain `+` and `#` wildcards.
+fn topic_matches(filter: &str, topic: &str) -> bool {
+    // Wildcards at the first level don't match topics such as `$SYS/...`.
+    if topic.starts_with('$') && (filter.starts_with('+') || filter.starts_with('#')) {
+        return false;
+    }
+    let mut filter = filter.split('/');
+    let mut topic = topic.split('/');
+    loop {
+        match (filter.next(), topic.next()) {
+            (Some("#"), _) => return true,
+            (Some("+"), Some(_)) => {}
+            (Some(f), Some(t)) if f == t => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+type Subscriber = mpsc::UnboundedSender<Result<Publish, MqttError>>;
+
+struct Subscription {
+    filter: String,
+    sender: Subscriber,
+}
+
+struct Shared {
+    outgoing: mpsc::UnboundedSender<Message>,
+    subscriptions: HashMap<u64, Subscription>,
+    /// Subscriptions waiting for their `SUBACK`, by packet id
+    pending_subscriptions: HashMap<u16, u64>,
+    /// QoS 1 publishes waiting for their `PUBACK`, by packet id
+    pending_publishes: HashMap<u16, oneshot::Sender<Result<(), MqttError>>>,
+    next_subscription: u64,
+    next_packet_id: u16,
+    closed: bool,
+}
+
+impl Shared {
+    fn send(&self, packet: &Packet) {
+        let _ = self
+            .outgoing
+            .unbounded_send(Message::Bytes(packet.encode()));
+    }
+
+    fn packet_id(&mut self) -> u16 {
+        self.next_packet_id = self.next_packet_id.wrapping_add(1).max(1);
+        self.next_packet_id
+    }
+
+    fn fail(&mut self, error: impl Fn() -> MqttError) {
+        for (_, subscription) in self.subscriptions.drain() {
+            let _ = subscription.sender.unbounded_send(Err(error()));
+        }
+        for (_, publish) in self.pending_publishes.drain() {
+            let _ = publish.send(Err(error()));
+        }
+        self.pending_subscriptions.clear();
+        self.outgoing.close_channel();
+        self.closed = true;
+    }
+
+    fn dispatch(&mut self, packet: Packet) {
+        match packet {
+            Packet::Publish { packet_id, publish } => {
+                for subscription in self.subscriptions.values() {
+                    if topic_matches(&subscription.filter, &publish.topic) {
+                        let _ = subscription.sender.unbounded_send(Ok(publish.clone()));
+                    }
+                }
+                if let Some(packet_id) = packet_id {
+                    self.send(&Packet::PubAck(packet_id));
+                }
+            }
+            Packet::PubAck(packet_id) => {
+                if let Some(publish) = self.pending_publishes.remove(&packet_id) {
+                    let _ = publish.send(Ok(()));
+                }
+            }
+            Packet::SubAck { packet_id, codes } => {
+                let id = self.pending_subscriptions.remove(&packet_id);
+                if codes.first().is_none_or(|&code| code & 0x80 != 0) {
+                    if let Some(subscription) = id.and_then(|id| self.subscriptions.remove(&id)) {
+                        let _ = subscription
+                            .sender
+                            .unbounded_send(Err(MqttError::SubscriptionRejected));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn read_error(msg: Option<Result<Message, WebSocketError>>) -> MqttError {
+    match msg {
+        Some(Ok(_)) => MqttError::InvalidPacket,
+        Some(Err(WebSocketError::ConnectionClose(e))) => MqttError::Closed(Some(e)),
+        Some(Err(e)) => MqttError::Socket(e),
+        None => MqttError::Closed(None),
+    }
+}
+
+/// Accumulates binary messages and splits them into packets, as a packet may span several
+/// messages.
+#[derive(Default)]
+struct PacketReader {
+    buf: Vec<u8>,
+}
+
+impl PacketReader {
+    fn push(&mut self, data: &[u8]) -> Result<Vec<Packet>, MqttError> {
+        self.buf.extend_from_slice(data);
+        let mut packets = Vec::new();
+        let mut consumed = 0;
+        while let Some((packet, len)) = Packet::decode(&self.buf[consumed..])? {
+            packets.push(packet);
+            consumed += len;
+        }
+        self.buf.drain(..consumed);
+        Ok(packets)
+    }
+}
+
+/// An MQTT client over a WebSocket.
+///
+/// The connection is driven by tasks spawned with `spawn_local`. Clones share the connection.
+#[derive(Clone)]
+pub struct MqttClient {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl MqttClient {
+    /// Opens a socket offering the [`PROTOCOL`] and connects to the broker.
+    pub async fn open(url: &str, options: MqttOptions) -> Result<Self, MqttError> {
+        let ws = WebSocket::open_with_protocol(url, PROTOCOL).map_err(MqttError::Socket)?;
+        Self::connect(ws, options).await
+    }
+
+    /// Connects to the broker over an already open socket, waiting for the `CONNACK` packet.
+    pub async fn connect(ws: WebSocket, options: MqttOptions) -> Result<Self, MqttError> {
+        let keep_alive = options.keep_alive;
+        let (mut write, mut read) = ws.split();
+        write
+            .send(Message::Bytes(Packet::Connect(options).encode()))
+            .await
+            .map_err(MqttError::Socket)?;
+
+        let mut reader = PacketReader::default();
+        let mut early = Vec::new();
+        loop {
+            let msg = read.next().await;
+            let data = match &msg {
+                Some(Ok(Message::Bytes(data))) => data,
+                _ => return Err(read_error(msg)),
+            };
+            let mut packets = reader.push(data)?.into_iter();
+            match packets.next() {
+                Some(Packet::ConnAck { code: 0 }) => {
+                    // Retained messages may arrive right after the acknowledgement.
+                    early.extend(packets);
+                    break;
+                }
+                Some(Packet::ConnAck { code }) => return Err(MqttError::Refused(code)),
+                Some(_) => return Err(MqttError::InvalidPacket),
+                None => {}
+            }
+        }
+
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded();
+        spawn_local(async move {
+            while let Some(msg) = outgoing_rx.next().await {
+                if write.send(msg).await.is_err() {
+                    break;
+                }
+            }
+            let _ = SinkExt::<Message>::close(&mut write).await;
+        });
+
+        let shared = Rc::new(RefCell::new(Shared {
+            outgoing,
+            subscriptions: HashMap::new(),
+            pending_subscriptions: HashMap::new(),
+            pending_publishes: HashMap::new(),
+            next_subscription: 0,
+            next_packet_id: 0,
+            closed: false,
+        }));
+        let ping_pending = Rc::new(Cell::new(false));
+
+        if keep_alive > 0 {
+            let weak = Rc::downgrade(&shared);
+            let ping_pending = Rc::clone(&ping_pending);
+            spawn_local(async move {
+                let mut ticks = IntervalStream::new(u32::from(keep_alive) * 1000);
+                while ticks.next().await.is_some() {
+                    let shared = match weak.upgrade() {
+                        Some(shared) => shared,
+                        None => return,
+                    };
+                    let mut shared = shared.borrow_mut();
+                    if shared.closed {
+                        return;
+                    }
+                    if ping_pending.replace(true) {
+                        shared.fail(|| MqttError::KeepAliveTimeout);
+                        return;
+                    }
+                    shared.send(&Packet::PingReq);
+                }
+            });
+        }
+
+        let weak = Rc::downgrade(&shared);
+        spawn_local(async move {
+            let mut packets = early;
+            let error = loop {
+                if let Some(shared) = weak.upgrade() {
+                    let mut shared = shared.borrow_mut();
+                    for packet in packets.drain(..) {
+                        if packet == Packet::PingResp {
+                            ping_pending.set(false);
+                        }
+                        shared.dispatch(packet);
+                    }
+                } else {
+                    return;
+                }
+
+                let msg = read.next().await;
+                match &msg {
+                    Some(Ok(Message::Bytes(data))) => match reader.push(data) {
+                        Ok(new) => packets = new,
+                        Err(e) => break e,
+                    },
+                    // Text messages are not part of the protocol.
+                    Some(Ok(Message::Text(_))) => {}
+                    _ => break read_error(msg),
+                }
+            };
+
+            if let Some(shared) = weak.upgrade() {
+                let mut shared = shared.borrow_mut();
+                match error {
+                    MqttError::InvalidPacket => shared.fail(|| MqttError::InvalidPacket),
+                    MqttError::Closed(e) => shared.fail(|| MqttError::Closed(e.clone())),
+                    _ => shared.fail(|| MqttError::Closed(None)),
+                }
+            }
+        });
+
+        Ok(Self { shared })
+    }
+
+    /// Subscribes to the topics matching `filter`.
+    ///
+    /// Dropping the returned stream unsubscribes. Messages matching several subscriptions of
+    /// the client are delivered to each of them.
+    pub fn subscribe(&self, filter: &str, qos: QoS) -> MqttSubscription {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut shared = self.shared.borrow_mut();
+        let id = shared.next_subscription;
+        shared.next_subscription += 1;
+
+        if shared.closed {
+            let _ = sender.unbounded_send(Err(MqttError::Closed(None)));
+        } else {
+            let packet_id = shared.packet_id();
+            shared.send(&Packet::Subscribe {
+                packet_id,
+                filter: filter.to_string(),
+                qos,
+            });
+            shared.pending_subscriptions.insert(packet_id, id);
+            shared.subscriptions.insert(
+                id,
+                Subscription {
+                    filter: filter.to_string(),
+                    sender,
+                },
+            );
+        }
+
+        MqttSubscription {
+            id,
+            filter: filter.to_string(),
+            receiver,
+            shared: Rc::clone(&self.shared),
+        }
+    }
+
+    /// Publishes `payload` to `topic`.
+    ///
+    /// With [`QoS::AtLeastOnce`] this resolves once the broker acknowledged the message,
+    /// otherwise once it has been queued.
+    pub async fn publish(
+        &self,
+        topic: &str,
+        payload: impl Into<Vec<u8>>,
+        qos: QoS,
+        retain: bool,
+    ) -> Result<(), MqttError> {
+        let acknowledged = {
+            let mut shared = self.shared.borrow_mut();
+            if shared.closed {
+                return Err(MqttError::Closed(None));
+            }
+            let publish = Publish {
+                topic: topic.to_string(),
+                payload: payload.into(),
+                qos,
+                retain,
+            };
+            match qos {
+                QoS::AtMostOnce => {
+                    shared.send(&Packet::Publish {
+                        packet_id: None,
+                        publish,
+                    });
+                    return Ok(());
+                }
+                QoS::AtLeastOnce => {
+                    let packet_id = shared.packet_id();
+                    let (sender, receiver) = oneshot::channel();
+                    shared.pending_publishes.insert(packet_id, sender);
+                    shared.send(&Packet::Publish {
+                        packet_id: Some(packet_id),
+                        publish,
+                    });
+                    receiver
+                }
+            }
+        };
+        acknowledged.await.unwrap_or(Err(MqttError::Closed(None)))
+    }
+
+    /// Sends a `DISCONNECT` packet and closes the socket once it has been sent.
+    pub fn disconnect(&self) {
+        let mut shared = self.shared.borrow_mut();
+        if !shared.closed {
+            shared.send(&Packet::Disconnect);
+            shared.fail(|| MqttError::Closed(None));
+        }
+    }
+}
+
+impl fmt::Debug for MqttClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shared = self.shared.borrow();
+        f.debug_struct("MqttClient")
+            .field("subscriptions", &shared.subscriptions.len())
+            .field("pending_publishes", &shared.pending_publishes.len())
+            .field("closed", &shared.closed)
+            .finish()
+    }
+}
+
+/// The messages of a subscription created with [`MqttClient::subscribe`].
+pub struct MqttSubscription {
+    id: u64,
+    filter: String,
+    receiver: mpsc::UnboundedReceiver<Result<Publish, MqttError>>,
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl MqttSubscription {
+    /// The topic filter of the subscription.
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+}
+
+impl Stream for MqttSubscription {
+    type Item = Result<Publish, MqttError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl Drop for MqttSubscription {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        if shared.subscriptions.remove(&self.id).is_none() {
+            return;
+        }
+        // Other subscriptions may still need the broker to deliver this filter.
+        let filter = &self.filter;
+        if shared.subscriptions.values().all(|s| &s.filter != filter) {
+            let packet_id = shared.packet_id();
+            shared.send(&Packet::Unsubscribe {
+                packet_id,
+                filter: self.filter.clone(),
+            });
+        }
+    }
+}
+
+impl fmt::Debug for MqttSubscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MqttSubscription")
+            .field("filter", &self.filter)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(packet: Packet) {
+        let encoded = packet.encode();
+        assert_eq!(
+            Packet::decode(&encoded).unwrap(),
+            Some((packet, encoded.len()))
+        );
+        assert_eq!(Packet::decode(&encoded[..encoded.len() - 1]).unwrap(), None);
+    }
+
+    #[test]
+    fn encodes_and_decodes_packets() {
+        assert_eq!(
+            Packet::Connect(MqttOptions::new("id").credentials("u", "p")).encode(),
+            [
+                0x10, 20, 0, 4, b'M', b'Q', b'T', b'T', 4, 0xc2, 0, 30, 0, 2, b'i', b'd', 0, 1,
+                b'u', 0, 1, b'p',
+            ]
+        );
+        assert_eq!(Packet::PingReq.encode(), [0xc0, 0]);
+
+        roundtrip(Packet::ConnAck { code: 0 });
+        roundtrip(Packet::PubAck(7));
+        roundtrip(Packet::UnsubAck(9));
+        roundtrip(Packet::PingResp);
+        roundtrip(Packet::SubAck {
+            packet_id: 3,
+            codes: vec![1],
+        });
+        roundtrip(Packet::Publish {
+            packet_id: Some(5),
+            publish: Publish {
+                topic: "a/b".to_string(),
+                payload: vec![0; 300],
+                qos: QoS::AtLeastOnce,
+                retain: true,
+            },
+        });
+        roundtrip(Packet::Publish {
+            packet_id: None,
+            publish: Publish {
+                topic: "a".to_string(),
+                payload: b"x".to_vec(),
+                qos: QoS::AtMostOnce,
+                retain: false,
+            },
+        });
+    }
+
+    #[test]
+    fn reads_packets_across_messages() {
+        let mut data = Packet::PingResp.encode();
+        data.extend(Packet::PubAck(1).encode());
+        let mut reader = PacketReader::default();
+        assert_eq!(reader.push(&data[..3]).unwrap(), vec![Packet::PingResp]);
+        assert_eq!(reader.push(&data[3..]).unwrap(), vec![Packet::PubAck(1)]);
+        assert!(reader.push(&[0xf0, 0]).is_err());
+    }
+
+    #[test]
+    fn matches_topics() {
+        assert!(topic_matches("a/b", "a/b"));
+        assert!(!topic_matches("a/b", "a/c"));
+        assert!(topic_matches("a/+/c", "a/b/c"));
+        assert!(!topic_matches("a/+", "a/b/c"));
+        assert!(topic_matches("a/#", "a/b/c"));
+        assert!(topic_matches("a/#", "a"));
+        assert!(topic_matches("#", "a/b"));
+        assert!(!topic_matches("#", "$SYS/uptime"));
+        assert!(!topic_matches("+/uptime", "$SYS/uptime"));
+        assert!(topic_matches("$SYS/#", "$SYS/uptime"));
+        assert!(topic_matches("a/+", "a/$b"));
+    }
+}
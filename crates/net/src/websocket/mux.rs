@@ -0,0 +1,321 @@
+//! Multiplexing named logical channels over one WebSocket.
+//!
+//! Browsers limit the number of connections per host, so applications with several independent
+//! message flows can carve one socket into channels instead of opening a socket per flow. Both
+//! ends need to use [`Mux`] (or implement the same framing).
+//!
+//! Every message is sent as a binary WebSocket message made of the length of the channel name as
+//! one byte, the UTF-8 channel name, one byte telling whether the payload is text (`0`) or binary
+//! (`1`), and the payload.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::{futures::WebSocket, mux::Mux, Message};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::{SinkExt, StreamExt};
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! let mux = Mux::new(WebSocket::open("wss://example.com/mux").unwrap());
+//! let mut chat = mux.channel("chat").unwrap();
+//! let mut presence = mux.channel("presence").unwrap();
+//!
+//! spawn_local(async move {
+//!     chat.send("hello").await.unwrap();
+//!     while let Some(msg) = presence.next().await {
+//!         console_log!(format!("{:?}", msg))
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::events::{CloseCode, CloseEvent};
+use crate::websocket::{Message, WebSocketError};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use futures_sink::Sink;
+use futures_util::{SinkExt, StreamExt};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use wasm_bindgen_futures::spawn_local;
+
+const TEXT: u8 = 0;
+const BINARY: u8 = 1;
+
+fn encode(channel: &str, message: Message) -> Vec<u8> {
+    let (kind, payload) = match message {
+        Message::Text(text) => (TEXT, text.into_bytes()),
+        Message::Bytes(bytes) => (BINARY, bytes),
+    };
+    let mut frame = Vec::with_capacity(channel.len() + payload.len() + 2);
+    frame.push(channel.len() as u8);
+    frame.extend_from_slice(channel.as_bytes());
+    frame.push(kind);
+    frame.extend_from_slice(&payload);
+    frame
+}
+
+fn decode(frame: &[u8]) -> Option<(&str, Message)> {
+    let (&len, rest) = frame.split_first()?;
+    let name = std::str::from_utf8(rest.get(..len as usize)?).ok()?;
+    let (&kind, payload) = rest[len as usize..].split_first()?;
+    let message = match kind {
+        TEXT => Message::Text(String::from_utf8(payload.to_vec()).ok()?),
+        BINARY => Message::Bytes(payload.to_vec()),
+        _ => return None,
+    };
+    Some((name, message))
+}
+
+type ChannelSender = mpsc::UnboundedSender<Result<Message, WebSocketError>>;
+
+struct Shared {
+    outgoing: mpsc::UnboundedSender<Vec<u8>>,
+    /// The open channels by name, with the id of the [`Channel`] owning the name
+    channels: HashMap<String, (u64, ChannelSender)>,
+    next_id: u64,
+    closed: Option<CloseEvent>,
+}
+
+impl Shared {
+    /// Closes the channel if `id` still owns its name.
+    fn remove(&mut self, name: &str, id: u64) {
+        if self
+            .channels
+            .get(name)
+            .is_some_and(|(owner, _)| *owner == id)
+        {
+            self.channels.remove(name);
+        }
+    }
+}
+
+/// Carves one socket into named channels.
+///
+/// The socket is driven by tasks spawned with `spawn_local`, and closed once the `Mux` and all
+/// its channels have been dropped. Messages received for channels which aren't open are
+/// dropped, as are binary messages which don't follow the framing.
+#[derive(Clone)]
+pub struct Mux {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Mux {
+    /// Multiplexes `socket`, usually a [`WebSocket`](crate::websocket::futures::WebSocket).
+    pub fn new<S>(socket: S) -> Self
+    where
+        S: Stream<Item = Result<Message, WebSocketError>>
+            + Sink<Message, Error = WebSocketError>
+            + 'static,
+    {
+        let (mut write, mut read) = socket.split();
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded::<Vec<u8>>();
+        spawn_local(async move {
+            while let Some(frame) = outgoing_rx.next().await {
+                if write.send(Message::Bytes(frame)).await.is_err() {
+                    break;
+                }
+            }
+            let _ = write.close().await;
+        });
+
+        let shared = Rc::new(RefCell::new(Shared {
+            outgoing,
+            channels: HashMap::new(),
+            next_id: 0,
+            closed: None,
+        }));
+
+        let weak = Rc::downgrade(&shared);
+        spawn_local(async move {
+            let mut close_event = None;
+            while let Some(msg) = read.next().await {
+                let shared = match weak.upgrade() {
+                    Some(shared) => shared,
+                    None => return,
+                };
+                let shared = shared.borrow();
+                match msg {
+                    Ok(Message::Bytes(frame)) => {
+                        if let Some((name, msg)) = decode(&frame) {
+                            if let Some((_, channel)) = shared.channels.get(name) {
+                                let _ = channel.unbounded_send(Ok(msg));
+                            }
+                        }
+                    }
+                    Ok(Message::Text(_)) => {}
+                    Err(WebSocketError::ConnectionError(e)) => {
+                        for (_, channel) in shared.channels.values() {
+                            let _ = channel
+                                .unbounded_send(Err(WebSocketError::ConnectionError(e.clone())));
+                        }
+                    }
+                    Err(WebSocketError::ConnectionClose(e)) => close_event = Some(e),
+                    Err(_) => {}
+                }
+            }
+
+            if let Some(shared) = weak.upgrade() {
+                let mut shared = shared.borrow_mut();
+                let close_event = close_event.unwrap_or_else(abnormal_closure);
+                for (_, (_, channel)) in shared.channels.drain() {
+                    let _ = channel
+                        .unbounded_send(Err(WebSocketError::ConnectionClose(close_event.clone())));
+                }
+                shared.closed = Some(close_event);
+            }
+        });
+
+        Self { shared }
+    }
+
+    /// Opens the channel called `name`, returning `None` if it is already open.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is longer than 255 bytes.
+    pub fn channel(&self, name: &str) -> Option<Channel> {
+        assert!(name.len() <= 255, "channel names must be at most 255 bytes");
+        let mut shared = self.shared.borrow_mut();
+        if shared.channels.contains_key(name) {
+            return None;
+        }
+        let (sender, receiver) = mpsc::unbounded();
+        let id = shared.next_id;
+        shared.next_id += 1;
+        match &shared.closed {
+            Some(e) => {
+                let _ = sender.unbounded_send(Err(WebSocketError::ConnectionClose(e.clone())));
+            }
+            None => {
+                shared.channels.insert(name.to_string(), (id, sender));
+            }
+        }
+        Some(Channel {
+            id,
+            name: name.to_string(),
+            receiver,
+            shared: Rc::clone(&self.shared),
+        })
+    }
+}
+
+impl fmt::Debug for Mux {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shared = self.shared.borrow();
+        f.debug_struct("Mux")
+            .field("channels", &shared.channels.keys().collect::<Vec<_>>())
+            .field("closed", &shared.closed.is_some())
+            .finish()
+    }
+}
+
+/// The close event reported when the socket ended without one.
+fn abnormal_closure() -> CloseEvent {
+    CloseEvent {
+        code: CloseCode::Abnormal,
+        reason: String::new(),
+        was_clean: false,
+    }
+}
+
+/// A logical channel of a [`Mux`], sending and receiving messages like a socket.
+///
+/// Closing or dropping the channel only stops receiving its messages, the underlying socket
+/// stays open for the other channels.
+pub struct Channel {
+    id: u64,
+    name: String,
+    receiver: mpsc::UnboundedReceiver<Result<Message, WebSocketError>>,
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Channel {
+    /// The name of the channel.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Stream for Channel {
+    type Item = Result<Message, WebSocketError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl<T: Into<Message>> Sink<T> for Channel {
+    type Error = WebSocketError;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let shared = self.shared.borrow();
+        if let Some(e) = &shared.closed {
+            return Err(WebSocketError::ConnectionClose(e.clone()));
+        }
+        shared
+            .outgoing
+            .unbounded_send(encode(&self.name, item.into()))
+            .map_err(|_| WebSocketError::ConnectionClose(abnormal_closure()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        this.shared.borrow_mut().remove(&this.name, this.id);
+        this.receiver.close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for Channel {
+    fn drop(&mut self) {
+        // The channel may have been closed and its name reused since.
+        self.shared.borrow_mut().remove(&self.name, self.id);
+    }
+}
+
+impl fmt::Debug for Channel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_and_decodes_frames() {
+        let frame = encode("chat", Message::Text("hi".to_string()));
+        assert_eq!(frame, b"\x04chat\x00hi");
+        assert_eq!(
+            decode(&frame),
+            Some(("chat", Message::Text("hi".to_string())))
+        );
+
+        let frame = encode("", Message::Bytes(vec![1, 2]));
+        assert_eq!(decode(&frame), Some(("", Message::Bytes(vec![1, 2]))));
+
+        assert_eq!(decode(b""), None);
+        assert_eq!(decode(b"\x05chat"), None);
+        assert_eq!(decode(b"\x04chat"), None);
+        assert_eq!(decode(b"\x04chat\x02"), None);
+        assert_eq!(decode(b"\x04chat\x00\xff"), None);
+    }
+}
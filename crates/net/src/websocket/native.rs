@@ -0,0 +1,458 @@
+//! A `tokio-tungstenite` backed WebSocket for native targets.
+//!
+//! With the `native` feature enabled on targets other than `wasm32`, [`futures::WebSocket`]
+//! refers to the [`WebSocket`] in this module, so crates sharing code between the browser and
+//! native binaries or tests don't need to `cfg` their socket handling.
+//!
+//! Only the core API is available: opening, sending, receiving and closing. The other modules of
+//! [`websocket`](crate::websocket), e.g. the reconnecting wrapper, keep using the
+//! [browser](crate::websocket::browser) socket. Connections need to be polled from within a tokio
+//! runtime. `wss://` URLs require one of the TLS features of
+//! `tokio-tungstenite` to be enabled, e.g. `native-tls` or `rustls-tls-webpki-roots`.
+//!
+//! [`futures::WebSocket`]: crate::websocket::futures::WebSocket
+use crate::websocket::adapters::{BytesWebSocket, TextWebSocket};
+use crate::websocket::events::{CloseCode, CloseEvent, ErrorEvent};
+use crate::websocket::io::WebSocketIo;
+use crate::websocket::{Message, State, WebSocketError};
+use futures_core::{ready, Stream};
+use futures_sink::Sink;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::client::{Request, Response};
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::{self, Error};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type ConnectFuture = Pin<Box<dyn Future<Output = Result<(Socket, Response), Error>> + Send>>;
+
+enum Connection {
+    Connecting(ConnectFuture),
+    Open(Box<Socket>),
+    Closed,
+}
+
+/// A WebSocket connection with the same interface as the browser based
+/// [`WebSocket`](crate::websocket::futures::WebSocket).
+pub struct WebSocket {
+    connection: Connection,
+    protocol: String,
+    closing: bool,
+    /// Errors and close events yielded by the stream before it ends.
+    pending: VecDeque<WebSocketError>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl WebSocket {
+    /// Establish a WebSocket connection.
+    ///
+    /// Like in the browser, this returns immediately and the connection is established when
+    /// the socket is first polled. The only error returned here is an invalid URL, later
+    /// failures are yielded by the stream.
+    pub fn open(url: &str) -> Result<Self, WebSocketError> {
+        Self::open_with_protocols::<&str>(url, &[])
+    }
+
+    /// Establish a WebSocket connection offering a sub-protocol.
+    ///
+    /// See [`WebSocket::open`].
+    pub fn open_with_protocol(url: &str, protocol: &str) -> Result<Self, WebSocketError> {
+        Self::open_with_protocols(url, &[protocol])
+    }
+
+    /// Establish a WebSocket connection offering sub-protocols in order of preference.
+    ///
+    /// See [`WebSocket::open`].
+    pub fn open_with_protocols<S: AsRef<str>>(
+        url: &str,
+        protocols: &[S],
+    ) -> Result<Self, WebSocketError> {
+        let mut request: Request = url.into_client_request().map_err(WebSocketError::from)?;
+        if !protocols.is_empty() {
+            let protocols = protocols
+                .iter()
+                .map(|p| p.as_ref())
+                .collect::<Vec<_>>()
+                .join(", ");
+            let value = HeaderValue::from_str(&protocols)
+                .map_err(|e| WebSocketError::from(Error::HttpFormat(e.into())))?;
+            request
+                .headers_mut()
+                .insert("Sec-WebSocket-Protocol", value);
+        }
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("websocket", url = %request.uri());
+        trace_event!(debug, parent: &span, "connecting");
+        Ok(Self {
+            connection: Connection::Connecting(Box::pin(tokio_tungstenite::connect_async(request))),
+            protocol: String::new(),
+            closing: false,
+            pending: VecDeque::new(),
+            #[cfg(feature = "tracing")]
+            span,
+        })
+    }
+
+    /// Closes the websocket.
+    ///
+    /// The closing handshake is completed in the background. Codes which can't be sent, see
+    /// [`CloseCode::can_be_sent`], are rejected with [`WebSocketError::InvalidCloseCode`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime.
+    pub fn close(
+        self,
+        code: Option<CloseCode>,
+        reason: Option<&str>,
+    ) -> Result<(), WebSocketError> {
+        if let Some(code) = code.filter(|code| !code.can_be_sent()) {
+            return Err(WebSocketError::InvalidCloseCode(code));
+        }
+        let frame = match (code, reason) {
+            (None, None) => None,
+            (code, reason) => Some(CloseFrame {
+                code: u16::from(code.unwrap_or(CloseCode::Normal)).into(),
+                reason: reason.unwrap_or_default().to_string().into(),
+            }),
+        };
+        trace_event!(debug, parent: &self.span, code = ?code.map(u16::from), "closing");
+        match self.connection {
+            Connection::Open(mut stream) => {
+                tokio::spawn(async move {
+                    let _ = stream.close(frame).await;
+                });
+            }
+            // Dropping the handshake aborts the connection attempt.
+            Connection::Connecting(_) | Connection::Closed => {}
+        }
+        Ok(())
+    }
+
+    /// Converts this into a byte stream implementing `AsyncRead` and `AsyncWrite`.
+    ///
+    /// See [`WebSocketIo`] for how bytes map to messages.
+    pub fn into_io(self) -> WebSocketIo<Self> {
+        WebSocketIo::new(self)
+    }
+
+    /// Converts this into a stream and sink of text messages.
+    ///
+    /// See [`TextWebSocket`].
+    pub fn into_text_stream(self) -> TextWebSocket<Self> {
+        TextWebSocket::new(self)
+    }
+
+    /// Converts this into a stream and sink of binary messages.
+    ///
+    /// See [`BytesWebSocket`].
+    pub fn into_bytes_stream(self) -> BytesWebSocket<Self> {
+        BytesWebSocket::new(self)
+    }
+
+    /// The current state of the websocket.
+    pub fn state(&self) -> State {
+        match &self.connection {
+            Connection::Connecting(_) => State::Connecting,
+            Connection::Open(_) if self.closing => State::Closing,
+            Connection::Open(_) => State::Open,
+            Connection::Closed => State::Closed,
+        }
+    }
+
+    /// The extensions in use. Extensions are not supported by this backend, so this is always
+    /// empty.
+    pub fn extensions(&self) -> String {
+        String::new()
+    }
+
+    /// The sub-protocol in use.
+    pub fn protocol(&self) -> String {
+        self.protocol.clone()
+    }
+
+    /// Drives the handshake, resolving once the connection is open.
+    fn poll_connect(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), WebSocketError>> {
+        let connect = match &mut self.connection {
+            Connection::Connecting(connect) => connect,
+            Connection::Open(_) => return Poll::Ready(Ok(())),
+            Connection::Closed => {
+                return Poll::Ready(Err(WebSocketError::ConnectionError(error_event(None))))
+            }
+        };
+        match ready!(connect.as_mut().poll(cx)) {
+            Ok((stream, response)) => {
+                self.protocol = response
+                    .headers()
+                    .get("Sec-WebSocket-Protocol")
+                    .and_then(|value| value.to_str().ok())
+                    .unwrap_or_default()
+                    .to_string();
+                self.connection = Connection::Open(Box::new(stream));
+                trace_event!(debug, parent: &self.span, protocol = %self.protocol, "connection open");
+                Poll::Ready(Ok(()))
+            }
+            Err(e) => {
+                // Mirror the `error` and `close` events fired by browsers.
+                self.connection = Connection::Closed;
+                trace_event!(warn, parent: &self.span, error = %e, "connection failed");
+                let error = error_event(Some(e.to_string()));
+                self.pending.push_back(WebSocketError::from(e));
+                self.pending
+                    .push_back(WebSocketError::ConnectionClose(CloseEvent {
+                        code: CloseCode::Abnormal,
+                        reason: String::new(),
+                        was_clean: false,
+                    }));
+                Poll::Ready(Err(WebSocketError::ConnectionError(error)))
+            }
+        }
+    }
+
+    fn stream(&mut self) -> Result<&mut Socket, WebSocketError> {
+        match &mut self.connection {
+            Connection::Open(stream) => Ok(stream),
+            _ => Err(WebSocketError::from(Error::AlreadyClosed)),
+        }
+    }
+}
+
+fn error_event(message: Option<String>) -> ErrorEvent {
+    ErrorEvent {
+        event_type: "error".to_string(),
+        message,
+        ready_state: State::Closed,
+        close_code: None,
+    }
+}
+
+impl<T: Into<Message>> Sink<T> for WebSocket {
+    type Error = WebSocketError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_connect(cx))?;
+        Pin::new(this.stream()?)
+            .poll_ready(cx)
+            .map_err(WebSocketError::from)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let item = match item.into() {
+            Message::Text(text) => tungstenite::Message::Text(text),
+            Message::Bytes(bytes) => tungstenite::Message::Binary(bytes),
+        };
+        let this = self.get_mut();
+        trace_event!(
+            trace,
+            parent: &this.span,
+            binary = item.is_binary(),
+            len = item.len(),
+            "message sent"
+        );
+        Pin::new(this.stream()?)
+            .start_send(item)
+            .map_err(WebSocketError::from)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match &mut self.get_mut().connection {
+            Connection::Open(stream) => Pin::new(stream)
+                .poll_flush(cx)
+                .map_err(WebSocketError::from),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if !this.closing {
+            trace_event!(debug, parent: &this.span, "closing");
+        }
+        this.closing = true;
+        match &mut this.connection {
+            Connection::Open(stream) => Pin::new(stream)
+                .poll_close(cx)
+                .map_err(WebSocketError::from),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+impl Stream for WebSocket {
+    type Item = Result<Message, WebSocketError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(e) = this.pending.pop_front() {
+                return Poll::Ready(Some(Err(e)));
+            }
+            let stream = match &mut this.connection {
+                Connection::Connecting(_) => {
+                    // Failures are queued in `pending`.
+                    let _ = ready!(this.poll_connect(cx));
+                    continue;
+                }
+                Connection::Open(stream) => stream,
+                Connection::Closed => return Poll::Ready(None),
+            };
+            let msg = ready!(Pin::new(stream).poll_next(cx));
+            #[cfg(feature = "tracing")]
+            if let Some(Ok(msg)) = &msg {
+                tracing::trace!(
+                    parent: &this.span,
+                    binary = msg.is_binary(),
+                    len = msg.len(),
+                    "message received"
+                );
+            }
+            match msg {
+                Some(Ok(tungstenite::Message::Text(text))) => {
+                    return Poll::Ready(Some(Ok(Message::Text(text))))
+                }
+                Some(Ok(tungstenite::Message::Binary(bytes))) => {
+                    return Poll::Ready(Some(Ok(Message::Bytes(bytes))))
+                }
+                Some(Ok(tungstenite::Message::Close(frame))) => {
+                    this.closing = true;
+                    let close_event = match frame {
+                        Some(frame) => CloseEvent {
+                            code: u16::from(frame.code).into(),
+                            reason: frame.reason.into_owned(),
+                            was_clean: true,
+                        },
+                        None => CloseEvent {
+                            code: CloseCode::NoStatus,
+                            reason: String::new(),
+                            was_clean: true,
+                        },
+                    };
+                    trace_event!(
+                        debug,
+                        parent: &this.span,
+                        code = u16::from(close_event.code),
+                        reason = %close_event.reason,
+                        "connection closed"
+                    );
+                    return Poll::Ready(Some(Err(WebSocketError::ConnectionClose(close_event))));
+                }
+                // Pings are answered by tungstenite.
+                Some(Ok(_)) => {}
+                Some(Err(Error::ConnectionClosed | Error::AlreadyClosed)) | None => {
+                    this.connection = Connection::Closed;
+                }
+                Some(Err(e)) => {
+                    this.connection = Connection::Closed;
+                    trace_event!(warn, parent: &this.span, error = %e, "connection error");
+                    this.pending
+                        .push_back(WebSocketError::ConnectionClose(CloseEvent {
+                            code: CloseCode::Abnormal,
+                            reason: e.to_string(),
+                            was_clean: false,
+                        }));
+                    let mut error = error_event(Some(e.to_string()));
+                    error.close_code = Some(CloseCode::Abnormal);
+                    return Poll::Ready(Some(Err(WebSocketError::ConnectionError(error))));
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for WebSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocket")
+            .field("state", &self.state())
+            .field("protocol", &self.protocol)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn echo() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            // The reply to the close frame is sent by tungstenite.
+            while let Some(Ok(msg)) = ws.next().await {
+                if msg.is_text() || msg.is_binary() {
+                    ws.send(msg).await.unwrap();
+                }
+            }
+        });
+
+        let mut ws = WebSocket::open(&format!("ws://{addr}")).unwrap();
+        assert_eq!(ws.state(), State::Connecting);
+        ws.send(Message::Text("test".to_string())).await.unwrap();
+        assert_eq!(ws.state(), State::Open);
+        ws.send(Message::Bytes(vec![1, 2, 3])).await.unwrap();
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            Message::Text("test".to_string())
+        );
+        assert_eq!(
+            ws.next().await.unwrap().unwrap(),
+            Message::Bytes(vec![1, 2, 3])
+        );
+
+        SinkExt::<Message>::close(&mut ws).await.unwrap();
+        let close = ws.next().await;
+        assert!(
+            matches!(
+                close,
+                Some(Err(WebSocketError::ConnectionClose(CloseEvent {
+                    was_clean: true,
+                    ..
+                })))
+            ),
+            "{:?}",
+            close
+        );
+        assert!(ws.next().await.is_none());
+        assert_eq!(ws.state(), State::Closed);
+    }
+
+    #[tokio::test]
+    async fn connection_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let mut ws = WebSocket::open(&format!("ws://{addr}")).unwrap();
+        assert!(matches!(
+            ws.next().await,
+            Some(Err(WebSocketError::Native(_)))
+        ));
+        assert!(matches!(
+            ws.next().await,
+            Some(Err(WebSocketError::ConnectionClose(CloseEvent {
+                was_clean: false,
+                ..
+            })))
+        ));
+        assert!(ws.next().await.is_none());
+        assert_eq!(ws.state(), State::Closed);
+    }
+
+    #[test]
+    fn invalid_url() {
+        assert!(WebSocket::open("not a url").is_err());
+    }
+}
@@ -0,0 +1,118 @@
+//! A typed Protocol Buffers layer over a WebSocket.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::{futures::WebSocket, protobuf::ProtobufWebSocket};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::{SinkExt, StreamExt};
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! // Any `prost::Message`, usually generated by `prost-build`.
+//! let ws = WebSocket::open("wss://example.com/feed").unwrap();
+//! let mut ws = ProtobufWebSocket::<String, u64>::new(ws);
+//!
+//! spawn_local(async move {
+//!     ws.send("BTC".to_string()).await.unwrap();
+//!     while let Some(price) = ws.next().await {
+//!         console_log!(format!("{:?}", price))
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::{futures::WebSocket, Message, WebSocketError};
+use futures_core::{ready, Stream};
+use futures_sink::Sink;
+use pin_project::pin_project;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Encodes outgoing `Tx` messages to binary messages and decodes incoming binary messages into
+/// `Rx` messages.
+///
+/// Messages which fail to decode are yielded as [`WebSocketError::ProtobufError`], and text
+/// messages as [`WebSocketError::UnexpectedMessage`], without ending the stream.
+///
+/// `S` can be any [`Message`] based socket, such as a
+/// [`ReconnectingWebSocket`](crate::websocket::reconnect::ReconnectingWebSocket).
+#[pin_project]
+pub struct ProtobufWebSocket<Tx, Rx, S = WebSocket> {
+    #[pin]
+    inner: S,
+    _marker: PhantomData<fn(Tx) -> Rx>,
+}
+
+impl<Tx, Rx, S> ProtobufWebSocket<Tx, Rx, S> {
+    /// Wraps a socket.
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            _marker: PhantomData,
+        }
+    }
+
+    /// A reference to the wrapped socket.
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Unwraps the socket.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<Tx, Rx, S> Stream for ProtobufWebSocket<Tx, Rx, S>
+where
+    Rx: prost::Message + Default,
+    S: Stream<Item = Result<Message, WebSocketError>>,
+{
+    type Item = Result<Rx, WebSocketError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let msg = ready!(self.project().inner.poll_next(cx));
+        Poll::Ready(msg.map(|msg| match msg? {
+            Message::Bytes(bytes) => {
+                Rx::decode(bytes.as_slice()).map_err(WebSocketError::ProtobufError)
+            }
+            msg => Err(WebSocketError::UnexpectedMessage(msg)),
+        }))
+    }
+}
+
+impl<Tx, Rx, S> Sink<Tx> for ProtobufWebSocket<Tx, Rx, S>
+where
+    Tx: prost::Message,
+    S: Sink<Message, Error = WebSocketError>,
+{
+    type Error = WebSocketError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_ready(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Tx) -> Result<(), Self::Error> {
+        self.project()
+            .inner
+            .start_send(Message::Bytes(item.encode_to_vec()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+impl<Tx, Rx, S> fmt::Debug for ProtobufWebSocket<Tx, Rx, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProtobufWebSocket").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,281 @@
+//! The queue connecting the WebSocket event callbacks to the `Stream` implementation.
+
+use crate::websocket::browser::OverflowPolicy;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+struct Inner<T> {
+    items: VecDeque<Slot<T>>,
+    /// Number of data items in `items`, including reserved ones.
+    data_len: usize,
+    capacity: Option<usize>,
+    policy: OverflowPolicy,
+    /// Whether an overflow error has been queued since the last item was consumed.
+    overflowed: bool,
+    next_reservation: u64,
+    waker: Option<Waker>,
+}
+
+enum Item<T> {
+    Data(T),
+    Control(T),
+}
+
+enum Slot<T> {
+    Ready(Item<T>),
+    /// A data item whose value isn't available yet. Everything behind it waits.
+    Reserved(Reservation),
+}
+
+/// A place in the queue for a data item which is produced asynchronously.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Reservation(u64);
+
+/// A single consumer queue with an optional capacity for data items.
+///
+/// Unlike `futures_channel::mpsc`, control items (errors, close events) can always be pushed,
+/// so the stream is guaranteed to observe the end of the connection even when it is full.
+pub(crate) struct MessageQueue<T> {
+    inner: Rc<RefCell<Inner<T>>>,
+}
+
+impl<T> Clone for MessageQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Rc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> MessageQueue<T> {
+    pub(crate) fn new(capacity: Option<usize>, policy: OverflowPolicy) -> Self {
+        Self {
+            inner: Rc::new(RefCell::new(Inner {
+                items: VecDeque::new(),
+                data_len: 0,
+                capacity,
+                policy,
+                overflowed: false,
+                next_reservation: 0,
+                waker: None,
+            })),
+        }
+    }
+
+    /// Pushes a data item, applying the overflow policy if the queue is at capacity.
+    ///
+    /// With [`OverflowPolicy::Error`], `overflow` creates the control item queued in place of
+    /// the dropped data.
+    pub(crate) fn push_data(&self, item: T, overflow: impl FnOnce() -> T) {
+        let mut inner = self.inner.borrow_mut();
+        if inner.make_room(overflow) {
+            inner.data_len += 1;
+            inner.push(Slot::Ready(Item::Data(item)));
+        }
+    }
+
+    /// Reserves the place of a data item which will be provided later through
+    /// [`fulfil`](Self::fulfil). Items pushed in the meantime are yielded after it.
+    ///
+    /// Returns `None` if the overflow policy discarded the item.
+    pub(crate) fn reserve(&self, overflow: impl FnOnce() -> T) -> Option<Reservation> {
+        let mut inner = self.inner.borrow_mut();
+        if !inner.make_room(overflow) {
+            return None;
+        }
+        let reservation = Reservation(inner.next_reservation);
+        inner.next_reservation += 1;
+        inner.data_len += 1;
+        inner.items.push_back(Slot::Reserved(reservation));
+        Some(reservation)
+    }
+
+    /// Provides the item for a reservation, or gives up the place with `None`.
+    pub(crate) fn fulfil(&self, reservation: Reservation, item: Option<T>) {
+        let mut inner = self.inner.borrow_mut();
+        let index = match inner
+            .items
+            .iter()
+            .position(|slot| matches!(slot, Slot::Reserved(r) if *r == reservation))
+        {
+            Some(index) => index,
+            // Discarded by `OverflowPolicy::DropOldest` in the meantime.
+            None => return,
+        };
+        match item {
+            Some(item) => inner.items[index] = Slot::Ready(Item::Data(item)),
+            None => {
+                inner.items.remove(index);
+                inner.data_len -= 1;
+            }
+        }
+        if index == 0 {
+            inner.wake();
+        }
+    }
+
+    /// Pushes a control item regardless of the capacity.
+    pub(crate) fn push_control(&self, item: T) {
+        self.inner
+            .borrow_mut()
+            .push(Slot::Ready(Item::Control(item)));
+    }
+
+    /// The number of data items waiting to be consumed.
+    pub(crate) fn len(&self) -> usize {
+        self.inner.borrow().data_len
+    }
+
+    pub(crate) fn poll_next(&self, cx: &mut Context<'_>) -> Poll<T> {
+        let mut inner = self.inner.borrow_mut();
+        match inner.items.front() {
+            Some(Slot::Ready(_)) => {}
+            Some(Slot::Reserved(_)) | None => {
+                inner.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            }
+        }
+        inner.overflowed = false;
+        match inner.items.pop_front() {
+            Some(Slot::Ready(Item::Data(item))) => {
+                inner.data_len -= 1;
+                Poll::Ready(item)
+            }
+            Some(Slot::Ready(Item::Control(item))) => Poll::Ready(item),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<T> Inner<T> {
+    /// Applies the overflow policy, returns whether a new data item may be queued.
+    fn make_room(&mut self, overflow: impl FnOnce() -> T) -> bool {
+        let full = matches!(self.capacity, Some(capacity) if self.data_len >= capacity);
+        if !full {
+            return true;
+        }
+        match self.policy {
+            OverflowPolicy::DropNewest => false,
+            OverflowPolicy::DropOldest => {
+                if let Some(index) = self
+                    .items
+                    .iter()
+                    .position(|slot| matches!(slot, Slot::Ready(Item::Data(_)) | Slot::Reserved(_)))
+                {
+                    self.items.remove(index);
+                    self.data_len -= 1;
+                    if index == 0 {
+                        self.wake();
+                    }
+                }
+                true
+            }
+            OverflowPolicy::Error => {
+                if !self.overflowed {
+                    self.overflowed = true;
+                    self.push(Slot::Ready(Item::Control(overflow())));
+                }
+                false
+            }
+        }
+    }
+
+    fn push(&mut self, slot: Slot<T>) {
+        self.items.push_back(slot);
+        self.wake();
+    }
+
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::task::noop_waker_ref;
+
+    fn drain(queue: &MessageQueue<i32>) -> Vec<i32> {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut items = Vec::new();
+        while let Poll::Ready(item) = queue.poll_next(&mut cx) {
+            items.push(item);
+        }
+        items
+    }
+
+    fn fill(queue: &MessageQueue<i32>) {
+        for i in 1..=4 {
+            queue.push_data(i, || -1);
+        }
+        queue.push_control(0);
+    }
+
+    #[test]
+    fn unbounded_keeps_everything() {
+        let queue = MessageQueue::new(None, OverflowPolicy::DropNewest);
+        fill(&queue);
+        assert_eq!(queue.len(), 4);
+        assert_eq!(drain(&queue), vec![1, 2, 3, 4, 0]);
+        assert_eq!(queue.len(), 0);
+    }
+
+    #[test]
+    fn drop_newest() {
+        let queue = MessageQueue::new(Some(2), OverflowPolicy::DropNewest);
+        fill(&queue);
+        assert_eq!(queue.len(), 2);
+        assert_eq!(drain(&queue), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let queue = MessageQueue::new(Some(2), OverflowPolicy::DropOldest);
+        fill(&queue);
+        assert_eq!(drain(&queue), vec![3, 4, 0]);
+    }
+
+    #[test]
+    fn error_is_queued_once_per_overflow() {
+        let queue = MessageQueue::new(Some(2), OverflowPolicy::Error);
+        fill(&queue);
+        assert_eq!(drain(&queue), vec![1, 2, -1, 0]);
+    }
+
+    #[test]
+    fn reservations_keep_order() {
+        let queue = MessageQueue::new(None, OverflowPolicy::DropNewest);
+        queue.push_data(1, || -1);
+        let second = queue.reserve(|| -1).unwrap();
+        let third = queue.reserve(|| -1).unwrap();
+        queue.push_data(4, || -1);
+        queue.push_control(0);
+        assert_eq!(drain(&queue), vec![1]);
+
+        queue.fulfil(third, Some(3));
+        assert!(drain(&queue).is_empty());
+        queue.fulfil(second, Some(2));
+        assert_eq!(drain(&queue), vec![2, 3, 4, 0]);
+    }
+
+    #[test]
+    fn abandoned_reservation() {
+        let queue = MessageQueue::new(Some(2), OverflowPolicy::DropOldest);
+        let first = queue.reserve(|| -1).unwrap();
+        queue.push_data(2, || -1);
+        queue.push_data(3, || -1);
+        // `first` was dropped to make room for 3.
+        queue.fulfil(first, Some(1));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(drain(&queue), vec![2, 3]);
+
+        let second = queue.reserve(|| -1).unwrap();
+        queue.push_data(5, || -1);
+        queue.fulfil(second, None);
+        assert_eq!(drain(&queue), vec![5]);
+    }
+}
@@ -0,0 +1,190 @@
+//! Binary messages which stay in the JavaScript heap.
+//!
+//! [`Message::Bytes`] copies every binary payload into wasm memory, and sending one copies it
+//! back. When the payloads are produced or consumed by JavaScript anyway, e.g. decoded by a
+//! `Blob` URL or filled from a `File`, a [`RawWebSocket`] avoids both copies by exchanging
+//! `Uint8Array`s instead.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::raw::RawMessage;
+//! # #[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+//! # use gloo_net::websocket::browser::WebSocket;
+//! # #[cfg(not(all(feature = "native", not(target_arch = "wasm32"))))]
+//! use gloo_net::websocket::futures::WebSocket;
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::{SinkExt, StreamExt};
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! let mut ws = WebSocket::open("wss://echo.websocket.org")
+//!     .unwrap()
+//!     .into_raw_stream();
+//!
+//! spawn_local(async move {
+//!     let frame = js_sys::Uint8Array::new_with_length(1 << 20);
+//!     ws.send(RawMessage::Bytes(frame)).await.unwrap();
+//!     while let Some(Ok(RawMessage::Bytes(array))) = ws.next().await {
+//!         console_log!(format!("received {} bytes", array.length()))
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::{browser::WebSocket, Message, WebSocketError};
+use futures_core::Stream;
+use futures_sink::Sink;
+use pin_project::pin_project;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A message whose binary payload is a view into the JavaScript heap.
+#[derive(Clone, Debug)]
+pub enum RawMessage {
+    /// String message
+    Text(String),
+    /// Binary message, viewing the `ArrayBuffer` it was received in
+    Bytes(js_sys::Uint8Array),
+}
+
+impl RawMessage {
+    /// Converts this into a [`Message`], copying a binary payload into wasm memory.
+    pub fn into_message(self) -> Message {
+        match self {
+            RawMessage::Text(text) => Message::Text(text),
+            RawMessage::Bytes(array) => Message::Bytes(array.to_vec()),
+        }
+    }
+}
+
+impl From<String> for RawMessage {
+    fn from(text: String) -> Self {
+        RawMessage::Text(text)
+    }
+}
+
+impl From<&str> for RawMessage {
+    fn from(text: &str) -> Self {
+        RawMessage::Text(text.to_string())
+    }
+}
+
+impl From<js_sys::Uint8Array> for RawMessage {
+    fn from(array: js_sys::Uint8Array) -> Self {
+        RawMessage::Bytes(array)
+    }
+}
+
+impl From<js_sys::ArrayBuffer> for RawMessage {
+    fn from(array_buffer: js_sys::ArrayBuffer) -> Self {
+        RawMessage::Bytes(js_sys::Uint8Array::new(&array_buffer))
+    }
+}
+
+/// A stream and sink of [`RawMessage`]s, returned by [`WebSocket::into_raw_stream`].
+///
+/// Binary messages received from now on are yielded without being copied. Sending
+/// [`RawMessage::Bytes`] hands the viewed bytes to the browser directly.
+#[pin_project]
+pub struct RawWebSocket {
+    #[pin]
+    inner: WebSocket,
+}
+
+impl RawWebSocket {
+    pub(crate) fn new(inner: WebSocket) -> Self {
+        Self { inner }
+    }
+
+    /// A reference to the wrapped socket.
+    pub fn get_ref(&self) -> &WebSocket {
+        &self.inner
+    }
+
+    /// Unwraps the socket, which copies binary messages into [`Message::Bytes`] again.
+    pub fn into_inner(self) -> WebSocket {
+        self.inner.set_raw(false);
+        self.inner
+    }
+}
+
+impl Stream for RawWebSocket {
+    type Item = Result<RawMessage, WebSocketError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_raw(cx)
+    }
+}
+
+impl Sink<RawMessage> for RawWebSocket {
+    type Error = WebSocketError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Message>::poll_ready(self.project().inner, cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: RawMessage) -> Result<(), Self::Error> {
+        match item {
+            RawMessage::Text(text) => {
+                Sink::<Message>::start_send(self.project().inner, Message::Text(text))
+            }
+            RawMessage::Bytes(array) => self.inner.send_array(&array),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Message>::poll_flush(self.project().inner, cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Sink::<Message>::poll_close(self.project().inner, cx)
+    }
+}
+
+impl fmt::Debug for RawWebSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RawWebSocket")
+            .field("state", &self.inner.state())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use wasm_bindgen_test::*;
+
+    wasm_bindgen_test_configure!(run_in_browser);
+
+    #[allow(clippy::option_env_unwrap)]
+    async fn open_echo() -> WebSocket {
+        let ws_echo_server_url =
+            option_env!("WS_ECHO_SERVER_URL").expect("Did you set WS_ECHO_SERVER_URL?");
+        let mut ws = WebSocket::open(ws_echo_server_url).unwrap();
+        // The echo server sends its info in the first message.
+        let _ = ws.next().await;
+        ws
+    }
+
+    #[wasm_bindgen_test]
+    async fn sends_and_receives_arrays() {
+        let mut ws = open_echo().await.into_raw_stream();
+
+        let array = js_sys::Uint8Array::from(&[1, 2, 3][..]);
+        ws.send(RawMessage::from(array)).await.unwrap();
+        match ws.next().await {
+            Some(Ok(RawMessage::Bytes(array))) => assert_eq!(array.to_vec(), [1, 2, 3]),
+            item => panic!("expected a binary message, got {:?}", item),
+        }
+
+        ws.send(RawMessage::from("hello")).await.unwrap();
+        assert_eq!(
+            ws.next().await.unwrap().unwrap().into_message(),
+            Message::Text("hello".to_string())
+        );
+    }
+}
@@ -0,0 +1,493 @@
+//! A [`WebSocket`] wrapper which transparently re-establishes dropped connections.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::{Message, reconnect::{Backoff, ReconnectingWebSocket}};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::{SinkExt, StreamExt};
+//! use std::time::Duration;
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! let mut ws = ReconnectingWebSocket::open("wss://echo.websocket.org")
+//!     .unwrap()
+//!     .backoff(Backoff {
+//!         initial_delay: Duration::from_millis(250),
+//!         ..Backoff::default()
+//!     })
+//!     .max_retries(Some(10));
+//! let mut events = ws.events();
+//!
+//! spawn_local(async move {
+//!     while let Some(event) = events.next().await {
+//!         console_log!(format!("connection: {:?}", event))
+//!     }
+//! });
+//!
+//! spawn_local(async move {
+//!     ws.send(Message::Text(String::from("test"))).await.unwrap();
+//!     while let Some(msg) = ws.next().await {
+//!         console_log!(format!("1. {:?}", msg))
+//!     }
+//!     console_log!("Gave up reconnecting")
+//! })
+//! # }
+//! ```
+use crate::websocket::browser::{WebSocket, WebSocketBuilder};
+use crate::websocket::events::{CloseCode, CloseEvent, ErrorEvent};
+use crate::websocket::metrics::Metrics;
+use crate::websocket::{Message, State, WebSocketError};
+use futures_channel::mpsc;
+use futures_core::{ready, Stream};
+use futures_sink::Sink;
+use gloo_timers::future::TimeoutFuture;
+use std::convert::TryFrom;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+
+/// Exponential backoff used between reconnection attempts.
+///
+/// The delay before attempt `n` (starting at `0`) is `initial_delay * multiplier^n`, capped at
+/// `max_delay`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    /// Delay before the first reconnection attempt.
+    pub initial_delay: Duration,
+    /// Upper bound for the delay between two attempts.
+    pub max_delay: Duration,
+    /// Factor the delay is multiplied by after every failed attempt.
+    pub multiplier: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2,
+        }
+    }
+}
+
+impl Backoff {
+    /// The delay to wait before the given (zero based) reconnection attempt.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.initial_delay
+            .checked_mul(self.multiplier.saturating_pow(attempt))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+}
+
+/// Connection lifecycle events emitted by a [`ReconnectingWebSocket`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum ReconnectEvent {
+    /// The connection reached the `Open` state.
+    Connected,
+    /// The connection was lost. Contains the close event, if one was received.
+    Disconnected(Option<CloseEvent>),
+    /// The connection reported an error, it is usually followed by
+    /// [`Disconnected`](Self::Disconnected).
+    Error(ErrorEvent),
+    /// A reconnection attempt has been scheduled.
+    Reconnecting {
+        /// The number of the attempt, starting at `1`.
+        attempt: u32,
+        /// How long it will be until the attempt is made.
+        delay: Duration,
+    },
+    /// The maximum number of retries has been exhausted and no further attempts will be made.
+    GaveUp,
+}
+
+type HookFuture = Pin<Box<dyn Future<Output = Result<WebSocket, WebSocketError>>>>;
+type ReconnectHook = Rc<dyn Fn(WebSocket) -> HookFuture>;
+
+enum Connection {
+    Open(WebSocket),
+    Waiting(TimeoutFuture),
+    /// A new socket, handed to the [`on_reconnect`](ReconnectingWebSocket::on_reconnect) hooks.
+    Resuming(HookFuture),
+    Closed,
+}
+
+/// A WebSocket which reconnects with exponential backoff when the connection drops.
+///
+/// This implements the same [`Sink`] and [`Stream`] interface as [`WebSocket`]. Connection
+/// errors and close events are not yielded by the stream, they are reported through
+/// [`ReconnectingWebSocket::events`] instead. Messages sent while reconnecting fail with
+/// [`WebSocketError::NotOpen`]. Once [`max_retries`](Self::max_retries) is
+/// exhausted, the stream yields the last close event as an error and then ends.
+///
+/// Dropped connections are detected by the [`Stream`] half so it must be polled, even if the
+/// application only sends messages.
+pub struct ReconnectingWebSocket {
+    builder: WebSocketBuilder,
+    backoff: Backoff,
+    max_retries: Option<u32>,
+    attempt: u32,
+    reconnects: u32,
+    connection: Connection,
+    connected: bool,
+    last_close: Option<CloseEvent>,
+    read_waker: Option<Waker>,
+    write_waker: Option<Waker>,
+    listeners: Vec<mpsc::UnboundedSender<ReconnectEvent>>,
+    hooks: Vec<ReconnectHook>,
+}
+
+impl ReconnectingWebSocket {
+    /// Establish a WebSocket connection which will be re-established when it drops.
+    ///
+    /// This fails under the same conditions as [`WebSocket::open`]. Failures of later
+    /// reconnection attempts are retried instead.
+    pub fn open(url: &str) -> Result<Self, WebSocketError> {
+        Self::from_builder(WebSocket::builder(url))
+    }
+
+    /// Establish a WebSocket connection using the given sub-protocol which will be
+    /// re-established when it drops.
+    ///
+    /// This fails under the same conditions as [`WebSocket::open_with_protocol`].
+    pub fn open_with_protocol(url: &str, protocol: &str) -> Result<Self, WebSocketError> {
+        Self::from_builder(WebSocket::builder(url).protocol(protocol))
+    }
+
+    /// Establish a WebSocket connection configured by `builder`. Every reconnection attempt
+    /// opens a new socket with the same configuration.
+    ///
+    /// This fails under the same conditions as [`WebSocketBuilder::open`].
+    pub fn from_builder(builder: WebSocketBuilder) -> Result<Self, WebSocketError> {
+        let ws = builder.open()?;
+        Ok(Self {
+            builder,
+            backoff: Backoff::default(),
+            max_retries: None,
+            attempt: 0,
+            reconnects: 0,
+            connection: Connection::Open(ws),
+            connected: false,
+            last_close: None,
+            read_waker: None,
+            write_waker: None,
+            listeners: Vec::new(),
+            hooks: Vec::new(),
+        })
+    }
+
+    /// Sets the backoff used between reconnection attempts.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the maximum number of consecutive reconnection attempts.
+    ///
+    /// `None`, the default, retries forever. The counter is reset every time a connection is
+    /// successfully opened.
+    pub fn max_retries(mut self, max_retries: Option<u32>) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Registers a hook run on every new connection before the stream resumes, e.g. to
+    /// authenticate again or to re-subscribe to topics.
+    ///
+    /// The hook is handed the new socket, which may still be connecting, and returns it once
+    /// done. Hooks run in the order they were registered, and while they do the wrapper is in the
+    /// [`State::Connecting`] state. [`ReconnectEvent::Connected`] is emitted after all of them
+    /// succeeded. If a hook fails, the socket is dropped and another reconnection attempt is
+    /// scheduled, which counts towards [`max_retries`](Self::max_retries).
+    ///
+    /// Hooks don't run for the first connection.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use gloo_net::websocket::{reconnect::ReconnectingWebSocket, Message};
+    /// use futures::SinkExt;
+    ///
+    /// # fn no_run() {
+    /// let ws = ReconnectingWebSocket::open("wss://example.com/feed")
+    ///     .unwrap()
+    ///     .on_reconnect(|mut ws| async move {
+    ///         ws.send(Message::Text("subscribe prices".to_string())).await?;
+    ///         Ok(ws)
+    ///     });
+    /// # }
+    /// ```
+    pub fn on_reconnect<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: Fn(WebSocket) -> Fut + 'static,
+        Fut: Future<Output = Result<WebSocket, WebSocketError>> + 'static,
+    {
+        self.hooks
+            .push(Rc::new(move |ws| Box::pin(hook(ws)) as HookFuture));
+        self
+    }
+
+    /// A stream of [`ReconnectEvent`]s for this connection.
+    ///
+    /// Every call returns a new stream which receives all events emitted from then on.
+    pub fn events(&mut self) -> impl Stream<Item = ReconnectEvent> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.listeners.push(sender);
+        receiver
+    }
+
+    /// The state of the current underlying connection.
+    ///
+    /// While waiting to reconnect, this is [`State::Connecting`]. After giving up, it is
+    /// [`State::Closed`].
+    pub fn state(&self) -> State {
+        match &self.connection {
+            Connection::Open(ws) => ws.state(),
+            Connection::Waiting(_) | Connection::Resuming(_) => State::Connecting,
+            Connection::Closed => State::Closed,
+        }
+    }
+
+    /// The traffic statistics of the current underlying connection, see [`WebSocket::metrics`].
+    ///
+    /// [`Metrics::reconnects`] counts the connections opened after the first one. While waiting
+    /// to reconnect, all other fields are zero.
+    pub fn metrics(&self) -> Metrics {
+        let metrics = match &self.connection {
+            Connection::Open(ws) => ws.metrics(),
+            _ => Metrics::default(),
+        };
+        Metrics {
+            reconnects: self.reconnects,
+            ..metrics
+        }
+    }
+
+    /// Closes the websocket without reconnecting.
+    ///
+    /// See [`WebSocket::close`].
+    pub fn close(
+        mut self,
+        code: Option<CloseCode>,
+        reason: Option<&str>,
+    ) -> Result<(), WebSocketError> {
+        match std::mem::replace(&mut self.connection, Connection::Closed) {
+            Connection::Open(ws) => ws.close(code, reason),
+            _ => Ok(()),
+        }
+    }
+
+    fn emit(&mut self, event: ReconnectEvent) {
+        self.listeners
+            .retain(|listener| listener.unbounded_send(event.clone()).is_ok());
+    }
+
+    fn wake_all(&mut self) {
+        if let Some(waker) = self.read_waker.take() {
+            waker.wake();
+        }
+        if let Some(waker) = self.write_waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn schedule_reconnect(&mut self) {
+        self.connected = false;
+        if matches!(self.max_retries, Some(max) if self.attempt >= max) {
+            self.connection = Connection::Closed;
+            self.emit(ReconnectEvent::GaveUp);
+        } else {
+            let delay = self.backoff.delay(self.attempt);
+            let millis = u32::try_from(delay.as_millis()).unwrap_or(u32::MAX);
+            self.connection = Connection::Waiting(TimeoutFuture::new(millis));
+            self.emit(ReconnectEvent::Reconnecting {
+                attempt: self.attempt + 1,
+                delay,
+            });
+        }
+        self.wake_all();
+    }
+
+    /// Drives the reconnection state machine until there is a socket to work with.
+    fn poll_connection(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), WebSocketError>> {
+        loop {
+            match &mut self.connection {
+                Connection::Open(ws) => {
+                    if !self.connected {
+                        if matches!(ws.state(), State::Open) {
+                            self.connected = true;
+                            self.attempt = 0;
+                            self.emit(ReconnectEvent::Connected);
+                        } else {
+                            // Polled again once the socket opens, to report it right away.
+                            ws.register_lifecycle_waker(cx.waker());
+                        }
+                    }
+                    return Poll::Ready(Ok(()));
+                }
+                Connection::Waiting(timer) => {
+                    ready!(Pin::new(timer).poll(cx));
+                    self.attempt += 1;
+                    match self.builder.open() {
+                        Ok(ws) => {
+                            self.reconnects += 1;
+                            self.connection = match self.hooks.as_slice() {
+                                [] => Connection::Open(ws),
+                                hooks => Connection::Resuming(run_hooks(hooks.to_vec(), ws)),
+                            };
+                            self.wake_all();
+                        }
+                        Err(_) => self.schedule_reconnect(),
+                    }
+                }
+                Connection::Resuming(hooks) => match ready!(hooks.as_mut().poll(cx)) {
+                    Ok(ws) => {
+                        self.connection = Connection::Open(ws);
+                        self.wake_all();
+                    }
+                    Err(_) => self.schedule_reconnect(),
+                },
+                Connection::Closed => {
+                    return Poll::Ready(Err(match self.last_close.clone() {
+                        Some(close_event) => WebSocketError::ConnectionClose(close_event),
+                        None => WebSocketError::ConnectionError(ErrorEvent {
+                            event_type: "error".to_string(),
+                            message: None,
+                            ready_state: State::Closed,
+                            close_code: None,
+                        }),
+                    }))
+                }
+            }
+        }
+    }
+
+    fn socket(&mut self) -> Pin<&mut WebSocket> {
+        match &mut self.connection {
+            Connection::Open(ws) => Pin::new(ws),
+            _ => unreachable!("socket accessed while not connected -- this is a bug"),
+        }
+    }
+}
+
+fn run_hooks(hooks: Vec<ReconnectHook>, ws: WebSocket) -> HookFuture {
+    Box::pin(async move {
+        let mut ws = ws;
+        for hook in hooks {
+            ws = hook(ws).await?;
+        }
+        Ok(ws)
+    })
+}
+
+impl Stream for ReconnectingWebSocket {
+    type Item = Result<Message, WebSocketError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if ready!(this.poll_connection(cx)).is_err() {
+                return Poll::Ready(
+                    this.last_close
+                        .take()
+                        .map(|e| Err(WebSocketError::ConnectionClose(e))),
+                );
+            }
+            this.read_waker = Some(cx.waker().clone());
+
+            match ready!(this.socket().poll_next(cx)) {
+                Some(Ok(msg)) => return Poll::Ready(Some(Ok(msg))),
+                Some(Err(WebSocketError::ConnectionError(e))) => {
+                    this.emit(ReconnectEvent::Error(e))
+                }
+                // Followed by the close of the socket, reported as `Disconnected`.
+                Some(Err(WebSocketError::ConnectTimeout | WebSocketError::KeepAliveTimeout)) => {}
+                Some(Err(WebSocketError::ConnectionClose(e))) => this.last_close = Some(e),
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => {
+                    let close_event = this.last_close.clone();
+                    this.emit(ReconnectEvent::Disconnected(close_event));
+                    this.schedule_reconnect();
+                }
+            }
+        }
+    }
+}
+
+impl<T: Into<Message>> Sink<T> for ReconnectingWebSocket {
+    type Error = WebSocketError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_connection(cx))?;
+        this.write_waker = Some(cx.waker().clone());
+        Sink::<Message>::poll_ready(this.socket(), cx)
+    }
+
+    /// Fails with [`WebSocketError::NotOpen`] if the connection dropped since
+    /// [`poll_ready`](Sink::poll_ready), e.g. as detected by the stream half of a split socket.
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        match &mut this.connection {
+            Connection::Open(ws) => Pin::new(ws).start_send(item.into()),
+            _ => Err(WebSocketError::NotOpen(this.state())),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        match this.connection {
+            Connection::Open(_) => Sink::<Message>::poll_flush(this.socket(), cx),
+            _ => Poll::Ready(Ok(())),
+        }
+    }
+
+    /// Closes the current connection and stops reconnecting.
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if let Connection::Open(_) = this.connection {
+            ready!(Sink::<Message>::poll_close(this.socket(), cx))?;
+        }
+        this.connection = Connection::Closed;
+        this.wake_all();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl fmt::Debug for ReconnectingWebSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectingWebSocket")
+            .field("builder", &self.builder)
+            .field("backoff", &self.backoff)
+            .field("max_retries", &self.max_retries)
+            .field("attempt", &self.attempt)
+            .field("hooks", &self.hooks.len())
+            .field("state", &self.state())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_max() {
+        let backoff = Backoff {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            multiplier: 2,
+        };
+        assert_eq!(backoff.delay(0), Duration::from_millis(100));
+        assert_eq!(backoff.delay(1), Duration::from_millis(200));
+        assert_eq!(backoff.delay(3), Duration::from_millis(800));
+        assert_eq!(backoff.delay(4), Duration::from_secs(1));
+        assert_eq!(backoff.delay(u32::MAX), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,810 @@
+//! A [Socket.IO](https://socket.io/docs/v4/socket-io-protocol/) client, speaking Socket.IO 5
+//! over Engine.IO 4.
+//!
+//! The client connects with a [`WebSocket`]. With the `http` feature enabled it can fall back to
+//! HTTP long-polling when the WebSocket can't be opened, see
+//! [`SocketIoOptions::polling_fallback`]. Binary attachments are not supported.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::socketio::{SocketIo, SocketIoOptions};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::StreamExt;
+//! use serde_json::json;
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! spawn_local(async {
+//!     let socket = SocketIo::connect("https://example.com", SocketIoOptions::default())
+//!         .await
+//!         .unwrap();
+//!     let mut messages = socket.on("chat message");
+//!     let reply = socket.emit_with_ack("join", vec![json!("lobby")]).await.unwrap();
+//!     console_log!(format!("joined: {:?}", reply));
+//!     while let Some(event) = messages.next().await {
+//!         console_log!(format!("{:?}", event.args));
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::{browser::WebSocket, Message, WebSocketError};
+use futures_channel::{mpsc, oneshot};
+use futures_core::Stream;
+use futures_util::{SinkExt, StreamExt};
+use gloo_timers::future::IntervalStream;
+use serde_json::Value;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use thiserror::Error as ThisError;
+use wasm_bindgen_futures::spawn_local;
+
+/// Errors of a [`SocketIo`] connection.
+#[derive(Debug, ThisError)]
+#[non_exhaustive]
+pub enum SocketIoError {
+    /// The WebSocket transport failed.
+    #[error("{0}")]
+    Socket(WebSocketError),
+    /// The polling transport failed.
+    #[cfg(feature = "http")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+    #[error("{0}")]
+    Http(crate::Error),
+    /// The server refused to connect to the namespace, with the data it sent.
+    #[error("Socket.IO connection refused: {0}")]
+    ConnectError(Value),
+    /// The connection is closed, also when the server didn't ping within the negotiated
+    /// interval.
+    #[error("Socket.IO connection closed")]
+    Closed,
+    /// The server sent a packet which doesn't follow the protocol.
+    #[error("invalid Socket.IO packet: {0}")]
+    Protocol(String),
+}
+
+/// Options of a [`SocketIo`] connection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SocketIoOptions {
+    /// The path the server is mounted at, `/socket.io/` by default.
+    pub path: String,
+    /// The namespace to connect to, `/` by default.
+    pub namespace: String,
+    /// The payload sent with the namespace `CONNECT` packet, usually credentials.
+    pub auth: Option<Value>,
+    /// Whether to use HTTP long-polling if the WebSocket can't be opened.
+    #[cfg(feature = "http")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+    pub polling_fallback: bool,
+}
+
+impl Default for SocketIoOptions {
+    fn default() -> Self {
+        Self {
+            path: "/socket.io/".to_string(),
+            namespace: "/".to_string(),
+            auth: None,
+            #[cfg(feature = "http")]
+            polling_fallback: false,
+        }
+    }
+}
+
+impl SocketIoOptions {
+    /// Sets the path the server is mounted at.
+    pub fn path(mut self, path: &str) -> Self {
+        self.path = path.to_string();
+        self
+    }
+
+    /// Sets the namespace to connect to.
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.namespace = namespace.to_string();
+        self
+    }
+
+    /// Sets the payload sent with the namespace `CONNECT` packet.
+    pub fn auth(mut self, auth: Value) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Sets whether to use HTTP long-polling if the WebSocket can't be opened.
+    #[cfg(feature = "http")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+    pub fn polling_fallback(mut self, polling_fallback: bool) -> Self {
+        self.polling_fallback = polling_fallback;
+        self
+    }
+}
+
+/// The Engine.IO endpoint for `transport`, mapping the scheme of `base` accordingly.
+fn engine_url(base: &str, path: &str, transport: &str) -> String {
+    let (base, query) = match base.split_once('?') {
+        Some((base, query)) => (base, format!("{}&", query)),
+        None => (base, String::new()),
+    };
+    let base = base.trim_end_matches('/');
+    let base = match (transport, base.split_once("://")) {
+        ("websocket", Some(("http", rest))) => format!("ws://{}", rest),
+        ("websocket", Some(("https", rest))) => format!("wss://{}", rest),
+        ("polling", Some(("ws", rest))) => format!("http://{}", rest),
+        ("polling", Some(("wss", rest))) => format!("https://{}", rest),
+        _ => base.to_string(),
+    };
+    let path = path.trim_matches('/');
+    format!("{}/{}/?{}EIO=4&transport={}", base, path, query, transport)
+}
+
+/// The parameters of the Engine.IO `open` packet.
+#[derive(Debug, PartialEq)]
+struct Handshake {
+    sid: String,
+    ping_interval: u32,
+    ping_timeout: u32,
+}
+
+impl Handshake {
+    fn parse(packet: &str) -> Result<Self, SocketIoError> {
+        let invalid = || SocketIoError::Protocol(packet.to_string());
+        let data = packet.strip_prefix('0').ok_or_else(invalid)?;
+        let data: Value = serde_json::from_str(data).map_err(|_| invalid())?;
+        let number = |key: &str| {
+            data[key]
+                .as_u64()
+                .map(|n| n.min(u32::MAX as u64) as u32)
+                .ok_or_else(invalid)
+        };
+        Ok(Self {
+            sid: data["sid"].as_str().ok_or_else(invalid)?.to_string(),
+            ping_interval: number("pingInterval")?,
+            ping_timeout: number("pingTimeout")?,
+        })
+    }
+}
+
+/// A Socket.IO packet, without binary attachments.
+#[derive(Clone, Debug, PartialEq)]
+enum Packet {
+    Connect {
+        namespace: String,
+        data: Option<Value>,
+    },
+    Disconnect {
+        namespace: String,
+    },
+    Event {
+        namespace: String,
+        id: Option<u64>,
+        data: Vec<Value>,
+    },
+    Ack {
+        namespace: String,
+        id: u64,
+        data: Vec<Value>,
+    },
+    ConnectError {
+        namespace: String,
+        data: Value,
+    },
+}
+
+impl Packet {
+    /// Encodes the packet as the payload of an Engine.IO `message` packet.
+    fn encode(&self) -> String {
+        let (ty, namespace, id, data) = match self {
+            Packet::Connect { namespace, data } => ('0', namespace, None, data.clone()),
+            Packet::Disconnect { namespace } => ('1', namespace, None, None),
+            Packet::Event {
+                namespace,
+                id,
+                data,
+            } => ('2', namespace, *id, Some(Value::from(data.clone()))),
+            Packet::Ack {
+                namespace,
+                id,
+                data,
+            } => ('3', namespace, Some(*id), Some(Value::from(data.clone()))),
+            Packet::ConnectError { namespace, data } => ('4', namespace, None, Some(data.clone())),
+        };
+        let mut out = ty.to_string();
+        if namespace != "/" {
+            out.push_str(namespace);
+            out.push(',');
+        }
+        if let Some(id) = id {
+            out.push_str(&id.to_string());
+        }
+        if let Some(data) = data {
+            out.push_str(&data.to_string());
+        }
+        out
+    }
+
+    fn parse(packet: &str) -> Result<Self, SocketIoError> {
+        let invalid = || SocketIoError::Protocol(packet.to_string());
+        let mut chars = packet.chars();
+        let ty = chars.next().ok_or_else(invalid)?;
+        let mut rest = chars.as_str();
+
+        let namespace = match rest.strip_prefix('/') {
+            Some(_) => {
+                let end = rest.find(',').unwrap_or(rest.len());
+                let namespace = rest[..end].to_string();
+                rest = rest.get(end + 1..).unwrap_or_default();
+                namespace
+            }
+            None => "/".to_string(),
+        };
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let id = match digits {
+            0 => None,
+            _ => Some(rest[..digits].parse().map_err(|_| invalid())?),
+        };
+        let data: Option<Value> = match &rest[digits..] {
+            "" => None,
+            data => Some(serde_json::from_str(data).map_err(|_| invalid())?),
+        };
+        let array = |data: Option<Value>| match data {
+            Some(Value::Array(values)) => Ok(values),
+            _ => Err(invalid()),
+        };
+
+        let packet = match ty {
+            '0' => Packet::Connect { namespace, data },
+            '1' => Packet::Disconnect { namespace },
+            '2' => Packet::Event {
+                namespace,
+                id,
+                data: array(data)?,
+            },
+            '3' => Packet::Ack {
+                namespace,
+                id: id.ok_or_else(invalid)?,
+                data: array(data)?,
+            },
+            '4' => Packet::ConnectError {
+                namespace,
+                data: data.unwrap_or(Value::Null),
+            },
+            '5' | '6' => {
+                return Err(SocketIoError::Protocol(
+                    "binary packets are not supported".to_string(),
+                ))
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(packet)
+    }
+}
+
+/// Engine.IO packets exchanged with the server, over either transport.
+struct Transport {
+    handshake: Handshake,
+    outgoing: mpsc::UnboundedSender<String>,
+    incoming: mpsc::UnboundedReceiver<Result<String, SocketIoError>>,
+}
+
+async fn websocket_transport(url: &str) -> Result<Transport, SocketIoError> {
+    let ws = WebSocket::open(url).map_err(SocketIoError::Socket)?;
+    let (mut write, mut read) = ws.split();
+    let handshake = match read.next().await {
+        Some(Ok(Message::Text(packet))) => Handshake::parse(&packet)?,
+        Some(Ok(Message::Bytes(_))) => {
+            return Err(SocketIoError::Protocol(
+                "unexpected binary message".to_string(),
+            ))
+        }
+        Some(Err(e)) => return Err(SocketIoError::Socket(e)),
+        None => return Err(SocketIoError::Closed),
+    };
+
+    let (outgoing, mut outgoing_rx) = mpsc::unbounded::<String>();
+    spawn_local(async move {
+        while let Some(packet) = outgoing_rx.next().await {
+            if write.send(Message::Text(packet)).await.is_err() {
+                break;
+            }
+        }
+        let _ = SinkExt::<Message>::close(&mut write).await;
+    });
+
+    let (incoming_tx, incoming) = mpsc::unbounded();
+    spawn_local(async move {
+        while let Some(msg) = read.next().await {
+            let packet = match msg {
+                Ok(Message::Text(packet)) => Ok(packet),
+                // Binary messages carry attachments, which are not supported.
+                Ok(Message::Bytes(_)) => continue,
+                Err(e) => Err(SocketIoError::Socket(e)),
+            };
+            if incoming_tx.unbounded_send(packet).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(Transport {
+        handshake,
+        outgoing,
+        incoming,
+    })
+}
+
+#[cfg(feature = "http")]
+async fn polling_transport(url: &str) -> Result<Transport, SocketIoError> {
+    use crate::http::browser::Request;
+
+    const SEPARATOR: char = '\x1e';
+
+    async fn get(url: &str) -> Result<String, SocketIoError> {
+        let response = Request::get(url)
+            .send()
+            .await
+            .map_err(SocketIoError::Http)?;
+        if !response.ok() {
+            return Err(SocketIoError::Closed);
+        }
+        response.text().await.map_err(SocketIoError::Http)
+    }
+
+    let payload = get(url).await?;
+    let mut packets = payload.split(SEPARATOR);
+    let handshake = Handshake::parse(packets.next().unwrap_or_default())?;
+    let url = format!("{}&sid={}", url, handshake.sid);
+
+    let (incoming_tx, incoming) = mpsc::unbounded();
+    for packet in packets {
+        let _ = incoming_tx.unbounded_send(Ok(packet.to_string()));
+    }
+
+    let (outgoing, mut outgoing_rx) = mpsc::unbounded::<String>();
+    let post_url = url.clone();
+    spawn_local(async move {
+        while let Some(packet) = outgoing_rx.next().await {
+            let mut payload = packet;
+            while let Ok(packet) = outgoing_rx.try_recv() {
+                payload.push(SEPARATOR);
+                payload.push_str(&packet);
+            }
+            let sent = match Request::post(&post_url).body(payload) {
+                Ok(request) => request.send().await.map(|response| response.ok()),
+                Err(e) => Err(e),
+            };
+            if !matches!(sent, Ok(true)) {
+                break;
+            }
+        }
+    });
+
+    spawn_local(async move {
+        loop {
+            let result = get(&url).await;
+            let payload = match result {
+                Ok(payload) => payload,
+                Err(e) => {
+                    let _ = incoming_tx.unbounded_send(Err(e));
+                    return;
+                }
+            };
+            for packet in payload.split(SEPARATOR) {
+                if incoming_tx.unbounded_send(Ok(packet.to_string())).is_err() || packet == "1" {
+                    return;
+                }
+            }
+        }
+    });
+
+    Ok(Transport {
+        handshake,
+        outgoing,
+        incoming,
+    })
+}
+
+/// An event emitted by the server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    /// The name of the event
+    pub name: String,
+    /// The arguments of the event
+    pub args: Vec<Value>,
+    /// The id to acknowledge the event with, if the server asked for an acknowledgement
+    pub ack_id: Option<u64>,
+}
+
+struct Shared {
+    outgoing: mpsc::UnboundedSender<String>,
+    namespace: String,
+    listeners: HashMap<String, Vec<mpsc::UnboundedSender<Event>>>,
+    acks: HashMap<u64, oneshot::Sender<Vec<Value>>>,
+    next_ack: u64,
+    closed: bool,
+}
+
+impl Shared {
+    fn send(&self, packet: &Packet) {
+        let _ = self
+            .outgoing
+            .unbounded_send(format!("4{}", packet.encode()));
+    }
+
+    fn close(&mut self) {
+        // Dropping the senders ends the event streams and fails pending acknowledgements.
+        self.listeners.clear();
+        self.acks.clear();
+        self.outgoing.close_channel();
+        self.closed = true;
+    }
+
+    /// Handles an Engine.IO packet received after the namespace was connected.
+    fn dispatch(&mut self, packet: &str) {
+        match packet.split_at(packet.len().min(1)) {
+            ("2", data) => {
+                let _ = self.outgoing.unbounded_send(format!("3{}", data));
+            }
+            ("1", _) => self.close(),
+            ("4", data) => match Packet::parse(data) {
+                Ok(Packet::Event {
+                    namespace,
+                    id,
+                    mut data,
+                }) if namespace == self.namespace => {
+                    let name = match data.first().and_then(Value::as_str) {
+                        Some(name) => name.to_string(),
+                        None => return,
+                    };
+                    data.remove(0);
+                    if let Some(listeners) = self.listeners.get_mut(&name) {
+                        let event = Event {
+                            name,
+                            args: data,
+                            ack_id: id,
+                        };
+                        listeners.retain(|listener| listener.unbounded_send(event.clone()).is_ok());
+                    }
+                }
+                Ok(Packet::Ack {
+                    namespace,
+                    id,
+                    data,
+                }) if namespace == self.namespace => {
+                    if let Some(ack) = self.acks.remove(&id) {
+                        let _ = ack.send(data);
+                    }
+                }
+                Ok(Packet::Disconnect { namespace }) if namespace == self.namespace => self.close(),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+/// A connection to a Socket.IO namespace.
+///
+/// The connection is driven by tasks spawned with `spawn_local`. Clones share the connection.
+#[derive(Clone)]
+pub struct SocketIo {
+    shared: Rc<RefCell<Shared>>,
+    sid: Rc<str>,
+}
+
+impl SocketIo {
+    /// Connects to the Socket.IO server at `url`, e.g. `https://example.com`, and to the
+    /// namespace of `options`.
+    pub async fn connect(url: &str, options: SocketIoOptions) -> Result<Self, SocketIoError> {
+        let transport =
+            match websocket_transport(&engine_url(url, &options.path, "websocket")).await {
+                Ok(transport) => transport,
+                #[cfg(feature = "http")]
+                Err(_) if options.polling_fallback => {
+                    polling_transport(&engine_url(url, &options.path, "polling")).await?
+                }
+                Err(e) => return Err(e),
+            };
+        let Transport {
+            handshake,
+            outgoing,
+            mut incoming,
+        } = transport;
+
+        let connect = Packet::Connect {
+            namespace: options.namespace.clone(),
+            data: options.auth.clone(),
+        };
+        let _ = outgoing.unbounded_send(format!("4{}", connect.encode()));
+        let sid = loop {
+            let packet = match incoming.next().await {
+                Some(packet) => packet?,
+                None => return Err(SocketIoError::Closed),
+            };
+            match packet.split_at(packet.len().min(1)) {
+                ("2", data) => {
+                    let _ = outgoing.unbounded_send(format!("3{}", data));
+                }
+                ("1", _) => return Err(SocketIoError::Closed),
+                ("4", data) => match Packet::parse(data)? {
+                    Packet::Connect { namespace, data } if namespace == options.namespace => {
+                        break data
+                            .as_ref()
+                            .and_then(|data| data["sid"].as_str())
+                            .unwrap_or(&handshake.sid)
+                            .into();
+                    }
+                    Packet::ConnectError { namespace, data } if namespace == options.namespace => {
+                        return Err(SocketIoError::ConnectError(data));
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        };
+
+        let shared = Rc::new(RefCell::new(Shared {
+            outgoing,
+            namespace: options.namespace,
+            listeners: HashMap::new(),
+            acks: HashMap::new(),
+            next_ack: 0,
+            closed: false,
+        }));
+        let pinged = Rc::new(Cell::new(true));
+
+        let weak = Rc::downgrade(&shared);
+        let watchdog_pinged = Rc::clone(&pinged);
+        spawn_local(async move {
+            let timeout = handshake
+                .ping_interval
+                .saturating_add(handshake.ping_timeout);
+            let mut ticks = IntervalStream::new(timeout);
+            while ticks.next().await.is_some() {
+                let shared = match weak.upgrade() {
+                    Some(shared) => shared,
+                    None => return,
+                };
+                let mut shared = shared.borrow_mut();
+                if shared.closed {
+                    return;
+                }
+                if !watchdog_pinged.replace(false) {
+                    shared.close();
+                    return;
+                }
+            }
+        });
+
+        let weak = Rc::downgrade(&shared);
+        spawn_local(async move {
+            while let Some(packet) = incoming.next().await {
+                let shared = match weak.upgrade() {
+                    Some(shared) => shared,
+                    None => return,
+                };
+                let mut shared = shared.borrow_mut();
+                match packet {
+                    Ok(packet) => {
+                        if packet.starts_with('2') {
+                            pinged.set(true);
+                        }
+                        shared.dispatch(&packet);
+                    }
+                    Err(_) => break,
+                }
+                if shared.closed {
+                    return;
+                }
+            }
+            if let Some(shared) = weak.upgrade() {
+                shared.borrow_mut().close();
+            }
+        });
+
+        Ok(Self { shared, sid })
+    }
+
+    /// The id of the connection to the namespace.
+    pub fn id(&self) -> &str {
+        &self.sid
+    }
+
+    /// Whether the connection has been closed.
+    pub fn is_closed(&self) -> bool {
+        self.shared.borrow().closed
+    }
+
+    /// Emits `event` with `args`.
+    pub fn emit(&self, event: &str, args: Vec<Value>) {
+        self.emit_packet(event, args, None);
+    }
+
+    /// Emits `event` with `args` and waits for the server to acknowledge it, resolving to the
+    /// arguments of the acknowledgement.
+    pub async fn emit_with_ack(
+        &self,
+        event: &str,
+        args: Vec<Value>,
+    ) -> Result<Vec<Value>, SocketIoError> {
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut shared = self.shared.borrow_mut();
+            if shared.closed {
+                return Err(SocketIoError::Closed);
+            }
+            let id = shared.next_ack;
+            shared.next_ack += 1;
+            shared.acks.insert(id, sender);
+            drop(shared);
+            self.emit_packet(event, args, Some(id));
+        }
+        receiver.await.map_err(|_| SocketIoError::Closed)
+    }
+
+    fn emit_packet(&self, event: &str, args: Vec<Value>, id: Option<u64>) {
+        let shared = self.shared.borrow();
+        let mut data = Vec::with_capacity(args.len() + 1);
+        data.push(Value::from(event));
+        data.extend(args);
+        shared.send(&Packet::Event {
+            namespace: shared.namespace.clone(),
+            id,
+            data,
+        });
+    }
+
+    /// Acknowledges an event the server emitted with an acknowledgement id.
+    pub fn ack(&self, event: &Event, args: Vec<Value>) {
+        if let Some(id) = event.ack_id {
+            let shared = self.shared.borrow();
+            shared.send(&Packet::Ack {
+                namespace: shared.namespace.clone(),
+                id,
+                data: args,
+            });
+        }
+    }
+
+    /// Returns a stream of the `event`s emitted by the server.
+    ///
+    /// The stream ends when the connection is closed.
+    pub fn on(&self, event: &str) -> EventStream {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut shared = self.shared.borrow_mut();
+        if !shared.closed {
+            shared
+                .listeners
+                .entry(event.to_string())
+                .or_default()
+                .push(sender);
+        }
+        EventStream { receiver }
+    }
+
+    /// Disconnects from the namespace and closes the transport.
+    pub fn disconnect(&self) {
+        let mut shared = self.shared.borrow_mut();
+        if shared.closed {
+            return;
+        }
+        let namespace = shared.namespace.clone();
+        shared.send(&Packet::Disconnect { namespace });
+        let _ = shared.outgoing.unbounded_send("1".to_string());
+        shared.close();
+    }
+}
+
+impl fmt::Debug for SocketIo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shared = self.shared.borrow();
+        f.debug_struct("SocketIo")
+            .field("id", &self.sid)
+            .field("namespace", &shared.namespace)
+            .field("closed", &shared.closed)
+            .finish()
+    }
+}
+
+/// The events of one name, returned by [`SocketIo::on`].
+#[derive(Debug)]
+pub struct EventStream {
+    receiver: mpsc::UnboundedReceiver<Event>,
+}
+
+impl Stream for EventStream {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn builds_engine_urls() {
+        assert_eq!(
+            engine_url("https://example.com/", "/socket.io/", "websocket"),
+            "wss://example.com/socket.io/?EIO=4&transport=websocket"
+        );
+        assert_eq!(
+            engine_url("ws://example.com?token=a", "custom", "polling"),
+            "http://example.com/custom/?token=a&EIO=4&transport=polling"
+        );
+    }
+
+    #[test]
+    fn parses_handshake() {
+        assert_eq!(
+            Handshake::parse(
+                r#"0{"sid":"abc","upgrades":[],"pingInterval":25000,"pingTimeout":20000,"maxPayload":1000000}"#
+            )
+            .unwrap(),
+            Handshake {
+                sid: "abc".to_string(),
+                ping_interval: 25000,
+                ping_timeout: 20000,
+            }
+        );
+        assert!(Handshake::parse("4hello").is_err());
+    }
+
+    #[test]
+    fn encodes_and_parses_packets() {
+        let packets = [
+            (
+                Packet::Connect {
+                    namespace: "/".to_string(),
+                    data: Some(json!({ "token": "t" })),
+                },
+                r#"0{"token":"t"}"#,
+            ),
+            (
+                Packet::Disconnect {
+                    namespace: "/admin".to_string(),
+                },
+                "1/admin,",
+            ),
+            (
+                Packet::Event {
+                    namespace: "/".to_string(),
+                    id: Some(12),
+                    data: vec![json!("hello"), json!(1)],
+                },
+                r#"212["hello",1]"#,
+            ),
+            (
+                Packet::Ack {
+                    namespace: "/admin".to_string(),
+                    id: 3,
+                    data: vec![],
+                },
+                "3/admin,3[]",
+            ),
+            (
+                Packet::ConnectError {
+                    namespace: "/".to_string(),
+                    data: json!({ "message": "no" }),
+                },
+                r#"4{"message":"no"}"#,
+            ),
+        ];
+        for (packet, encoded) in packets {
+            assert_eq!(packet.encode(), encoded);
+            assert_eq!(Packet::parse(encoded).unwrap(), packet);
+        }
+        assert!(Packet::parse(r#"51-["a",{"_placeholder":true,"num":0}]"#).is_err());
+        assert!(Packet::parse("2").is_err());
+        assert!(Packet::parse("9").is_err());
+    }
+}
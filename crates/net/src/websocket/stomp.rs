@@ -0,0 +1,697 @@
+//! A [STOMP 1.2](https://stomp.github.io/stomp-specification-1.2.html) client, as spoken by
+//! brokers such as RabbitMQ, ActiveMQ and Spring's message broker.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::stomp::{AckMode, ConnectOptions, StompClient};
+//! use wasm_bindgen_futures::spawn_local;
+//! use futures::StreamExt;
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! spawn_local(async {
+//!     let client = StompClient::open("wss://example.com/stomp", ConnectOptions::new("/"))
+//!         .await
+//!         .unwrap();
+//!     let mut orders = client.subscribe("/queue/orders", AckMode::Client);
+//!     client.send("/queue/orders", "hello");
+//!     while let Some(Ok(msg)) = orders.next().await {
+//!         console_log!(format!("{:?}", msg.text()));
+//!         client.ack(&msg);
+//!     }
+//! })
+//! # }
+//! ```
+use crate::websocket::{browser::WebSocket, events::CloseEvent, Message, WebSocketError};
+use futures_channel::mpsc;
+use futures_core::Stream;
+use futures_util::{SinkExt, StreamExt};
+use gloo_timers::future::IntervalStream;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use thiserror::Error as ThisError;
+use wasm_bindgen_futures::spawn_local;
+
+/// The sub-protocol offered by [`StompClient::open`].
+pub const PROTOCOL: &str = "v12.stomp";
+
+/// Errors of a [`StompClient`] and its subscriptions.
+#[derive(Debug, ThisError)]
+#[non_exhaustive]
+pub enum StompError {
+    /// The socket failed.
+    #[error("{0}")]
+    Socket(WebSocketError),
+    /// The connection was closed, with the close event if one was received.
+    #[error("STOMP connection closed")]
+    Closed(Option<CloseEvent>),
+    /// The broker sent an `ERROR` frame. The broker closes the connection afterwards.
+    #[error("STOMP error: {}", .0.header("message").unwrap_or_default())]
+    Broker(Frame),
+    /// The broker didn't send a heart-beat in time.
+    #[error("STOMP heart-beat timed out")]
+    HeartBeatTimeout,
+    /// The broker sent data which isn't a valid frame.
+    #[error("invalid STOMP frame")]
+    InvalidFrame,
+}
+
+/// A STOMP frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// The command, e.g. `SEND` or `MESSAGE`
+    pub command: String,
+    /// The headers, in order. For repeated headers only the first one counts.
+    pub headers: Vec<(String, String)>,
+    /// The body
+    pub body: Vec<u8>,
+}
+
+impl Frame {
+    /// A frame without headers and body.
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Appends a header.
+    pub fn header_value(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// The value of the first header called `name`.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Serializes the frame, including the terminating NUL.
+    ///
+    /// A `content-length` header is added if the frame has a body and doesn't have one.
+    pub fn encode(&self) -> Vec<u8> {
+        // CONNECT and CONNECTED headers are not escaped, for compatibility with STOMP 1.0.
+        let escape = self.command != "CONNECT" && self.command != "CONNECTED";
+        let mut out = Vec::with_capacity(self.command.len() + self.body.len() + 64);
+        out.extend_from_slice(self.command.as_bytes());
+        out.push(b'\n');
+        for (name, value) in &self.headers {
+            push_header_part(&mut out, name, escape);
+            out.push(b':');
+            push_header_part(&mut out, value, escape);
+            out.push(b'\n');
+        }
+        if !self.body.is_empty() && self.header("content-length").is_none() {
+            out.extend_from_slice(format!("content-length:{}\n", self.body.len()).as_bytes());
+        }
+        out.push(b'\n');
+        out.extend_from_slice(&self.body);
+        out.push(0);
+        out
+    }
+
+    /// Parses the frames in a WebSocket message, skipping heart-beats.
+    pub fn decode_all(mut data: &[u8]) -> Result<Vec<Frame>, StompError> {
+        let mut frames = Vec::new();
+        loop {
+            while let [b'\r' | b'\n', rest @ ..] = data {
+                data = rest;
+            }
+            if data.is_empty() {
+                return Ok(frames);
+            }
+            let (frame, rest) = Self::decode(data)?;
+            frames.push(frame);
+            data = rest;
+        }
+    }
+
+    fn decode(data: &[u8]) -> Result<(Frame, &[u8]), StompError> {
+        let header_end = find(data, b"\n\n")
+            .map(|i| (i, i + 2))
+            .into_iter()
+            .chain(find(data, b"\r\n\r\n").map(|i| (i, i + 4)))
+            .min()
+            .ok_or(StompError::InvalidFrame)?;
+        let head =
+            std::str::from_utf8(&data[..header_end.0]).map_err(|_| StompError::InvalidFrame)?;
+        let mut lines = head.lines();
+        let command = lines.next().ok_or(StompError::InvalidFrame)?.to_string();
+        let unescape = command != "CONNECT" && command != "CONNECTED";
+        let mut headers = Vec::new();
+        for line in lines {
+            let (name, value) = line.split_once(':').ok_or(StompError::InvalidFrame)?;
+            if unescape {
+                headers.push((unescape_header(name)?, unescape_header(value)?));
+            } else {
+                headers.push((name.to_string(), value.to_string()));
+            }
+        }
+        let mut frame = Frame {
+            command,
+            headers,
+            body: Vec::new(),
+        };
+
+        let rest = &data[header_end.1..];
+        let body_len = match frame.header("content-length") {
+            Some(len) => len.parse().map_err(|_| StompError::InvalidFrame)?,
+            None => rest
+                .iter()
+                .position(|&b| b == 0)
+                .ok_or(StompError::InvalidFrame)?,
+        };
+        if rest.get(body_len) != Some(&0) {
+            return Err(StompError::InvalidFrame);
+        }
+        frame.body = rest[..body_len].to_vec();
+        Ok((frame, &rest[body_len + 1..]))
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+fn push_header_part(out: &mut Vec<u8>, s: &str, escape: bool) {
+    if !escape {
+        out.extend_from_slice(s.as_bytes());
+        return;
+    }
+    for c in s.chars() {
+        match c {
+            '\\' => out.extend_from_slice(b"\\\\"),
+            '\r' => out.extend_from_slice(b"\\r"),
+            '\n' => out.extend_from_slice(b"\\n"),
+            ':' => out.extend_from_slice(b"\\c"),
+            c => out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+}
+
+fn unescape_header(s: &str) -> Result<String, StompError> {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next() {
+            Some('\\') => '\\',
+            Some('r') => '\r',
+            Some('n') => '\n',
+            Some('c') => ':',
+            _ => return Err(StompError::InvalidFrame),
+        });
+    }
+    Ok(out)
+}
+
+/// Options of the `CONNECT` frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectOptions {
+    /// The virtual host to connect to
+    pub host: String,
+    /// The user to log in as
+    pub login: Option<String>,
+    /// The password of the user
+    pub passcode: Option<String>,
+    /// The heart-beats the client can send and wants to receive, in milliseconds. Zero
+    /// disables the direction.
+    pub heart_beat: (u32, u32),
+}
+
+impl ConnectOptions {
+    /// Options connecting anonymously to `host`, with 10 second heart-beats.
+    pub fn new(host: &str) -> Self {
+        Self {
+            host: host.to_string(),
+            login: None,
+            passcode: None,
+            heart_beat: (10_000, 10_000),
+        }
+    }
+
+    /// Sets the credentials.
+    pub fn credentials(mut self, login: &str, passcode: &str) -> Self {
+        self.login = Some(login.to_string());
+        self.passcode = Some(passcode.to_string());
+        self
+    }
+
+    /// Sets the heart-beat intervals, in milliseconds.
+    pub fn heart_beat(mut self, send: u32, receive: u32) -> Self {
+        self.heart_beat = (send, receive);
+        self
+    }
+
+    fn frame(&self) -> Frame {
+        let mut frame = Frame::new("CONNECT")
+            .header_value("accept-version", "1.2")
+            .header_value("host", &self.host)
+            .header_value(
+                "heart-beat",
+                &format!("{},{}", self.heart_beat.0, self.heart_beat.1),
+            );
+        if let Some(login) = &self.login {
+            frame = frame.header_value("login", login);
+        }
+        if let Some(passcode) = &self.passcode {
+            frame = frame.header_value("passcode", passcode);
+        }
+        frame
+    }
+}
+
+/// Negotiates the heart-beat intervals from what the client offered and the `heart-beat`
+/// header of the `CONNECTED` frame, returning the `(send, receive)` intervals.
+fn negotiate_heart_beat(client: (u32, u32), server: Option<&str>) -> (u32, u32) {
+    let server = server
+        .and_then(|h| h.split_once(','))
+        .and_then(|(sx, sy)| Some((sx.trim().parse().ok()?, sy.trim().parse().ok()?)))
+        .unwrap_or((0, 0));
+    let interval = |ours: u32, theirs: u32| {
+        if ours == 0 || theirs == 0 {
+            0
+        } else {
+            ours.max(theirs)
+        }
+    };
+    (interval(client.0, server.1), interval(client.1, server.0))
+}
+
+/// How received messages are acknowledged.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum AckMode {
+    /// The broker considers messages acknowledged as soon as they are sent.
+    #[default]
+    Auto,
+    /// [`StompClient::ack`] acknowledges the message and all previous ones.
+    Client,
+    /// [`StompClient::ack`] acknowledges only the given message.
+    ClientIndividual,
+}
+
+impl AckMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            AckMode::Auto => "auto",
+            AckMode::Client => "client",
+            AckMode::ClientIndividual => "client-individual",
+        }
+    }
+}
+
+/// A message received through a subscription.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StompMessage {
+    /// The `MESSAGE` frame
+    pub frame: Frame,
+}
+
+impl StompMessage {
+    /// The destination the message was sent to.
+    pub fn destination(&self) -> Option<&str> {
+        self.frame.header("destination")
+    }
+
+    /// The body of the message.
+    pub fn body(&self) -> &[u8] {
+        &self.frame.body
+    }
+
+    /// The body as text, if it is valid UTF-8.
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.frame.body).ok()
+    }
+}
+
+type Subscriber = mpsc::UnboundedSender<Result<StompMessage, StompError>>;
+
+struct Shared {
+    outgoing: mpsc::UnboundedSender<Message>,
+    subscriptions: HashMap<String, Subscriber>,
+    next_id: u64,
+    closed: bool,
+}
+
+impl Shared {
+    fn send_frame(&self, frame: &Frame) {
+        let _ = self.outgoing.unbounded_send(frame_message(frame));
+    }
+
+    fn fail(&mut self, error: impl Fn() -> StompError) {
+        for (_, subscriber) in self.subscriptions.drain() {
+            let _ = subscriber.unbounded_send(Err(error()));
+        }
+        self.closed = true;
+    }
+}
+
+/// Frames are sent as text messages when they are valid UTF-8, which is what most brokers
+/// expect.
+fn frame_message(frame: &Frame) -> Message {
+    match String::from_utf8(frame.encode()) {
+        Ok(text) => Message::Text(text),
+        Err(e) => Message::Bytes(e.into_bytes()),
+    }
+}
+
+fn read_frames(msg: Option<Result<Message, WebSocketError>>) -> Result<Vec<Frame>, StompError> {
+    match msg {
+        Some(Ok(Message::Text(text))) => Frame::decode_all(text.as_bytes()),
+        Some(Ok(Message::Bytes(bytes))) => Frame::decode_all(&bytes),
+        Some(Err(WebSocketError::ConnectionClose(e))) => Err(StompError::Closed(Some(e))),
+        Some(Err(e)) => Err(StompError::Socket(e)),
+        None => Err(StompError::Closed(None)),
+    }
+}
+
+/// A STOMP client over a WebSocket.
+///
+/// The connection is driven by tasks spawned with `spawn_local`. Clones share the connection.
+#[derive(Clone)]
+pub struct StompClient {
+    shared: Rc<RefCell<Shared>>,
+    heart_beat: (u32, u32),
+}
+
+impl StompClient {
+    /// Opens a socket offering the [`PROTOCOL`] and connects to the broker.
+    pub async fn open(url: &str, options: ConnectOptions) -> Result<Self, StompError> {
+        let ws = WebSocket::open_with_protocols(url, &[PROTOCOL, "v11.stomp", "v10.stomp"])
+            .map_err(StompError::Socket)?;
+        Self::connect(ws, options).await
+    }
+
+    /// Connects to the broker over an already open socket, waiting for the `CONNECTED` frame.
+    pub async fn connect(ws: WebSocket, options: ConnectOptions) -> Result<Self, StompError> {
+        let (mut write, mut read) = ws.split();
+        write
+            .send(frame_message(&options.frame()))
+            .await
+            .map_err(StompError::Socket)?;
+        let connected = loop {
+            let mut frames = read_frames(read.next().await)?.into_iter();
+            if let Some(frame) = frames.next() {
+                match frame.command.as_str() {
+                    "CONNECTED" => break frame,
+                    "ERROR" => return Err(StompError::Broker(frame)),
+                    _ => return Err(StompError::InvalidFrame),
+                }
+            }
+        };
+        let heart_beat = negotiate_heart_beat(options.heart_beat, connected.header("heart-beat"));
+
+        let (outgoing, mut outgoing_rx) = mpsc::unbounded();
+        spawn_local(async move {
+            while let Some(msg) = outgoing_rx.next().await {
+                if write.send(msg).await.is_err() {
+                    break;
+                }
+            }
+            let _ = SinkExt::<Message>::close(&mut write).await;
+        });
+
+        let shared = Rc::new(RefCell::new(Shared {
+            outgoing,
+            subscriptions: HashMap::new(),
+            next_id: 0,
+            closed: false,
+        }));
+        let received = Rc::new(Cell::new(true));
+
+        if heart_beat.0 > 0 {
+            let weak = Rc::downgrade(&shared);
+            spawn_local(async move {
+                let mut ticks = IntervalStream::new(heart_beat.0);
+                while ticks.next().await.is_some() {
+                    match weak.upgrade() {
+                        Some(shared) if !shared.borrow().closed => {
+                            let _ = shared
+                                .borrow()
+                                .outgoing
+                                .unbounded_send(Message::Text("\n".to_string()));
+                        }
+                        _ => return,
+                    }
+                }
+            });
+        }
+        if heart_beat.1 > 0 {
+            let weak = Rc::downgrade(&shared);
+            let received = Rc::clone(&received);
+            spawn_local(async move {
+                // Allow the broker some slack, as recommended by the specification.
+                let mut ticks = IntervalStream::new(heart_beat.1.saturating_mul(2));
+                while ticks.next().await.is_some() {
+                    let shared = match weak.upgrade() {
+                        Some(shared) => shared,
+                        None => return,
+                    };
+                    let mut shared = shared.borrow_mut();
+                    if shared.closed {
+                        return;
+                    }
+                    if !received.replace(false) {
+                        shared.fail(|| StompError::HeartBeatTimeout);
+                        shared.outgoing.close_channel();
+                        return;
+                    }
+                }
+            });
+        }
+
+        let weak = Rc::downgrade(&shared);
+        spawn_local(async move {
+            let mut error = None;
+            while let Some(msg) = read.next().await {
+                received.set(true);
+                let shared = match weak.upgrade() {
+                    Some(shared) => shared,
+                    None => return,
+                };
+                let mut shared = shared.borrow_mut();
+                let frames = match read_frames(Some(msg)) {
+                    Ok(frames) => frames,
+                    Err(e) => {
+                        error = Some(e);
+                        continue;
+                    }
+                };
+                for frame in frames {
+                    match frame.command.as_str() {
+                        "MESSAGE" => {
+                            let subscriber = frame
+                                .header("subscription")
+                                .and_then(|id| shared.subscriptions.get(id));
+                            if let Some(subscriber) = subscriber {
+                                let _ = subscriber.unbounded_send(Ok(StompMessage { frame }));
+                            }
+                        }
+                        "ERROR" => {
+                            shared.fail(|| StompError::Broker(frame.clone()));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            if let Some(shared) = weak.upgrade() {
+                let mut shared = shared.borrow_mut();
+                let close_event = match error {
+                    Some(StompError::Closed(e)) => e,
+                    _ => None,
+                };
+                shared.fail(|| StompError::Closed(close_event.clone()));
+            }
+        });
+
+        Ok(Self { shared, heart_beat })
+    }
+
+    /// The negotiated `(send, receive)` heart-beat intervals in milliseconds, zero if disabled.
+    pub fn heart_beat(&self) -> (u32, u32) {
+        self.heart_beat
+    }
+
+    /// Subscribes to `destination`.
+    ///
+    /// Dropping the returned stream unsubscribes.
+    pub fn subscribe(&self, destination: &str, ack: AckMode) -> StompSubscription {
+        let (sender, receiver) = mpsc::unbounded();
+        let mut shared = self.shared.borrow_mut();
+        let id = shared.next_id.to_string();
+        shared.next_id += 1;
+
+        if shared.closed {
+            let _ = sender.unbounded_send(Err(StompError::Closed(None)));
+        } else {
+            shared.send_frame(
+                &Frame::new("SUBSCRIBE")
+                    .header_value("id", &id)
+                    .header_value("destination", destination)
+                    .header_value("ack", ack.as_str()),
+            );
+            shared.subscriptions.insert(id.clone(), sender);
+        }
+
+        StompSubscription {
+            id,
+            receiver,
+            shared: Rc::clone(&self.shared),
+        }
+    }
+
+    /// Sends `body` to `destination`.
+    pub fn send(&self, destination: &str, body: impl Into<Vec<u8>>) {
+        self.send_frame(
+            Frame::new("SEND")
+                .header_value("destination", destination)
+                .body(body),
+        )
+    }
+
+    /// Sends an arbitrary frame, e.g. a `SEND` frame with custom headers or a transaction.
+    pub fn send_frame(&self, frame: Frame) {
+        self.shared.borrow().send_frame(&frame)
+    }
+
+    /// Acknowledges a message received with [`AckMode::Client`] or
+    /// [`AckMode::ClientIndividual`].
+    pub fn ack(&self, message: &StompMessage) {
+        self.acknowledge("ACK", message)
+    }
+
+    /// Tells the broker a message received with [`AckMode::Client`] or
+    /// [`AckMode::ClientIndividual`] was not consumed.
+    pub fn nack(&self, message: &StompMessage) {
+        self.acknowledge("NACK", message)
+    }
+
+    fn acknowledge(&self, command: &str, message: &StompMessage) {
+        if let Some(id) = message.frame.header("ack") {
+            self.send_frame(Frame::new(command).header_value("id", id));
+        }
+    }
+
+    /// Sends a `DISCONNECT` frame and closes the socket once it has been sent.
+    pub fn disconnect(&self) {
+        let mut shared = self.shared.borrow_mut();
+        shared.send_frame(&Frame::new("DISCONNECT"));
+        shared.outgoing.close_channel();
+        shared.closed = true;
+    }
+}
+
+impl fmt::Debug for StompClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let shared = self.shared.borrow();
+        f.debug_struct("StompClient")
+            .field("subscriptions", &shared.subscriptions.len())
+            .field("heart_beat", &self.heart_beat)
+            .field("closed", &shared.closed)
+            .finish()
+    }
+}
+
+/// The messages of a subscription created with [`StompClient::subscribe`].
+pub struct StompSubscription {
+    id: String,
+    receiver: mpsc::UnboundedReceiver<Result<StompMessage, StompError>>,
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl StompSubscription {
+    /// The id of the subscription.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl Stream for StompSubscription {
+    type Item = Result<StompMessage, StompError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.receiver).poll_next(cx)
+    }
+}
+
+impl Drop for StompSubscription {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        if shared.subscriptions.remove(&self.id).is_some() {
+            shared.send_frame(&Frame::new("UNSUBSCRIBE").header_value("id", &self.id));
+        }
+    }
+}
+
+impl fmt::Debug for StompSubscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StompSubscription")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_and_decodes_frames() {
+        let frame = Frame::new("SEND")
+            .header_value("destination", "/queue/a:b")
+            .body("hello\0world");
+        let encoded = frame.encode();
+        assert!(encoded.starts_with(b"SEND\ndestination:/queue/a\\cb\ncontent-length:11\n\n"));
+
+        let mut data = b"\n".to_vec();
+        data.extend_from_slice(&encoded);
+        data.extend_from_slice(b"\r\n");
+        data.extend_from_slice(b"MESSAGE\r\nsubscription:0\r\n\r\nhi\0");
+        let frames = Frame::decode_all(&data).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].header("destination"), Some("/queue/a:b"));
+        assert_eq!(frames[0].body, b"hello\0world");
+        assert_eq!(frames[1].command, "MESSAGE");
+        assert_eq!(frames[1].body, b"hi");
+
+        assert!(Frame::decode_all(b"\n\n").unwrap().is_empty());
+        assert!(Frame::decode_all(b"SEND\n\nunterminated").is_err());
+        assert!(Frame::decode_all(b"SEND\nbad:\\x\n\n\0").is_err());
+    }
+
+    #[test]
+    fn negotiates_heart_beats() {
+        assert_eq!(
+            negotiate_heart_beat((1000, 2000), Some("500,5000")),
+            (5000, 2000)
+        );
+        assert_eq!(negotiate_heart_beat((1000, 2000), Some("0,0")), (0, 0));
+        assert_eq!(
+            negotiate_heart_beat((0, 2000), Some("3000,1000")),
+            (0, 3000)
+        );
+        assert_eq!(negotiate_heart_beat((1000, 1000), None), (0, 0));
+    }
+}
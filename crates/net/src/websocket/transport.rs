@@ -0,0 +1,162 @@
+//! A trait for code which works with any WebSocket-like connection.
+//!
+//! Services written against [`WebSocketTransport`] instead of a concrete socket can be handed a
+//! [`WebSocket`](crate::websocket::futures::WebSocket) in production and a fake transport in
+//! unit tests, without a browser or a server.
+//!
+//! # Example
+//!
+//! ```rust
+//! use gloo_net::websocket::{transport::WebSocketTransport, Message, WebSocketError};
+//! use futures::{SinkExt, StreamExt};
+//!
+//! /// Sends a greeting and returns the first text reply.
+//! async fn greet<T: WebSocketTransport + Unpin>(
+//!     transport: &mut T,
+//! ) -> Result<Option<String>, WebSocketError> {
+//!     transport.send(Message::Text("hello".to_string())).await?;
+//!     while let Some(msg) = transport.next().await {
+//!         if let Message::Text(text) = msg? {
+//!             return Ok(Some(text));
+//!         }
+//!     }
+//!     Ok(None)
+//! }
+//! ```
+use crate::websocket::{Message, State, WebSocketError};
+use futures_core::Stream;
+use futures_sink::Sink;
+
+/// A connection exchanging [`Message`]s, implemented by the sockets of this crate.
+///
+/// Implementing it for a fake only requires the [`Stream`] and [`Sink`] implementations and
+/// [`state`](Self::state).
+pub trait WebSocketTransport:
+    Stream<Item = Result<Message, WebSocketError>> + Sink<Message, Error = WebSocketError>
+{
+    /// The current state of the connection.
+    fn state(&self) -> State;
+}
+
+impl<T> WebSocketTransport for Box<T>
+where
+    T: WebSocketTransport + Unpin + ?Sized,
+{
+    fn state(&self) -> State {
+        (**self).state()
+    }
+}
+
+impl WebSocketTransport for crate::websocket::futures::WebSocket {
+    fn state(&self) -> State {
+        self.state()
+    }
+}
+
+#[cfg(all(feature = "native", not(target_arch = "wasm32")))]
+impl WebSocketTransport for crate::websocket::browser::WebSocket {
+    fn state(&self) -> State {
+        self.state()
+    }
+}
+
+impl WebSocketTransport for crate::websocket::reconnect::ReconnectingWebSocket {
+    fn state(&self) -> State {
+        self.state()
+    }
+}
+
+impl<S: WebSocketTransport> WebSocketTransport for crate::websocket::chunked::ChunkedWebSocket<S> {
+    fn state(&self) -> State {
+        self.get_ref().state()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::channel::mpsc;
+    use futures::executor::block_on;
+    use futures::{SinkExt, StreamExt};
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+
+    /// A transport answering every message with its echo.
+    struct Echo {
+        sender: mpsc::UnboundedSender<Message>,
+        receiver: mpsc::UnboundedReceiver<Message>,
+    }
+
+    impl Echo {
+        fn new() -> Self {
+            let (sender, receiver) = mpsc::unbounded();
+            Self { sender, receiver }
+        }
+    }
+
+    impl Stream for Echo {
+        type Item = Result<Message, WebSocketError>;
+
+        fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            self.receiver.poll_next_unpin(cx).map(|msg| msg.map(Ok))
+        }
+    }
+
+    impl Sink<Message> for Echo {
+        type Error = WebSocketError;
+
+        fn poll_ready(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn start_send(self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+            self.sender.unbounded_send(item).unwrap();
+            Ok(())
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            self.sender.close_channel();
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl WebSocketTransport for Echo {
+        fn state(&self) -> State {
+            if self.sender.is_closed() {
+                State::Closed
+            } else {
+                State::Open
+            }
+        }
+    }
+
+    async fn round_trip<T: WebSocketTransport + Unpin>(transport: &mut T) -> Option<Message> {
+        transport
+            .send(Message::Text("ping".to_string()))
+            .await
+            .ok()?;
+        transport.next().await?.ok()
+    }
+
+    #[test]
+    fn generic_over_transports() {
+        let mut echo = Echo::new();
+        assert_eq!(
+            block_on(round_trip(&mut echo)),
+            Some(Message::Text("ping".to_string()))
+        );
+
+        let mut boxed: Box<dyn WebSocketTransport + Unpin> = Box::new(Echo::new());
+        assert_eq!(boxed.state(), State::Open);
+        assert_eq!(
+            block_on(round_trip(&mut boxed)),
+            Some(Message::Text("ping".to_string()))
+        );
+        block_on(boxed.close()).unwrap();
+        assert_eq!(boxed.state(), State::Closed);
+    }
+}
@@ -0,0 +1,197 @@
+//! Running WebSockets inside a web worker.
+//!
+//! [`WebSocket`] doesn't depend on `window` and can be opened in dedicated and shared workers
+//! directly. [`WebSocketWorker`] is a ready made [`gloo_worker::Worker`] which owns sockets on
+//! behalf of the bridges connected to it, so received messages are parsed off the main thread.
+//!
+//! # Example
+//!
+//! In the worker's entry point:
+//!
+//! ```rust
+//! use gloo_net::websocket::worker::WebSocketWorker;
+//! use gloo_worker::Registrable;
+//!
+//! # fn no_run() {
+//! WebSocketWorker::registrar().register();
+//! # }
+//! ```
+//!
+//! On the main thread:
+//!
+//! ```rust
+//! use gloo_net::websocket::worker::{WebSocketWorker, WorkerRequest, WorkerResponse};
+//! use gloo_net::websocket::Message;
+//! use gloo_worker::Spawnable;
+//!
+//! # macro_rules! console_log {
+//! #    ($($expr:expr),*) => {{}};
+//! # }
+//! # fn no_run() {
+//! let bridge = WebSocketWorker::spawner()
+//!     .callback(|response| match response {
+//!         WorkerResponse::Message(msg) => console_log!(format!("{:?}", msg)),
+//!         _ => {}
+//!     })
+//!     .spawn("/websocket_worker.js");
+//!
+//! bridge.send(WorkerRequest::Open {
+//!     url: "wss://echo.websocket.org".to_string(),
+//!     protocols: Vec::new(),
+//! });
+//! bridge.send(WorkerRequest::Send(Message::Text("hello".to_string())));
+//! # }
+//! ```
+use crate::websocket::browser::{CloseHandle, WebSocket};
+use crate::websocket::events::{CloseCode, CloseEvent};
+use crate::websocket::{Message, WebSocketError};
+use futures_channel::mpsc;
+use futures_util::{SinkExt, StreamExt};
+use gloo_worker::{HandlerId, Worker, WorkerScope};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use wasm_bindgen_futures::spawn_local;
+
+/// A request sent to a [`WebSocketWorker`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkerRequest {
+    /// Opens a socket for this bridge, closing the previous one.
+    Open {
+        /// The URL to connect to
+        url: String,
+        /// The sub-protocols to offer, in order of preference
+        protocols: Vec<String>,
+    },
+    /// Sends a message through the bridge's socket.
+    Send(Message),
+    /// Closes the bridge's socket.
+    Close {
+        /// Close code
+        code: Option<CloseCode>,
+        /// Close reason
+        reason: Option<String>,
+    },
+}
+
+/// A response sent by a [`WebSocketWorker`] to the bridge owning the socket.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum WorkerResponse {
+    /// The connection is open.
+    Open,
+    /// A message was received.
+    Message(Message),
+    /// The socket failed, or couldn't be opened.
+    Error(String),
+    /// The connection was closed. No more responses follow for this socket.
+    Close(CloseEvent),
+}
+
+struct Socket {
+    sender: mpsc::UnboundedSender<Message>,
+    close_handle: CloseHandle,
+}
+
+/// A worker pumping messages between WebSockets and the bridges connected to it.
+///
+/// Every bridge can own one socket, which is closed when the bridge is dropped.
+pub struct WebSocketWorker {
+    sockets: HashMap<HandlerId, Socket>,
+}
+
+impl WebSocketWorker {
+    fn open(&mut self, scope: &WorkerScope<Self>, id: HandlerId, url: &str, protocols: &[String]) {
+        self.close(id, None, None);
+
+        let ws = match WebSocket::open_with_protocols(url, protocols) {
+            Ok(ws) => ws,
+            Err(e) => {
+                scope.respond(id, WorkerResponse::Error(e.to_string()));
+                return;
+            }
+        };
+        let close_handle = ws.close_handle();
+        let connected = ws.connected();
+        let (mut write, mut read) = ws.split();
+        let (sender, mut receiver) = mpsc::unbounded();
+
+        spawn_local(async move {
+            while let Some(msg) = receiver.next().await {
+                if write.send(msg).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let scope = scope.clone();
+        spawn_local(async move {
+            if connected.await.is_ok() {
+                scope.respond(id, WorkerResponse::Open);
+            }
+            while let Some(msg) = read.next().await {
+                let response = match msg {
+                    Ok(msg) => WorkerResponse::Message(msg),
+                    Err(WebSocketError::ConnectionClose(e)) => WorkerResponse::Close(e),
+                    Err(e) => WorkerResponse::Error(e.to_string()),
+                };
+                scope.respond(id, response);
+            }
+        });
+
+        self.sockets.insert(
+            id,
+            Socket {
+                sender,
+                close_handle,
+            },
+        );
+    }
+
+    fn close(&mut self, id: HandlerId, code: Option<CloseCode>, reason: Option<&str>) {
+        if let Some(socket) = self.sockets.remove(&id) {
+            let _ = socket.close_handle.close(code, reason);
+        }
+    }
+}
+
+impl Worker for WebSocketWorker {
+    type Message = ();
+    type Input = WorkerRequest;
+    type Output = WorkerResponse;
+
+    fn create(_scope: &WorkerScope<Self>) -> Self {
+        Self {
+            sockets: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, _scope: &WorkerScope<Self>, _msg: Self::Message) {}
+
+    fn received(&mut self, scope: &WorkerScope<Self>, msg: Self::Input, id: HandlerId) {
+        match msg {
+            WorkerRequest::Open { url, protocols } => self.open(scope, id, &url, &protocols),
+            WorkerRequest::Send(msg) => match self.sockets.get(&id) {
+                Some(socket) => {
+                    let _ = socket.sender.unbounded_send(msg);
+                }
+                None => scope.respond(
+                    id,
+                    WorkerResponse::Error("no WebSocket is open for this bridge".to_string()),
+                ),
+            },
+            WorkerRequest::Close { code, reason } => self.close(id, code, reason.as_deref()),
+        }
+    }
+
+    fn disconnected(&mut self, _scope: &WorkerScope<Self>, id: HandlerId) {
+        self.close(id, None, None);
+    }
+}
+
+impl fmt::Debug for WebSocketWorker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketWorker")
+            .field("sockets", &self.sockets.len())
+            .finish()
+    }
+}
@@ -1,10 +1,17 @@
-use gloo_net::http::Request;
+#![cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+use futures::StreamExt;
+use gloo_net::http::{
+    cookies, ApiError, Auth, Client, Multipart, Next, RateLimit, Request, RequestPriority, Response,
+};
+use gloo_net::Error;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use wasm_bindgen_test::*;
 
 wasm_bindgen_test_configure!(run_in_browser);
 
+#[allow(clippy::option_env_unwrap)]
 static HTTPBIN_URL: Lazy<&'static str> =
     Lazy::new(|| option_env!("HTTPBIN_URL").expect("Did you set HTTPBIN_URL?"));
 
@@ -42,6 +49,50 @@ async fn auth_valid_bearer() {
     assert_eq!(resp.status(), 200);
 }
 
+#[wasm_bindgen_test]
+async fn middleware_chain() {
+    let resp = Request::get(&format!("{}/bearer", *HTTPBIN_URL))
+        .middleware(|request: Request, next: Next| async move {
+            request.headers().set("Authorization", "Bearer token");
+            next.run(request).await
+        })
+        .middleware(|request: Request, next: Next| async move {
+            let resp = next.run(request).await?;
+            assert_eq!(resp.status(), 200);
+            Ok(resp)
+        })
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(resp.status(), 200);
+}
+
+#[wasm_bindgen_test]
+async fn client_defaults() {
+    let client = Client::builder()
+        .base_url(*HTTPBIN_URL)
+        .default_header("Authorization", "Bearer token")
+        .build();
+    let resp = client.get("/bearer").send().await.unwrap();
+
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.url(), format!("{}/bearer", *HTTPBIN_URL));
+}
+
+#[wasm_bindgen_test]
+async fn client_get_cached() {
+    let client = Client::builder().base_url(*HTTPBIN_URL).build();
+    let first = client.get_cached("/etag/v1").await.unwrap();
+    assert_eq!(first.status(), 200);
+    let first = first.text().await.unwrap();
+
+    // httpbin responds with `304 Not Modified` to the `If-None-Match: v1` header.
+    let second = client.get_cached("/etag/v1").await.unwrap();
+    assert_eq!(second.status(), 200);
+    assert_eq!(second.text().await.unwrap(), first);
+}
+
 #[wasm_bindgen_test]
 async fn gzip_response() {
     #[derive(Deserialize, Debug)]
@@ -118,6 +169,222 @@ async fn fetch_binary() {
     assert_eq!(json.data, ""); // default is empty string
 }
 
+#[cfg(feature = "file")]
+#[wasm_bindgen_test]
+async fn fetch_object_url() {
+    let resp = Request::get(&format!("{}/image/png", *HTTPBIN_URL))
+        .send()
+        .await
+        .unwrap();
+    let url = resp.object_url().await.unwrap();
+    assert!(url.starts_with("blob:"));
+}
+
+#[cfg(feature = "file")]
+#[wasm_bindgen_test]
+async fn fetch_blob() {
+    let resp = Request::get(&format!("{}/image/png", *HTTPBIN_URL))
+        .send()
+        .await
+        .unwrap();
+    let blob = resp.blob().await.unwrap();
+    assert_eq!(blob.raw_mime_type(), "image/png");
+    assert!(blob.size() > 0);
+}
+
+#[wasm_bindgen_test]
+async fn response_form_data() {
+    let resp = Response::builder()
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(Some("name=gloo&kind=crate"))
+        .unwrap();
+    let form = resp.form_data().await.unwrap();
+    assert_eq!(form.get("name").as_string().as_deref(), Some("gloo"));
+    assert_eq!(form.get("kind").as_string().as_deref(), Some("crate"));
+}
+
+#[cfg(feature = "file")]
+#[wasm_bindgen_test]
+async fn file_body() {
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        data: String,
+        headers: HashMap<String, String>,
+    }
+
+    let file =
+        gloo_file::File::new_with_options("notes.txt", "some notes", Some("text/plain"), None);
+    let resp = Request::post(&format!("{}/post", *HTTPBIN_URL))
+        .body(file)
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    let json: HttpBin = resp.json().await.unwrap();
+    assert_eq!(json.data, "some notes");
+    assert_eq!(
+        json.headers.get("Content-Type").map(String::as_str),
+        Some("text/plain")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn fetch_body_stream() {
+    let resp = Request::get(&format!(
+        "{}/stream-bytes/65536?chunk_size=4096",
+        *HTTPBIN_URL
+    ))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(resp.status(), 200);
+    let mut len = 0;
+    let mut body = resp.body_stream();
+    while let Some(chunk) = body.next().await {
+        len += chunk.unwrap().len();
+    }
+    assert_eq!(len, 65536);
+    // The body can only be read once.
+    assert!(resp.body_stream().next().await.unwrap().is_err());
+}
+
+#[wasm_bindgen_test]
+async fn fetch_text_lines() {
+    let resp = Request::get(&format!("{}/stream/5", *HTTPBIN_URL))
+        .send()
+        .await
+        .unwrap();
+    let lines: Vec<String> = resp
+        .text_lines()
+        .unwrap()
+        .map(|line| line.unwrap())
+        .collect()
+        .await;
+    assert_eq!(lines.len(), 5);
+    assert!(lines.iter().all(|line| line.starts_with('{')));
+}
+
+#[wasm_bindgen_test]
+async fn fetch_ndjson() {
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        id: u32,
+    }
+
+    let resp = Request::get(&format!("{}/stream/3", *HTTPBIN_URL))
+        .send()
+        .await
+        .unwrap();
+    let ids: Vec<u32> = resp
+        .ndjson::<HttpBin>()
+        .unwrap()
+        .map(|item| item.unwrap().id)
+        .collect()
+        .await;
+    assert_eq!(ids, [0, 1, 2]);
+}
+
+#[wasm_bindgen_test]
+async fn abort_request() {
+    let request = Request::get(&format!("{}/delay/5", *HTTPBIN_URL)).send();
+    let handle = request.abort_handle();
+    handle.abort();
+    assert!(handle.is_aborted());
+    assert!(matches!(request.await, Err(Error::Aborted)));
+}
+
+#[wasm_bindgen_test]
+async fn request_timeout() {
+    let result = Request::get(&format!("{}/delay/5", *HTTPBIN_URL))
+        .timeout(std::time::Duration::from_millis(100))
+        .send()
+        .await;
+    assert!(matches!(result, Err(Error::Timeout)));
+}
+
+#[wasm_bindgen_test]
+async fn post_form() {
+    #[derive(Serialize)]
+    struct Login<'a> {
+        user: &'a str,
+        remember: bool,
+    }
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        form: HashMap<String, String>,
+    }
+
+    let resp = Request::post(&format!("{}/post", *HTTPBIN_URL))
+        .form(&Login {
+            user: "a b&c",
+            remember: true,
+        })
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let json: HttpBin = resp.json().await.unwrap();
+    assert_eq!(json.form["user"], "a b&c");
+    assert_eq!(json.form["remember"], "true");
+}
+
+#[wasm_bindgen_test]
+async fn post_multipart() {
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        form: HashMap<String, String>,
+        files: HashMap<String, String>,
+    }
+
+    let form = Multipart::new()
+        .text("title", "report")
+        .bytes("data", b"1,2,3", "data.csv", "text/csv");
+    let resp = Request::post(&format!("{}/post", *HTTPBIN_URL))
+        .multipart(form)
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    let json: HttpBin = resp.json().await.unwrap();
+    assert_eq!(json.form["title"], "report");
+    assert_eq!(json.files["data"], "1,2,3");
+}
+
+#[wasm_bindgen_test]
+async fn error_for_status() {
+    let result = Request::get(&format!("{}/status/404", *HTTPBIN_URL))
+        .send()
+        .await
+        .unwrap()
+        .error_for_status();
+    assert!(matches!(result, Err(Error::Status { status: 404, .. })));
+}
+
+#[wasm_bindgen_test]
+async fn json_or_error() {
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        url: String,
+    }
+
+    let url = format!("{}/get", *HTTPBIN_URL);
+    let resp = Request::get(&url).send().await.unwrap();
+    let json = resp.json_or_error::<HttpBin, String>().await.unwrap();
+    assert_eq!(json.url, url);
+
+    let resp = Request::get(&format!("{}/status/500", *HTTPBIN_URL))
+        .send()
+        .await
+        .unwrap();
+    // The empty error body isn't JSON.
+    assert!(matches!(
+        resp.json_or_error::<HttpBin, String>().await,
+        Err(ApiError::Error(Error::SerdeError(_)))
+    ));
+}
+
 #[wasm_bindgen_test]
 async fn query_preserve_initial() {
     let resp = Request::get(&format!("{}/get?key=value", *HTTPBIN_URL))
@@ -137,3 +404,627 @@ async fn query_preserve_duplicate_params() {
         .unwrap();
     assert_eq!(resp.url(), format!("{}/get?q=1&q=2", *HTTPBIN_URL));
 }
+
+#[wasm_bindgen_test]
+async fn cookies_roundtrip() {
+    let options = cookies::CookieOptions::new()
+        .path("/")
+        .same_site(cookies::SameSite::Lax);
+    cookies::set("gloo test", "a=b; c", &options).unwrap();
+    assert_eq!(cookies::get("gloo test").as_deref(), Some("a=b; c"));
+    assert!(cookies::all()
+        .unwrap()
+        .contains(&("gloo test".to_string(), "a=b; c".to_string())));
+
+    cookies::delete("gloo test", &options).unwrap();
+    assert_eq!(cookies::get("gloo test"), None);
+}
+
+#[wasm_bindgen_test]
+async fn with_credentials() {
+    let resp = Request::get(&format!("{}/cookies", *HTTPBIN_URL))
+        .with_credentials()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[wasm_bindgen_test]
+async fn redirect_policy() {
+    let url = format!("{}/redirect-to?url=/get", *HTTPBIN_URL);
+    let resp = Request::get(&url).send().await.unwrap();
+    assert!(resp.redirected());
+    assert!(resp.url().ends_with("/get"));
+
+    let request = Request::get(&url)
+        .redirect(web_sys::RequestRedirect::Manual)
+        .build()
+        .unwrap();
+    assert_eq!(request.redirect(), web_sys::RequestRedirect::Manual);
+    let resp = request.send().await.unwrap();
+    assert!(resp.is_opaque_redirect());
+    assert_eq!(resp.status(), 0);
+
+    let resp = Request::get(&url)
+        .redirect(web_sys::RequestRedirect::Error)
+        .send()
+        .await;
+    assert!(resp.is_err());
+}
+
+#[wasm_bindgen_test]
+async fn request_try_clone() {
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        data: String,
+    }
+
+    let request = Request::post(&format!("{}/post", *HTTPBIN_URL))
+        .body("replayed")
+        .unwrap();
+    let copy = request.try_clone().unwrap();
+    let first: HttpBin = request.send().await.unwrap().json().await.unwrap();
+    let second: HttpBin = copy.send().await.unwrap().json().await.unwrap();
+    assert_eq!(first.data, "replayed");
+    assert_eq!(second.data, "replayed");
+}
+
+#[cfg(any(feature = "cbor", feature = "rmp"))]
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+#[cfg(feature = "cbor")]
+#[wasm_bindgen_test]
+async fn cbor_body() {
+    let request = Request::post("/points")
+        .cbor(&Point { x: 1, y: -2 })
+        .unwrap();
+    assert_eq!(
+        request.headers().get("Content-Type").as_deref(),
+        Some("application/cbor")
+    );
+    let resp = gloo_net::http::Response::builder()
+        .body(request.body().as_ref())
+        .unwrap();
+    assert_eq!(resp.cbor::<Point>().await.unwrap(), Point { x: 1, y: -2 });
+}
+
+#[cfg(feature = "rmp")]
+#[wasm_bindgen_test]
+async fn msgpack_body() {
+    let request = Request::post("/points")
+        .msgpack(&Point { x: 1, y: -2 })
+        .unwrap();
+    assert_eq!(
+        request.headers().get("Content-Type").as_deref(),
+        Some("application/msgpack")
+    );
+    let resp = gloo_net::http::Response::builder()
+        .body(request.body().as_ref())
+        .unwrap();
+    assert_eq!(
+        resp.msgpack::<Point>().await.unwrap(),
+        Point { x: 1, y: -2 }
+    );
+}
+
+#[cfg(feature = "test-utils")]
+#[wasm_bindgen_test]
+async fn mock_server() {
+    use gloo_net::http::mock::{MockResponse, MockServer};
+    use gloo_net::http::Method;
+    use std::time::Duration;
+
+    let server = MockServer::install();
+    server
+        .on(
+            Method::GET,
+            "/api/users",
+            MockResponse::new(200).header("X-Total", "0").text("[]"),
+        )
+        .on(Method::POST, "/api/users", MockResponse::network_error())
+        .on(
+            Method::GET,
+            "/api/slow",
+            MockResponse::new(204).delay(Duration::from_millis(200)),
+        );
+
+    let resp = Request::get("/api/users?page=1").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers().get("X-Total").as_deref(), Some("0"));
+    assert_eq!(resp.text().await.unwrap(), "[]");
+    assert!(matches!(
+        Request::post("/api/users").send().await,
+        Err(Error::JsError(_))
+    ));
+    assert!(matches!(
+        Request::get("/api/slow")
+            .timeout(Duration::from_millis(50))
+            .send()
+            .await,
+        Err(Error::Timeout)
+    ));
+    assert!(Request::get("/api/unknown").send().await.is_err());
+    assert_eq!(server.requests().len(), 4);
+
+    drop(server);
+    let resp = Request::get(&format!("{}/get", *HTTPBIN_URL))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[wasm_bindgen_test]
+async fn beacon_and_keepalive() {
+    Request::beacon(&format!("{}/post", *HTTPBIN_URL), "unload").unwrap();
+
+    let resp = Request::post(&format!("{}/post", *HTTPBIN_URL))
+        .keepalive(true)
+        .body("unload")
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[wasm_bindgen_test]
+async fn request_priority() {
+    let resp = Request::get(&format!("{}/get", *HTTPBIN_URL))
+        .priority(RequestPriority::Low)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[wasm_bindgen_test]
+async fn download_range() {
+    use gloo_net::http::Download;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let received = Rc::new(Cell::new(0));
+    let progress = Rc::clone(&received);
+    let body = Download::new(&format!("{}/range/4096?chunk_size=512", *HTTPBIN_URL))
+        .on_progress(move |p| progress.set(p.received))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(body.len(), 4096);
+    assert_eq!(received.get(), 4096);
+}
+
+#[wasm_bindgen_test]
+async fn body_stream_decompressed() {
+    use gloo_net::http::{Compression, Response};
+
+    // "hello gloo", gzipped.
+    let mut gzip = [
+        31, 139, 8, 0, 0, 0, 0, 0, 2, 3, 203, 72, 205, 201, 201, 87, 72, 207, 201, 207, 7, 0, 168,
+        31, 43, 28, 10, 0, 0, 0,
+    ];
+    let resp = Response::builder().body(Some(&mut gzip[..])).unwrap();
+    let chunks: Vec<Vec<u8>> = resp
+        .body_stream_decompressed(Compression::Gzip)
+        .unwrap()
+        .map(|chunk| chunk.unwrap())
+        .collect()
+        .await;
+    assert_eq!(chunks.concat(), b"hello gloo");
+}
+
+#[wasm_bindgen_test]
+async fn typed_headers() {
+    use gloo_net::http::Headers;
+
+    let headers: Headers = vec![
+        ("Content-Type", "Application/JSON; charset=utf-8"),
+        ("content-length", "42"),
+        ("Cache-Control", "max-age=60, private"),
+    ]
+    .into_iter()
+    .collect();
+    assert_eq!(headers.content_type().as_deref(), Some("application/json"));
+    assert_eq!(headers.content_length(), Some(42));
+    let cache_control = headers.cache_control().unwrap();
+    assert_eq!(
+        cache_control.max_age,
+        Some(std::time::Duration::from_secs(60))
+    );
+    assert!(cache_control.private);
+    assert_eq!(headers.get("CONTENT-LENGTH").as_deref(), Some("42"));
+
+    let raw: web_sys::Headers = headers.into();
+    assert!(Headers::from(raw).has("cache-control"));
+}
+
+#[cfg(feature = "http-types")]
+#[wasm_bindgen_test]
+async fn http_types_interop() {
+    use std::convert::TryFrom;
+
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        data: String,
+        headers: HashMap<String, String>,
+    }
+
+    let request = http::Request::post(format!("{}/post", *HTTPBIN_URL))
+        .header("X-Shared", "yes")
+        .body(b"shared".to_vec())
+        .unwrap();
+    let resp = Request::try_from(request).unwrap().send().await.unwrap();
+    let resp = resp.into_http().await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(resp.headers().contains_key("content-type"));
+    let json: HttpBin = serde_json::from_slice(resp.body()).unwrap();
+    assert_eq!(json.data, "shared");
+    assert_eq!(
+        json.headers.get("X-Shared").map(String::as_str),
+        Some("yes")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn client_json_helpers() {
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        json: Payload,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Payload {
+        name: String,
+    }
+
+    let client = Client::builder().base_url(&HTTPBIN_URL).build();
+    let payload = Payload {
+        name: "Ferris".to_string(),
+    };
+    let resp: HttpBin = client.post_json("/post", &payload).await.unwrap();
+    assert_eq!(resp.json, payload);
+    let resp: HttpBin = client.put_json("/put", &payload).await.unwrap();
+    assert_eq!(resp.json, payload);
+
+    let result = client.get_json::<HttpBin>("/status/404").await;
+    assert!(matches!(result, Err(Error::Status { status: 404, .. })));
+}
+
+#[wasm_bindgen_test]
+async fn rate_limit() {
+    use std::time::Duration;
+
+    let limit = RateLimit::new(1, Duration::from_secs(60)).max_delay(Duration::ZERO);
+    let client = Client::builder()
+        .base_url(&HTTPBIN_URL)
+        .rate_limit(limit.clone())
+        .build();
+    let resp = client.get("/get").send().await.unwrap();
+    assert_eq!(resp.status(), 200);
+
+    let result = client.get("/get").send().await;
+    assert!(matches!(result, Err(Error::RateLimited { .. })));
+    // Clones share the bucket.
+    let result = Request::get(&format!("{}/get", *HTTPBIN_URL))
+        .middleware(limit)
+        .send()
+        .await;
+    assert!(matches!(result, Err(Error::RateLimited { .. })));
+}
+
+#[wasm_bindgen_test]
+async fn auth_refresh() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    let refreshes = Rc::new(Cell::new(0));
+    let counter = Rc::clone(&refreshes);
+    let auth = Auth::new(None, move || {
+        counter.set(counter.get() + 1);
+        async { Ok("fresh".to_string()) }
+    });
+    let client = Client::builder()
+        .base_url(&HTTPBIN_URL)
+        .auth(auth.clone())
+        .build();
+    // `/bearer` rejects requests without a bearer token.
+    let (a, b) =
+        futures::future::join(client.get("/bearer").send(), client.get("/bearer").send()).await;
+    assert_eq!(a.unwrap().status(), 200);
+    assert_eq!(b.unwrap().status(), 200);
+    assert_eq!(refreshes.get(), 1);
+    assert_eq!(auth.token().as_deref(), Some("fresh"));
+}
+
+#[wasm_bindgen_test]
+async fn upload_progress() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        data: String,
+    }
+
+    let body = "x".repeat(64 * 1024);
+    let progress = Rc::new(RefCell::new(Vec::new()));
+    let events = Rc::clone(&progress);
+    let resp = Request::post(&format!("{}/post", *HTTPBIN_URL))
+        .body(body.clone())
+        .unwrap()
+        .send_with_upload_progress(move |progress| events.borrow_mut().push(progress))
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(resp.url().ends_with("/post"));
+    assert_eq!(resp.json::<HttpBin>().await.unwrap().data, body);
+
+    let last = *progress.borrow().last().expect("no upload progress");
+    assert_eq!(last.sent, body.len() as u64);
+    assert_eq!(last.total, Some(body.len() as u64));
+}
+
+#[wasm_bindgen_test]
+async fn send_all() {
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        args: HashMap<String, String>,
+    }
+
+    let client = Client::builder().base_url(&HTTPBIN_URL).build();
+    let requests: Vec<_> = (0..5)
+        .map(|i| client.get("/get").query([("i", i.to_string())]))
+        .collect();
+    let responses: Vec<_> = client.send_all(requests, 2).collect().await;
+    assert_eq!(responses.len(), 5);
+    for (i, resp) in responses.into_iter().enumerate() {
+        let json: HttpBin = resp.unwrap().json().await.unwrap();
+        assert_eq!(json.args.get("i"), Some(&i.to_string()));
+    }
+}
+
+#[cfg(feature = "macros")]
+#[wasm_bindgen_test]
+async fn gloo_api() {
+    use gloo_net::http::gloo_api;
+
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        url: String,
+        args: HashMap<String, String>,
+        headers: HashMap<String, String>,
+        json: Option<Payload>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Payload {
+        name: String,
+    }
+
+    #[gloo_api]
+    trait HttpBinApi {
+        #[get("/anything/{id}")]
+        async fn get(
+            &self,
+            id: &str,
+            #[query("q")] query: &str,
+            #[query] page: Option<u32>,
+            #[header("X-Test")] test: u32,
+        ) -> Result<HttpBin, Error>;
+
+        #[put("/anything/{id}")]
+        async fn put(&self, id: u32, #[body] payload: &Payload) -> Result<HttpBin, Error>;
+
+        #[get("/status/{status}")]
+        async fn status(&self, status: u16) -> Result<(), Error>;
+    }
+
+    let api = HttpBinApiClient::new(Client::builder().base_url(&HTTPBIN_URL).build());
+    let resp = api.get("a b", "rust", None, 7).await.unwrap();
+    assert!(resp.url.ends_with("/anything/a%20b?q=rust"));
+    assert_eq!(resp.args.get("q").map(String::as_str), Some("rust"));
+    assert!(!resp.args.contains_key("page"));
+    assert_eq!(resp.headers.get("X-Test").map(String::as_str), Some("7"));
+
+    let payload = Payload {
+        name: "gloo".to_string(),
+    };
+    let resp = api.put(1, &payload).await.unwrap();
+    assert_eq!(resp.json, Some(payload));
+
+    api.status(204).await.unwrap();
+    match api.status(404).await {
+        Err(Error::Status { status: 404, .. }) => {}
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[cfg(feature = "tower")]
+#[wasm_bindgen_test]
+async fn tower_service() {
+    use bytes::Bytes;
+    use tower_service::Service;
+
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        data: String,
+        headers: HashMap<String, String>,
+    }
+
+    let mut client = Client::builder()
+        .base_url(&HTTPBIN_URL)
+        .default_header("X-Client", "gloo")
+        .build();
+    let request = http::Request::post("/anything")
+        .header("X-Test", "tower")
+        .body(Bytes::from_static(b"hello"))
+        .unwrap();
+    let response = client.call(request).await.unwrap();
+    assert_eq!(response.status(), 200);
+    let json: HttpBin = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(json.data, "hello");
+    assert_eq!(
+        json.headers.get("X-Test").map(String::as_str),
+        Some("tower")
+    );
+    assert_eq!(
+        json.headers.get("X-Client").map(String::as_str),
+        Some("gloo")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn get_paginated() {
+    let client = Client::builder().base_url(&HTTPBIN_URL).build();
+    // `/response-headers` responds with the headers of its query as JSON.
+    let link = format!("<{}/get?page=2>; rel=\"next\"", *HTTPBIN_URL);
+    let first = gloo_net::UrlBuilder::new("/response-headers")
+        .query("Link", &link)
+        .build();
+    let pages: Vec<_> = client
+        .get_paginated::<serde_json::Value>(&first)
+        .collect()
+        .await;
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[0].as_ref().unwrap()["Link"], link.as_str());
+    assert_eq!(pages[1].as_ref().unwrap()["args"]["page"], "2");
+}
+
+#[wasm_bindgen_test]
+async fn tee_body_stream() {
+    let resp = Request::get(&format!(
+        "{}/stream-bytes/65536?chunk_size=4096&seed=1",
+        *HTTPBIN_URL
+    ))
+    .send()
+    .await
+    .unwrap();
+    let (a, b) = resp.tee_body_stream().unwrap();
+    let collect = |stream: gloo_net::http::BodyStream| async move {
+        let chunks: Vec<_> = stream.collect().await;
+        chunks
+            .into_iter()
+            .flat_map(Result::unwrap)
+            .collect::<Vec<u8>>()
+    };
+    let (a, b) = futures::future::join(collect(a), collect(b)).await;
+    assert_eq!(a.len(), 65536);
+    assert_eq!(a, b);
+    // The body is locked by the branches.
+    assert!(resp.tee_body_stream().is_err());
+}
+
+#[wasm_bindgen_test]
+async fn sign_requests() {
+    use gloo_net::http::Signer;
+
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        data: String,
+        headers: HashMap<String, String>,
+    }
+
+    let signer = Signer::new(|request| async move {
+        let signature = format!(
+            "{} {} {}",
+            request.method,
+            request.url.ends_with("/anything"),
+            String::from_utf8(request.body).unwrap()
+        );
+        request.headers.set("X-Signature", &signature);
+        Ok(())
+    });
+    let client = Client::builder()
+        .base_url(&HTTPBIN_URL)
+        .sign(signer)
+        .build();
+    let resp = client
+        .post("/anything")
+        .body("signed body")
+        .unwrap()
+        .send()
+        .await
+        .unwrap();
+    let json: HttpBin = resp.json().await.unwrap();
+    // The body is still sent after being read for the signature.
+    assert_eq!(json.data, "signed body");
+    assert_eq!(
+        json.headers.get("X-Signature").map(String::as_str),
+        Some("POST true signed body")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn verify_integrity() {
+    use gloo_net::http::verify_integrity;
+
+    // `/base64` responds with the decoded path, `hello`.
+    let body = Request::get(&format!("{}/base64/aGVsbG8=", *HTTPBIN_URL))
+        .send()
+        .await
+        .unwrap()
+        .binary()
+        .await
+        .unwrap();
+    let integrity = "sha256-LPJNul+wow4m6DsqxbninhsWHlwfp0JecwQzYpOLmCQ=";
+    verify_integrity(&body, integrity).await.unwrap();
+    verify_integrity(&body, &format!("sha1-x {integrity}"))
+        .await
+        .unwrap();
+    assert!(matches!(
+        verify_integrity(b"tampered", integrity).await,
+        Err(Error::IntegrityMismatch { .. })
+    ));
+}
+
+#[wasm_bindgen_test]
+async fn send_no_cors() {
+    // The tests are served from another origin than httpbin.
+    let resp = Request::get(&format!("{}/get", *HTTPBIN_URL))
+        .send_no_cors()
+        .await
+        .unwrap();
+    assert!(resp.is_opaque());
+    assert_eq!(resp.as_raw().status(), 0);
+    assert!(resp.into_readable().is_err());
+
+    let request = Request::get("/")
+        .cache(web_sys::RequestCache::NoStore)
+        .integrity("sha256-abc")
+        .referrer_policy(web_sys::ReferrerPolicy::NoReferrer)
+        .build()
+        .unwrap();
+    assert_eq!(request.cache(), web_sys::RequestCache::NoStore);
+    assert_eq!(request.integrity(), "sha256-abc");
+    assert_eq!(
+        request.referrer_policy(),
+        web_sys::ReferrerPolicy::NoReferrer
+    );
+}
+
+#[cfg(feature = "json")]
+#[wasm_bindgen_test]
+async fn get_json_swr() {
+    #[derive(Deserialize, Debug)]
+    struct HttpBin {
+        url: String,
+    }
+
+    let client = Client::builder().base_url(*HTTPBIN_URL).build();
+    let mut first = client.get_json_swr::<HttpBin>("/etag/swr");
+    assert!(first.cached().is_none());
+    let fresh = first.next().await.unwrap().unwrap();
+    assert_eq!(fresh.url, format!("{}/etag/swr", *HTTPBIN_URL));
+    assert!(first.next().await.is_none());
+
+    // httpbin responds with `304 Not Modified` to the `If-None-Match: swr` header.
+    let mut second = client.get_json_swr::<HttpBin>("/etag/swr");
+    assert_eq!(second.cached().unwrap().url, fresh.url);
+    assert!(second.next().await.is_none());
+}
@@ -0,0 +1,22 @@
+#![cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+//! Requests sent from a service worker, where there is no `window`.
+
+use gloo_net::http::Request;
+use once_cell::sync::Lazy;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_service_worker);
+
+#[allow(clippy::option_env_unwrap)]
+static HTTPBIN_URL: Lazy<&'static str> =
+    Lazy::new(|| option_env!("HTTPBIN_URL").expect("Did you set HTTPBIN_URL?"));
+
+#[wasm_bindgen_test]
+async fn fetch_in_service_worker() {
+    let resp = Request::get(&format!("{}/get", *HTTPBIN_URL))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(resp.text().await.unwrap().contains("\"url\""));
+}
@@ -0,0 +1,39 @@
+#![cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
+//! Requests sent from a dedicated worker, where there is no `window`.
+
+use gloo_net::http::Request;
+use once_cell::sync::Lazy;
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_dedicated_worker);
+
+#[allow(clippy::option_env_unwrap)]
+static HTTPBIN_URL: Lazy<&'static str> =
+    Lazy::new(|| option_env!("HTTPBIN_URL").expect("Did you set HTTPBIN_URL?"));
+
+#[wasm_bindgen_test]
+async fn fetch_in_worker() {
+    let resp = Request::get(&format!("{}/get", *HTTPBIN_URL))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(resp.status(), 200);
+}
+
+#[cfg(feature = "json")]
+#[wasm_bindgen_test]
+async fn client_in_worker() {
+    use gloo_net::http::Client;
+
+    #[derive(serde::Deserialize)]
+    struct HttpBin {
+        json: serde_json::Value,
+    }
+
+    let client = Client::builder().base_url(*HTTPBIN_URL).build();
+    let resp: HttpBin = client
+        .post_json("/anything", &serde_json::json!({ "worker": true }))
+        .await
+        .unwrap();
+    assert_eq!(resp.json, serde_json::json!({ "worker": true }));
+}
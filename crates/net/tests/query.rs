@@ -1,3 +1,4 @@
+#![cfg(not(all(feature = "native-http", not(target_arch = "wasm32"))))]
 use gloo_net::http::QueryParams;
 use wasm_bindgen_test::*;
 
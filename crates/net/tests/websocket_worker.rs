@@ -0,0 +1,26 @@
+#![cfg(feature = "websocket")]
+//! WebSockets opened from a dedicated worker, where there is no `window`.
+
+use futures::{SinkExt, StreamExt};
+use gloo_net::websocket::{futures::WebSocket, Message};
+use wasm_bindgen_test::*;
+
+wasm_bindgen_test_configure!(run_in_dedicated_worker);
+
+#[wasm_bindgen_test]
+#[allow(clippy::option_env_unwrap)]
+async fn websocket_works_in_worker() {
+    let ws_echo_server_url =
+        option_env!("WS_ECHO_SERVER_URL").expect("Did you set WS_ECHO_SERVER_URL?");
+
+    let mut ws = WebSocket::open(ws_echo_server_url).unwrap();
+    ws.send(Message::Text("test".to_string())).await.unwrap();
+
+    // ignore first message
+    // the echo-server uses it to send it's info in the first message
+    let _ = ws.next().await;
+    assert_eq!(
+        ws.next().await.unwrap().unwrap(),
+        Message::Text("test".to_string())
+    );
+}
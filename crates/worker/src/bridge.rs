@@ -1,18 +1,91 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::fmt;
+use std::future::{poll_fn, Future};
 use std::marker::PhantomData;
+use std::pin::Pin;
 use std::rc::Rc;
 use std::rc::Weak;
+use std::task::{Context, Poll, Waker};
 
 use crate::codec::Codec;
 use crate::handler_id::HandlerId;
 use crate::messages::ToWorker;
 use crate::native_worker::NativeWorkerExt;
+#[cfg(feature = "futures")]
+use crate::stream::WorkerStream;
 use crate::traits::Worker;
 use crate::{Callback, Shared};
 
 pub(crate) type ToWorkerQueue<W> = Vec<ToWorker<W>>;
+
+/// How many inputs the bridges of a worker may have in flight, and how many each of them has.
+pub(crate) struct FlowControl {
+    capacity: usize,
+    /// The inputs queued until the worker is loaded, or not responded to or processed by it yet.
+    in_flight: usize,
+    outstanding: HashMap<HandlerId, usize>,
+    waiters: Vec<Waker>,
+}
+
+impl FlowControl {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            in_flight: 0,
+            outstanding: HashMap::new(),
+            waiters: Vec::new(),
+        }
+    }
+
+    /// An input was sent by the bridge `id`.
+    pub fn sent(&mut self, id: HandlerId) {
+        self.in_flight += 1;
+        *self.outstanding.entry(id).or_insert(0) += 1;
+    }
+
+    /// An input of the bridge `id` was responded to or processed by the worker.
+    ///
+    /// Outputs and processed marks beyond the inputs the bridge has in flight, e.g. several
+    /// outputs responding to one input, don't free any room.
+    pub fn processed(&mut self, id: HandlerId) {
+        if let Some(outstanding) = self.outstanding.get_mut(&id) {
+            *outstanding -= 1;
+            if *outstanding == 0 {
+                self.outstanding.remove(&id);
+            }
+            self.in_flight -= 1;
+            self.wake();
+        }
+    }
+
+    /// The bridge `id` disconnected, none of its inputs are in flight anymore.
+    pub fn release(&mut self, id: HandlerId) {
+        if let Some(outstanding) = self.outstanding.remove(&id) {
+            self.in_flight -= outstanding;
+            self.wake();
+        }
+    }
+
+    pub fn has_room(&self) -> bool {
+        self.in_flight < self.capacity
+    }
+
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.has_room() {
+            Poll::Ready(())
+        } else {
+            self.waiters.push(cx.waker().clone());
+            Poll::Pending
+        }
+    }
+
+    fn wake(&mut self) {
+        for waker in self.waiters.drain(..) {
+            waker.wake();
+        }
+    }
+}
 pub(crate) type CallbackMap<W> = HashMap<HandlerId, Weak<dyn Fn(<W as Worker>::Output)>>;
 
 struct WorkerBridgeInner<W>
@@ -22,6 +95,7 @@ where
     // When worker is loaded, queue becomes None.
     pending_queue: Shared<Option<ToWorkerQueue<W>>>,
     callbacks: Shared<CallbackMap<W>>,
+    flow: Shared<FlowControl>,
     post_msg: Rc<dyn Fn(ToWorker<W>)>,
 }
 
@@ -40,6 +114,11 @@ where
 {
     /// Send a message to the worker, queuing the message if necessary
     fn send_message(&self, msg: ToWorker<W>) {
+        match msg {
+            ToWorker::ProcessInput(id, _) => self.flow.borrow_mut().sent(id),
+            ToWorker::Disconnected(id) => self.flow.borrow_mut().release(id),
+            _ => {}
+        }
         let mut pending_queue = self.pending_queue.borrow_mut();
 
         match pending_queue.as_mut() {
@@ -78,15 +157,17 @@ impl<W> WorkerBridge<W>
 where
     W: Worker,
 {
-    pub(crate) fn new<CODEC>(
+    pub(crate) fn new<CODEC, T>(
         id: HandlerId,
-        native_worker: web_sys::Worker,
+        native_worker: T,
         pending_queue: Rc<RefCell<Option<ToWorkerQueue<W>>>>,
         callbacks: Rc<RefCell<CallbackMap<W>>>,
+        flow: Shared<FlowControl>,
         callback: Option<Callback<W::Output>>,
     ) -> Self
     where
         CODEC: Codec,
+        T: 'static + NativeWorkerExt,
     {
         let post_msg =
             { move |msg: ToWorker<W>| native_worker.post_packed_message::<_, CODEC>(msg) };
@@ -95,6 +176,7 @@ where
             inner: WorkerBridgeInner {
                 pending_queue,
                 callbacks,
+                flow,
                 post_msg: Rc::new(post_msg),
             }
             .into(),
@@ -105,11 +187,93 @@ where
     }
 
     /// Send a message to the current worker.
+    ///
+    /// This is the unbounded escape hatch: the message is sent, or queued until the worker is
+    /// loaded, even if the worker has [`ready_capacity`](crate::WorkerSpawner::ready_capacity)
+    /// inputs in flight already. It still counts towards the capacity. Only
+    /// [`try_send`](Self::try_send), [`poll_ready`](Self::poll_ready) and [`ready`](Self::ready)
+    /// apply backpressure, use them to keep a fast producer from queuing messages without bounds.
     pub fn send(&self, msg: W::Input) {
         let msg = ToWorker::ProcessInput(self.id, msg);
         self.inner.send_message(msg);
     }
 
+    /// Send a message to the current worker if it has room for it, returning the message
+    /// otherwise.
+    pub fn try_send(&self, msg: W::Input) -> Result<(), W::Input> {
+        if !self.has_room() {
+            return Err(msg);
+        }
+        self.send(msg);
+        Ok(())
+    }
+
+    /// Checks whether the worker has room for another message, registering the current task to
+    /// be woken when it has if it hasn't.
+    ///
+    /// The worker has room while fewer than
+    /// [`ready_capacity`](crate::WorkerSpawner::ready_capacity) inputs of its bridges are waiting
+    /// for it to load, or for it to respond to them or mark them as
+    /// [`processed`](crate::WorkerScope::processed). An output or processed mark only frees an
+    /// input of the bridge it's sent to, if that bridge has one in flight, and the inputs of a
+    /// bridge are all freed once it's dropped.
+    pub fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<()> {
+        self.inner.flow.borrow_mut().poll_ready(cx)
+    }
+
+    /// Waits for the worker to have room for another message.
+    pub async fn ready(&self) {
+        poll_fn(|cx| self.poll_ready(cx)).await
+    }
+
+    fn has_room(&self) -> bool {
+        self.inner.flow.borrow().has_room()
+    }
+
+    /// Sends a message to the current worker and waits for its response.
+    ///
+    /// The message is sent from a bridge forked for the call, so the worker receives it with a
+    /// [`HandlerId`] of its own and responds to it with
+    /// [`WorkerScope::respond`](crate::WorkerScope::respond), like to any other bridge. The first
+    /// output sent to that id resolves the call, later ones are ignored. Dropping the future
+    /// disconnects the forked bridge, cancelling the
+    /// [`CancellationToken`](crate::CancellationToken) of the call.
+    pub fn call(&self, msg: W::Input) -> WorkerCall<W> {
+        let state: Shared<CallState<W::Output>> = Rc::new(RefCell::new(CallState {
+            output: None,
+            waker: None,
+        }));
+        let bridge = {
+            let state = state.clone();
+            self.fork(Some(move |output| {
+                let mut state = state.borrow_mut();
+                if state.output.is_none() {
+                    state.output = Some(output);
+                    if let Some(waker) = state.waker.take() {
+                        waker.wake();
+                    }
+                }
+            }))
+        };
+        bridge.send(msg);
+
+        WorkerCall {
+            bridge: Some(bridge),
+            state,
+        }
+    }
+
+    /// Forks the bridge into a [`Stream`](futures_core::Stream) of the outputs of the worker and
+    /// a [`Sink`](futures_sink::Sink) of its inputs.
+    ///
+    /// Like [`fork`](Self::fork), this creates a new [`HandlerId`], which only receives the
+    /// outputs responding to the inputs sent with the stream.
+    #[cfg(feature = "futures")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "futures")))]
+    pub fn stream(&self) -> WorkerStream<W> {
+        WorkerStream::new(self)
+    }
+
     /// Forks the bridge with a different callback.
     ///
     /// This creates a new [HandlerID] that helps the worker to differentiate bridges.
@@ -177,3 +341,278 @@ where
         self.id == rhs.id
     }
 }
+
+struct CallState<O> {
+    output: Option<O>,
+    waker: Option<Waker>,
+}
+
+/// The response of the worker to a message sent with [`WorkerBridge::call`].
+#[must_use = "futures do nothing unless polled"]
+pub struct WorkerCall<W>
+where
+    W: Worker,
+{
+    // Taken once the call is resolved.
+    bridge: Option<WorkerBridge<W>>,
+    state: Shared<CallState<W::Output>>,
+}
+
+impl<W> WorkerCall<W>
+where
+    W: Worker,
+{
+    /// Cancels the call, disconnecting its bridge right away.
+    ///
+    /// The worker is told by the [`CancellationToken`](crate::CancellationToken) of the call.
+    pub fn cancel(mut self) {
+        self.finish();
+    }
+
+    /// Disconnects the bridge of the call and removes its callback.
+    pub(crate) fn finish(&mut self) {
+        if let Some(bridge) = self.bridge.take() {
+            // Dropped from a callback, the callback is removed by the spawner once its outputs
+            // find it dropped.
+            if let Ok(mut callbacks) = bridge.inner.callbacks.try_borrow_mut() {
+                callbacks.remove(&bridge.id);
+            }
+        }
+    }
+}
+
+impl<W> Future for WorkerCall<W>
+where
+    W: Worker,
+{
+    type Output = W::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let output = {
+            let mut state = this.state.borrow_mut();
+            match state.output.take() {
+                Some(output) => output,
+                None => {
+                    state.waker = Some(cx.waker().clone());
+                    return Poll::Pending;
+                }
+            }
+        };
+        this.finish();
+        Poll::Ready(output)
+    }
+}
+
+impl<W> Unpin for WorkerCall<W> where W: Worker {}
+
+impl<W> Drop for WorkerCall<W>
+where
+    W: Worker,
+{
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+impl<W> fmt::Debug for WorkerCall<W>
+where
+    W: Worker,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("WorkerCall<_>")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::Bincode;
+    use crate::scope::WorkerScope;
+    use futures::task::{noop_waker, waker, ArcWake};
+    use serde::{Deserialize, Serialize};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct Flag(AtomicBool);
+
+    impl ArcWake for Flag {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn woken(flag: &Flag) -> bool {
+        flag.0.swap(false, Ordering::SeqCst)
+    }
+
+    #[test]
+    fn pending_until_processed() {
+        let flag = Arc::new(Flag::default());
+        let waker = waker(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut flow = FlowControl::new(2);
+        let id = HandlerId::new();
+
+        assert!(flow.poll_ready(&mut cx).is_ready());
+        flow.sent(id);
+        assert!(flow.poll_ready(&mut cx).is_ready());
+        flow.sent(id);
+        assert!(flow.poll_ready(&mut cx).is_pending());
+        assert!(!woken(&flag));
+
+        flow.processed(id);
+        assert!(woken(&flag));
+        assert!(flow.poll_ready(&mut cx).is_ready());
+    }
+
+    #[test]
+    fn processed_only_frees_inputs_of_the_bridge() {
+        let mut flow = FlowControl::new(2);
+        let (a, b) = (HandlerId::new(), HandlerId::new());
+
+        flow.sent(a);
+        flow.sent(a);
+        flow.processed(b);
+        assert!(!flow.has_room());
+
+        flow.processed(a);
+        assert!(flow.has_room());
+        // More outputs than inputs don't free room for later inputs.
+        flow.processed(a);
+        flow.processed(a);
+        flow.sent(b);
+        flow.sent(b);
+        assert!(!flow.has_room());
+    }
+
+    #[test]
+    fn release_frees_all_inputs_of_the_bridge() {
+        let flag = Arc::new(Flag::default());
+        let waker = waker(flag.clone());
+        let mut cx = Context::from_waker(&waker);
+        let mut flow = FlowControl::new(3);
+        let (a, b) = (HandlerId::new(), HandlerId::new());
+
+        flow.sent(a);
+        flow.sent(a);
+        flow.sent(b);
+        assert!(flow.poll_ready(&mut cx).is_pending());
+
+        flow.release(b);
+        assert!(woken(&flag));
+        flow.sent(b);
+        flow.release(a);
+        flow.sent(b);
+        flow.sent(b);
+        assert!(!flow.has_room());
+
+        // Releasing a bridge without inputs in flight doesn't wake anyone.
+        assert!(flow.poll_ready(&mut cx).is_pending());
+        flow.release(a);
+        assert!(!woken(&flag));
+    }
+
+    #[derive(Debug)]
+    struct Echo;
+
+    impl Worker for Echo {
+        type Message = ();
+        type Input = u32;
+        type Output = u32;
+
+        fn create(_: &WorkerScope<Self>) -> Self {
+            Echo
+        }
+
+        fn update(&mut self, _: &WorkerScope<Self>, _: ()) {}
+
+        fn received(&mut self, scope: &WorkerScope<Self>, msg: u32, id: HandlerId) {
+            scope.respond(id, msg);
+        }
+    }
+
+    /// A worker that never loads, its bridges queue all their messages.
+    struct Unloaded;
+
+    impl NativeWorkerExt for Unloaded {
+        fn set_on_packed_message<T, CODEC, F>(&self, _: F)
+        where
+            T: Serialize + for<'de> Deserialize<'de>,
+            CODEC: Codec,
+            F: 'static + Fn(T),
+        {
+            unreachable!("the worker never loads")
+        }
+
+        fn post_packed_message<T, CODEC>(&self, _: T)
+        where
+            T: Serialize + for<'de> Deserialize<'de>,
+            CODEC: Codec,
+        {
+            unreachable!("the worker never loads")
+        }
+    }
+
+    /// Routes an output like the spawner does.
+    fn respond(callbacks: &Shared<CallbackMap<Echo>>, id: HandlerId, output: u32) {
+        if let Some(cb) = callbacks.borrow().get(&id).and_then(Weak::upgrade) {
+            cb(output);
+        }
+    }
+
+    #[test]
+    fn call_resolves_with_the_first_output_to_its_id() {
+        let pending_queue = Rc::new(RefCell::new(Some(Vec::new())));
+        let callbacks: Shared<CallbackMap<Echo>> = Rc::default();
+        let flow = Rc::new(RefCell::new(FlowControl::new(8)));
+        let id = HandlerId::new();
+        let bridge = WorkerBridge::<Echo>::new::<Bincode, _>(
+            id,
+            Unloaded,
+            pending_queue.clone(),
+            callbacks.clone(),
+            flow,
+            None,
+        );
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        let mut first = bridge.call(1);
+        let mut second = bridge.call(2);
+        let ids: Vec<_> = pending_queue
+            .borrow()
+            .iter()
+            .flatten()
+            .map(|msg| match msg {
+                ToWorker::ProcessInput(id, _) => *id,
+                msg => panic!("unexpected message: {:?}", msg),
+            })
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids[0] != ids[1] && !ids.contains(&id));
+
+        respond(&callbacks, id, 0);
+        respond(&callbacks, ids[1], 20);
+        assert!(Pin::new(&mut first).poll(&mut cx).is_pending());
+        assert_eq!(Pin::new(&mut second).poll(&mut cx), Poll::Ready(20));
+
+        respond(&callbacks, ids[0], 10);
+        respond(&callbacks, ids[0], 11);
+        assert_eq!(Pin::new(&mut first).poll(&mut cx), Poll::Ready(10));
+
+        // Resolved calls disconnect their bridges.
+        assert!(callbacks.borrow().is_empty());
+        let disconnected: Vec<_> = pending_queue
+            .borrow()
+            .iter()
+            .flatten()
+            .filter_map(|msg| match msg {
+                ToWorker::Disconnected(id) => Some(*id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(disconnected, [ids[1], ids[0]]);
+    }
+}
@@ -1,8 +1,17 @@
 use js_sys::Uint8Array;
 use serde::{Deserialize, Serialize};
-use wasm_bindgen::JsValue;
+use wasm_bindgen::{JsCast, JsValue};
 
 /// Message Encoding and Decoding Format
+///
+/// The bridge and the worker have to use the same codec, set with
+/// [`WorkerSpawner::encoding`](crate::WorkerSpawner::encoding) and
+/// [`WorkerRegistrar::encoding`](crate::WorkerRegistrar::encoding). Besides the default
+/// [`Bincode`], `Cbor` and `SerdeWasmBindgen` are available with the `cbor` and
+/// `serde-wasm-bindgen` features, and other formats can be used by implementing this trait.
+///
+/// There's no [postcard](https://docs.rs/postcard) codec yet. `gloo-net` adapts its WebSocket
+/// codecs, e.g. for JSON and MessagePack, with `gloo_net::websocket::codec::WorkerCodec`.
 pub trait Codec {
     /// Encode an input to JsValue
     fn encode<I>(input: I) -> JsValue
@@ -13,6 +22,18 @@ pub trait Codec {
     fn decode<O>(input: JsValue) -> O
     where
         O: for<'de> Deserialize<'de>;
+
+    /// Decode a message to a type, returning `None` if it isn't a message of this codec.
+    ///
+    /// This is used for messages which may come from other code, e.g. the ones posted to a page
+    /// by its service worker. The default implementation calls [`decode`](Self::decode), and
+    /// panics like it.
+    fn try_decode<O>(input: JsValue) -> Option<O>
+    where
+        O: for<'de> Deserialize<'de>,
+    {
+        Some(Self::decode(input))
+    }
 }
 
 /// Default message encoding with [bincode].
@@ -35,4 +56,79 @@ impl Codec for Bincode {
         let data = Uint8Array::from(input).to_vec();
         bincode::deserialize(&data).expect("can't deserialize an worker message")
     }
+
+    fn try_decode<O>(input: JsValue) -> Option<O>
+    where
+        O: for<'de> Deserialize<'de>,
+    {
+        let data = input.dyn_into::<Uint8Array>().ok()?.to_vec();
+        bincode::deserialize(&data).ok()
+    }
+}
+
+/// Message encoding with [CBOR](https://cbor.io), using [ciborium].
+#[cfg(feature = "cbor")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+#[derive(Debug)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Codec for Cbor {
+    fn encode<I>(input: I) -> JsValue
+    where
+        I: Serialize,
+    {
+        let mut buf = Vec::new();
+        ciborium::into_writer(&input, &mut buf).expect("can't serialize an worker message");
+        Uint8Array::from(buf.as_slice()).into()
+    }
+
+    fn decode<O>(input: JsValue) -> O
+    where
+        O: for<'de> Deserialize<'de>,
+    {
+        let data = Uint8Array::from(input).to_vec();
+        ciborium::from_reader(data.as_slice()).expect("can't deserialize an worker message")
+    }
+
+    fn try_decode<O>(input: JsValue) -> Option<O>
+    where
+        O: for<'de> Deserialize<'de>,
+    {
+        let data = input.dyn_into::<Uint8Array>().ok()?.to_vec();
+        ciborium::from_reader(data.as_slice()).ok()
+    }
+}
+
+/// Message encoding as JavaScript values with [serde_wasm_bindgen].
+///
+/// Messages are passed with the structured clone of the browser, which skips serializing them
+/// to bytes and back in wasm, at the cost of many calls into JavaScript for large messages.
+#[cfg(feature = "serde-wasm-bindgen")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde-wasm-bindgen")))]
+#[derive(Debug)]
+pub struct SerdeWasmBindgen;
+
+#[cfg(feature = "serde-wasm-bindgen")]
+impl Codec for SerdeWasmBindgen {
+    fn encode<I>(input: I) -> JsValue
+    where
+        I: Serialize,
+    {
+        serde_wasm_bindgen::to_value(&input).expect("can't serialize an worker message")
+    }
+
+    fn decode<O>(input: JsValue) -> O
+    where
+        O: for<'de> Deserialize<'de>,
+    {
+        serde_wasm_bindgen::from_value(input).expect("can't deserialize an worker message")
+    }
+
+    fn try_decode<O>(input: JsValue) -> Option<O>
+    where
+        O: for<'de> Deserialize<'de>,
+    {
+        serde_wasm_bindgen::from_value(input).ok()
+    }
 }
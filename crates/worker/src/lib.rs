@@ -9,6 +9,25 @@
 //! A Bridge allows bi-directional communication between an worker and a component.
 //! Bridges also allow workers to communicate with one another.
 //!
+//! Messages are sent with [`WorkerBridge::send`] and outputs are received by the callback of the
+//! bridge. For request/response style workers, [`WorkerBridge::call`] returns a future of the
+//! output responding to a message instead, and with the `futures` feature,
+//! [`WorkerBridge::stream`] turns a bridge into a `Stream` of outputs and `Sink` of inputs.
+//! [`WorkerBridge::poll_ready`] and [`WorkerBridge::try_send`] keep a producer from sending more
+//! inputs than the worker keeps up with, [`WorkerBridge::send`] always sends.
+//!
+//! #### Pools
+//!
+//! [`WorkerSpawner::spawn_pool`] spawns several instances of a worker, running the tasks given to
+//! the [`WorkerPool`] on all of them, e.g. to parse or process images on all cores.
+//!
+//! #### Shared workers
+//!
+//! A worker registered with [`WorkerRegistrar::register_shared`] runs as a
+//! [shared worker](https://developer.mozilla.org/en-US/docs/Web/API/SharedWorker), a single
+//! instance of which is shared by all the tabs connecting to it with
+//! [`WorkerSpawner::spawn_shared`], e.g. to share a WebSocket connection between tabs.
+//!
 //! #### Scopes
 //!
 //! Scopes are used by workers to communicates with bridges and send updates to itself after
@@ -20,6 +39,8 @@
 //! messages they send and receive. Bridges use [bincode](https://!github.com/servo/bincode)
 //! by default to communicate with workers, so the cost is substantially higher
 //! than just calling a function.
+//! The encoding can be changed to another [`Codec`], e.g. one of the formats enabled by the
+//! `cbor` and `serde-wasm-bindgen` features.
 
 #![deny(
     clippy::all,
@@ -37,17 +58,32 @@ mod handler_id;
 mod lifecycle;
 mod messages;
 mod native_worker;
+mod pool;
 mod registrar;
 mod scope;
+#[cfg(feature = "service-worker")]
+#[cfg_attr(docsrs, doc(cfg(feature = "service-worker")))]
+pub mod service_worker;
+mod shared;
 mod spawner;
+#[cfg(feature = "futures")]
+mod stream;
 mod traits;
 
-pub use bridge::WorkerBridge;
+pub use bridge::{WorkerBridge, WorkerCall};
+#[cfg(feature = "cbor")]
+pub use codec::Cbor;
+#[cfg(feature = "serde-wasm-bindgen")]
+pub use codec::SerdeWasmBindgen;
 pub use codec::{Bincode, Codec};
 pub use handler_id::HandlerId;
+pub use pool::{Dispatch, PoolTask, WorkerPool};
 pub use registrar::WorkerRegistrar;
-pub use scope::{WorkerDestroyHandle, WorkerScope};
+pub use scope::{CancellationToken, WorkerDestroyHandle, WorkerScope};
 pub use spawner::WorkerSpawner;
+#[cfg(feature = "futures")]
+#[cfg_attr(docsrs, doc(cfg(feature = "futures")))]
+pub use stream::WorkerStream;
 pub use traits::Registrable;
 pub use traits::{Spawnable, Worker};
 
@@ -1,7 +1,6 @@
 use wasm_bindgen::prelude::*;
 
 use crate::messages::ToWorker;
-use crate::native_worker::{DedicatedWorker, WorkerSelf};
 use crate::scope::{WorkerDestroyHandle, WorkerScope};
 use crate::traits::Worker;
 use crate::Shared;
@@ -76,6 +75,7 @@ where
                     .as_mut()
                     .expect_throw("worker was not created to process connected messages");
 
+                scope.connect(id);
                 worker.connected(scope, id);
             }
             WorkerLifecycleEvent::Remote(ToWorker::ProcessInput(id, inp)) => {
@@ -88,6 +88,7 @@ where
                     .as_mut()
                     .expect_throw("worker was not created to process inputs");
 
+                scope.connect(id);
                 worker.received(scope, inp, id);
             }
             WorkerLifecycleEvent::Remote(ToWorker::Disconnected(id)) => {
@@ -100,6 +101,7 @@ where
                     .as_mut()
                     .expect_throw("worker was not created to process disconnected messages");
 
+                scope.cancel(Some(id));
                 worker.disconnected(scope, id);
             }
             WorkerLifecycleEvent::Remote(ToWorker::Destroy) => {
@@ -114,18 +116,19 @@ where
                     .as_mut()
                     .expect_throw("trying to destroy not existent worker");
 
+                scope.cancel(None);
                 let destruct = WorkerDestroyHandle::new(scope.clone());
 
                 worker.destroy(scope, destruct);
             }
 
             WorkerLifecycleEvent::Destroy => {
-                state
+                let (_, scope) = state
                     .worker
                     .take()
                     .expect_throw("worker is not initialised or already destroyed");
 
-                DedicatedWorker::worker_self().close();
+                scope.close();
             }
         }
     }
@@ -29,4 +29,6 @@ where
     WorkerLoaded,
     /// Outgoing message to consumer
     ProcessOutput(HandlerId, W::Output),
+    /// Worker is done with an input of a bridge it doesn't respond to
+    Processed(HandlerId),
 }
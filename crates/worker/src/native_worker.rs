@@ -4,7 +4,9 @@ use wasm_bindgen::closure::Closure;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::{JsCast, JsValue};
 pub(crate) use web_sys::Worker as DedicatedWorker;
-use web_sys::{DedicatedWorkerGlobalScope, MessageEvent};
+use web_sys::{
+    DedicatedWorkerGlobalScope, MessageEvent, MessagePort, SharedWorker, SharedWorkerGlobalScope,
+};
 
 pub(crate) trait WorkerSelf {
     type GlobalScope;
@@ -20,6 +22,14 @@ impl WorkerSelf for DedicatedWorker {
     }
 }
 
+impl WorkerSelf for SharedWorker {
+    type GlobalScope = SharedWorkerGlobalScope;
+
+    fn worker_self() -> Self::GlobalScope {
+        JsValue::from(js_sys::global()).into()
+    }
+}
+
 pub(crate) trait NativeWorkerExt {
     fn set_on_packed_message<T, CODEC, F>(&self, handler: F)
     where
@@ -63,5 +73,5 @@ macro_rules! worker_ext_impl {
 }
 
 worker_ext_impl! {
-    DedicatedWorker, DedicatedWorkerGlobalScope
+    DedicatedWorker, DedicatedWorkerGlobalScope, MessagePort
 }
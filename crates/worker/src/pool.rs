@@ -0,0 +1,281 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use crate::bridge::{WorkerBridge, WorkerCall};
+use crate::traits::Worker;
+
+/// How a [`WorkerPool`] picks the worker running a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dispatch {
+    /// Every worker in turn.
+    #[default]
+    RoundRobin,
+    /// The worker running the fewest tasks, the first one of them on a tie.
+    LeastBusy,
+}
+
+struct PoolState {
+    /// The tasks running on every worker.
+    running: Vec<usize>,
+    next: usize,
+    /// Woken when a task completes, while the pool is shut down.
+    shutdown: Option<Waker>,
+}
+
+impl PoolState {
+    fn pick(&mut self, dispatch: Dispatch) -> usize {
+        let worker = match dispatch {
+            Dispatch::RoundRobin => {
+                let worker = self.next % self.running.len();
+                self.next = worker + 1;
+                worker
+            }
+            Dispatch::LeastBusy => (0..self.running.len())
+                .min_by_key(|worker| self.running[*worker])
+                .unwrap_or(0),
+        };
+        self.running[worker] += 1;
+        worker
+    }
+
+    fn complete(&mut self, worker: usize) {
+        self.running[worker] -= 1;
+        if let Some(waker) = self.shutdown.take() {
+            waker.wake();
+        }
+    }
+}
+
+/// A pool of workers running tasks, created by
+/// [`WorkerSpawner::spawn_pool`](crate::WorkerSpawner::spawn_pool).
+///
+/// Tasks are inputs sent with [`WorkerBridge::call`], to spread CPU-bound work over the cores.
+/// Every worker may run several tasks at a time, it is up to the worker to queue them.
+pub struct WorkerPool<W>
+where
+    W: Worker,
+{
+    bridges: Vec<WorkerBridge<W>>,
+    dispatch: Dispatch,
+    state: Rc<RefCell<PoolState>>,
+}
+
+impl<W> WorkerPool<W>
+where
+    W: Worker,
+{
+    pub(crate) fn new(bridges: Vec<WorkerBridge<W>>) -> Self {
+        let state = PoolState {
+            running: vec![0; bridges.len()],
+            next: 0,
+            shutdown: None,
+        };
+        Self {
+            bridges,
+            dispatch: Dispatch::default(),
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    /// Sets how the worker running a task is picked, [`Dispatch::RoundRobin`] by default.
+    pub fn dispatch(mut self, dispatch: Dispatch) -> Self {
+        self.dispatch = dispatch;
+        self
+    }
+
+    /// The number of workers in the pool.
+    pub fn size(&self) -> usize {
+        self.bridges.len()
+    }
+
+    /// The number of tasks running.
+    pub fn running(&self) -> usize {
+        self.state.borrow().running.iter().sum()
+    }
+
+    /// Runs a task on one of the workers, returning the output responding to it.
+    ///
+    /// Dropping the future frees its worker for the next task, as far as the pool is concerned,
+    /// and cancels the [`CancellationToken`](crate::CancellationToken) of the task.
+    pub fn run(&self, input: W::Input) -> PoolTask<W> {
+        let worker = self.state.borrow_mut().pick(self.dispatch);
+        PoolTask {
+            call: self.bridges[worker].call(input),
+            worker: Some(worker),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Waits for the running tasks to complete, and destroys the workers.
+    ///
+    /// The tasks complete as long as their futures are polled, dropping them completes them
+    /// right away.
+    pub async fn shutdown(self) {
+        poll_fn(|cx| {
+            let mut state = self.state.borrow_mut();
+            if state.running.iter().all(|running| *running == 0) {
+                Poll::Ready(())
+            } else {
+                state.shutdown = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        })
+        .await;
+    }
+}
+
+impl<W> fmt::Debug for WorkerPool<W>
+where
+    W: Worker,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkerPool")
+            .field("size", &self.size())
+            .field("running", &self.running())
+            .field("dispatch", &self.dispatch)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The output of a task run by [`WorkerPool::run`].
+#[must_use = "futures do nothing unless polled"]
+pub struct PoolTask<W>
+where
+    W: Worker,
+{
+    call: WorkerCall<W>,
+    // Taken once the task completes.
+    worker: Option<usize>,
+    state: Rc<RefCell<PoolState>>,
+}
+
+impl<W> PoolTask<W>
+where
+    W: Worker,
+{
+    /// Cancels the task, freeing its worker for the next task.
+    ///
+    /// The worker is told by the [`CancellationToken`](crate::CancellationToken) of the task.
+    pub fn cancel(mut self) {
+        self.call.finish();
+        self.complete();
+    }
+
+    fn complete(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            self.state.borrow_mut().complete(worker);
+        }
+    }
+}
+
+impl<W> Future for PoolTask<W>
+where
+    W: Worker,
+{
+    type Output = W::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let output = match Pin::new(&mut this.call).poll(cx) {
+            Poll::Ready(output) => output,
+            Poll::Pending => return Poll::Pending,
+        };
+        this.complete();
+        Poll::Ready(output)
+    }
+}
+
+impl<W> Drop for PoolTask<W>
+where
+    W: Worker,
+{
+    fn drop(&mut self) {
+        self.complete();
+    }
+}
+
+impl<W> fmt::Debug for PoolTask<W>
+where
+    W: Worker,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PoolTask")
+            .field("worker", &self.worker)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::task::{waker, ArcWake};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct Flag(AtomicBool);
+
+    impl ArcWake for Flag {
+        fn wake_by_ref(arc_self: &Arc<Self>) {
+            arc_self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn state(size: usize) -> PoolState {
+        PoolState {
+            running: vec![0; size],
+            next: 0,
+            shutdown: None,
+        }
+    }
+
+    #[test]
+    fn round_robin_picks_every_worker_in_turn() {
+        let mut state = state(3);
+
+        let picked: Vec<_> = (0..4).map(|_| state.pick(Dispatch::RoundRobin)).collect();
+        assert_eq!(picked, [0, 1, 2, 0]);
+        assert_eq!(state.running, [2, 1, 1]);
+
+        // Completing tasks doesn't change the turn.
+        state.complete(1);
+        assert_eq!(state.pick(Dispatch::RoundRobin), 1);
+    }
+
+    #[test]
+    fn least_busy_picks_the_first_idlest_worker() {
+        let mut state = state(3);
+
+        assert_eq!(state.pick(Dispatch::LeastBusy), 0);
+        assert_eq!(state.pick(Dispatch::LeastBusy), 1);
+        assert_eq!(state.pick(Dispatch::LeastBusy), 2);
+        assert_eq!(state.pick(Dispatch::LeastBusy), 0);
+
+        state.complete(2);
+        state.complete(1);
+        assert_eq!(state.pick(Dispatch::LeastBusy), 1);
+        assert_eq!(state.running, [2, 1, 0]);
+    }
+
+    #[test]
+    fn complete_wakes_shutdown() {
+        let flag = Arc::new(Flag::default());
+        let mut state = state(2);
+
+        state.pick(Dispatch::RoundRobin);
+        state.pick(Dispatch::RoundRobin);
+        state.shutdown = Some(waker(flag.clone()));
+
+        state.complete(0);
+        assert!(flag.0.swap(false, Ordering::SeqCst));
+        // The waker is taken, shutdown registers it again when polled.
+        assert!(state.shutdown.is_none());
+        state.complete(1);
+        assert!(!flag.0.load(Ordering::SeqCst));
+        assert_eq!(state.running, [0, 0]);
+    }
+}
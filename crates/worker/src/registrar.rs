@@ -6,6 +6,7 @@ use crate::lifecycle::WorkerLifecycleEvent;
 use crate::messages::{FromWorker, ToWorker};
 use crate::native_worker::{DedicatedWorker, NativeWorkerExt, WorkerSelf};
 use crate::scope::WorkerScope;
+use crate::shared;
 use crate::traits::Worker;
 
 /// A Worker Registrar.
@@ -59,4 +60,17 @@ where
         worker.set_on_packed_message::<_, CODEC, _>(handler);
         worker.post_packed_message::<_, CODEC>(loaded);
     }
+
+    /// Executes a shared worker in the current environment.
+    ///
+    /// The bridges of every tab connected with
+    /// [`WorkerSpawner::spawn_shared`](crate::WorkerSpawner::spawn_shared) are connected to the
+    /// same worker, each with a [`HandlerId`](crate::HandlerId) of its own. The worker is
+    /// destroyed once all tabs dropped their bridges.
+    pub fn register_shared(&self)
+    where
+        CODEC: Codec,
+    {
+        shared::register::<W, CODEC>();
+    }
 }
@@ -1,4 +1,5 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::fmt;
 #[cfg(feature = "futures")]
 use std::future::Future;
@@ -43,10 +44,32 @@ where
     }
 }
 
+/// Tells a worker whether the bridge it is running a task for is gone, returned by
+/// [`WorkerScope::cancellation`].
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    cancelled: Rc<Cell<bool>>,
+}
+
+impl CancellationToken {
+    fn new(cancelled: bool) -> Self {
+        Self {
+            cancelled: Rc::new(Cell::new(cancelled)),
+        }
+    }
+
+    /// Whether the task was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.get()
+    }
+}
+
 /// This struct holds a reference to a component and to a global scheduler.
 pub struct WorkerScope<W: Worker> {
     state: Shared<WorkerState<W>>,
     post_msg: Rc<dyn Fn(FromWorker<W>)>,
+    close: Rc<dyn Fn()>,
+    cancellations: Shared<HashMap<HandlerId, CancellationToken>>,
 }
 
 impl<W: Worker> fmt::Debug for WorkerScope<W> {
@@ -60,6 +83,8 @@ impl<W: Worker> Clone for WorkerScope<W> {
         WorkerScope {
             state: self.state.clone(),
             post_msg: self.post_msg.clone(),
+            close: self.close.clone(),
+            cancellations: self.cancellations.clone(),
         }
     }
 }
@@ -77,13 +102,29 @@ where
             DedicatedWorker::worker_self().post_packed_message::<_, CODEC>(msg)
         };
 
+        Self::with_channel(post_msg, || DedicatedWorker::worker_self().close())
+    }
+
+    /// Create worker scope sending messages with `post_msg`, closed by `close`.
+    pub(crate) fn with_channel<P, C>(post_msg: P, close: C) -> Self
+    where
+        P: 'static + Fn(FromWorker<W>),
+        C: 'static + Fn(),
+    {
         let state = Rc::new(RefCell::new(WorkerState::new()));
         WorkerScope {
             post_msg: Rc::new(post_msg),
+            close: Rc::new(close),
+            cancellations: Rc::default(),
             state,
         }
     }
 
+    /// Close the worker
+    pub(crate) fn close(&self) {
+        (self.close)();
+    }
+
     /// Schedule message for sending to worker
     pub(crate) fn send(&self, event: WorkerLifecycleEvent<W>) {
         let state = self.state.clone();
@@ -95,7 +136,61 @@ where
         });
     }
 
+    /// Tracks the bridge `id` until it is disconnected, giving it a token to cancel then.
+    pub(crate) fn connect(&self, id: HandlerId) {
+        self.cancellations
+            .borrow_mut()
+            .entry(id)
+            .or_insert_with(|| CancellationToken::new(false));
+    }
+
+    /// Cancels the token of the bridge `id`, or of all bridges.
+    pub(crate) fn cancel(&self, id: Option<HandlerId>) {
+        let mut cancellations = self.cancellations.borrow_mut();
+        let cancelled: Vec<_> = match id {
+            Some(id) => cancellations.remove(&id).into_iter().collect(),
+            None => cancellations.drain().map(|(_, token)| token).collect(),
+        };
+        for token in cancelled {
+            token.cancelled.set(true);
+        }
+    }
+
+    /// The cancellation token of the bridge `id`, cancelled once the bridge is disconnected.
+    ///
+    /// The bridges of [`WorkerBridge::call`](crate::WorkerBridge::call) are disconnected when
+    /// the call is cancelled or dropped, so a worker running a long task can check the token
+    /// and stop early. Messages are only received while the worker yields to the event loop,
+    /// so the token can only be cancelled between the steps of a task, e.g. one split with
+    /// [`send_future`](Self::send_future) or timeouts. A worker stopping a cancelled task
+    /// still marks its input as [`processed`](Self::processed).
+    ///
+    /// The token of a bridge that is already disconnected, or never sent an input, is
+    /// cancelled.
+    pub fn cancellation(&self, id: HandlerId) -> CancellationToken {
+        self.cancellations
+            .borrow()
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| CancellationToken::new(true))
+    }
+
+    /// Tell the bridge `id` the worker is done with one of its inputs without responding to it,
+    /// making room for another one.
+    ///
+    /// An input is done once the worker responds to it with [`respond`](Self::respond), which
+    /// tells the bridge with the response. Inputs the worker doesn't respond to have to be
+    /// marked as processed with this method, or [`WorkerBridge::poll_ready`] eventually waits
+    /// forever.
+    ///
+    /// [`WorkerBridge::poll_ready`]: crate::WorkerBridge::poll_ready
+    pub fn processed(&self, id: HandlerId) {
+        (self.post_msg)(FromWorker::<W>::Processed(id));
+    }
+
     /// Send response to a worker bridge.
+    ///
+    /// Every response marks one input of the bridge as [`processed`](Self::processed).
     pub fn respond(&self, id: HandlerId, output: W::Output) {
         let msg = FromWorker::<W>::ProcessOutput(id, output);
         (self.post_msg)(msg);
@@ -167,3 +262,71 @@ where
         wasm_bindgen_futures::spawn_local(js_future);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Echo;
+
+    impl Worker for Echo {
+        type Message = ();
+        type Input = u32;
+        type Output = u32;
+
+        fn create(_: &WorkerScope<Self>) -> Self {
+            Echo
+        }
+
+        fn update(&mut self, _: &WorkerScope<Self>, _: ()) {}
+
+        fn received(&mut self, scope: &WorkerScope<Self>, msg: u32, id: HandlerId) {
+            scope.respond(id, msg);
+        }
+    }
+
+    fn scope() -> WorkerScope<Echo> {
+        WorkerScope::with_channel(|_| {}, || {})
+    }
+
+    #[test]
+    fn cancellation_of_a_connected_bridge() {
+        let scope = scope();
+        let id = HandlerId::new();
+        scope.connect(id);
+
+        let token = scope.cancellation(id);
+        assert!(!token.is_cancelled());
+        // Connecting again, e.g. with every input, keeps the token.
+        scope.connect(id);
+        assert!(!scope.cancellation(id).is_cancelled());
+
+        scope.cancel(Some(HandlerId::new()));
+        assert!(!token.is_cancelled());
+        scope.cancel(Some(id));
+        assert!(token.is_cancelled());
+        assert!(scope.cancellation(id).is_cancelled());
+    }
+
+    #[test]
+    fn cancellation_of_an_unknown_bridge() {
+        let scope = scope();
+
+        assert!(scope.cancellation(HandlerId::new()).is_cancelled());
+    }
+
+    #[test]
+    fn cancel_all_bridges() {
+        let scope = scope();
+        let (a, b) = (HandlerId::new(), HandlerId::new());
+        scope.connect(a);
+        scope.connect(b);
+        let tokens = [scope.cancellation(a), scope.cancellation(b)];
+
+        scope.cancel(None);
+        assert!(tokens.iter().all(CancellationToken::is_cancelled));
+        // A bridge connecting after gets a new token.
+        scope.connect(a);
+        assert!(!scope.cancellation(a).is_cancelled());
+    }
+}
@@ -0,0 +1,406 @@
+//! Registering [service workers](https://developer.mozilla.org/en-US/docs/Web/API/Service_Worker_API)
+//! and communicating with them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use futures::StreamExt;
+//! # use gloo_worker::service_worker::{self, Change, Channel};
+//! # use web_sys::ServiceWorkerState;
+//! # async fn no_run() {
+//! let registration = service_worker::register("/sw.js").await.unwrap();
+//! let mut changes = registration.changes();
+//! while let Some(change) = changes.next().await {
+//!     if let Change::State(worker, ServiceWorkerState::Installed) = change {
+//!         // Ask the new version to take over, with a message it handles by calling
+//!         // `service_worker::skip_waiting`.
+//!         let channel = Channel::<String, String>::new();
+//!         channel.send_to(&worker, "skip-waiting".to_string()).unwrap();
+//!     }
+//! }
+//! # }
+//! ```
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use gloo_events::EventListener;
+use gloo_utils::errors::JsError;
+use gloo_utils::window;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    MessageEvent, RegistrationOptions, ServiceWorker, ServiceWorkerContainer,
+    ServiceWorkerGlobalScope, ServiceWorkerRegistration, ServiceWorkerState,
+};
+
+use crate::codec::{Bincode, Codec};
+
+fn js_to_error(value: JsValue) -> JsError {
+    JsError::try_from(value).unwrap_or_else(|e| js_sys::Error::new(&e.to_string()).into())
+}
+
+fn container() -> ServiceWorkerContainer {
+    window().navigator().service_worker()
+}
+
+/// Registers the service worker at `script_url`, or updates its registration.
+pub async fn register(script_url: &str) -> Result<Registration, JsError> {
+    register_with_options(script_url, &RegistrationOptions::new()).await
+}
+
+/// Registers the service worker at `script_url` with `options`, e.g. to set its scope.
+pub async fn register_with_options(
+    script_url: &str,
+    options: &RegistrationOptions,
+) -> Result<Registration, JsError> {
+    let registration = JsFuture::from(container().register_with_options(script_url, options))
+        .await
+        .map_err(js_to_error)?;
+    Ok(Registration::from(
+        registration.unchecked_into::<ServiceWorkerRegistration>(),
+    ))
+}
+
+/// The registration of the service worker controlling the current page, if there is one.
+pub async fn registration() -> Result<Option<Registration>, JsError> {
+    let registration = JsFuture::from(container().get_registration())
+        .await
+        .map_err(js_to_error)?;
+    Ok(registration
+        .dyn_into::<ServiceWorkerRegistration>()
+        .ok()
+        .map(Registration::from))
+}
+
+/// Waits for the registration of the current page to have an active service worker.
+pub async fn ready() -> Result<Registration, JsError> {
+    let ready = container().ready().map_err(js_to_error)?;
+    let registration = JsFuture::from(ready).await.map_err(js_to_error)?;
+    Ok(Registration::from(
+        registration.unchecked_into::<ServiceWorkerRegistration>(),
+    ))
+}
+
+/// The active service worker controlling the current page, if there is one.
+pub fn controller() -> Option<ServiceWorker> {
+    container().controller()
+}
+
+/// Activates the service worker running this code right away, instead of waiting for the pages
+/// controlled by the previous version to be closed.
+///
+/// This is called from the service worker itself, typically while it is installed.
+pub async fn skip_waiting() -> Result<(), JsError> {
+    let global: ServiceWorkerGlobalScope = js_sys::global().unchecked_into();
+    let skipped = global.skip_waiting().map_err(js_to_error)?;
+    JsFuture::from(skipped).await.map_err(js_to_error)?;
+    Ok(())
+}
+
+/// Makes the service worker running this code control the open pages in its scope, which were
+/// loaded before it was activated.
+///
+/// This is called from the service worker itself, typically once it is activated.
+pub async fn claim_clients() -> Result<(), JsError> {
+    let global: ServiceWorkerGlobalScope = js_sys::global().unchecked_into();
+    JsFuture::from(global.clients().claim())
+        .await
+        .map_err(js_to_error)?;
+    Ok(())
+}
+
+/// The registration of a service worker.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Registration {
+    raw: ServiceWorkerRegistration,
+}
+
+impl Registration {
+    /// The URL of the pages controlled by the service worker.
+    pub fn scope(&self) -> String {
+        self.raw.scope()
+    }
+
+    /// The service worker being installed, if there is one.
+    pub fn installing(&self) -> Option<ServiceWorker> {
+        self.raw.installing()
+    }
+
+    /// The installed service worker waiting for the previous version to stop controlling pages,
+    /// if there is one.
+    pub fn waiting(&self) -> Option<ServiceWorker> {
+        self.raw.waiting()
+    }
+
+    /// The active service worker, if there is one.
+    pub fn active(&self) -> Option<ServiceWorker> {
+        self.raw.active()
+    }
+
+    /// Checks for a new version of the service worker, installing it if there is one.
+    pub async fn update(&self) -> Result<(), JsError> {
+        let update = self.raw.update().map_err(js_to_error)?;
+        JsFuture::from(update).await.map_err(js_to_error)?;
+        Ok(())
+    }
+
+    /// Unregisters the service worker, returning whether it was registered.
+    ///
+    /// The service worker keeps controlling the pages open in its scope until they are closed.
+    pub async fn unregister(self) -> Result<bool, JsError> {
+        let unregister = self.raw.unregister().map_err(js_to_error)?;
+        let unregistered = JsFuture::from(unregister).await.map_err(js_to_error)?;
+        Ok(unregistered.is_truthy())
+    }
+
+    /// A stream of the changes to the service workers of the registration, and to the one
+    /// controlling the current page.
+    pub fn changes(&self) -> Changes {
+        Changes::new(self)
+    }
+
+    /// Gets the raw [`web_sys::ServiceWorkerRegistration`].
+    pub fn as_raw(&self) -> &ServiceWorkerRegistration {
+        &self.raw
+    }
+}
+
+impl From<ServiceWorkerRegistration> for Registration {
+    fn from(raw: ServiceWorkerRegistration) -> Self {
+        Self { raw }
+    }
+}
+
+/// A change to the service workers of a [`Registration`], yielded by [`Changes`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A new version of the service worker is being installed.
+    UpdateFound(ServiceWorker),
+    /// A service worker of the registration moved into a new state.
+    State(ServiceWorker, ServiceWorkerState),
+    /// Another service worker controls the current page.
+    Controller(Option<ServiceWorker>),
+}
+
+/// Items queued by event listeners until they are polled.
+struct Queue<T> {
+    items: VecDeque<T>,
+    waker: Option<Waker>,
+}
+
+impl<T> Queue<T> {
+    fn shared() -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            items: VecDeque::new(),
+            waker: None,
+        }))
+    }
+
+    fn push(&mut self, item: T) {
+        self.items.push_back(item);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        match self.items.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None => {
+                self.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A [`Stream`] of the [`Change`]s to the service workers of a registration, created by
+/// [`Registration::changes`].
+///
+/// The stream never ends, the listeners are removed when it is dropped.
+#[must_use = "streams do nothing unless polled"]
+pub struct Changes {
+    queue: Rc<RefCell<Queue<Change>>>,
+    _update_found: EventListener,
+    _controller_change: EventListener,
+    state_changes: Rc<RefCell<Vec<EventListener>>>,
+}
+
+impl Changes {
+    fn new(registration: &Registration) -> Self {
+        let queue = Queue::shared();
+        let state_changes = Rc::new(RefCell::new(Vec::new()));
+        let registration = registration.raw.clone();
+        for worker in [
+            registration.installing(),
+            registration.waiting(),
+            registration.active(),
+        ]
+        .iter()
+        .flatten()
+        {
+            state_changes
+                .borrow_mut()
+                .push(listen_state(worker, &queue));
+        }
+
+        let update_found = {
+            let queue = queue.clone();
+            let state_changes = state_changes.clone();
+            EventListener::new(&registration.clone(), "updatefound", move |_| {
+                if let Some(worker) = registration.installing() {
+                    state_changes
+                        .borrow_mut()
+                        .push(listen_state(&worker, &queue));
+                    queue.borrow_mut().push(Change::UpdateFound(worker));
+                }
+            })
+        };
+        let controller_change = {
+            let queue = queue.clone();
+            EventListener::new(&container(), "controllerchange", move |_| {
+                queue.borrow_mut().push(Change::Controller(controller()));
+            })
+        };
+
+        Self {
+            queue,
+            _update_found: update_found,
+            _controller_change: controller_change,
+            state_changes,
+        }
+    }
+}
+
+fn listen_state(worker: &ServiceWorker, queue: &Rc<RefCell<Queue<Change>>>) -> EventListener {
+    let queue = queue.clone();
+    let target = worker.clone();
+    EventListener::new(worker, "statechange", move |_| {
+        queue
+            .borrow_mut()
+            .push(Change::State(target.clone(), target.state()));
+    })
+}
+
+impl Stream for Changes {
+    type Item = Change;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.queue.borrow_mut().poll(cx)
+    }
+}
+
+impl fmt::Debug for Changes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Changes")
+            .field("queued", &self.queue.borrow().items.len())
+            .field("workers", &self.state_changes.borrow().len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A typed channel to the service workers, sending `I`s and receiving `O`s encoded with `CODEC`.
+///
+/// The channel is a [`Stream`] of the messages the service workers post to the current page. The
+/// service worker decodes the messages it receives with [`Codec::decode`], and encodes the ones it
+/// posts with [`Codec::encode`], of the same codec. Messages which can't be decoded with
+/// [`Codec::try_decode`], e.g. the ones posted by other libraries, are skipped.
+#[must_use = "streams do nothing unless polled"]
+pub struct Channel<I, O, CODEC = Bincode>
+where
+    CODEC: Codec,
+{
+    queue: Rc<RefCell<Queue<O>>>,
+    _message: EventListener,
+    _marker: PhantomData<(I, CODEC)>,
+}
+
+impl<I, O, CODEC> Channel<I, O, CODEC>
+where
+    I: Serialize,
+    O: for<'de> Deserialize<'de> + 'static,
+    CODEC: Codec,
+{
+    /// Creates a channel, receiving the messages posted to the current page from now on.
+    pub fn new() -> Self {
+        let queue = Queue::shared();
+        let message = {
+            let queue = queue.clone();
+            EventListener::new(&container(), "message", move |event| {
+                let event = event.unchecked_ref::<MessageEvent>();
+                if let Some(msg) = CODEC::try_decode(event.data()) {
+                    queue.borrow_mut().push(msg);
+                }
+            })
+        };
+        Self {
+            queue,
+            _message: message,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sends a message to the active service worker, waiting for one if the page isn't
+    /// controlled by one yet.
+    pub async fn send(&self, msg: I) -> Result<(), JsError> {
+        let worker = match controller() {
+            Some(worker) => worker,
+            None => ready()
+                .await?
+                .active()
+                .ok_or_else(|| JsError::from(js_sys::Error::new("no active service worker")))?,
+        };
+        self.send_to(&worker, msg)
+    }
+
+    /// Sends a message to `worker`, e.g. to a waiting one.
+    pub fn send_to(&self, worker: &ServiceWorker, msg: I) -> Result<(), JsError> {
+        worker
+            .post_message(&CODEC::encode(msg))
+            .map_err(js_to_error)
+    }
+}
+
+impl<I, O, CODEC> Default for Channel<I, O, CODEC>
+where
+    I: Serialize,
+    O: for<'de> Deserialize<'de> + 'static,
+    CODEC: Codec,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I, O, CODEC> Stream for Channel<I, O, CODEC>
+where
+    CODEC: Codec,
+{
+    type Item = O;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.queue.borrow_mut().poll(cx)
+    }
+}
+
+impl<I, O, CODEC> Unpin for Channel<I, O, CODEC> where CODEC: Codec {}
+
+impl<I, O, CODEC> fmt::Debug for Channel<I, O, CODEC>
+where
+    CODEC: Codec,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Channel")
+            .field("queued", &self.queue.borrow().items.len())
+            .finish_non_exhaustive()
+    }
+}
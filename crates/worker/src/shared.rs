@@ -0,0 +1,242 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{MessageEvent, MessagePort, SharedWorker};
+
+use crate::codec::Codec;
+use crate::handler_id::HandlerId;
+use crate::lifecycle::WorkerLifecycleEvent;
+use crate::messages::{FromWorker, ToWorker};
+use crate::native_worker::{NativeWorkerExt, WorkerSelf};
+use crate::scope::WorkerScope;
+use crate::traits::Worker;
+
+/// The ports of the tabs connected to a shared worker.
+///
+/// Every tab numbers its bridges on its own, so the ids of their bridges are mapped to ids unique
+/// within the worker.
+struct Ports<P = MessagePort> {
+    ports: HashMap<usize, P>,
+    next_port: usize,
+    /// The port and the id in the tab of every bridge.
+    remote: HashMap<HandlerId, (usize, HandlerId)>,
+    /// The id in the worker of every bridge.
+    local: HashMap<(usize, HandlerId), HandlerId>,
+}
+
+impl<P> Default for Ports<P> {
+    fn default() -> Self {
+        Self {
+            ports: HashMap::new(),
+            next_port: 0,
+            remote: HashMap::new(),
+            local: HashMap::new(),
+        }
+    }
+}
+
+impl<P> Ports<P> {
+    fn connect(&mut self, port: P) -> usize {
+        let port_id = self.next_port;
+        self.next_port += 1;
+        self.ports.insert(port_id, port);
+        port_id
+    }
+
+    fn local_id(&mut self, port_id: usize, id: HandlerId) -> HandlerId {
+        let remote = &mut self.remote;
+        *self.local.entry((port_id, id)).or_insert_with(|| {
+            let local_id = HandlerId::new();
+            remote.insert(local_id, (port_id, id));
+            local_id
+        })
+    }
+
+    /// The port and the id in the tab of a bridge of the worker.
+    fn route(&self, id: HandlerId) -> Option<(&P, HandlerId)> {
+        self.remote
+            .get(&id)
+            .map(|(port_id, remote_id)| (&self.ports[port_id], *remote_id))
+    }
+
+    fn forget(&mut self, port_id: usize, id: HandlerId) {
+        if let Some(local_id) = self.local.remove(&(port_id, id)) {
+            self.remote.remove(&local_id);
+        }
+    }
+
+    /// Maps a message from the bridges of a port to the ids of the worker.
+    ///
+    /// Returns `None` for the `Destroy` message of a tab, unless it was the last one connected.
+    fn receive<W>(&mut self, port_id: usize, msg: ToWorker<W>) -> Option<ToWorker<W>>
+    where
+        W: Worker,
+    {
+        let msg = match msg {
+            ToWorker::Connected(id) => ToWorker::Connected(self.local_id(port_id, id)),
+            ToWorker::ProcessInput(id, input) => {
+                ToWorker::ProcessInput(self.local_id(port_id, id), input)
+            }
+            ToWorker::Disconnected(id) => {
+                let local_id = self.local_id(port_id, id);
+                self.forget(port_id, id);
+                ToWorker::Disconnected(local_id)
+            }
+            ToWorker::Destroy => {
+                self.ports.remove(&port_id);
+                self.local.retain(|(port, _), _| *port != port_id);
+                self.remote.retain(|_, (port, _)| *port != port_id);
+                if !self.ports.is_empty() {
+                    return None;
+                }
+                ToWorker::Destroy
+            }
+        };
+        Some(msg)
+    }
+}
+
+/// Executes a worker in the current shared worker environment.
+pub(crate) fn register<W, CODEC>()
+where
+    W: Worker,
+    CODEC: Codec,
+{
+    let ports: Rc<RefCell<Ports>> = Rc::default();
+
+    let post_msg = {
+        let ports = ports.clone();
+        move |msg: FromWorker<W>| {
+            let ports = ports.borrow();
+            match msg {
+                FromWorker::WorkerLoaded => {}
+                FromWorker::ProcessOutput(id, output) => {
+                    if let Some((port, remote_id)) = ports.route(id) {
+                        port.post_packed_message::<_, CODEC>(FromWorker::<W>::ProcessOutput(
+                            remote_id, output,
+                        ));
+                    }
+                }
+                FromWorker::Processed(id) => {
+                    if let Some((port, remote_id)) = ports.route(id) {
+                        port.post_packed_message::<_, CODEC>(FromWorker::<W>::Processed(remote_id));
+                    }
+                }
+            }
+        }
+    };
+    let scope = WorkerScope::<W>::with_channel(post_msg, || SharedWorker::worker_self().close());
+    scope.send(WorkerLifecycleEvent::Create(scope.clone()));
+
+    let on_connect = move |event: MessageEvent| {
+        let port: MessagePort = event.ports().get(0).unchecked_into();
+        let port_id = ports.borrow_mut().connect(port.clone());
+
+        let handler = {
+            let ports = ports.clone();
+            let scope = scope.clone();
+            move |msg: ToWorker<W>| {
+                let msg = ports.borrow_mut().receive(port_id, msg);
+                if let Some(msg) = msg {
+                    scope.send(WorkerLifecycleEvent::Remote(msg));
+                }
+            }
+        };
+        port.set_on_packed_message::<_, CODEC, _>(handler);
+        port.post_packed_message::<_, CODEC>(FromWorker::<W>::WorkerLoaded);
+    };
+    let on_connect =
+        Closure::wrap(Box::new(on_connect) as Box<dyn Fn(MessageEvent)>).into_js_value();
+    SharedWorker::worker_self().set_onconnect(Some(on_connect.unchecked_ref()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Echo;
+
+    impl Worker for Echo {
+        type Message = ();
+        type Input = u32;
+        type Output = u32;
+
+        fn create(_: &WorkerScope<Self>) -> Self {
+            Echo
+        }
+
+        fn update(&mut self, _: &WorkerScope<Self>, _: ()) {}
+
+        fn received(&mut self, scope: &WorkerScope<Self>, msg: u32, id: HandlerId) {
+            scope.respond(id, msg);
+        }
+    }
+
+    fn connected(ports: &mut Ports<&'static str>, port_id: usize, id: HandlerId) -> HandlerId {
+        match ports.receive::<Echo>(port_id, ToWorker::Connected(id)) {
+            Some(ToWorker::Connected(local_id)) => local_id,
+            msg => panic!("unexpected message: {:?}", msg),
+        }
+    }
+
+    #[test]
+    fn maps_the_bridges_of_every_port_to_ids_of_their_own() {
+        let mut ports = Ports::default();
+        let a = ports.connect("a");
+        let b = ports.connect("b");
+        // Both tabs number their bridges from the same ids.
+        let id = HandlerId::new();
+
+        let local_a = connected(&mut ports, a, id);
+        let local_b = connected(&mut ports, b, id);
+        assert_ne!(local_a, local_b);
+        assert_eq!(connected(&mut ports, a, id), local_a);
+
+        match ports.receive::<Echo>(b, ToWorker::ProcessInput(id, 1)) {
+            Some(ToWorker::ProcessInput(local_id, 1)) => assert_eq!(local_id, local_b),
+            msg => panic!("unexpected message: {:?}", msg),
+        }
+        assert_eq!(ports.route(local_a), Some((&"a", id)));
+        assert_eq!(ports.route(local_b), Some((&"b", id)));
+    }
+
+    #[test]
+    fn forgets_disconnected_bridges() {
+        let mut ports = Ports::default();
+        let a = ports.connect("a");
+        let id = HandlerId::new();
+        let local_id = connected(&mut ports, a, id);
+
+        match ports.receive::<Echo>(a, ToWorker::Disconnected(id)) {
+            Some(ToWorker::Disconnected(disconnected)) => assert_eq!(disconnected, local_id),
+            msg => panic!("unexpected message: {:?}", msg),
+        }
+        assert_eq!(ports.route(local_id), None);
+        // The bridge gets a new id if it sends again.
+        assert_ne!(connected(&mut ports, a, id), local_id);
+    }
+
+    #[test]
+    fn destroys_only_from_the_last_port() {
+        let mut ports = Ports::default();
+        let a = ports.connect("a");
+        let b = ports.connect("b");
+        let id = HandlerId::new();
+        let local_a = connected(&mut ports, a, id);
+        let local_b = connected(&mut ports, b, id);
+
+        assert!(ports.receive::<Echo>(a, ToWorker::Destroy).is_none());
+        assert_eq!(ports.route(local_a), None);
+        assert_eq!(ports.route(local_b), Some((&"b", id)));
+
+        assert!(matches!(
+            ports.receive::<Echo>(b, ToWorker::Destroy),
+            Some(ToWorker::Destroy)
+        ));
+        assert_eq!(ports.route(local_b), None);
+    }
+}
@@ -6,38 +6,85 @@ use std::rc::{Rc, Weak};
 
 use gloo_utils::window;
 use js_sys::Array;
-use web_sys::{Blob, BlobPropertyBag, Url};
+use wasm_bindgen::JsValue;
+use web_sys::{Blob, BlobPropertyBag, SharedWorker, Url, WorkerOptions, WorkerType};
 
-use crate::bridge::{CallbackMap, WorkerBridge};
+use crate::bridge::{CallbackMap, FlowControl, WorkerBridge};
 use crate::codec::{Bincode, Codec};
 use crate::handler_id::HandlerId;
 use crate::messages::FromWorker;
 use crate::native_worker::{DedicatedWorker, NativeWorkerExt};
+use crate::pool::WorkerPool;
 use crate::traits::Worker;
 use crate::{Callback, Shared};
 
-fn create_worker(path: &str) -> DedicatedWorker {
-    let js_shim_url = Url::new_with_base(
+fn absolute_url(path: &str) -> String {
+    Url::new_with_base(
         path,
         &window().location().href().expect("failed to read href."),
     )
     .expect("failed to create url for javascript entrypoint")
-    .to_string();
+    .href()
+}
 
+fn create_worker(path: &str, module: bool) -> DedicatedWorker {
+    let js_shim_url = absolute_url(path);
     let wasm_url = js_shim_url.replace(".js", "_bg.wasm");
 
+    let script = if module {
+        format!(r#"import init from "{js_shim_url}";init("{wasm_url}");"#)
+    } else {
+        format!(r#"importScripts("{js_shim_url}");wasm_bindgen("{wasm_url}");"#)
+    };
+    create_worker_from_script(&[&script], module)
+}
+
+fn create_inline_worker(glue: &str, wasm_url: &str, module: bool) -> DedicatedWorker {
+    // Relative URLs can't be resolved from a Blob URL.
+    let wasm_url = absolute_url(wasm_url);
+
+    if module {
+        // The glue exports the function initializing the wasm, import it from a module of its own.
+        let glue_url = object_url(&[glue]);
+        let script = format!(r#"import init from "{glue_url}";init("{wasm_url}");"#);
+        create_worker_from_script(&[&script], module)
+    } else {
+        create_worker_from_script(&[glue, &format!(r#";wasm_bindgen("{wasm_url}");"#)], module)
+    }
+}
+
+fn object_url(parts: &[&str]) -> String {
     let array = Array::new();
-    array.push(&format!(r#"importScripts("{js_shim_url}");wasm_bindgen("{wasm_url}");"#).into());
-    let blob = Blob::new_with_str_sequence_and_options(
-        &array,
-        BlobPropertyBag::new().type_("application/javascript"),
-    )
-    .unwrap();
-    let url = Url::create_object_url_with_blob(&blob).unwrap();
+    for part in parts {
+        array.push(&JsValue::from_str(part));
+    }
+    let blob_property = BlobPropertyBag::new();
+    blob_property.set_type("application/javascript");
+    let blob = Blob::new_with_str_sequence_and_options(&array, &blob_property).unwrap();
+    Url::create_object_url_with_blob(&blob).unwrap()
+}
+
+fn worker_options(module: bool) -> WorkerOptions {
+    let options = WorkerOptions::new();
+    if module {
+        options.set_type(WorkerType::Module);
+    }
+    options
+}
 
-    DedicatedWorker::new(&url).expect("failed to spawn worker")
+fn create_worker_from_script(parts: &[&str], module: bool) -> DedicatedWorker {
+    DedicatedWorker::new_with_options(&object_url(parts), &worker_options(module))
+        .expect("failed to spawn worker")
 }
 
+fn create_shared_worker(path: &str, module: bool) -> SharedWorker {
+    // Tabs share a worker created from the same URL, so it can't be wrapped in a Blob URL.
+    SharedWorker::new_with_worker_options(&absolute_url(path), &worker_options(module))
+        .expect("failed to spawn shared worker")
+}
+
+const DEFAULT_CAPACITY: usize = 128;
+
 /// A spawner to create workers.
 #[derive(Clone)]
 pub struct WorkerSpawner<W, CODEC = Bincode>
@@ -47,6 +94,8 @@ where
 {
     _marker: PhantomData<(W, CODEC)>,
     callback: Option<Callback<W::Output>>,
+    module: bool,
+    capacity: usize,
 }
 
 impl<W, CODEC> fmt::Debug for WorkerSpawner<W, CODEC>
@@ -79,6 +128,8 @@ where
         Self {
             _marker: PhantomData,
             callback: None,
+            module: false,
+            capacity: DEFAULT_CAPACITY,
         }
     }
 
@@ -90,9 +141,38 @@ where
         WorkerSpawner {
             _marker: PhantomData,
             callback: self.callback.clone(),
+            module: self.module,
+            capacity: self.capacity,
         }
     }
 
+    /// Sets whether workers are spawned as ES module workers, `false` by default.
+    ///
+    /// Module workers load the glue of `wasm-bindgen --target web` instead of
+    /// `--target no-modules`, which is what bundlers like Vite output, and can use `import`
+    /// statements.
+    pub fn module(&mut self, module: bool) -> &mut Self {
+        self.module = module;
+
+        self
+    }
+
+    /// Sets how many inputs the bridges of a worker may have in flight before they stop being
+    /// ready, 128 by default.
+    ///
+    /// Inputs are in flight while they are queued until the worker is loaded, or until the worker
+    /// responds to them or marks them as [`processed`](crate::WorkerScope::processed), or until
+    /// the bridge which sent them is dropped. Once the
+    /// capacity is reached, [`WorkerBridge::poll_ready`], [`WorkerBridge::ready`] and the sink of
+    /// [`WorkerBridge::stream`] wait for the worker to catch up, and [`WorkerBridge::try_send`]
+    /// gives the input back. [`WorkerBridge::send`] doesn't check the capacity, it always sends.
+    /// The capacity is at least 1.
+    pub fn ready_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.capacity = capacity.max(1);
+
+        self
+    }
+
     /// Sets a callback.
     pub fn callback<F>(&mut self, cb: F) -> &mut Self
     where
@@ -105,6 +185,59 @@ where
 
     /// Spawns a Worker.
     pub fn spawn(&self, path: &str) -> WorkerBridge<W> {
+        self.spawn_with(create_worker(path, self.module))
+    }
+
+    /// Spawns a Worker from the wasm-bindgen JavaScript glue embedded in the binary.
+    ///
+    /// `glue` is the output of `wasm-bindgen --target no-modules` for the worker, or of
+    /// `--target web` for [module](Self::module) workers, e.g. included with `include_str!`, and `wasm_url` the URL of its wasm file, relative to the current
+    /// page. The worker is started from a Blob URL, so that no JavaScript file has to be served
+    /// alongside the wasm.
+    ///
+    /// ```no_run
+    /// # use gloo_worker::{HandlerId, Spawnable, Worker, WorkerScope};
+    /// # struct MyWorker;
+    /// # impl Worker for MyWorker {
+    /// #     type Message = ();
+    /// #     type Input = ();
+    /// #     type Output = ();
+    /// #     fn create(_: &WorkerScope<Self>) -> Self { MyWorker }
+    /// #     fn update(&mut self, _: &WorkerScope<Self>, _: ()) {}
+    /// #     fn received(&mut self, _: &WorkerScope<Self>, _: (), _: HandlerId) {}
+    /// # }
+    /// # const GLUE: &str = "";
+    /// // const GLUE: &str = include_str!(concat!(env!("OUT_DIR"), "/worker.js"));
+    /// let bridge = MyWorker::spawner().spawn_inline(GLUE, "/assets/worker_bg.wasm");
+    /// ```
+    pub fn spawn_inline(&self, glue: &str, wasm_url: &str) -> WorkerBridge<W> {
+        self.spawn_with(create_inline_worker(glue, wasm_url, self.module))
+    }
+
+    /// Connects to a shared Worker, spawning it if no other tab is connected to it.
+    ///
+    /// The worker has to be registered with
+    /// [`WorkerRegistrar::register_shared`](crate::WorkerRegistrar::register_shared), by a
+    /// script at `path` which loads the wasm-bindgen output itself, e.g.
+    /// `importScripts("worker.js"); wasm_bindgen("worker_bg.wasm");`, or
+    /// `import init from "./worker.js"; init("./worker_bg.wasm");` for [module](Self::module)
+    /// workers. All tabs of the origin
+    /// spawning a shared worker from the same `path` talk to the same instance of the worker,
+    /// which sees the bridges of all of them.
+    pub fn spawn_shared(&self, path: &str) -> WorkerBridge<W> {
+        self.spawn_with(create_shared_worker(path, self.module).port())
+    }
+
+    /// Spawns a pool of `size` Workers, at least one.
+    pub fn spawn_pool(&self, path: &str, size: usize) -> WorkerPool<W> {
+        let bridges = (0..size.max(1)).map(|_| self.spawn(path)).collect();
+        WorkerPool::new(bridges)
+    }
+
+    fn spawn_with<T>(&self, worker: T) -> WorkerBridge<W>
+    where
+        T: 'static + Clone + NativeWorkerExt,
+    {
         let pending_queue = Rc::new(RefCell::new(Some(Vec::new())));
         let handler_id = HandlerId::new();
         let mut callbacks = HashMap::new();
@@ -114,11 +247,12 @@ where
         }
 
         let callbacks: Shared<CallbackMap<W>> = Rc::new(RefCell::new(callbacks));
+        let flow = Rc::new(RefCell::new(FlowControl::new(self.capacity)));
 
         let worker = {
             let pending_queue = pending_queue.clone();
             let callbacks = callbacks.clone();
-            let worker = create_worker(path);
+            let flow = flow.clone();
 
             let handler = {
                 let worker = worker.clone();
@@ -132,6 +266,7 @@ where
                         }
                     }
                     FromWorker::ProcessOutput(id, output) => {
+                        flow.borrow_mut().processed(id);
                         let mut callbacks = callbacks.borrow_mut();
 
                         if let Some(m) = callbacks.get(&id) {
@@ -142,6 +277,7 @@ where
                             }
                         }
                     }
+                    FromWorker::Processed(id) => flow.borrow_mut().processed(id),
                 }
             };
 
@@ -149,11 +285,12 @@ where
             worker
         };
 
-        WorkerBridge::<W>::new::<CODEC>(
+        WorkerBridge::<W>::new::<CODEC, _>(
             handler_id,
             worker,
             pending_queue,
             callbacks,
+            flow,
             self.callback.clone(),
         )
     }
@@ -0,0 +1,117 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+
+use crate::bridge::WorkerBridge;
+use crate::traits::Worker;
+use crate::Shared;
+
+struct Outputs<O> {
+    queue: VecDeque<O>,
+    waker: Option<Waker>,
+}
+
+/// A bridge to a worker as a [`Stream`] of its outputs and a [`Sink`] of its inputs, created by
+/// [`WorkerBridge::stream`].
+///
+/// The sink is ready while the worker has room for another input, see
+/// [`WorkerBridge::poll_ready`], and inputs are sent right away. Outputs are queued until they
+/// are polled, and the stream never ends, as long as the worker is running it may respond.
+#[must_use = "streams do nothing unless polled"]
+pub struct WorkerStream<W>
+where
+    W: Worker,
+{
+    bridge: WorkerBridge<W>,
+    outputs: Shared<Outputs<W::Output>>,
+}
+
+impl<W> WorkerStream<W>
+where
+    W: Worker,
+{
+    pub(crate) fn new(bridge: &WorkerBridge<W>) -> Self {
+        let outputs = Rc::new(RefCell::new(Outputs {
+            queue: VecDeque::new(),
+            waker: None,
+        }));
+        let bridge = {
+            let outputs = outputs.clone();
+            bridge.fork(Some(move |output| {
+                let mut outputs = outputs.borrow_mut();
+                outputs.queue.push_back(output);
+                if let Some(waker) = outputs.waker.take() {
+                    waker.wake();
+                }
+            }))
+        };
+
+        Self { bridge, outputs }
+    }
+
+    /// The bridge sending the inputs of the stream.
+    pub fn bridge(&self) -> &WorkerBridge<W> {
+        &self.bridge
+    }
+}
+
+impl<W> Stream for WorkerStream<W>
+where
+    W: Worker,
+{
+    type Item = W::Output;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut outputs = self.outputs.borrow_mut();
+        match outputs.queue.pop_front() {
+            Some(output) => Poll::Ready(Some(output)),
+            None => {
+                outputs.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl<W> Sink<W::Input> for WorkerStream<W>
+where
+    W: Worker,
+{
+    type Error = std::convert::Infallible;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.bridge.poll_ready(cx).map(Ok)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: W::Input) -> Result<(), Self::Error> {
+        self.bridge.send(item);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W> Unpin for WorkerStream<W> where W: Worker {}
+
+impl<W> fmt::Debug for WorkerStream<W>
+where
+    W: Worker,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkerStream")
+            .field("queued", &self.outputs.borrow().queue.len())
+            .finish_non_exhaustive()
+    }
+}
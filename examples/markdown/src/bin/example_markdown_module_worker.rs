@@ -0,0 +1,11 @@
+use example_markdown::MarkdownWorker;
+
+use gloo::worker::Registrable;
+
+// The same worker, built by trunk with the glue of `wasm-bindgen --target web` to be spawned as
+// an ES module worker.
+fn main() {
+    console_error_panic_hook::set_once();
+
+    MarkdownWorker::registrar().register();
+}
@@ -52,13 +52,16 @@ impl Worker for MarkdownWorker {
 mod tests {
     use super::*;
 
-    use gloo::worker::Spawnable;
+    use gloo::net::http::Request;
+    use gloo::worker::{Dispatch, Spawnable};
     use wasm_bindgen_test::*;
 
     use js_sys::Promise;
     use wasm_bindgen_futures::{spawn_local, JsFuture};
 
     use futures::channel::oneshot;
+    use futures::future::{join, join_all};
+    use futures::{SinkExt, StreamExt};
     use std::cell::RefCell;
 
     wasm_bindgen_test_configure!(run_in_browser);
@@ -70,6 +73,8 @@ This content is *rendered* by a **web worker**.
 
 "#;
 
+    static WORKER_URL: &str = "http://127.0.0.1:9999/example_markdown_worker.js";
+
     #[wasm_bindgen_test]
     async fn markdown_worker_works() {
         let (tx, rx) = oneshot::channel();
@@ -103,4 +108,75 @@ This content is *rendered* by a **web worker**.
 "#
         );
     }
+
+    #[wasm_bindgen_test]
+    async fn markdown_worker_call_works() {
+        let bridge = MarkdownWorker::spawner().spawn(WORKER_URL);
+
+        // Every call gets the response to its own input.
+        let (one, two) = join(
+            bridge.call("# One".to_owned()),
+            bridge.call("# Two".to_owned()),
+        )
+        .await;
+
+        assert_eq!(one, "<h1>One</h1>\n");
+        assert_eq!(two, "<h1>Two</h1>\n");
+    }
+
+    #[wasm_bindgen_test]
+    async fn markdown_worker_pool_works() {
+        let pool = MarkdownWorker::spawner()
+            .spawn_pool(WORKER_URL, 2)
+            .dispatch(Dispatch::LeastBusy);
+
+        let tasks = ["# One", "# Two", "# Three"].map(|input| pool.run(input.to_owned()));
+        assert_eq!(pool.running(), 3);
+
+        assert_eq!(
+            join_all(tasks).await,
+            ["<h1>One</h1>\n", "<h1>Two</h1>\n", "<h1>Three</h1>\n"]
+        );
+        assert_eq!(pool.running(), 0);
+        pool.shutdown().await;
+    }
+
+    #[wasm_bindgen_test]
+    async fn markdown_worker_stream_works() {
+        let bridge = MarkdownWorker::spawner().spawn(WORKER_URL);
+        let mut stream = bridge.stream();
+
+        stream.send("# One".to_owned()).await.unwrap();
+        stream.send("# Two".to_owned()).await.unwrap();
+
+        assert_eq!(stream.next().await.unwrap(), "<h1>One</h1>\n");
+        assert_eq!(stream.next().await.unwrap(), "<h1>Two</h1>\n");
+    }
+
+    #[wasm_bindgen_test]
+    async fn markdown_worker_inline_works() {
+        // The glue is usually embedded with `include_str!`, it's built by trunk here.
+        let glue = Request::get(WORKER_URL)
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        let bridge = MarkdownWorker::spawner().spawn_inline(
+            &glue,
+            "http://127.0.0.1:9999/example_markdown_worker_bg.wasm",
+        );
+
+        assert_eq!(bridge.call("# One".to_owned()).await, "<h1>One</h1>\n");
+    }
+
+    #[wasm_bindgen_test]
+    async fn markdown_module_worker_works() {
+        let bridge = MarkdownWorker::spawner()
+            .module(true)
+            .spawn("http://127.0.0.1:9999/example_markdown_module_worker.js");
+
+        assert_eq!(bridge.call("# One".to_owned()).await, "<h1>One</h1>\n");
+    }
 }